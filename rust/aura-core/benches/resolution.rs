@@ -0,0 +1,117 @@
+//! Benchmarks of dependency resolution, over synthetic package sets.
+//!
+//! Run with `cargo bench -p aura-core`. Resolution itself is measured against
+//! a fixture: an empty local database, no sync databases, and a local clone of
+//! every package, so that the AUR is never asked. This covers the walk through
+//! the dependencies and the reading of each `.SRCINFO`. How the AUR and real
+//! repos affect it on a given machine is left to `aura debug --bench-resolve`.
+
+use alpm::Alpm;
+use aura_core::aur::dependencies::{build_order, resolve, synthetic, Buildable};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::path::{Path, PathBuf};
+
+/// Hands out ALPM handles over the fixture's database.
+struct Fixture {
+    db: PathBuf,
+}
+
+impl r2d2::ManageConnection for Fixture {
+    type Connection = Alpm;
+    type Error = alpm::Error;
+
+    fn connect(&self) -> Result<Alpm, alpm::Error> {
+        Alpm::new("/", self.db.to_str().unwrap_or_default())
+    }
+
+    fn is_valid(&self, _: &mut Alpm) -> Result<(), alpm::Error> {
+        Ok(())
+    }
+
+    fn has_broken(&self, _: &mut Alpm) -> bool {
+        false
+    }
+}
+
+/// Lay out an empty database, and a clone holding a `.SRCINFO` for each of the
+/// given packages.
+fn fixture(root: &Path, pkgs: &[Buildable]) {
+    let local = root.join("db").join("local");
+    std::fs::create_dir_all(&local).unwrap();
+    std::fs::write(local.join("ALPM_DB_VERSION"), "9\n").unwrap();
+
+    for p in pkgs {
+        let clone = root.join("clones").join(&p.name);
+        std::fs::create_dir_all(&clone).unwrap();
+
+        let mut srcinfo = format!(
+            "pkgbase = {}\n\tpkgver = 1.0\n\tpkgrel = 1\n\tarch = any\n",
+            p.name
+        );
+        for d in p.deps.iter() {
+            srcinfo.push_str(&format!("\tdepends = {d}\n"));
+        }
+        srcinfo.push_str(&format!("\npkgname = {}\n", p.name));
+
+        std::fs::write(clone.join(".SRCINFO"), srcinfo).unwrap();
+    }
+}
+
+fn resolution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve");
+    let fetch = |_: &str| -> Result<Vec<aura_core::faur::Package>, ()> { Ok(Vec::new()) };
+
+    for size in [10, 100, 1000] {
+        let root = std::env::temp_dir().join(format!("aura-bench-{}-{size}", std::process::id()));
+        let pkgs = synthetic(size, 4);
+        fixture(&root, &pkgs);
+
+        let pool = r2d2::Pool::builder()
+            .max_size(4)
+            .build(Fixture {
+                db: root.join("db"),
+            })
+            .unwrap();
+        let clones = root.join("clones");
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &pkgs, |b, pkgs| {
+            b.iter(|| {
+                let top = pkgs.iter().take(1).map(|p| p.name.as_str());
+                resolve(pool.clone(), &fetch, &clones, "x86_64", top).is_ok()
+            })
+        });
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    group.finish();
+}
+
+fn ordering(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_order");
+
+    for size in [10, 100, 1000, 5000] {
+        let pkgs = synthetic(size, 4);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &pkgs, |b, pkgs| {
+            b.iter(|| build_order::<()>(pkgs))
+        });
+    }
+
+    group.finish();
+}
+
+fn fanout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_order_fanout");
+
+    for fanout in [1, 4, 16] {
+        let pkgs = synthetic(1000, fanout);
+        group.bench_with_input(BenchmarkId::from_parameter(fanout), &pkgs, |b, pkgs| {
+            b.iter(|| build_order::<()>(pkgs))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, ordering, fanout, resolution);
+criterion_main!(benches);
@@ -0,0 +1,12 @@
+//! Any text at all, as lines of the Pacman log.
+
+#![no_main]
+
+use aura_core::log::Action;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for line in String::from_utf8_lossy(data).lines() {
+        let _ = Action::parse(line);
+    }
+});
@@ -0,0 +1,10 @@
+//! Any bytes at all, as the `.SRCINFO` of an AUR package.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::path::Path;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = aura_core::metadata::srcinfo_from(Path::new(".SRCINFO"), data);
+});
@@ -0,0 +1,14 @@
+//! Any two lines of text, as package versions to compare.
+
+#![no_main]
+
+use aura_core::version::{cmp, parse_evr};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let raw = String::from_utf8_lossy(data);
+    let (a, b) = raw.split_once('\n').unwrap_or((&raw, ""));
+
+    let _ = parse_evr(a);
+    let _ = cmp(a, b);
+});
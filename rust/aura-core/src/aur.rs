@@ -3,6 +3,7 @@
 pub mod dependencies;
 
 use log::debug;
+use srcinfo::ArchVec;
 use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 
@@ -40,6 +41,27 @@ impl std::fmt::Display for Error {
     }
 }
 
+impl crate::Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Git(e) => e.code(),
+            Error::FaurFetch(_) => "aur-faur-fetch",
+            Error::PackageDoesNotExist(_) => "aur-package-does-not-exist",
+            Error::TooManyFaurResults(_) => "aur-too-many-faur-results",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Git(e) => e.context(),
+            Error::FaurFetch(u) => vec![("url", u.clone())],
+            Error::PackageDoesNotExist(p) | Error::TooManyFaurResults(p) => {
+                vec![("package", p.clone())]
+            }
+        }
+    }
+}
+
 /// The result of inspecting the existance status of a collection of package
 /// names.
 pub struct PkgPartition<'a> {
@@ -73,6 +95,27 @@ where
     Ok(part)
 }
 
+/// Does a `.SRCINFO` field apply to the given architecture? Fields like
+/// `depends_aarch64` apply only to theirs, while plain ones apply to all.
+pub fn arch_applies(av: &ArchVec, arch: &str) -> bool {
+    av.arch.as_deref().map(|a| a == arch).unwrap_or(true)
+}
+
+/// Can a package with the given `arch=()` array be built for the given
+/// architecture?
+///
+/// ```
+/// use aura_core::aur::supports_arch;
+///
+/// let arches = vec!["x86_64".to_string(), "aarch64".to_string()];
+/// assert!(supports_arch(&arches, "aarch64"));
+/// assert!(!supports_arch(&arches, "armv7h"));
+/// assert!(supports_arch(&["any".to_string()], "armv7h"));
+/// ```
+pub fn supports_arch(arches: &[String], arch: &str) -> bool {
+    arches.is_empty() || arches.iter().any(|a| a == "any" || a == arch)
+}
+
 /// Quickly check some given package's name against the local cache of package
 /// clones to see if its a real AUR package.
 ///
@@ -141,6 +184,9 @@ where
 }
 
 /// Clone a package's AUR repository and return the full path to the clone.
+///
+/// A clone that fails partway (or is interrupted) is removed again, so that it
+/// isn't mistaken for a complete one later.
 pub fn clone_aur_repo(root: Option<&Path>, package: &str) -> Result<PathBuf, crate::git::Error> {
     let mut url: PathBuf = [AUR_BASE_URL, package].iter().collect();
     url.set_extension("git");
@@ -150,7 +196,16 @@ pub fn clone_aur_repo(root: Option<&Path>, package: &str) -> Result<PathBuf, cra
         Some(r) => r.join(package),
     };
 
-    crate::git::shallow_clone(&url, &clone_path).map(|_| clone_path)
+    let existed = clone_path.exists();
+
+    match crate::git::shallow_clone(&url, &clone_path) {
+        Ok(()) => Ok(clone_path),
+        Err(e) if existed => Err(e),
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&clone_path);
+            Err(e)
+        }
+    }
 }
 
 /// Yield a path to the local git clone of the given package. The path won't
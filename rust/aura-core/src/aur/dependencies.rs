@@ -9,7 +9,7 @@ use petgraph::graph::NodeIndex;
 use petgraph::Graph;
 use r2d2::{ManageConnection, Pool};
 use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
-use srcinfo::Srcinfo;
+use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
@@ -27,7 +27,7 @@ pub enum Error<E> {
     /// An error pulling from the resource pool.
     R2D2(r2d2::Error),
     /// An error parsing a `.SRCINFO` file.
-    Srcinfo(PathBuf, srcinfo::Error),
+    Srcinfo(PathBuf, crate::metadata::Error),
     /// An error cloning or pulling a repo.
     Git(crate::git::Error),
     /// Multiple errors during concurrent dependency resolution.
@@ -81,8 +81,42 @@ impl<E: std::fmt::Display> std::fmt::Display for Error<E> {
     }
 }
 
+impl<E: crate::Coded> crate::Coded for Error<E> {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::PoisonedMutex => "deps-poisoned-mutex",
+            Error::R2D2(_) => "deps-r2d2",
+            Error::Srcinfo(_, _) => "deps-srcinfo",
+            Error::Git(e) => e.code(),
+            Error::Resolutions(_) => "deps-resolutions",
+            Error::DoesntExist(_) => "deps-doesnt-exist",
+            Error::DoesntExistWithParent(_, _) => "deps-doesnt-exist-with-parent",
+            Error::MalformedGraph => "deps-malformed-graph",
+            Error::CyclicDep(_) => "deps-cyclic-dep",
+            Error::Faur(e) => e.code(),
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Srcinfo(p, _) => vec![("path", p.display().to_string())],
+            Error::Git(e) => e.context(),
+            Error::Resolutions(es) => {
+                let codes = es.iter().map(|e| e.code()).collect::<Vec<_>>();
+                vec![("errors", codes.join(", "))]
+            }
+            Error::DoesntExist(p) | Error::CyclicDep(p) => vec![("package", p.clone())],
+            Error::DoesntExistWithParent(par, p) => {
+                vec![("parent", par.clone()), ("package", p.clone())]
+            }
+            Error::Faur(e) => e.context(),
+            Error::PoisonedMutex | Error::R2D2(_) | Error::MalformedGraph => Vec::new(),
+        }
+    }
+}
+
 /// The results of dependency resolution.
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct Resolution {
     /// Packages to be installed from official repos.
     pub to_install: HashSet<Official>,
@@ -90,6 +124,9 @@ pub struct Resolution {
     pub to_build: HashSet<Buildable>,
     /// Packages already installed on the system.
     pub satisfied: HashSet<String>,
+    /// Packages to be built whose `arch=()` array doesn't include the target
+    /// architecture.
+    pub unsupported: HashSet<String>,
     /// Packages that are somehow accounted for. A dependency might be provided
     /// by some package, but under a slightly different name. This also takes
     /// split packages into account.
@@ -107,7 +144,7 @@ impl Resolution {
 }
 
 /// An official ALPM package.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Official(String);
 
 impl Borrow<str> for Official {
@@ -129,7 +166,7 @@ impl std::fmt::Display for Official {
 }
 
 /// A buildable package from the AUR.
-#[derive(Eq)]
+#[derive(Eq, Serialize, Deserialize)]
 pub struct Buildable {
     /// The name of the AUR package.
     pub name: String,
@@ -169,11 +206,13 @@ impl Hash for Buildable {
     }
 }
 
-/// Determine all packages to be built and installed.
+/// Determine all packages to be built and installed for the given
+/// architecture, like `x86_64` or `aarch64`.
 pub fn resolve<'a, I, M, F, E>(
     pool: Pool<M>,
     fetch: &F,
     clone_d: &Path,
+    arch: &str,
     pkgs: I,
 ) -> Result<Resolution, Error<E>>
 where
@@ -191,7 +230,10 @@ where
 
     let start = OffsetDateTime::now_utc();
     orig.par_iter()
-        .map(|pkg| resolve_one(pool.clone(), arc.clone(), fetch, clone_d, &orig, None, pkg))
+        .map(|pkg| {
+            let a = arc.clone();
+            resolve_one(pool.clone(), a, fetch, clone_d, arch, &orig, None, pkg)
+        })
         .collect::<Validated<(), Error<E>>>()
         .ok()
         .map_err(|es| Error::Resolutions(Box::new(es)))?;
@@ -208,11 +250,13 @@ where
     Ok(res)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn resolve_one<M, F, E>(
     pool: Pool<M>,
     mutx: Arc<Mutex<Resolution>>,
     fetch: &F,
     clone_d: &Path,
+    arch: &str,
     orig: &HashSet<&str>,
     parent: Option<&str>,
     pkg_raw: &str,
@@ -286,7 +330,8 @@ where
                     deps.into_par_iter()
                         .map(|d| {
                             let p = Some(prnt.as_str());
-                            resolve_one(pool.clone(), mutx.clone(), fetch, clone_d, orig, p, &d)
+                            let m = mutx.clone();
+                            resolve_one(pool.clone(), m, fetch, clone_d, arch, orig, p, &d)
                         })
                         .collect::<Validated<(), Error<E>>>()
                         .ok()
@@ -302,8 +347,10 @@ where
                     let path = pull_or_clone(fetch, clone_d, parent, &pkg)?;
                     debug!("Parsing .SRCINFO for {}", pkg);
                     let full = path.join(".SRCINFO");
-                    let info = Srcinfo::parse_file(&full).map_err(|e| Error::Srcinfo(full, e))?;
+                    let info =
+                        crate::metadata::srcinfo(&full).map_err(|e| Error::Srcinfo(full, e))?;
                     let name = info.base.pkgbase;
+                    let supported = super::supports_arch(&info.pkg.arch, arch);
 
                     // --- Package identities provided by this one --- //
                     let mut prov = Vec::new();
@@ -319,6 +366,7 @@ where
                             prov.push(p.pkgname);
                             p.depends
                         }))
+                        .filter(|av| super::arch_applies(av, arch))
                         .flat_map(|av| av.vec)
                         .collect();
 
@@ -327,11 +375,16 @@ where
                     let buildable = Buildable { name, deps };
 
                     mutx.lock().map_err(|_| Error::PoisonedMutex).map(|mut r| {
+                        if supported.not() {
+                            r.unsupported.insert(buildable.name.clone());
+                        }
+
                         r.to_build.insert(buildable);
 
                         info.pkg
                             .provides
                             .into_iter()
+                            .filter(|av| super::arch_applies(av, arch))
                             .flat_map(|av| av.vec)
                             .chain(prov)
                             .for_each(|p| r.provided.insert(p).disown())
@@ -341,7 +394,8 @@ where
                         .into_par_iter()
                         .map(|p| {
                             let prnt = Some(parent.as_str());
-                            resolve_one(pool.clone(), mutx.clone(), fetch, clone_d, orig, prnt, &p)
+                            let m = mutx.clone();
+                            resolve_one(pool.clone(), m, fetch, clone_d, arch, orig, prnt, &p)
                         })
                         .collect::<Validated<(), Error<E>>>()
                         .ok()
@@ -497,6 +551,35 @@ fn dep_graph(to_build: &[Buildable]) -> Graph<&Buildable, ()> {
     graph
 }
 
+/// A made-up set of packages to build, for measuring how resolution scales.
+///
+/// Package `i` depends on up to `fanout` of the packages after it, at
+/// increasing distances, so that the graph is deep as well as wide. It never
+/// has cycles.
+///
+/// ```
+/// use aura_core::aur::dependencies::{build_order, synthetic};
+///
+/// let pkgs = synthetic(100, 3);
+/// let order = build_order::<()>(&pkgs).unwrap();
+///
+/// assert_eq!(100, order.iter().map(|layer| layer.len()).sum::<usize>());
+/// ```
+pub fn synthetic(packages: usize, fanout: usize) -> Vec<Buildable> {
+    let name = |i: usize| format!("pkg-{i}");
+
+    (0..packages)
+        .map(|i| Buildable {
+            name: name(i),
+            deps: (1..=fanout)
+                .map(|k| i + k * k)
+                .filter(|d| *d < packages)
+                .map(name)
+                .collect(),
+        })
+        .collect()
+}
+
 /// Strip version demands from a dependency string.
 fn strip_version<S>(stri: S) -> String
 where
@@ -1,7 +1,9 @@
 //! Cache manipulation internals.
 
+use crate::explain::Explain;
 use crate::Package;
 use alpm::Alpm;
+use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::fs::Metadata;
@@ -65,7 +67,12 @@ impl<'a> PkgPath<'a> {
     // TODO I'd like it if this could be avoided.
     /// Remove this via a shell call to `rm`.
     pub fn sudo_remove(self) -> Result<(), PathBuf> {
-        match Command::new("sudo").arg("rm").arg(&self.path).status() {
+        match Command::new("sudo")
+            .arg("rm")
+            .arg(&self.path)
+            .explained()
+            .status()
+        {
             Ok(s) if s.success() => Ok(()),
             Ok(_) | Err(_) => Err(self.path),
         }
@@ -189,6 +196,19 @@ where
         .filter_map(PkgPath::new)
 }
 
+/// Like [`package_paths`], but only yields the newest tarball of each package.
+pub fn newest_package_paths<P>(caches: &[P]) -> Vec<PkgPath<'static>>
+where
+    P: AsRef<Path>,
+{
+    package_paths(caches)
+        .sorted_by(|p0, p1| p1.cmp(p0))
+        .group_by(|pp| pp.as_package().name.clone())
+        .into_iter()
+        .filter_map(|(_, mut group)| group.next())
+        .collect()
+}
+
 /// Installed official packages that have no tarball in the cache.
 pub fn officials_missing_tarballs<'a>(
     alpm: &'a Alpm,
@@ -0,0 +1,115 @@
+//! Showing the external commands that are run, as they're run.
+//!
+//! This is off unless a frontend turns it on. Once on, each command is printed
+//! to `stderr` just before it runs, written so that it could be copied into a
+//! shell as-is. Unlike a dry run, the commands are still run.
+//!
+//! ```
+//! use aura_core::explain;
+//! use std::process::Command;
+//!
+//! let mut cmd = Command::new("git");
+//! cmd.arg("commit").arg("-m").arg("Fix it");
+//!
+//! assert_eq!("git commit -m 'Fix it'", explain::line(&cmd));
+//! ```
+
+use std::ops::Not;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Print every command from now on, before it runs.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Are commands being printed?
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Print a command, if commands are being printed.
+pub fn show(cmd: &Command) {
+    if is_enabled() {
+        eprintln!("$ {}", line(cmd));
+    }
+}
+
+/// A command as it would be typed into a shell, along with the directory it
+/// runs in and any changes to its environment.
+///
+/// ```
+/// use aura_core::explain::line;
+/// use std::process::Command;
+///
+/// let mut cmd = Command::new("makepkg");
+/// cmd.current_dir("/tmp/my build").env("PKGDEST", "/tmp/pkgs");
+/// assert_eq!("cd '/tmp/my build' && PKGDEST=/tmp/pkgs makepkg", line(&cmd));
+///
+/// cmd.env_remove("MAKEFLAGS");
+/// assert_eq!(
+///     "cd '/tmp/my build' && env -u MAKEFLAGS PKGDEST=/tmp/pkgs makepkg",
+///     line(&cmd)
+/// );
+/// ```
+pub fn line(cmd: &Command) -> String {
+    let mut words = Vec::new();
+
+    if let Some(dir) = cmd.get_current_dir() {
+        words.push(format!("cd {} &&", quote(&dir.to_string_lossy())));
+    }
+
+    // Variables can only be unset for a single command through `env`.
+    let (set, unset): (Vec<_>, Vec<_>) = cmd.get_envs().partition(|(_, v)| v.is_some());
+    if unset.is_empty().not() {
+        words.push("env".to_string());
+        for (k, _) in unset {
+            words.push(format!("-u {}", quote(&k.to_string_lossy())));
+        }
+    }
+    for (k, v) in set {
+        let v = v.map(|v| v.to_string_lossy()).unwrap_or_default();
+        words.push(format!("{}={}", k.to_string_lossy(), quote(&v)));
+    }
+
+    words.extend(
+        std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|a| quote(&a.to_string_lossy())),
+    );
+    words.join(" ")
+}
+
+/// Quote a word for the shell, if it needs it.
+///
+/// ```
+/// use aura_core::explain::quote;
+///
+/// assert_eq!("-Syu", quote("-Syu"));
+/// assert_eq!("'my disk'", quote("my disk"));
+/// ```
+pub fn quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+
+    if word.is_empty().not() && word.chars().all(plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Print a [`Command`] before running it, from within a chain of its builder
+/// calls.
+pub trait Explain {
+    /// Print this command, if commands are being printed.
+    fn explained(&mut self) -> &mut Self;
+}
+
+impl Explain for Command {
+    fn explained(&mut self) -> &mut Self {
+        show(self);
+        self
+    }
+}
@@ -1,13 +1,18 @@
 //! Core interactions with a [faur](https://git.sr.ht/~fosskers/faur) instance.
 
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 /// The main `faur` instance.
 pub const FAUR_URL: &str = "https://faur.fosskers.ca";
 
+/// The most packages to ask about in a single request, which keeps request URLs
+/// to a length that any server accepts.
+pub const BATCH_SIZE: usize = 100;
+
 /// Package information returned from a `faur` instance. Identical in format to
 /// the AUR's RPC, but yields empty lists for missing fields.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase", deny_unknown_fields)]
 pub struct Package {
     /// Dependencies only necessary for testing.
@@ -84,6 +89,42 @@ where
     fetch(&url)
 }
 
+/// Like [`info`], but for any number of packages, split across as many requests
+/// as necessary. Results are in the order the names were given, and a name
+/// that no package has is paired with `None`. Duplicate names appear once.
+///
+/// ```
+/// use aura_core::faur::{info_batched, Package};
+///
+/// let fetch = |_: &str| -> Result<Vec<Package>, ()> { Ok(Vec::new()) };
+/// let res = info_batched(&["aura", "nonexistent"], &fetch).unwrap();
+///
+/// assert_eq!(2, res.len());
+/// assert!(res.iter().all(|(_, p)| p.is_none()));
+/// ```
+pub fn info_batched<'a, F, E>(
+    pkgs: &[&'a str],
+    fetch: &F,
+) -> Result<Vec<(&'a str, Option<Package>)>, E>
+where
+    F: Fn(&str) -> Result<Vec<Package>, E>,
+{
+    let mut seen = HashSet::new();
+    let names: Vec<&str> = pkgs.iter().copied().filter(|p| seen.insert(*p)).collect();
+    let mut found: HashMap<String, Package> = HashMap::new();
+
+    for chunk in names.chunks(BATCH_SIZE) {
+        found.extend(
+            info(chunk.iter().copied(), fetch)?
+                .into_iter()
+                .map(|p| (p.name.clone(), p)),
+        );
+    }
+
+    let res = names.into_iter().map(|n| (n, found.remove(n))).collect();
+    Ok(res)
+}
+
 /// Look up packages whose names and/or descriptions contain all of the K-many
 /// given terms: `O(klogn)`
 pub fn search<'a, I, F, E>(terms: I, fetch: &F) -> Result<Vec<Package>, E>
@@ -122,4 +163,33 @@ mod test {
 
         assert_eq!(3, pkgs.len());
     }
+
+    #[test]
+    fn batched_order() {
+        let fetch = |url: &str| -> Result<Vec<Package>, ()> {
+            let file = BufReader::new(File::open("tests/faur.json").unwrap());
+            let all: Vec<Package> = serde_json::from_reader(file).unwrap();
+            let asked = url.split_once("names=").unwrap().1;
+
+            Ok(all
+                .into_iter()
+                .filter(|p| asked.split(',').any(|a| a == p.name))
+                .collect())
+        };
+
+        let res = info_batched(&["aura-git", "nope", "aura", "aura-git"], &fetch).unwrap();
+        let names: Vec<_> = res
+            .iter()
+            .map(|(n, p)| (*n, p.as_ref().map(|p| p.name.as_str())))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("aura-git", Some("aura-git")),
+                ("nope", None),
+                ("aura", Some("aura"))
+            ],
+            names
+        );
+    }
 }
@@ -0,0 +1,187 @@
+//! Package file-list internals.
+
+use crate::cache::PkgPath;
+use alpm::{Alpm, SigLevel};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Would the package in the given tarball own the given file, were it
+/// installed?
+///
+/// The file is expected to be an absolute path, like `/usr/bin/aura`.
+pub fn tarball_owns(alpm: &Alpm, tarball: &Path, file: &Path) -> bool {
+    let rel = file.strip_prefix("/").unwrap_or(file);
+
+    tarball
+        .to_str()
+        .and_then(|t| alpm.pkg_load(t, true, SigLevel::USE_DEFAULT).ok())
+        .map(|pkg| {
+            pkg.files()
+                .files()
+                .iter()
+                .any(|f| Path::new(f.name()) == rel)
+        })
+        .unwrap_or(false)
+}
+
+/// Files within a package tarball that already exist on the filesystem, but
+/// which aren't owned by the currently installed version of that same package.
+/// These are the files that would cause a `pacman -U` to fail.
+///
+/// Directories are ignored, since packages may freely share them.
+pub fn conflicts(alpm: &Alpm, tarball: &Path) -> Vec<PathBuf> {
+    let loaded = match tarball
+        .to_str()
+        .and_then(|t| alpm.pkg_load(t, true, SigLevel::USE_DEFAULT).ok())
+    {
+        Some(l) => l,
+        None => return Vec::new(),
+    };
+
+    let db = alpm.localdb();
+    let owned: HashSet<String> = match db.pkg(loaded.name()) {
+        Ok(p) => p
+            .files()
+            .files()
+            .iter()
+            .map(|f| f.name().to_string())
+            .collect(),
+        Err(_) => HashSet::new(),
+    };
+
+    loaded
+        .files()
+        .files()
+        .iter()
+        .map(|f| f.name())
+        .filter(|f| !f.ends_with('/'))
+        .filter(|f| !owned.contains(*f))
+        .map(|f| Path::new("/").join(f))
+        .filter(|p| p.exists() || p.is_symlink())
+        .collect()
+}
+
+/// A searchable index of the files provided by built packages.
+#[derive(Default, Serialize, Deserialize)]
+pub struct FileIndex {
+    /// Tarball filenames, mapped to the contents of that tarball.
+    pub tarballs: HashMap<String, IndexEntry>,
+}
+
+/// The indexed contents of a single package tarball.
+#[derive(Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// The name of the package.
+    pub package: String,
+    /// The version of the package.
+    pub version: String,
+    /// Every non-directory file within the tarball, without a leading `/`.
+    pub files: Vec<String>,
+}
+
+impl FileIndex {
+    /// Attempt to read a `FileIndex` from a JSON file.
+    pub fn from_path(path: &Path) -> Option<FileIndex> {
+        let file = BufReader::new(File::open(path).ok()?);
+        serde_json::from_reader(file).ok()
+    }
+
+    /// Has the given tarball already been indexed?
+    pub fn contains(&self, pp: &PkgPath) -> bool {
+        file_name(pp.as_path())
+            .map(|f| self.tarballs.contains_key(f))
+            .unwrap_or(false)
+    }
+
+    /// Read the file list of a package tarball into the index, replacing any
+    /// other indexed version of the same package. Yields `false` if the tarball
+    /// couldn't be loaded by ALPM.
+    pub fn insert(&mut self, alpm: &Alpm, pp: &PkgPath) -> bool {
+        let loaded = file_name(pp.as_path()).and_then(|f| {
+            pp.as_path()
+                .to_str()
+                .and_then(|t| alpm.pkg_load(t, true, SigLevel::USE_DEFAULT).ok())
+                .map(|l| (f, l))
+        });
+
+        match loaded {
+            None => false,
+            Some((file, pkg)) => {
+                let files = pkg
+                    .files()
+                    .files()
+                    .iter()
+                    .map(|f| f.name())
+                    .filter(|f| !f.ends_with('/'))
+                    .map(|f| f.to_string())
+                    .collect();
+
+                let entry = IndexEntry {
+                    package: pp.as_package().name.to_string(),
+                    version: pp.as_package().version.to_string(),
+                    files,
+                };
+
+                self.tarballs.retain(|_, e| e.package != entry.package);
+                self.tarballs.insert(file.to_string(), entry);
+                true
+            }
+        }
+    }
+
+    /// Find every indexed package that provides a matching file. A query
+    /// containing a `/` must match a full path exactly, while any other query
+    /// is matched against the final component of each path (i.e. the name of
+    /// a binary).
+    pub fn search<'a>(&'a self, query: &'a str) -> impl Iterator<Item = (&'a IndexEntry, &'a str)> {
+        let full = query.contains('/');
+        let query = query.trim_start_matches('/');
+
+        self.tarballs.values().flat_map(move |entry| {
+            entry
+                .files
+                .iter()
+                .filter(move |f| {
+                    if full {
+                        f.as_str() == query
+                    } else {
+                        f.rsplit('/').next() == Some(query)
+                    }
+                })
+                .map(move |f| (entry, f.as_str()))
+        })
+    }
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|f| f.to_str())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn index_search() {
+        let entry = IndexEntry {
+            package: "aura-bin".to_string(),
+            version: "3.2.9-1".to_string(),
+            files: vec![
+                "usr/bin/aura".to_string(),
+                "usr/share/man/man8/aura.8.gz".to_string(),
+            ],
+        };
+        let mut index = FileIndex::default();
+        index
+            .tarballs
+            .insert("aura-bin-3.2.9-1-x86_64.pkg.tar.zst".to_string(), entry);
+
+        assert_eq!(1, index.search("aura").count());
+        assert_eq!(1, index.search("/usr/bin/aura").count());
+        assert_eq!(0, index.search("/usr/bin/aur").count());
+        assert_eq!(0, index.search("bin").count());
+    }
+}
@@ -1,5 +1,7 @@
 //! Generalized `git` interaction.
 
+use crate::explain::Explain;
+use crate::timings::{self, Phase};
 use crate::Apply;
 use from_variants::FromVariants;
 use log::debug;
@@ -20,6 +22,12 @@ pub enum Error {
     /// A git diff failed.
     #[from_variants(skip)]
     Diff(PathBuf),
+    /// A git push failed.
+    #[from_variants(skip)]
+    Push(PathBuf),
+    /// A git checkout failed.
+    #[from_variants(skip)]
+    Checkout(PathBuf),
     /// Converting a git hash to a Rust string failed.
     ReadHash(std::string::FromUtf8Error),
 }
@@ -32,6 +40,33 @@ impl std::fmt::Display for Error {
             Error::Pull(p) => write!(f, "A git pull failed: {}", p.display()),
             Error::ReadHash(e) => write!(f, "Reading a git hash into Rust failed: {e}"),
             Error::Diff(p) => write!(f, "A git diff failed: {}", p.display()),
+            Error::Push(p) => write!(f, "A git push failed: {}", p.display()),
+            Error::Checkout(p) => write!(f, "A git checkout failed: {}", p.display()),
+        }
+    }
+}
+
+impl crate::Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "git-io",
+            Error::Clone(_) => "git-clone",
+            Error::Pull(_) => "git-pull",
+            Error::Diff(_) => "git-diff",
+            Error::Push(_) => "git-push",
+            Error::Checkout(_) => "git-checkout",
+            Error::ReadHash(_) => "git-read-hash",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Clone(p)
+            | Error::Pull(p)
+            | Error::Diff(p)
+            | Error::Push(p)
+            | Error::Checkout(p) => vec![("path", p.display().to_string())],
+            Error::Io(_) | Error::ReadHash(_) => Vec::new(),
         }
     }
 }
@@ -41,6 +76,7 @@ impl std::fmt::Display for Error {
 /// `Path` on the filesystem.
 pub fn shallow_clone(url: &Path, target: &Path) -> Result<(), Error> {
     debug!("Cloning {}", url.display());
+    let _timer = timings::time(Phase::Clones);
 
     Command::new("git")
         .arg("clone")
@@ -49,6 +85,7 @@ pub fn shallow_clone(url: &Path, target: &Path) -> Result<(), Error> {
         .arg(target)
         .stdout(Stdio::null())
         .stderr(Stdio::null())
+        .explained()
         .status()?
         .success()
         .then(|| ())
@@ -62,6 +99,7 @@ pub fn shallow_clone(url: &Path, target: &Path) -> Result<(), Error> {
 /// diverged from the `origin` or this will fail.
 pub fn pull(dir: &Path) -> Result<(), Error> {
     debug!("Pulling {}", dir.display());
+    let _timer = timings::time(Phase::Clones);
 
     Command::new("git")
         .arg("pull")
@@ -70,6 +108,7 @@ pub fn pull(dir: &Path) -> Result<(), Error> {
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .current_dir(dir)
+        .explained()
         .status()?
         .success()
         .then(|| ())
@@ -85,6 +124,7 @@ pub fn hash(dir: &Path) -> Result<String, Error> {
         .arg("rev-parse")
         .arg("HEAD")
         .current_dir(dir)
+        .explained()
         .output()?
         .stdout
         .apply(String::from_utf8)
@@ -99,8 +139,120 @@ pub fn diff(dir: &Path, hash: &str) -> Result<(), Error> {
         .arg("diff")
         .arg(hash)
         .current_dir(dir)
+        .explained()
         .status()?
         .success()
         .then(|| ())
         .ok_or_else(|| Error::Diff(dir.to_path_buf()))
 }
+
+/// Does the given repo have no uncommitted changes, including untracked files?
+pub fn is_clean(dir: &Path) -> Result<bool, Error> {
+    debug!("git status: {}", dir.display());
+
+    let out = Command::new("git")
+        .arg("status")
+        .arg("--porcelain")
+        .current_dir(dir)
+        .explained()
+        .output()?;
+
+    Ok(out.status.success() && out.stdout.is_empty())
+}
+
+/// The URL that the `origin` remote of a given repo points to, if any.
+pub fn remote_url(dir: &Path) -> Option<String> {
+    Command::new("git")
+        .arg("remote")
+        .arg("get-url")
+        .arg("origin")
+        .current_dir(dir)
+        .explained()
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|url| url.trim().to_string())
+}
+
+/// Push the current branch to `origin`.
+pub fn push(dir: &Path) -> Result<(), Error> {
+    debug!("git push: {}", dir.display());
+
+    Command::new("git")
+        .arg("push")
+        .arg("origin")
+        .arg("HEAD:master")
+        .current_dir(dir)
+        .explained()
+        .status()?
+        .success()
+        .then(|| ())
+        .ok_or_else(|| Error::Push(dir.to_path_buf()))
+}
+
+/// Make a full copy of a local repository, history included.
+pub fn local_clone(source: &Path, target: &Path) -> Result<(), Error> {
+    debug!("Cloning {} to {}", source.display(), target.display());
+
+    Command::new("git")
+        .arg("clone")
+        .arg("--quiet")
+        .arg("--no-hardlinks")
+        .arg(source)
+        .arg(target)
+        .explained()
+        .status()?
+        .success()
+        .then(|| ())
+        .ok_or_else(|| Error::Clone(source.to_path_buf()))
+}
+
+/// Check out a specific commit of a given repo.
+pub fn checkout(dir: &Path, hash: &str) -> Result<(), Error> {
+    debug!("git checkout {}: {}", hash, dir.display());
+
+    Command::new("git")
+        .arg("checkout")
+        .arg("--quiet")
+        .arg(hash)
+        .current_dir(dir)
+        .explained()
+        .status()?
+        .success()
+        .then(|| ())
+        .ok_or_else(|| Error::Checkout(dir.to_path_buf()))
+}
+
+/// The one-line summaries of upstream commits made since a given hash, newest
+/// first. Fetches from `origin` beforehand, but leaves the working tree alone.
+///
+/// Yields `None` if the hash isn't known to the repo, as can happen with
+/// shallow clones.
+pub fn log_since(dir: &Path, hash: &str) -> Option<Vec<String>> {
+    debug!("git log {}: {}", hash, dir.display());
+
+    // Failing to fetch (say, while offline) still leaves the last known state
+    // of the upstream branch to compare against.
+    let _ = Command::new("git")
+        .arg("fetch")
+        .arg("--quiet")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .current_dir(dir)
+        .explained()
+        .status();
+
+    Command::new("git")
+        .arg("log")
+        .arg("--oneline")
+        .arg(format!("{hash}..@{{upstream}}"))
+        .current_dir(dir)
+        .stderr(Stdio::null())
+        .explained()
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|log| log.lines().map(|l| l.to_string()).collect())
+}
@@ -0,0 +1,98 @@
+//! Shell-style wildcard patterns, for selecting many packages at once.
+
+use std::ops::Not;
+
+/// Does the given string contain any wildcard characters?
+///
+/// ```
+/// use aura_core::glob::is_glob;
+///
+/// assert!(is_glob("linux-*"));
+/// assert!(is_glob("python-?ip"));
+/// assert!(!is_glob("linux"));
+/// ```
+pub fn is_glob(s: &str) -> bool {
+    s.contains(['*', '?', '['])
+}
+
+/// Does a name match a wildcard pattern? Supports `*` (any run of characters),
+/// `?` (any single character), and bracketed classes like `[abc]`, `[a-z]`, or
+/// the negated `[!abc]`.
+///
+/// ```
+/// use aura_core::glob::matches;
+///
+/// assert!(matches("linux-*", "linux-lts"));
+/// assert!(matches("linux-*", "linux-"));
+/// assert!(!matches("linux-*", "linux"));
+/// assert!(matches("python-?ip", "python-pip"));
+/// assert!(matches("lib[0-9]*", "lib32-glibc"));
+/// assert!(!matches("lib[!0-9]*", "lib32-glibc"));
+/// assert!(matches("*-git", "aura-git"));
+/// ```
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    matches_work(&p, &n)
+}
+
+fn matches_work(p: &[char], n: &[char]) -> bool {
+    match p.first() {
+        None => n.is_empty(),
+        // Try every possible length of the run that the star consumes.
+        Some('*') => (0..=n.len()).any(|i| matches_work(&p[1..], &n[i..])),
+        Some('?') => n.is_empty().not() && matches_work(&p[1..], &n[1..]),
+        Some('[') => match (class(&p[1..]), n.first()) {
+            (Some(class), Some(c)) => class.contains(*c) && matches_work(class.rest, &n[1..]),
+            // An unclosed bracket is just a bracket.
+            (None, Some('[')) => matches_work(&p[1..], &n[1..]),
+            _ => false,
+        },
+        Some(c) => n.first() == Some(c) && matches_work(&p[1..], &n[1..]),
+    }
+}
+
+/// A bracketed character class, like `[a-z]`.
+struct Class<'a> {
+    negated: bool,
+    members: &'a [char],
+    /// The remainder of the pattern after the class.
+    rest: &'a [char],
+}
+
+impl Class<'_> {
+    fn contains(&self, c: char) -> bool {
+        let m = self.members;
+        let mut i = 0;
+        let mut found = false;
+
+        while i < m.len() {
+            if i + 2 < m.len() && m[i + 1] == '-' {
+                found |= m[i] <= c && c <= m[i + 2];
+                i += 3;
+            } else {
+                found |= m[i] == c;
+                i += 1;
+            }
+        }
+
+        found != self.negated
+    }
+}
+
+/// Parse a character class that follows an opening `[`.
+fn class(p: &[char]) -> Option<Class<'_>> {
+    let (negated, body) = match p.first() {
+        Some('!') | Some('^') => (true, &p[1..]),
+        _ => (false, p),
+    };
+
+    // A `]` right at the start is a literal member of the class.
+    let close = body.iter().skip(1).position(|c| *c == ']')? + 1;
+
+    Some(Class {
+        negated,
+        members: &body[..close],
+        rest: &body[close + 1..],
+    })
+}
@@ -6,10 +6,20 @@
 pub mod aur;
 pub mod cache;
 pub mod deps;
+pub mod explain;
 pub mod faur;
+pub mod files;
 pub mod git;
+pub mod glob;
+pub mod listing;
 pub mod log;
+pub mod metadata;
+pub mod provenance;
+pub mod query;
 pub mod snapshot;
+pub mod template;
+pub mod timings;
+pub mod version;
 
 use std::borrow::Cow;
 use std::cmp::Ordering;
@@ -76,7 +86,7 @@ impl<'a> Package<'a> {
     /// Does some given version string have the same value as the one in this
     /// `Package`?
     pub fn same_version(&self, other: &str) -> bool {
-        match alpm::vercmp(other, &self.version) {
+        match version::cmp(other, &self.version) {
             Ordering::Equal => true,
             Ordering::Less | Ordering::Greater => false,
         }
@@ -104,7 +114,7 @@ impl<'a> PartialOrd for Package<'a> {
 impl<'a> Ord for Package<'a> {
     fn cmp(&self, other: &Self) -> Ordering {
         match self.name.cmp(&other.name) {
-            Ordering::Equal => alpm::vercmp(self.version.as_ref(), other.version.as_ref()),
+            Ordering::Equal => version::cmp(self.version.as_ref(), other.version.as_ref()),
             otherwise => otherwise,
         }
     }
@@ -121,6 +131,18 @@ where
         .flatten()
 }
 
+/// An error that can identify itself to machines, not just to humans.
+pub trait Coded {
+    /// A short identifier for this kind of failure, like `git-clone`. These
+    /// are stable across releases and translations.
+    fn code(&self) -> &'static str;
+
+    /// Details of what failed, like the package, path, or URL involved.
+    fn context(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+
 /// Apply functions in method-position.
 pub trait Apply {
     /// Apply a given function in method-position.
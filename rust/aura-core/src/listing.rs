@@ -0,0 +1,375 @@
+//! Sorting and filtering the rows of any listing by their fields.
+//!
+//! Every listing names the fields its rows have, and yields the value of each
+//! as text. Values that are both numbers are compared as numbers, and all
+//! others as text, so that `votes > 100` and `name ~ 'python-*'` both work
+//! without the listing having to say which is which. A `version` is always
+//! compared as a package version, so that `1.10` comes after `1.9`.
+//!
+//! A filter is one or more comparisons joined by `and`, using the operators of
+//! [`crate::query`]. Values may be quoted, as in `desc ~ '*this and that*'`, and
+//! an `and` within quotes is part of the value. A sort is a field name,
+//! descending if it begins with `-`.
+//!
+//! ```
+//! use aura_core::listing::Listing;
+//!
+//! let rows = vec![("aura", "120"), ("paru", "900"), ("yay", "2000")];
+//! let value = |r: &(&str, &str), f: &str| match f {
+//!     "name" => r.0.to_string(),
+//!     _ => r.1.to_string(),
+//! };
+//!
+//! let l = Listing::new(Some("-votes"), Some("votes < 1000"), &["name", "votes"]).unwrap();
+//! let names: Vec<_> = l.apply(rows, value).into_iter().map(|r| r.0).collect();
+//!
+//! assert_eq!(vec!["paru", "aura"], names);
+//! ```
+
+use crate::glob;
+use crate::query::Op;
+use std::cmp::Ordering;
+use std::ops::Not;
+
+/// A problem with a sort or a filter.
+#[derive(Debug)]
+pub enum Error {
+    /// A comparison in a filter couldn't be read.
+    BadClause(String),
+    /// A field that isn't available, alongside those that are.
+    Unknown(String, Vec<String>),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::BadClause(c) => write!(f, "Not a comparison: {c}"),
+            Error::Unknown(field, known) => {
+                write!(
+                    f,
+                    "Can't sort or filter by {field}, only by: {}",
+                    known.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl crate::Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::BadClause(_) => "listing-bad-clause",
+            Error::Unknown(_, _) => "listing-unknown",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::BadClause(c) => vec![("clause", c.clone())],
+            Error::Unknown(field, _) => vec![("field", field.clone())],
+        }
+    }
+}
+
+/// A comparison of one field against a value.
+#[derive(Debug, PartialEq, Eq)]
+struct Clause {
+    field: String,
+    op: Op,
+    value: String,
+}
+
+impl Clause {
+    fn parse(raw: &str) -> Option<Clause> {
+        let is_op = |c: char| "=!<>~".contains(c);
+        let start = raw.find(is_op)?;
+        let len = raw[start..]
+            .find(|c| is_op(c).not())
+            .unwrap_or(raw.len() - start);
+
+        let field = raw[..start].trim();
+        let op = Op::parse(&raw[start..start + len])?;
+        let value = raw[start + len..].trim();
+        let value = value
+            .strip_prefix('\'')
+            .and_then(|v| v.strip_suffix('\''))
+            .unwrap_or(value);
+
+        (field.is_empty() || value.is_empty())
+            .not()
+            .then(|| Clause {
+                field: field.to_string(),
+                op,
+                value: value.to_string(),
+            })
+    }
+
+    fn holds(&self, actual: &str) -> bool {
+        let ord = compare_field(&self.field, actual, &self.value);
+
+        match self.op {
+            Op::Eq => ord == Ordering::Equal,
+            Op::Ne => ord != Ordering::Equal,
+            Op::Lt => ord == Ordering::Less,
+            Op::Le => ord != Ordering::Greater,
+            Op::Gt => ord == Ordering::Greater,
+            Op::Ge => ord != Ordering::Less,
+            Op::Like => glob::matches(&self.value, actual),
+        }
+    }
+}
+
+/// Split a filter into its comparisons, at each `and` that isn't quoted.
+fn clauses(filter: &str) -> Vec<&str> {
+    let bytes = filter.as_bytes();
+    let mut found = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\'' {
+            quoted = quoted.not();
+            i += 1;
+        } else if quoted.not() && bytes[i..].starts_with(b" and ") {
+            found.push(&filter[start..i]);
+            i += " and ".len();
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+
+    found.push(&filter[start..]);
+    found
+}
+
+/// Compare two values, as numbers if they both are.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+/// Compare two values of the given field. Versions are compared as `pacman`
+/// would, and everything else by [`compare`].
+fn compare_field(field: &str, a: &str, b: &str) -> Ordering {
+    match field {
+        "version" => crate::version::cmp(a, b),
+        _ => compare(a, b),
+    }
+}
+
+/// How to sort and filter the rows of a listing.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Listing {
+    sort: Option<(String, bool)>,
+    filter: Vec<Clause>,
+}
+
+impl Listing {
+    /// Read a sort and a filter, whose fields must all be among those given.
+    pub fn new(sort: Option<&str>, filter: Option<&str>, known: &[&str]) -> Result<Listing, Error> {
+        let check = |field: &str| {
+            if known.contains(&field) {
+                Ok(field.to_string())
+            } else {
+                let known = known.iter().map(|k| k.to_string()).collect();
+                Err(Error::Unknown(field.to_string(), known))
+            }
+        };
+
+        let sort = sort
+            .map(|s| match s.strip_prefix('-') {
+                Some(f) => check(f).map(|f| (f, true)),
+                None => check(s).map(|f| (f, false)),
+            })
+            .transpose()?;
+
+        let filter = filter
+            .map(|f| {
+                clauses(f)
+                    .into_iter()
+                    .map(|c| {
+                        let clause =
+                            Clause::parse(c).ok_or_else(|| Error::BadClause(c.to_string()))?;
+                        check(&clause.field)?;
+                        Ok(clause)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Listing { sort, filter })
+    }
+
+    /// Keep the rows that pass the filter, in the order of the sort. Without a
+    /// sort, rows keep the order they came in.
+    pub fn apply<T, F>(&self, rows: Vec<T>, value: F) -> Vec<T>
+    where
+        F: Fn(&T, &str) -> String,
+    {
+        let rows = rows
+            .into_iter()
+            .filter(|r| self.filter.iter().all(|c| c.holds(&value(r, &c.field))));
+
+        match self.sort.as_ref() {
+            None => rows.collect(),
+            Some((field, descending)) => {
+                let mut keyed: Vec<(String, T)> = rows.map(|r| (value(&r, field), r)).collect();
+                keyed.sort_by(|(a, _), (b, _)| {
+                    if *descending {
+                        compare_field(field, b, a)
+                    } else {
+                        compare_field(field, a, b)
+                    }
+                });
+                keyed.into_iter().map(|(_, r)| r).collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn clause(field: &str, op: Op, value: &str) -> Clause {
+        Clause {
+            field: field.to_string(),
+            op,
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(
+            Some(clause("votes", Op::Eq, "5")),
+            Clause::parse("votes = 5")
+        );
+        assert_eq!(
+            Some(clause("votes", Op::Eq, "5")),
+            Clause::parse("votes==5")
+        );
+        assert_eq!(
+            Some(clause("votes", Op::Ne, "5")),
+            Clause::parse("votes != 5")
+        );
+        assert_eq!(
+            Some(clause("votes", Op::Le, "5")),
+            Clause::parse("votes <= 5")
+        );
+        assert_eq!(
+            Some(clause("votes", Op::Ge, "5")),
+            Clause::parse("votes >= 5")
+        );
+        assert_eq!(
+            Some(clause("name", Op::Like, "py*")),
+            Clause::parse("name ~ py*")
+        );
+        assert_eq!(None, Clause::parse("votes 5"));
+        assert_eq!(None, Clause::parse("votes =< 5"));
+        assert_eq!(None, Clause::parse("= 5"));
+        assert_eq!(None, Clause::parse("votes >"));
+
+        assert!(clause("votes", Op::Lt, "100").holds("20"));
+        assert!(clause("votes", Op::Gt, "100").holds("20").not());
+        assert!(clause("name", Op::Lt, "b").holds("a"));
+        assert!(clause("votes", Op::Ne, "5").holds("5.0").not());
+        assert!(clause("name", Op::Like, "python-*").holds("python-requests"));
+        assert!(clause("version", Op::Gt, "1.9").holds("1.10"));
+        assert!(clause("version", Op::Lt, "1:0.1-1").holds("2.0-1"));
+        assert!(clause("version", Op::Eq, "1.0").holds("1.0"));
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(
+            Some(clause("desc", Op::Like, "*a tool*")),
+            Clause::parse("desc ~ '*a tool*'")
+        );
+        assert_eq!(
+            Some(clause("name", Op::Eq, "'aura")),
+            Clause::parse("name = 'aura")
+        );
+        assert_eq!(
+            Some(clause("desc", Op::Eq, "a <= b")),
+            Clause::parse("desc = 'a <= b'")
+        );
+    }
+
+    #[test]
+    fn splitting() {
+        assert_eq!(vec!["a = 1", "b = 2"], clauses("a = 1 and b = 2"));
+        assert_eq!(vec!["name = brand"], clauses("name = brand"));
+        assert_eq!(
+            vec!["desc ~ '*this and that*'", "votes > 1"],
+            clauses("desc ~ '*this and that*' and votes > 1")
+        );
+
+        let l = Listing::new(
+            None,
+            Some("desc = 'rock and roll' and votes > 1"),
+            &["desc", "votes"],
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                clause("desc", Op::Eq, "rock and roll"),
+                clause("votes", Op::Gt, "1")
+            ],
+            l.filter
+        );
+    }
+
+    #[test]
+    fn fields_checked() {
+        let known = ["name", "votes"];
+
+        assert!(matches!(
+            Listing::new(Some("-size"), None, &known),
+            Err(Error::Unknown(f, _)) if f == "size"
+        ));
+        assert!(matches!(
+            Listing::new(None, Some("name = aura and size > 1"), &known),
+            Err(Error::Unknown(f, _)) if f == "size"
+        ));
+        assert!(matches!(
+            Listing::new(None, Some("name aura"), &known),
+            Err(Error::BadClause(c)) if c == "name aura"
+        ));
+    }
+
+    #[test]
+    fn sorting() {
+        let rows = vec![("b", "10"), ("a", "9"), ("c", "100")];
+        let value = |r: &(&str, &str), f: &str| match f {
+            "name" => r.0.to_string(),
+            _ => r.1.to_string(),
+        };
+        let names = |l: Listing| -> Vec<&'static str> {
+            l.apply(rows.clone(), value)
+                .into_iter()
+                .map(|r| r.0)
+                .collect()
+        };
+
+        let known = ["name", "votes"];
+        assert_eq!(
+            vec!["a", "b", "c"],
+            names(Listing::new(Some("votes"), None, &known).unwrap())
+        );
+        assert_eq!(
+            vec!["c", "b", "a"],
+            names(Listing::new(Some("-name"), None, &known).unwrap())
+        );
+        assert_eq!(vec!["b", "a", "c"], names(Listing::default()));
+        assert_eq!(
+            vec!["c"],
+            names(Listing::new(None, Some("votes >= 100 and name ~ '?'"), &known).unwrap())
+        );
+    }
+}
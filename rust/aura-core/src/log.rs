@@ -1,9 +1,99 @@
 //! Log manipulation internals.
 
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
+/// Why a line of the Pacman log isn't an action on a package.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The line doesn't begin with a bracketed timestamp.
+    NoTimestamp,
+    /// The line was written by something other than ALPM itself, like a
+    /// scriptlet or `pacman`.
+    NotAlpm,
+    /// There was no action and package name.
+    NoPackage,
+    /// The package name wasn't followed by its versions in parentheses.
+    NoVersions,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NoTimestamp => write!(f, "No timestamp"),
+            Error::NotAlpm => write!(f, "Not an ALPM line"),
+            Error::NoPackage => write!(f, "No action or package"),
+            Error::NoVersions => write!(f, "No versions"),
+        }
+    }
+}
+
+impl crate::Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::NoTimestamp => "log-no-timestamp",
+            Error::NotAlpm => "log-not-alpm",
+            Error::NoPackage => "log-no-package",
+            Error::NoVersions => "log-no-versions",
+        }
+    }
+}
+
+/// A line of the Pacman log recording an action on a package.
+///
+/// ```
+/// use aura_core::log::{Action, Error};
+///
+/// let line = "[2022-08-01T10:00:00-0700] [ALPM] upgraded aura (3.2.9-1 -> 3.2.10-1)";
+/// let a = Action::parse(line).unwrap();
+/// assert_eq!("2022-08-01T10:00:00-0700", a.time);
+/// assert_eq!("upgraded", a.verb);
+/// assert_eq!("aura", a.package);
+/// assert_eq!("3.2.9-1 -> 3.2.10-1", a.versions);
+///
+/// let line = "[2022-08-01T10:00:01-0700] [ALPM-SCRIPTLET] ==> Done (aura)";
+/// assert_eq!(Err(Error::NotAlpm), Action::parse(line).map(|_| ()));
+/// assert_eq!(Err(Error::NoTimestamp), Action::parse("[2022").map(|_| ()));
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct Action<'a> {
+    /// When it happened, as written.
+    pub time: &'a str,
+    /// What happened, like `installed` or `upgraded`.
+    pub verb: &'a str,
+    /// The name of the package it happened to.
+    pub package: &'a str,
+    /// The version involved, or the old and new versions of an upgrade.
+    pub versions: &'a str,
+}
+
+impl<'a> Action<'a> {
+    /// Parse a single line of the log.
+    pub fn parse(line: &'a str) -> Result<Action<'a>, Error> {
+        let (time, rest) = line
+            .strip_prefix('[')
+            .and_then(|l| l.split_once("] "))
+            .ok_or(Error::NoTimestamp)?;
+        let rest = rest.strip_prefix("[ALPM] ").ok_or(Error::NotAlpm)?;
+        let (verb, rest) = rest.split_once(' ').ok_or(Error::NoPackage)?;
+        let (package, versions) = rest.split_once(" (").ok_or(Error::NoVersions)?;
+        let versions = versions.strip_suffix(')').ok_or(Error::NoVersions)?;
+
+        if verb.is_empty() || package.is_empty() || package.contains(' ') {
+            return Err(Error::NoPackage);
+        }
+
+        Ok(Action {
+            time,
+            verb,
+            package,
+            versions,
+        })
+    }
+}
+
 /// A collation of information about log entries for a particular package.
 #[derive(Debug)]
 pub struct LogEntry {
@@ -22,26 +112,39 @@ pub struct LogEntry {
 pub fn info(path: &Path, package: String) -> Option<LogEntry> {
     let file = File::open(path).ok()?;
     let read = BufReader::new(file);
-    let patt = format!(" {} (", package);
     let hits = read
         .lines()
         .filter_map(|line| line.ok())
-        .filter(|line| line.contains(&patt))
+        .filter(|line| Action::parse(line).map(|a| a.package == package) == Ok(true))
         .collect::<Vec<_>>();
 
-    if hits.is_empty() {
-        None
-    } else {
-        let installed = hits[0].chars().skip(1).take(16).collect();
-        let upgrades = hits.iter().filter(|l| l.contains(" upgraded ")).count();
-        let len = if hits.len() < 6 { 0 } else { hits.len() - 5 };
-        let recent = hits.into_iter().skip(len).collect();
-        let entry = LogEntry {
-            package,
-            installed,
-            upgrades,
-            recent,
-        };
-        Some(entry)
-    }
+    let first = hits.first().and_then(|l| Action::parse(l).ok())?;
+    let installed = first.time.chars().take(16).collect();
+    let upgrades = hits
+        .iter()
+        .filter(|l| Action::parse(l).map(|a| a.verb == "upgraded") == Ok(true))
+        .count();
+    let len = if hits.len() < 6 { 0 } else { hits.len() - 5 };
+    let recent = hits.into_iter().skip(len).collect();
+    let entry = LogEntry {
+        package,
+        installed,
+        upgrades,
+        recent,
+    };
+    Some(entry)
+}
+
+/// The names of every package that the Pacman log mentions, including those
+/// that have since been removed.
+pub fn packages(path: &Path) -> HashSet<String> {
+    File::open(path)
+        .map(|file| {
+            BufReader::new(file)
+                .lines()
+                .filter_map(|line| line.ok())
+                .filter_map(|line| Action::parse(&line).ok().map(|a| a.package.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
 }
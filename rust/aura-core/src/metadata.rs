@@ -0,0 +1,121 @@
+//! Reading package metadata written by strangers.
+//!
+//! The `.SRCINFO` of an AUR package is whatever its maintainer pushed, and Aura
+//! reads it before anyone has reviewed it. Parsing it is therefore bounded:
+//! files beyond any sane size are refused outright, before anything is read
+//! into memory. The parser itself is exercised by the fuzz targets in
+//! `aura-core/fuzz`, since release builds abort on a panic rather than unwind.
+//!
+//! ```
+//! use aura_core::metadata::{srcinfo_from, Error, MAX_SRCINFO};
+//! use std::path::Path;
+//!
+//! let raw = "pkgbase = aura-bin
+//! \tpkgver = 3.2.10
+//! \tpkgrel = 1
+//! \tarch = x86_64
+//!
+//! pkgname = aura-bin
+//! ";
+//!
+//! let path = Path::new(".SRCINFO");
+//! let info = srcinfo_from(path, raw.as_bytes()).unwrap();
+//! assert_eq!("aura-bin", info.base.pkgbase);
+//!
+//! let huge = vec![b'#'; MAX_SRCINFO as usize + 1];
+//! assert!(matches!(srcinfo_from(path, &huge), Err(Error::TooLarge(_, _))));
+//! ```
+
+use srcinfo::Srcinfo;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// The largest `.SRCINFO` that will be read, in bytes. Even those of packages
+/// that split into dozens of others are far smaller.
+pub const MAX_SRCINFO: u64 = 1024 * 1024;
+
+/// A `.SRCINFO` that couldn't be read.
+#[derive(Debug)]
+pub enum Error {
+    /// The file couldn't be opened or read.
+    Io(PathBuf, std::io::Error),
+    /// The file is larger than any real `.SRCINFO`, given its size.
+    TooLarge(PathBuf, u64),
+    /// The file isn't valid `.SRCINFO`.
+    Malformed(PathBuf, srcinfo::Error),
+}
+
+impl Error {
+    /// The file that couldn't be read.
+    pub fn path(&self) -> &Path {
+        match self {
+            Error::Io(p, _) => p,
+            Error::TooLarge(p, _) => p,
+            Error::Malformed(p, _) => p,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(_, e) => write!(f, "{e}"),
+            Error::TooLarge(_, size) => {
+                write!(f, "{size} bytes is too large, beyond {MAX_SRCINFO}")
+            }
+            Error::Malformed(_, e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl crate::Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Io(_, _) => "metadata-io",
+            Error::TooLarge(_, _) => "metadata-too-large",
+            Error::Malformed(_, _) => "metadata-malformed",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::TooLarge(p, size) => {
+                vec![
+                    ("path", p.display().to_string()),
+                    ("size", size.to_string()),
+                ]
+            }
+            e => vec![("path", e.path().display().to_string())],
+        }
+    }
+}
+
+/// Read and parse the `.SRCINFO` at the given path.
+pub fn srcinfo(path: &Path) -> Result<Srcinfo, Error> {
+    let io = |e| Error::Io(path.to_path_buf(), e);
+    let file = std::fs::File::open(path).map_err(io)?;
+    let size = file.metadata().map_err(io)?.len();
+
+    if size > MAX_SRCINFO {
+        return Err(Error::TooLarge(path.to_path_buf(), size));
+    }
+
+    // The file could still grow between the check and the read.
+    let mut bytes = Vec::with_capacity(size as usize);
+    file.take(MAX_SRCINFO + 1)
+        .read_to_end(&mut bytes)
+        .map_err(io)?;
+
+    srcinfo_from(path, &bytes)
+}
+
+/// Parse the contents of a `.SRCINFO`, which came from the given path.
+pub fn srcinfo_from(path: &Path, bytes: &[u8]) -> Result<Srcinfo, Error> {
+    let size = bytes.len() as u64;
+
+    if size > MAX_SRCINFO {
+        return Err(Error::TooLarge(path.to_path_buf(), size));
+    }
+
+    Srcinfo::parse_buf(bytes).map_err(|e| Error::Malformed(path.to_path_buf(), e))
+}
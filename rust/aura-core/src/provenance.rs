@@ -0,0 +1,73 @@
+//! Records of how Aura-built packages came to be.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// How each installed AUR package was built, keyed by package name.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Package names, mapped to the record of their latest build.
+    pub packages: HashMap<String, Record>,
+}
+
+/// The circumstances of a single build.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    /// The package base this package was built from.
+    pub pkgbase: String,
+    /// The version that was built.
+    pub version: String,
+    /// The hash of the AUR repo's commit that was built.
+    pub commit: String,
+    /// When the build finished, in seconds since the Unix epoch.
+    pub built: u64,
+    /// The flags `makepkg` was called with.
+    pub flags: Vec<String>,
+    /// The name of the machine that did the building.
+    pub host: String,
+    /// Does the PKGBUILD pull its sources from a version control system?
+    #[serde(default)]
+    pub vcs: bool,
+    /// The path or URL of the tarball, for packages that weren't built by Aura
+    /// but installed directly with `-U`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl Provenance {
+    /// Attempt to read a `Provenance` database from a JSON file.
+    pub fn from_path(path: &Path) -> Option<Provenance> {
+        let file = BufReader::new(File::open(path).ok()?);
+        serde_json::from_reader(file).ok()
+    }
+
+    /// The record of the latest build of a package, if there is one.
+    pub fn get(&self, package: &str) -> Option<&Record> {
+        self.packages.get(package)
+    }
+
+    /// Record a new build of a package, replacing any previous one.
+    pub fn insert(&mut self, package: String, record: Record) {
+        self.packages.insert(package, record);
+    }
+}
+
+/// Is the given `source` entry of a PKGBUILD fetched via version control?
+///
+/// ```
+/// use aura_core::provenance::is_vcs_source;
+///
+/// assert!(is_vcs_source("aura::git+https://github.com/fosskers/aura.git"));
+/// assert!(is_vcs_source("svn+https://example.org/trunk"));
+/// assert!(!is_vcs_source("https://example.org/aura-3.2.9.tar.gz"));
+/// ```
+pub fn is_vcs_source(source: &str) -> bool {
+    let url = source.split_once("::").map(|(_, u)| u).unwrap_or(source);
+
+    ["git+", "hg+", "svn+", "bzr+", "fossil+"]
+        .iter()
+        .any(|prefix| url.starts_with(prefix))
+}
@@ -0,0 +1,461 @@
+//! A small expression language for selecting sets of packages.
+//!
+//! An expression combines facts about a package with `and`, `or`, `not`, and
+//! parentheses:
+//!
+//! - Flags: `installed`, `foreign`, `orphan`, `explicit`, `dependency`
+//! - Names and repos: `name == aura`, `name ~ 'python-*'`, `repo != extra`
+//! - Sizes: `size > 100MB` (or `KB`, `GB`, `KiB`, `MiB`, `GiB`)
+//! - Ages: `older-than 1y`, `newer-than 2w` (or `d`, `m`)
+//!
+//! ```
+//! use aura_core::query::{Expr, Facts};
+//!
+//! let expr = Expr::parse("foreign and (size > 1MB or name ~ '*-git')").unwrap();
+//! let facts = Facts {
+//!     name: "aura-git",
+//!     repo: None,
+//!     installed: true,
+//!     foreign: true,
+//!     orphan: false,
+//!     explicit: true,
+//!     size: 512,
+//!     date: 0,
+//! };
+//!
+//! assert!(expr.eval(&facts, 0));
+//! ```
+
+use crate::glob;
+use std::iter::Peekable;
+use std::ops::Not;
+use std::str::Chars;
+
+const SECS_IN_DAY: i64 = 60 * 60 * 24;
+
+/// A problem in the text of an expression.
+#[derive(Debug)]
+pub enum Error {
+    /// The expression ended too early.
+    End,
+    /// Some token didn't belong where it was found.
+    Unexpected(String),
+    /// A field was compared in a way that doesn't make sense for it.
+    BadOperator(String, String),
+    /// A size like `100MB` couldn't be read.
+    BadSize(String),
+    /// A duration like `2w` couldn't be read.
+    BadDuration(String),
+    /// A quoted string was never closed.
+    Unclosed,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::End => write!(f, "Unexpected end of expression"),
+            Error::Unexpected(t) => write!(f, "Unexpected token: {t}"),
+            Error::BadOperator(field, op) => write!(f, "{field} can't be compared with {op}"),
+            Error::BadSize(s) => write!(f, "Not a size: {s}"),
+            Error::BadDuration(d) => write!(f, "Not a duration: {d}"),
+            Error::Unclosed => write!(f, "Unclosed quote"),
+        }
+    }
+}
+
+impl crate::Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::End => "query-end",
+            Error::Unexpected(_) => "query-unexpected",
+            Error::BadOperator(_, _) => "query-bad-operator",
+            Error::BadSize(_) => "query-bad-size",
+            Error::BadDuration(_) => "query-bad-duration",
+            Error::Unclosed => "query-unclosed",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Unexpected(t) => vec![("token", t.clone())],
+            Error::BadOperator(field, op) => {
+                vec![("field", field.clone()), ("operator", op.clone())]
+            }
+            Error::BadSize(t) | Error::BadDuration(t) => vec![("token", t.clone())],
+            Error::End | Error::Unclosed => Vec::new(),
+        }
+    }
+}
+
+/// Everything an expression can ask about a package.
+#[derive(Debug)]
+pub struct Facts<'a> {
+    /// The package's name.
+    pub name: &'a str,
+    /// The sync database the package is from, if any.
+    pub repo: Option<&'a str>,
+    /// Is the package installed?
+    pub installed: bool,
+    /// Is the package installed, but not in any sync database?
+    pub foreign: bool,
+    /// Was the package installed as a dependency that nothing needs anymore?
+    pub orphan: bool,
+    /// Was the package installed explicitly?
+    pub explicit: bool,
+    /// The installed size, in bytes.
+    pub size: i64,
+    /// When the package was installed (or built, if not installed), in seconds
+    /// since the Unix epoch.
+    pub date: i64,
+}
+
+/// A boolean property of some package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    /// The package is installed.
+    Installed,
+    /// The package is installed, but not from any sync database.
+    Foreign,
+    /// The package is an unneeded dependency.
+    Orphan,
+    /// The package was installed explicitly.
+    Explicit,
+    /// The package was installed as a dependency.
+    Dependency,
+}
+
+/// A way to compare two values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `~`, a wildcard match.
+    Like,
+}
+
+impl Op {
+    pub(crate) fn parse(s: &str) -> Option<Op> {
+        match s {
+            "==" | "=" => Some(Op::Eq),
+            "!=" => Some(Op::Ne),
+            "<" => Some(Op::Lt),
+            "<=" => Some(Op::Le),
+            ">" => Some(Op::Gt),
+            ">=" => Some(Op::Ge),
+            "~" => Some(Op::Like),
+            _ => None,
+        }
+    }
+
+    fn text(&self, pattern: &str, value: &str) -> bool {
+        match self {
+            Op::Eq => pattern == value,
+            Op::Ne => pattern != value,
+            Op::Like => glob::matches(pattern, value),
+            Op::Lt => value < pattern,
+            Op::Le => value <= pattern,
+            Op::Gt => value > pattern,
+            Op::Ge => value >= pattern,
+        }
+    }
+
+    fn number(&self, target: i64, value: i64) -> bool {
+        match self {
+            Op::Eq | Op::Like => value == target,
+            Op::Ne => value != target,
+            Op::Lt => value < target,
+            Op::Le => value <= target,
+            Op::Gt => value > target,
+            Op::Ge => value >= target,
+        }
+    }
+}
+
+/// A parsed expression.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Expr {
+    /// Both must hold.
+    And(Box<Expr>, Box<Expr>),
+    /// Either must hold.
+    Or(Box<Expr>, Box<Expr>),
+    /// The inner expression must not hold.
+    Not(Box<Expr>),
+    /// Some boolean property holds.
+    Flag(Flag),
+    /// A comparison against the package's name.
+    Name(Op, String),
+    /// A comparison against the package's repo.
+    Repo(Op, String),
+    /// A comparison against the package's installed size, in bytes.
+    Size(Op, i64),
+    /// Installed (or built) longer ago than this many seconds.
+    OlderThan(i64),
+    /// Installed (or built) more recently than this many seconds ago.
+    NewerThan(i64),
+}
+
+impl Expr {
+    /// Parse an expression from its text.
+    ///
+    /// ```
+    /// use aura_core::query::{Expr, Flag, Op};
+    ///
+    /// let e = Expr::parse("not orphan and repo == extra").unwrap();
+    /// let exp = Expr::And(
+    ///     Box::new(Expr::Not(Box::new(Expr::Flag(Flag::Orphan)))),
+    ///     Box::new(Expr::Repo(Op::Eq, "extra".to_string())),
+    /// );
+    /// assert_eq!(exp, e);
+    ///
+    /// assert_eq!(Expr::Size(Op::Gt, 1_500_000), Expr::parse("size>1.5MB").unwrap());
+    /// assert_eq!(Expr::OlderThan(2 * 7 * 86400), Expr::parse("older-than 2w").unwrap());
+    /// assert!(Expr::parse("size ~").is_err());
+    /// assert!(Expr::parse("(foreign").is_err());
+    /// assert!(Expr::parse("foreign orphan").is_err());
+    /// ```
+    pub fn parse(input: &str) -> Result<Expr, Error> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+
+        match parser.next() {
+            None => Ok(expr),
+            Some(t) => Err(Error::Unexpected(t.to_string())),
+        }
+    }
+
+    /// Does the expression hold for a package? `now` is the current time, in
+    /// seconds since the Unix epoch.
+    pub fn eval(&self, facts: &Facts<'_>, now: i64) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(facts, now) && b.eval(facts, now),
+            Expr::Or(a, b) => a.eval(facts, now) || b.eval(facts, now),
+            Expr::Not(e) => e.eval(facts, now).not(),
+            Expr::Flag(Flag::Installed) => facts.installed,
+            Expr::Flag(Flag::Foreign) => facts.foreign,
+            Expr::Flag(Flag::Orphan) => facts.orphan,
+            Expr::Flag(Flag::Explicit) => facts.installed && facts.explicit,
+            Expr::Flag(Flag::Dependency) => facts.installed && facts.explicit.not(),
+            Expr::Name(op, n) => op.text(n, facts.name),
+            Expr::Repo(op, r) => op.text(r, facts.repo.unwrap_or("")),
+            Expr::Size(op, s) => op.number(*s, facts.size),
+            Expr::OlderThan(secs) => now - facts.date > *secs,
+            Expr::NewerThan(secs) => now - facts.date <= *secs,
+        }
+    }
+}
+
+/// A single meaningful piece of an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Op(String),
+    Word(String),
+    Quoted(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Op(s) | Token::Word(s) => write!(f, "{s}"),
+            Token::Quoted(s) => write!(f, "'{s}'"),
+        }
+    }
+}
+
+fn is_op_char(c: char) -> bool {
+    matches!(c, '=' | '!' | '<' | '>' | '~')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let mut chars = input.chars().peekable();
+    let mut tokens = Vec::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            _ if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '\'' | '"' => tokens.push(Token::Quoted(quoted(&mut chars, c)?)),
+            _ if is_op_char(c) => {
+                let mut op = c.to_string();
+                while let Some(n) = chars.next_if(|n| is_op_char(*n)) {
+                    op.push(n);
+                }
+                tokens.push(Token::Op(op));
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(n) = chars.next_if(|n| {
+                    n.is_whitespace().not() && is_op_char(*n).not() && *n != '(' && *n != ')'
+                }) {
+                    word.push(n);
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn quoted(chars: &mut Peekable<Chars<'_>>, quote: char) -> Result<String, Error> {
+    let mut s = String::new();
+
+    loop {
+        match chars.next() {
+            None => return Err(Error::Unclosed),
+            Some(c) if c == quote => return Ok(s),
+            Some(c) => s.push(c),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w == word)
+    }
+
+    fn or(&mut self) -> Result<Expr, Error> {
+        let mut left = self.and()?;
+
+        while self.is_word("or") {
+            self.pos += 1;
+            let right = self.and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut left = self.unary()?;
+
+        while self.is_word("and") {
+            self.pos += 1;
+            let right = self.unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        match self.next().ok_or(Error::End)? {
+            Token::Word(w) if w == "not" => Ok(Expr::Not(Box::new(self.unary()?))),
+            Token::Open => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    Some(t) => Err(Error::Unexpected(t.to_string())),
+                    None => Err(Error::End),
+                }
+            }
+            Token::Word(w) => self.atom(w),
+            t => Err(Error::Unexpected(t.to_string())),
+        }
+    }
+
+    fn atom(&mut self, word: String) -> Result<Expr, Error> {
+        match word.as_str() {
+            "installed" => Ok(Expr::Flag(Flag::Installed)),
+            "foreign" => Ok(Expr::Flag(Flag::Foreign)),
+            "orphan" => Ok(Expr::Flag(Flag::Orphan)),
+            "explicit" => Ok(Expr::Flag(Flag::Explicit)),
+            "dependency" => Ok(Expr::Flag(Flag::Dependency)),
+            "older-than" => self.value().and_then(|v| duration(&v)).map(Expr::OlderThan),
+            "newer-than" => self.value().and_then(|v| duration(&v)).map(Expr::NewerThan),
+            "name" | "repo" | "size" => {
+                let op = match self.next().ok_or(Error::End)? {
+                    Token::Op(o) => Op::parse(&o).ok_or(Error::BadOperator(word.clone(), o))?,
+                    t => return Err(Error::Unexpected(t.to_string())),
+                };
+                let value = self.value()?;
+
+                match word.as_str() {
+                    "name" => Ok(Expr::Name(op, value)),
+                    "repo" => Ok(Expr::Repo(op, value)),
+                    _ if op == Op::Like => Err(Error::BadOperator(word, "~".to_string())),
+                    _ => size(&value).map(|s| Expr::Size(op, s)),
+                }
+            }
+            _ => Err(Error::Unexpected(word)),
+        }
+    }
+
+    /// The right-hand side of some comparison.
+    fn value(&mut self) -> Result<String, Error> {
+        match self.next().ok_or(Error::End)? {
+            Token::Word(w) | Token::Quoted(w) => Ok(w),
+            t => Err(Error::Unexpected(t.to_string())),
+        }
+    }
+}
+
+/// A number of bytes, like `100MB` or `1.5GiB`. Multiples without an `i` are
+/// powers of 1000.
+fn size(s: &str) -> Result<i64, Error> {
+    let split = s
+        .find(|c: char| c.is_ascii_digit().not() && c != '.')
+        .unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: f64 = num.parse().map_err(|_| Error::BadSize(s.to_string()))?;
+
+    let mult: f64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1e3,
+        "m" | "mb" => 1e6,
+        "g" | "gb" => 1e9,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(Error::BadSize(s.to_string())),
+    };
+
+    Ok((n * mult) as i64)
+}
+
+/// A number of seconds, from durations like `3d`, `2w`, `6m`, and `1y`.
+fn duration(s: &str) -> Result<i64, Error> {
+    let bad = || Error::BadDuration(s.to_string());
+    let unit = s.chars().last().ok_or_else(bad)?;
+    let n: i64 = s[..s.len() - unit.len_utf8()].parse().map_err(|_| bad())?;
+
+    let days = match unit {
+        'd' => 1,
+        'w' => 7,
+        'm' => 30,
+        'y' => 365,
+        _ => return Err(bad()),
+    };
+
+    Ok(n * days * SECS_IN_DAY)
+}
@@ -2,7 +2,7 @@
 
 use alpm::Alpm;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
@@ -16,8 +16,25 @@ pub struct Snapshot {
     pub time: OffsetDateTime,
     /// Should this `Snapshot` never be removed?
     pub pinned: bool,
+    /// A name to refer to this `Snapshot` by, like `before-nvidia-550`.
+    #[serde(default)]
+    pub name: Option<String>,
     /// Every package name in the `Snapshot`, with its version.
     pub packages: HashMap<String, String>,
+    /// The state of each sync database when the `Snapshot` was taken.
+    #[serde(default)]
+    pub databases: BTreeMap<String, Database>,
+}
+
+/// A sync database as it was when some [`Snapshot`] was taken.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Database {
+    /// When the database was last synced, in seconds since the epoch.
+    pub synced: Option<i64>,
+    /// The SHA-256 of the database file.
+    pub sha256: Option<String>,
+    /// The mirrors the database was synced from, in order of preference.
+    pub servers: Vec<String>,
 }
 
 impl Snapshot {
@@ -35,7 +52,9 @@ impl Snapshot {
         let snap = Snapshot {
             time,
             pinned: false,
+            name: None,
             packages,
+            databases: BTreeMap::new(),
         };
 
         Ok(snap)
@@ -0,0 +1,141 @@
+//! Templates for custom one-line output, like `{name} {version} {votes}`.
+//!
+//! Fields are named within braces, and everything else is printed as written.
+//! `{{` and `}}` stand for literal braces, and `\t` and `\n` for a tab and a
+//! newline, which are awkward to type within a shell's quotes.
+//!
+//! ```
+//! use aura_core::template::Template;
+//!
+//! let t = Template::parse("{name}\t{votes}", &["name", "votes"]).unwrap();
+//! let line = t.render(|f| match f {
+//!     "name" => "aura".to_string(),
+//!     _ => "100".to_string(),
+//! });
+//!
+//! assert_eq!("aura\t100", line);
+//! assert!(Template::parse("{nmae}", &["name"]).is_err());
+//! ```
+
+use std::ops::Not;
+
+/// A problem in the text of a template.
+#[derive(Debug)]
+pub enum Error {
+    /// A brace was opened but never closed, or closed but never opened.
+    Unbalanced,
+    /// A field that isn't available, alongside those that are.
+    Unknown(String, Vec<String>),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Unbalanced => write!(f, "Unbalanced braces"),
+            Error::Unknown(field, known) => {
+                write!(f, "No field {field}, only: {}", known.join(", "))
+            }
+        }
+    }
+}
+
+impl crate::Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Unbalanced => "template-unbalanced",
+            Error::Unknown(_, _) => "template-unknown",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Unbalanced => Vec::new(),
+            Error::Unknown(field, _) => vec![("field", field.clone())],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Field(String),
+}
+
+/// A parsed template.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    /// Parse a template, whose fields must all be among those given.
+    pub fn parse(raw: &str, known: &[&str]) -> Result<Template, Error> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = raw.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => return Err(Error::Unbalanced),
+                            Some(c) => field.push(c),
+                        }
+                    }
+                    let field = field.trim();
+
+                    if known.contains(&field).not() {
+                        let known = known.iter().map(|k| k.to_string()).collect();
+                        return Err(Error::Unknown(field.to_string(), known));
+                    }
+
+                    if text.is_empty().not() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Field(field.to_string()));
+                }
+                '}' => return Err(Error::Unbalanced),
+                '\\' if chars.peek() == Some(&'t') => {
+                    chars.next();
+                    text.push('\t');
+                }
+                '\\' if chars.peek() == Some(&'n') => {
+                    chars.next();
+                    text.push('\n');
+                }
+                c => text.push(c),
+            }
+        }
+
+        if text.is_empty().not() {
+            pieces.push(Piece::Text(text));
+        }
+
+        Ok(Template { pieces })
+    }
+
+    /// Fill in the template, given the value of each field.
+    pub fn render<F>(&self, value: F) -> String
+    where
+        F: Fn(&str) -> String,
+    {
+        self.pieces
+            .iter()
+            .map(|p| match p {
+                Piece::Text(t) => t.clone(),
+                Piece::Field(f) => value(f),
+            })
+            .collect()
+    }
+}
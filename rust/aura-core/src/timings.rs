@@ -0,0 +1,136 @@
+//! How long each phase of the work took, for finding bottlenecks.
+//!
+//! A phase is timed from when some work within it begins until no work within
+//! it remains, so that work done in parallel, like fetching the metadata of
+//! many packages at once, isn't counted more than once. Phases may still
+//! overlap each other: resolving dependencies includes waiting on the metadata
+//! and clones it needs.
+//!
+//! ```
+//! use aura_core::timings::{self, Phase};
+//!
+//! {
+//!     let _timer = timings::time(Phase::Builds);
+//! }
+//!
+//! assert!(timings::totals().iter().any(|(p, _)| *p == Phase::Builds));
+//! ```
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A phase of Aura's own work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Fetching package metadata from the AUR.
+    Metadata,
+    /// Resolving dependencies.
+    Resolution,
+    /// Cloning and pulling AUR repositories.
+    Clones,
+    /// Building packages.
+    Builds,
+    /// Installing packages.
+    Install,
+}
+
+impl Phase {
+    /// Every phase, in the order they usually occur.
+    pub const ALL: [Phase; 5] = [
+        Phase::Metadata,
+        Phase::Resolution,
+        Phase::Clones,
+        Phase::Builds,
+        Phase::Install,
+    ];
+
+    /// A short, stable name for this phase.
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Metadata => "metadata",
+            Phase::Resolution => "resolution",
+            Phase::Clones => "clones",
+            Phase::Builds => "builds",
+            Phase::Install => "install",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The running time of one phase.
+#[derive(Clone, Copy)]
+struct Clock {
+    /// How many timers of this phase are running.
+    running: usize,
+    /// When the first of those began.
+    since: Option<Instant>,
+    total: Duration,
+    /// Was this phase ever entered?
+    entered: bool,
+}
+
+const IDLE: Clock = Clock {
+    running: 0,
+    since: None,
+    total: Duration::ZERO,
+    entered: false,
+};
+
+static CLOCKS: Mutex<[Clock; 5]> = Mutex::new([IDLE; 5]);
+
+/// While alive, the given phase is being timed.
+pub struct Timer {
+    phase: Phase,
+}
+
+/// Time a phase until the yielded [`Timer`] is dropped.
+pub fn time(phase: Phase) -> Timer {
+    if let Ok(mut clocks) = CLOCKS.lock() {
+        let clock = &mut clocks[phase.index()];
+
+        if clock.running == 0 {
+            clock.since = Some(Instant::now());
+        }
+        clock.running += 1;
+        clock.entered = true;
+    }
+
+    Timer { phase }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Ok(mut clocks) = CLOCKS.lock() {
+            let clock = &mut clocks[self.phase.index()];
+            clock.running = clock.running.saturating_sub(1);
+
+            if clock.running == 0 {
+                if let Some(since) = clock.since.take() {
+                    clock.total += since.elapsed();
+                }
+            }
+        }
+    }
+}
+
+/// The time spent so far in each phase that was entered, in the order of
+/// [`Phase::ALL`].
+pub fn totals() -> Vec<(Phase, Duration)> {
+    let clocks = match CLOCKS.lock() {
+        Ok(c) => *c,
+        Err(_) => return Vec::new(),
+    };
+
+    Phase::ALL
+        .into_iter()
+        .filter_map(|p| {
+            let clock = clocks[p.index()];
+            let running = clock.since.map(|s| s.elapsed()).unwrap_or_default();
+
+            clock.entered.then(|| (p, clock.total + running))
+        })
+        .collect()
+}
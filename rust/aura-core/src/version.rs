@@ -0,0 +1,300 @@
+//! Comparison of package versions, exactly as `pacman` does it.
+//!
+//! A full version looks like `epoch:version-release`, where both the epoch and
+//! the release are optional. An absent epoch is `0`, and an absent release
+//! matches any other release.
+//!
+//! [`CASES`] and [`ASCENDING`] pin down how the trickier versions compare, and
+//! are checked by this crate's own tests, so code built on [`cmp`] may rely on
+//! them too.
+
+use std::cmp::Ordering;
+use std::ops::Not;
+
+/// Pairs of versions, and how the first compares to the second.
+pub const CASES: &[(&str, &str, Ordering)] = &[
+    // Leading zeros and separators.
+    ("1.5.0", "1.5.0", Ordering::Equal),
+    ("1.01", "1.1", Ordering::Equal),
+    ("1.0_1", "1.0.1", Ordering::Equal),
+    ("1.0.1", "1.0..1", Ordering::Less),
+    ("1.5.1", "1.5", Ordering::Greater),
+    // Letters.
+    ("1.5a", "1.5", Ordering::Less),
+    ("1.5.a", "1.5", Ordering::Greater),
+    ("1.0.1", "1.0a", Ordering::Greater),
+    (
+        "0.8.0.r988.g0f8e7b6",
+        "0.8.0.r1000.g1a2b3c4",
+        Ordering::Less,
+    ),
+    // Epochs.
+    ("0:1.0", "1.0", Ordering::Equal),
+    ("1.0", "1:1.0", Ordering::Less),
+    ("1:1.0", "2:0.1", Ordering::Less),
+    ("1:20220801", "2:1.0", Ordering::Less),
+    // Releases.
+    ("1.5", "1.5-1", Ordering::Equal),
+    ("1.5.0-1", "1.5.0-2", Ordering::Less),
+    ("1.5.0-2", "1.5.1-1", Ordering::Less),
+    ("1.2.3-1.1", "1.2.3-1", Ordering::Greater),
+    ("20220801-1", "20220731-3", Ordering::Greater),
+];
+
+/// Versions from oldest to newest. Any earlier one is older than any later.
+pub const ASCENDING: &[&str] = &[
+    "1.0alpha", "1.0beta", "1.0rc1", "1.0", "1.0.a", "1.0.1", "1.1", "1.10", "2.0", "2.0.1", "10",
+    "1:0.1", "2:0.0.1",
+];
+
+/// Compare two package versions. Agrees with `alpm_pkg_vercmp`, and thus with
+/// the `vercmp` tool that comes with `pacman`.
+///
+/// ```
+/// use aura_core::version::cmp;
+/// use std::cmp::Ordering::*;
+///
+/// // Plain versions.
+/// assert_eq!(Equal, cmp("1.5.0", "1.5.0"));
+/// assert_eq!(Greater, cmp("1.5.1", "1.5.0"));
+/// assert_eq!(Greater, cmp("1.5.1", "1.5"));
+/// assert_eq!(Equal, cmp("1.01", "1.1"));
+/// assert_eq!(Less, cmp("1.9", "1.10"));
+///
+/// // Releases.
+/// assert_eq!(Less, cmp("1.5.0-1", "1.5.0-2"));
+/// assert_eq!(Less, cmp("1.5.0-2", "1.5.1-1"));
+/// assert_eq!(Equal, cmp("1.5", "1.5-1"));
+///
+/// // Epochs.
+/// assert_eq!(Greater, cmp("1:1.0", "0:1.1"));
+/// assert_eq!(Less, cmp("1:1.0", "2:1.1"));
+/// assert_eq!(Equal, cmp("0:1.0", "1.0"));
+/// assert_eq!(Less, cmp("1.0", "1:1.0"));
+///
+/// // Pre-releases are older than the releases they precede, but a trailing
+/// // separated alpha segment is newer.
+/// assert_eq!(Less, cmp("1.0rc1", "1.0"));
+/// assert_eq!(Less, cmp("1.5a", "1.5"));
+/// assert_eq!(Greater, cmp("1.5.a", "1.5"));
+/// assert_eq!(Less, cmp("1.0alpha", "1.0beta"));
+/// assert_eq!(Greater, cmp("1.0.1", "1.0a"));
+///
+/// // Separators of the same length are interchangeable.
+/// assert_eq!(Equal, cmp("1.0_1", "1.0.1"));
+/// assert_eq!(Less, cmp("1.0.1", "1.0..1"));
+/// ```
+pub fn cmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let (ae, av, ar) = parse_evr(a);
+    let (be, bv, br) = parse_evr(b);
+
+    rpmvercmp(ae, be)
+        .then_with(|| rpmvercmp(av, bv))
+        .then_with(|| match (ar, br) {
+            (Some(ar), Some(br)) => rpmvercmp(ar, br),
+            _ => Ordering::Equal,
+        })
+}
+
+/// Split a full version into its epoch, version, and release.
+///
+/// ```
+/// use aura_core::version::parse_evr;
+///
+/// assert_eq!(("0", "1.2.3", Some("4")), parse_evr("1.2.3-4"));
+/// assert_eq!(("2", "1.2.3", None), parse_evr("2:1.2.3"));
+/// assert_eq!(("0", "a:b", None), parse_evr("a:b"));
+/// ```
+pub fn parse_evr(full: &str) -> (&str, &str, Option<&str>) {
+    let digits = full.bytes().take_while(|b| b.is_ascii_digit()).count();
+
+    let (epoch, rest) = match full[digits..].strip_prefix(':') {
+        Some(rest) if digits == 0 => ("0", rest),
+        Some(rest) => (&full[..digits], rest),
+        None => ("0", full),
+    };
+
+    match rest.rsplit_once('-') {
+        Some((version, release)) => (epoch, version, Some(release)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// The epoch and leading component of a version, which together change with
+/// each major release.
+///
+/// ```
+/// use aura_core::version::major;
+///
+/// assert_eq!(("0", "16"), major("16.1-2"));
+/// assert_eq!(("1", "2"), major("1:2.0.3"));
+/// assert_eq!(("0", "1"), major("1.0rc1"));
+/// assert_eq!(("0", "20231001"), major("20231001-1"));
+/// ```
+pub fn major(full: &str) -> (&str, &str) {
+    let (epoch, version, _) = parse_evr(full);
+    let end = version
+        .find(|c: char| c.is_ascii_digit().not())
+        .unwrap_or(version.len());
+
+    (epoch, &version[..end])
+}
+
+/// The segment-by-segment comparison at the heart of `pacman`'s versioning,
+/// inherited from RPM.
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let one = a.as_bytes();
+    let two = b.as_bytes();
+    let mut i = 0;
+    let mut j = 0;
+
+    while i < one.len() && j < two.len() {
+        let (si, sj) = (i, j);
+
+        while i < one.len() && one[i].is_ascii_alphanumeric().not() {
+            i += 1;
+        }
+        while j < two.len() && two[j].is_ascii_alphanumeric().not() {
+            j += 1;
+        }
+
+        if i == one.len() || j == two.len() {
+            break;
+        }
+
+        // Differing separator lengths decide the matter immediately.
+        if i - si != j - sj {
+            return (i - si).cmp(&(j - sj));
+        }
+
+        let is_num = one[i].is_ascii_digit();
+        let same_kind = |c: &u8| {
+            if is_num {
+                c.is_ascii_digit()
+            } else {
+                c.is_ascii_alphabetic()
+            }
+        };
+        let seg1 = segment(&one[i..], same_kind);
+        let seg2 = segment(&two[j..], same_kind);
+        i += seg1.len();
+        j += seg2.len();
+
+        // Segments of different kinds: numbers are always newer.
+        if seg2.is_empty() {
+            return if is_num {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let ord = if is_num {
+            let n1 = trim_zeros(seg1);
+            let n2 = trim_zeros(seg2);
+            n1.len().cmp(&n2.len()).then_with(|| n1.cmp(n2))
+        } else {
+            seg1.cmp(seg2)
+        };
+
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+
+    let rest1 = &one[i..];
+    let rest2 = &two[j..];
+
+    match (rest1.first(), rest2.first()) {
+        (None, None) => Ordering::Equal,
+        // A remaining alpha segment never beats an empty string.
+        (None, Some(c)) if c.is_ascii_alphabetic().not() => Ordering::Less,
+        (Some(c), _) if c.is_ascii_alphabetic() => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+fn segment<F>(bytes: &[u8], f: F) -> &[u8]
+where
+    F: Fn(&u8) -> bool,
+{
+    let len = bytes.iter().take_while(|c| f(c)).count();
+    &bytes[..len]
+}
+
+fn trim_zeros(bytes: &[u8]) -> &[u8] {
+    let zeros = bytes.iter().take_while(|c| **c == b'0').count();
+    &bytes[zeros..]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Versions as they're usually written, without an epoch or release.
+    const VERSION: &str = "[0-9a-z]{1,4}([._+][0-9a-z]{1,4}){0,3}";
+
+    /// Versions made only of numbers, like most releases.
+    const NUMERIC: &str = "[0-9]{1,3}(\\.[0-9]{1,3}){0,2}";
+
+    #[test]
+    fn fixtures() {
+        for (a, b, ord) in CASES {
+            assert_eq!(*ord, cmp(a, b), "{a} against {b}");
+        }
+
+        for (i, a) in ASCENDING.iter().enumerate() {
+            for b in ASCENDING[i + 1..].iter() {
+                assert_eq!(Ordering::Less, cmp(a, b), "{a} against {b}");
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn antisymmetric(a in ".*", b in ".*") {
+            prop_assert_eq!(cmp(&a, &b), cmp(&b, &a).reverse());
+        }
+
+        #[test]
+        fn any_release(v in VERSION, r in "[0-9]{1,2}") {
+            prop_assert_eq!(Ordering::Equal, cmp(&v, &format!("{v}-{r}")));
+        }
+
+        #[test]
+        fn releases_break_ties(v in VERSION, r1 in 1u32..100, r2 in 1u32..100) {
+            prop_assert_eq!(r1.cmp(&r2), cmp(&format!("{v}-{r1}"), &format!("{v}-{r2}")));
+        }
+
+        #[test]
+        fn prereleases_older(v in NUMERIC, tag in "(alpha|beta|pre|rc)[0-9]{0,2}") {
+            prop_assert_eq!(Ordering::Less, cmp(&format!("{v}{tag}"), &v));
+        }
+
+        #[test]
+        fn epochs_first(e1 in 0u32..100, e2 in 0u32..100, a in VERSION, b in VERSION) {
+            prop_assume!(e1 != e2);
+            prop_assert_eq!(e1.cmp(&e2), cmp(&format!("{e1}:{a}"), &format!("{e2}:{b}")));
+        }
+
+        #[test]
+        fn bumps_are_newer(mut parts in prop::collection::vec(0u32..1000, 1..5)) {
+            let join = |ps: &[u32]| ps.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(".");
+            let old = join(&parts);
+            if let Some(last) = parts.last_mut() {
+                *last += 1;
+            }
+
+            prop_assert_eq!(Ordering::Less, cmp(&old, &join(&parts)));
+        }
+    }
+}
@@ -1 +1,115 @@
 language-name = Deutsch
+
+# AUR Packages (-A)
+A-install-unreal = { $pkg } ist kein echtes Paket.
+A-install-cloning = Neue Pakete werden geklont...
+A-install-pulling = Bekannte Pakete werden aktualisiert...
+A-install-deps = Abhängigkeiten werden aufgelöst...
+A-install-repo-pkgs = Abhängigkeiten aus den Repositorys:
+A-install-aur-pkgs = AUR-Pakete:
+
+A-build-prep = Build-Verzeichnisse werden vorbereitet...
+A-build-pkg = { $pkg } wird gebaut...
+A-build-diff = Änderungen an den Build-Dateien anzeigen?
+A-build-hotedit-pkgbuild = PKGBUILD bearbeiten?
+A-build-hotedit-install = .install-Datei bearbeiten?
+A-build-fail = Das Paket konnte nicht gebaut werden:
+A-build-e-makepkg = makepkg ist fehlgeschlagen.
+A-build-continue = Mit den anderen Paketen fortfahren?
+
+A-i-repo = Repository
+A-i-version = Version
+A-i-status = AUR-Status
+A-i-maintainer = Betreuer
+A-i-proj-url = Projekt-URL
+A-i-aur-url = AUR-URL
+A-i-license = Lizenz
+A-i-group = Gruppen
+A-i-provides = Stellt bereit
+A-i-depends = Hängt ab von
+A-i-make = Build-Abhängigkeiten
+A-i-opt = Optionale Abhängigkeiten
+A-i-check = Test-Abhängigkeiten
+A-i-votes = Stimmen
+A-i-pop = Beliebtheit
+A-i-desc = Beschreibung
+A-i-keywords = Schlüsselwörter
+A-i-submitted = Eingereicht
+A-i-updated = Aktualisiert
+
+A-u-fetch-info = Paketinformationen werden abgerufen...
+A-u-comparing = Paketversionen werden verglichen...
+A-u-no-upgrades = Keine Aktualisierungen für AUR-Pakete nötig.
+A-u-to-upgrade = Zu aktualisierende AUR-Pakete:
+
+A-w = { $package } wird geklont...
+A-w-fail = Folgende Pakete konnten nicht geklont werden:
+
+# Cache (-C)
+C-size = Aktuelle Größe des Caches: { $size }
+C-c-keep = Von jedem Paket werden { $pkgs } Dateien behalten. Der Rest wird gelöscht.
+C-c-freed = { $bytes } freigegeben.
+C-downgrade-which = Welche Version von { $pkg } möchtest du?
+
+# Orphans (-O)
+O-abandon = Folgende verwaiste Pakete und ihre Abhängigkeiten werden entfernt:
+O-adopt = { $pkg } ist jetzt als ausdrücklich installiert markiert.
+O-explicit-err = { $pkg } konnte nicht als ausdrücklich installiert markiert werden.
+
+# Logs (-L)
+L-first = Erstinstallation
+L-upgrades = Aktualisierungen
+L-recent = Letzte Aktionen
+L-view-err = Dein ALPM-Log konnte nicht geöffnet werden.
+
+# Package Information (info)
+info-installed = Installiert
+info-reason = Installationsgrund
+info-install-date = Installationsdatum
+info-explicit = Ausdrücklich installiert
+info-dependency = Als Abhängigkeit installiert
+info-unknown = { $pkg } ist weder ein lokales noch ein Repository- oder AUR-Paket.
+
+# Languages (lang)
+lang-load = Die Sprachdaten konnten nicht geladen werden.
+lang-unknown = Aura hat keine Übersetzungen für { $lang }.
+lang-missing = { $lang } fehlen { $count } der { $total } Meldungen:
+lang-complete = { $lang } ist vollständig übersetzt.
+
+# Aura-specific Directories
+dir-mkdir = Das Verzeichnis konnte nicht erstellt werden: { $dir }.
+
+# Dependency Resolution
+dep-exist = Das Paket { $pkg } existiert nicht.
+dep-exist-par = Die Abhängigkeit { $pkg } von { $par } existiert nicht.
+dep-graph = Der Abhängigkeitsgraph ist irgendwie fehlerhaft.
+dep-cycle = Es gibt eine zyklische Abhängigkeit mit: { $pkg }
+dep-multi = Beim Auflösen der Abhängigkeiten sind mehrere Fehler aufgetreten.
+
+# Common Errors
+err-alpm = Der ALPM-Handle konnte nicht geöffnet werden.
+err-file-del = Konnte nicht gelöscht werden: { $file }
+err-file-open = Konnte nicht geöffnet werden: { $file }
+err-file-write = Konnte nicht geschrieben werden: { $file }
+err-none-exist = Keines der angegebenen Pakete existiert.
+err-read-dir = Das Verzeichnis konnte nicht gelesen werden: { $dir }
+err-srcinfo = Die .SRCINFO konnte nicht gelesen werden: { $file }
+err-sudo = Die Rechte konnten nicht erhöht werden.
+err-user-input = Die Eingabe konnte nicht gelesen werden.
+err-utf8 = Eine UTF-8-Umwandlung ist fehlgeschlagen.
+
+# Common Fields
+common-yes = Ja
+common-no = Nein
+common-none = Keine
+common-name = Name
+common-done = Fertig.
+common-total = Gesamt
+common-no-packages = Keine Pakete angegeben.
+common-no-valid = Keine gültigen Pakete angegeben.
+common-no-work = Nichts zu tun.
+common-cancelled = Vorgang abgebrochen.
+common-interrupted = Unterbrochen.
+
+# Misc.
+proceed = Fortfahren?
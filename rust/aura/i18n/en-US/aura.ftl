@@ -2,12 +2,39 @@ language-name = English
 
 # AUR Packages (-A)
 A-install-unreal = { $pkg } is not a real package.
+A-pick-which = No package is called { $pkg }. Which did you mean?
+A-pick-first = No package is called { $pkg }, so taking { $chosen }.
 A-install-cloning = Cloning new packages...
 A-install-pulling = Pulling known packages...
 A-install-deps = Resolving dependencies...
+A-install-memo = Reusing the last resolution of these packages.
 A-install-repo-pkgs = Repository dependencies:
+A-rebuild-official = Now in the official repositories, and so skipped: { $pkgs }
+A-rebuild-missing = No longer in the AUR, and so skipped: { $pkgs }
+A-stale-none = No AUR packages were left behind by a runtime upgrade.
+A-stale-found = Built for an older { $runtime } than { $version }:
+A-stale-advice = These AUR packages were built for an older Python, Perl, or GHC, and should be rebuilt with { $command }: { $pkgs }
+A-install-binaries = Pre-built AUR packages:
+A-binary-offer = { $repo } has { $pkg } { $version } pre-built. Install it instead of building?
+A-binary-remembered = Your answer for { $pkg } was saved to aura.toml.
 A-install-aur-pkgs = AUR packages:
 A-install-path-comp = Failed to extract final component of: { $path }
+A-install-ignored = The following are ignored by pacman.conf or aura.toml, but will be installed anyway: { $pkgs }
+A-install-arch = The following don't list { $arch } as a supported architecture, and may fail to build: { $pkgs }
+A-install-conflicts = The following files already exist on your filesystem, and would conflict:
+A-sources = Sources to be downloaded:
+A-sources-none = No sources.
+A-sources-skip = checksum skipped
+A-sources-plain = unencrypted
+A-sources-unreadable = Some .SRCINFO files couldn't be read, so their sources aren't shown.
+A-metered-total = Expected download size: { $size }
+A-metered-unknown = { $count } sources are of unknown size, and aren't included.
+A-metered-large = This exceeds your metered connection threshold of { $limit }.
+A-metered-deferred = Deferred until you're on an unmetered connection.
+A-power-battery = Running on battery, but these builds may be heavy: { $pkgs }
+A-power-hot = The CPU is already at { $temp }°C (above { $limit }°C), but these builds may be heavy: { $pkgs }
+A-power-deferred = Deferred until the machine is plugged in and cool.
+A-space = Not enough disk space at { $path }: about { $need } is needed, but only { $free } is free.
 
 A-build-prep = Preparing build directories...
 A-build-pkg = Building { $pkg }...
@@ -16,6 +43,8 @@ A-build-hotedit-pkgbuild = Edit the PKGBUILD?
 A-build-hotedit-install = Edit the .install file?
 A-build-fail = Package failed to build, citing:
 A-build-e-makepkg = makepkg failed.
+A-build-nocheck = The tests of { $pkg } took about { $minutes } minutes last time. Skip them?
+A-build-e-timeout = The build was stopped after { $minutes } minutes.
 A-build-e-edit = Failed to edit: { $file }
 A-build-e-tarball = Failed to move: { $file }
 A-build-e-filename = Failed to extract filename from: { $file }
@@ -23,10 +52,36 @@ A-build-e-copies = Failed to copy build files.
 A-build-pkglist = Failed to determine makepkg output paths from: { $dir }
 A-build-pull = Failed to pull latest commits - you may be building an old version!
 A-build-continue = Continue building other packages?
+A-build-tmpfs = This package needs about { $size } to build, which won't fit in your tmpfs. Building in { $dir } instead.
+A-build-sources = Downloading { $count } sources...
+A-build-e-aria2 = aria2c failed to download the sources.
+A-build-e-source = Failed to download: { $url }
+A-build-e-root = makepkg can't be run as root. Set a builduser in your aura.toml.
+A-build-e-chown = Failed to give { $dir } to { $user }.
+A-build-e-namcap = namcap raised complaints that you've marked as fatal:
+  { $lints }
+A-build-namcap = namcap findings:
+A-build-namcap-errors = Errors
+A-build-namcap-warnings = Warnings
+A-build-namcap-info = Information
+A-stage-install = Installing { $count } staged packages together...
+A-keep-going-failed = The following packages failed to build:
+A-keep-going-skipped = The following packages were skipped, since a dependency wasn't built:
+A-incomplete = { $count } packages weren't built.
+
+report-installed = installed
+report-prebuilt = installed (pre-built)
+report-failed = failed to build
+report-skipped = skipped, since a dependency wasn't built
+report-log = Build output: { $path }
+report-satisfied = Already satisfied: { $pkgs }
+report-order = Build order: { $order }
 
 A-i-repo = Repository
 A-i-version = Version
 A-i-status = AUR Status
+A-i-up-to-date = Up to Date
+A-i-out-of-date = Out of Date!
 A-i-maintainer = Maintainer
 A-i-proj-url = Project URL
 A-i-aur-url = AUR URL
@@ -43,6 +98,20 @@ A-i-desc = Description
 A-i-keywords = Keywords
 A-i-submitted = Submitted
 A-i-updated = Updated
+A-i-pkgbase = Package Base
+A-i-commit = AUR Commit
+A-i-built = Built
+A-i-flags = makepkg Flags
+A-i-host = Build Host
+A-i-vcs = VCS Package
+A-i-source = Installed From
+A-i-alternatives = Alternatives:
+A-i-installed = installed
+A-i-no-record = Aura has no record of building { $pkg }.
+A-i-not-found = { $pkg }: not found in the AUR.
+A-i-record-fail = Failed to record how these packages were built.
+A-trend-few = Aura has only seen { $pkg } once so far. Its trend builds up as Aura looks it up on other days.
+A-trend-since = Since { $date }: { $votes } votes, { $pop } popularity
 
 # upgradeAURPkgs_1
 A-u-fetch-info = Fetching package information...
@@ -53,6 +122,21 @@ A-u-no-upgrades = No AUR package upgrades necessary.
 # reportPkgsToUpgrade_1
 A-u-to-upgrade = AUR packages to upgrade:
 A-u-git = VCS packages to rebuild:
+A-u-changes-unknown = (commit history since the last install is unavailable)
+A-u-pick = Toggle upgrades by number, then press Enter to continue:
+
+A-push-checking = Checking { $dir } before pushing:
+A-push-pkgbuild = PKGBUILD present?
+A-push-srcinfo = .SRCINFO regenerated?
+A-push-namcap = namcap has no complaints?
+A-push-namcap-missing = namcap is not installed, so the PKGBUILD wasn't linted.
+A-push-committed = All changes committed?
+A-push-committed-fix = Fix: Commit your changes, including the regenerated .SRCINFO.
+A-push-remote = Remote points to the AUR over SSH?
+A-push-remote-fix = Fix: The origin remote is "{ $url }". Try ssh://aur@aur.archlinux.org/<pkgbase>.git
+A-push-proceed = Push to the AUR?
+A-push-e-srcinfo = Failed to generate a valid .SRCINFO in: { $dir }
+A-push-e-checklist = Not pushing, since some checks failed.
 
 A-w = Cloning { $package }...
 A-w-fail = Failed to clone the following packages:
@@ -65,7 +149,22 @@ A-y = Failed to pull the following packages:
 B-saved = Saved package state.
 B-clean = Remove stale snapshots?
 B-none = No usable snapshots found.
+B-bad-name = { $name } can't name a snapshot. Use only letters, numbers, and - _ .
+B-name-taken = A snapshot named { $name } already exists.
+B-no-such-name = No snapshot named { $name } is available.
+B-unconfigured = Set { $setting } in the [backups] section of your aura.toml first.
+B-missing-tool = { $tool } is needed for this, but isn't installed.
+B-remote-failed = Copying snapshots failed while running: { $command }
+B-pushing = Copying snapshots to { $remote }...
+B-pushed = Copied { $count } snapshots.
+B-pulling = Fetching snapshots from { $remote }...
+B-pulled = Fetched { $count } new snapshots.
+B-diff-none = Nothing has changed since this snapshot.
 B-select = Select a snapshot to restore:
+B-restore-mirrors = The mirrors of these repos have changed since this snapshot: { $repos }
+B-restore-archive = The mirrors no longer carry these versions, so they'll come from the Arch Linux Archive: { $pkgs }
+B-restore-unavailable = These aren't cached, and neither the mirrors nor the archive have them, so they won't be restored: { $pkgs }
+B-restore-changes = Restoring this snapshot will change these packages:
 
 # Cache (-C)
 C-size = Current cache size: { $size }
@@ -93,11 +192,19 @@ C-downgrade-which = What version of { $pkg } do you want?
 C-y-no-work = Package cache already synchronized.
 C-y-which-cache = Which cache should receive the downloaded tarballs?
 C-t-invalids = Removing invalid package tarballs.
+C-o-none = No cached tarball owns { $file }.
+C-z-count = { $count } tarballs will be recompressed at zstd level { $level }.
+C-z-sigs = The signatures of { $count } of them will no longer match, and will be deleted.
+C-z-saved = { $bytes } saved.
+C-z-missing = zstd is not installed.
 
 # Orphans (-O)
 O-abandon = The following orphans and their dependencies will be removed:
 O-adopt = { $pkg } now marked as explicitly installed.
 O-explicit-err = Failed to mark { $pkg } as explicitly installed.
+O-why-removed = { $pkg } became an orphan when you removed { $parent } on { $date }.
+O-why-dropped = { $pkg } was installed for { $parents }, which no longer depend on it.
+O-why-unknown = { $pkg }: there's no record of why it was installed.
 
 # Logs (-L)
 L-first = First Install
@@ -105,16 +212,174 @@ L-upgrades = Upgrades
 L-recent = Recent Actions
 L-search-err = Searching your logs via { $cmd } failed.
 L-view-err = Failed to open your ALPM log.
+L-audit-err = Failed to read the audit log.
+L-audit-empty = Aura has not run any elevated commands yet.
+
+# Tarball Installs (-U)
+U-checksum = The checksum of { $file } doesn't match the one published beside it.
+U-checksum-ok = Checksum verified: { $file }
+U-unsigned = { $file } has no signature file beside it.
+U-copy = Copy the installed tarballs to { $dir }, so that they can be downgraded to later?
+U-conflicts = { $count } files to be installed already exist:
+U-conflict-owned = (owned by { $pkg })
+U-conflict-unowned = (owned by no package)
+U-conflict-what = What should be done?
+U-conflict-overwrite = Overwrite them
+U-conflict-inspect = Inspect them
+U-conflict-abort = Abort
+U-conflict-gone = No longer exists.
+U-conflict-symlink = A symlink.
+U-conflict-dir = A directory.
+U-conflict-file = A file of { $size } bytes.
+U-conflict-modified = Last modified: { $time }
+U-conflict-from = Wanted by { $new }, owned by { $old }.
+
+# Package Information (info)
+info-installed = Installed
+info-reason = Install Reason
+info-install-date = Install Date
+info-explicit = Explicitly installed
+info-dependency = Installed as a dependency
+info-unknown = { $pkg } is not a local, repository, or AUR package.
+info-aur-failed = The AUR couldn't be reached, so only local and repository details are shown.
 
 # Opening Pages (open)
 open-err = Failed to open { $url }.
 
-# System Statistics (stats)
-stats-local = Failed to load language data.
+# Optional Dependencies (optdeps)
+optdeps-of = Optional dependencies of { $pkg }:
+optdeps-none = { $pkg } has no optional dependencies.
+optdeps-unused = The following are only held by the optional dependencies of other packages:
+optdeps-unused-remove = Remove them?
+optdeps-no-unused = No unused optional dependencies.
+
+# Upgrade Pins (pin)
+pin-none = Nothing is pinned.
+pin-held-repo = (whole repo)
+pin-added = Pinned { $pkg } at { $version }.
+pin-added-repo = Holding every package of { $repo }.
+pin-removed = Unpinned { $name }.
+pin-holding = Held back by pins: { $pkgs }
+pin-not-installed = { $pkg } is not installed, so a version must be given, as { $pkg }=VERSION.
+pin-not-pinned = Nothing named { $name } is pinned.
+pin-bad-config = Failed to parse { $file }
+
+# Staged Upgrades (-Su --canary)
+canary-none = No leaf packages have upgrades. Upgrading everything.
+canary-first = Upgrading { $count } leaf packages first:
+canary-check = Check that your system still works as expected.
+canary-continue = Upgrade everything else?
+canary-stopped = Stopped after the canary upgrades, so the system is only partly upgraded. Run the upgrade again soon to finish it.
+
+# Aliases (alias)
+alias-none = No aliases are defined. Add some to the [aliases] section of your aura.toml.
+
+# AUR File Ownership (provides)
+provides-no-index = No file index exists yet. Try `aura provides --refresh` first.
+provides-none = No indexed AUR package provides { $file }.
+provides-refresh = Indexing the files of cached AUR packages...
+provides-unreadable = Couldn't read { $file }, skipping.
+provides-indexed = { $count } packages indexed.
+provides-update-fail = Failed to update the file index.
+
+# Unified Search (search)
+search-none = No packages matched.
+search-aur-failed = The AUR couldn't be reached, so only the repositories were searched.
+search-installed = installed
+
+# Switching Variants (switch)
+switch-not-installed = { $pkg } is not installed.
+switch-same = { $pkg } is already the installed variant.
+switch-plan = { $from } will be replaced by:
+switch-remove = { $pkg } didn't conflict with its replacement, so it will be removed separately.
+switch-rollback = { $from } couldn't be removed, so { $to } will be removed again.
+switch-optdeps = No longer optional dependencies of anything, so marking as explicitly installed: { $pkgs }
+switch-done = Switched from { $from } to { $to }.
+
+# Upgrade Rules (rules/upgrades.toml)
+rule-postgresql = The database cluster must be upgraded with pg_upgrade, or dumped and restored. See https://wiki.archlinux.org/title/PostgreSQL#Upgrading_PostgreSQL
+rule-mariadb = Run mariadb-upgrade once the new server has started.
+rule-grub = The bootloader itself is not updated. Run grub-install as you did originally, then regenerate the config with grub-mkconfig -o /boot/grub/grub.cfg
+rule-jdk = The default Java environment is not switched automatically. Check it with archlinux-java status, and change it with archlinux-java set.
+rule-nextcloud = Run occ upgrade as the web server's user. Nextcloud can only skip one major version at a time.
+
+# Kernel Upgrades
+kernel-headers-behind = { $kernel } is being upgraded, but { $headers } isn't. Modules built for the new kernel will fail.
+kernel-headers-missing = { $mods } are built by DKMS, which needs { $headers } to build them for the new { $kernel }.
+kernel-dkms-series = { $mods } are built by DKMS, and may not support { $kernel } { $to } yet.
+kernel-modules-behind = These have modules for a specific version of { $kernel }, and won't work with { $to }: { $pkgs }
+
+kernel-dkms-ok = DKMS built every module for every kernel.
+kernel-dkms-failed = DKMS didn't install { $module } for kernel { $kernel }.
+kernel-dkms-log = See the build log: { $path }
+
+# Package Data (state_hooks)
+state-kept = Kept a copy of { $count } version markers of { $pkg }'s data.
+state-migrate = Upgrading { $pkg } from { $from } to { $to } needs some manual steps:
+state-rules-bad = Unable to parse the rules in { $file }.
+
+# Package Queries (select)
+query-parse = Failed to read the query: { $err }
+template-parse = Failed to read the --format template: { $err }
+listing-parse = Failed to read --sort or --filter: { $err }
+
+# First-run Setup (setup)
+setup-exists = { $file } already exists. Your answers will be saved into it, and everything else left as it is.
+setup-bad-config = { $file } couldn't be read as TOML, so it was left alone.
+setup-start = Press Enter to accept the default shown in brackets.
+setup-language = Which language should Aura use?
+setup-editor = Editor for viewing PKGBUILDs?
+setup-build = Where should AUR packages be built?
+setup-keep = What should remain after a build? (all, sources, none)
+setup-diff = Show PKGBUILD diffs before building?
+setup-delmakedeps = Remove make dependencies after building?
+setup-namcap = Lint packages with namcap?
+setup-snapshots = Where should package snapshots be saved?
+setup-written = Wrote { $file }
+
+# Migrating from Other Helpers (migrate)
+migrate-not-found = No configuration for { $helper } was found.
+migrate-bad-config = Failed to parse { $file }
+migrate-no-settings = No new settings to import.
+migrate-settings = The following will be set in { $file }:
+migrate-no-builds = No packages built by the other helper were found in { $dir }
+migrate-builds = Found { $total } packages built by the other helper. { $added } new build records were made.
+
+# Languages (lang)
+lang-load = Failed to load language data.
+lang-unknown = Aura has no localisations for { $lang }.
+lang-missing = { $lang } lacks { $count } of the { $total } messages:
+lang-complete = { $lang } is fully translated.
 
 # System Validation (check)
 check-start = Validating your system.
 check-missing-exec = Fix: Please install { $exec } and/or ensure it's on your PATH.
+check-fix = Fixing what can be fixed.
+check-fix-run = Run { $command }?
+check-fix-done = { $count } fixes applied.
+check-fix-failed = A fix failed: { $command }
+check-fix-lock = The database lock { $path } was left behind by a pacman that's no longer running.
+check-fix-mirrors = The mirrorlist hasn't been refreshed for { $days } days.
+check-fix-orphans-adopt = These orphans will be marked as explicitly installed: { $pkgs }
+check-fix-orphans-remove = These orphans will be removed: { $pkgs }
+check-fix-pacnew = { $path }.pacnew only differs from it by comments, and can replace it.
+check-boot = Boot Setup
+check-boot-mounted = /boot is mounted, if it should be?
+check-boot-mounted-fix = /boot is in /etc/fstab, but isn't mounted. Kernel upgrades made now won't reach the bootloader.
+check-boot-no-loader = Unable to find a systemd-boot, UKI, or GRUB configuration.
+check-boot-no-image = { $path } is missing.
+check-boot-stale-image = { $path } isn't the kernel that's installed. Was /boot mounted during the last upgrade?
+check-boot-no-initramfs = No initramfs image was found.
+check-boot-no-entry = No bootloader entry refers to this kernel.
+check-files = Verifying the files of { $count } packages...
+check-files-ok = All files match their packages.
+check-files-no-mtree = No file records for { $pkg }.
+check-files-missing = { $path } is missing.
+check-files-contents = { $path } has different contents.
+check-files-owner = { $path } has a different owner.
+check-files-mode = { $path } has mode { $actual }, not { $expected }.
+check-files-config = { $path } was changed (a config file).
+check-files-unreadable = { $count } files couldn't be read. Try again with sudo to check them too.
 check-env = Environment
 check-env-editor = EDITOR variable set?
 check-env-editor-exec = EDITOR value ({ $exec }) is executable?
@@ -123,8 +388,13 @@ check-env-installed = { $exec } installed and executable?
 check-pconf = Pacman Configuration (/etc/pacman.conf)
 check-pconf-par = Parallel downloads activated?
 check-pconf-par-fix = { $setting } is off, or set to 1. Set { $set } for faster tarball fetching.
+check-pconf-lock = Pacman's database is unlocked?
+check-pconf-lock-held = Currently locked by { $name } (PID { $pid }).
+check-pconf-lock-fix = Nothing holds the lock. If no pacman is running, remove it with { $command }
 check-pconf-ignores = No overlapping ignored packages?
 check-pconf-ignores-fix = The following packages are ignored in both pacman.conf and aura.toml: { $pkgs }
+check-pconf-partial = No risk of a partial upgrade?
+check-pconf-partial-fix = The databases were synced { $days } days after the last full upgrade, and { $count } packages are out of date. Run { $command } before installing anything.
 check-pconf-pacnew = All .pacnew files accounted for?
 check-pconf-pacnew-broken = Error: Call to { $fd } utterly failed.
 
@@ -140,6 +410,44 @@ check-aconf-aura-parse = Aura config file can be parsed?
 check-mconf = Makepkg Configuration (/etc/makepkg.conf)
 check-mconf-packager = PACKAGER set?
 check-mconf-packager-fix = Set { $cmd } within /etc/makepkg.conf
+check-partial-warn = The databases were synced { $days } days after the last full upgrade, and { $count } packages are out of date. Installing single packages now risks a partial upgrade; consider -Syu first.
+check-partial-sy = Refreshing the databases without -u risks a partial upgrade. Consider -Syu instead.
+check-updates = Pending updates:
+check-updates-repo = Official
+check-updates-aur = AUR
+check-updates-failed = Failed to count the updates from { $source }.
+check-updates-none = Everything is up to date.
+check-updates-total = { $total } updates in total.
+check-updates-cached = As of { $time }.
+check-self-latest = Aura { $version } is the latest release.
+check-self-outdated = Aura { $latest } is available (you have { $current }).
+check-self-critical = These releases contain critical fixes: { $versions }
+check-self-aur = Aura was installed from the AUR as { $package }.
+check-self-rebuild = Rebuild it now?
+check-self-manual = Aura wasn't installed from the AUR, so update it however you installed it.
+check-keys = Pacman's Keyring
+check-keys-keyring = Keyring packages are up to date?
+check-keys-keyring-missing = No keyring package is installed.
+check-keys-keyring-fix = Newer versions are available: { $pkgs }
+check-keys-expired = No trusted keys have expired?
+check-keys-expired-fix = { $count } trusted keys have expired. Refreshing the keys may renew them.
+check-keys-unreadable = The keyring couldn't be read.
+check-keys-errors = No recent signature errors?
+check-keys-repair-populate = Re-add the keys from the keyring packages (pacman-key --populate)?
+check-keys-repair-refresh = Refresh all keys from a keyserver (pacman-key --refresh-keys)? This can take a while.
+check-keys-repair-upgrade = Finish with a full system upgrade, including the keyring packages (pacman -Syu)?
+check-pkgs = Installed Packages
+check-pkgs-variants = No package is installed in several variants?
+check-pkgs-variants-fix = { $pkgs } are variants of the same package. Keep one with { $command }
+check-pkgs-conflicts = No conflicting packages are installed together?
+check-pkgs-conflicts-fix = { $a } and { $b } conflict, but both are installed.
+check-restart = Restarts and Reboots
+check-restart-kernel = Running kernel is still installed?
+check-restart-kernel-fix = Kernel { $running } is running, but only { $installed } is installed. Reboot to use the new kernel.
+check-restart-libs = No programs are running with outdated libraries?
+check-restart-libs-fix = Restart these to pick up upgraded libraries: { $procs }
+check-restart-reboot = Kernel { $running } is running, but { $installed } is installed. A reboot is recommended.
+check-restart-services = These programs still use libraries that were just upgraded, and should be restarted: { $procs }
 check-snapshots = Package Snapshots
 check-snapshots-unreadable = Unable to read snapshot path: { $path }
 check-snapshot-usable = All snapshots have corresponding tarballs?
@@ -154,18 +462,99 @@ check-cache-missing-fix = Fix: You can download missing official tarballs with {
 check-cache-missing-for = Every installed AUR package has a tarball?
 check-cache-missing-for-fix = Fix: View the missing packages with { $cmd } and reinstall them manually.
 
+# Bug Reports (debug)
+debug-start = Gathering details for a bug report:
+debug-done = Done. Please attach { $file } to your issue.
+debug-tar = Failed to pack the bug report with tar.
+debug-bench-start = Resolving { $pkgs }, { $runs } times:
+debug-bench-run = Run { $run }: { $secs }s
+debug-bench-graph = { $build } to build, { $install } from the repos, in { $layers } layers. Ordered in { $secs }s.
+debug-bench-warm = After the first run: min { $min }s, mean { $mean }s, max { $max }s
+
+# Build Artifacts (clean)
+clean-builds = Build directories:
+clean-builds-none = No build directories to clean.
+clean-builds-remove = Remove them?
+clean-del = Failed to delete: { $dir }
+
+# Reproducibility (verify)
+verify-not-installed = { $pkg } is not installed.
+verify-no-tarball = The originally installed tarball of { $pkg } is not in the cache.
+verify-no-hash = The commit this package was installed from is unknown. Building from the latest instead.
+verify-building = Rebuilding { $pkg } in a clean directory...
+verify-identical = Rebuilt package is bit-for-bit identical.
+verify-files-match = Package files are identical, but its metadata differs.
+verify-differs = Rebuilt package differs in:
+verify-mtree = Failed to read the .MTREE of a tarball.
+
+# Fleet Reports (report)
+report-sign = Failed to sign the report with gpg.
+report-bad-file = Not a readable report: { $file }
+report-none = No reports were found.
+report-host = Host
+report-packages = Packages
+report-foreign = Foreign
+report-orphans = Orphans
+report-repo = Repo Updates
+report-aur = AUR Updates
+report-advisories = Advisories
+report-signed = Signed
+report-time = Reported
+
+# External Subcommands
+external-not-found = { $cmd } is not an Aura command, and no aura-{ $cmd } was found on your PATH.
+external-failed = { $cmd } failed.
+
+# Aliases
+alias-cycle = Cyclic aliases: { $cycle }
+
 # Configuration (conf)
 conf-toml-err = Failed to serialize current config.
 
 # Runtime Environment
 env-missing-editor = Provided EDITOR is not on the PATH.
+env-missing-aria2 = The aria2 downloader was requested, but aria2c is not on the PATH.
+env-missing-namcap = namcap linting was requested, but namcap is not on the PATH.
+env-missing-devtools = A chroot build was requested, but makechrootpkg is not on the PATH.
+env-missing-systemd-run = Build memory or CPU limits were requested, but systemd-run is not on the PATH.
+env-unknown-profile = No build profile named { $profile } is defined in aura.toml.
+env-chroot-profile = The build profile { $profile } has a chroot, so it can't also set env or makepkg_conf. Set them in the chroot's own makepkg.conf instead.
 env-pconf = Failed to parse your pacman.conf file.
 
+# Changes to /etc
+etc-unchanged = No files in /etc were changed.
+etc-changed = { $count } files in /etc were changed:
+
+# Locks
+lock-held = Another Aura instance is running: { $cmd }, started { $time }. Use --wait to wait for it to finish.
+lock-waiting = Waiting for another Aura instance to finish: { $cmd }, started { $time }
+
+# Notifications
+notify-failed = Failed to send the upgrade summary to { $target }.
+
+# Logging
+log-file-open = Failed to open the log file { $file }.
+
+# Timings (--timings)
+timings-header = Time spent in each phase:
+timings-metadata = Metadata fetch
+timings-resolution = Dependency resolution
+timings-clones = Clones
+timings-builds = Builds
+timings-install = Installation
+timings-total = Total
+
 # Pacman Calls
 pacman-external = A call to pacman utterly failed.
 pacman-u = A call to pacman -U failed.
 pacman-s = A call to pacman -S failed.
 pacman-misc = A call to pacman gave a non-zero exit code.
+pacman-db-held = Pacman's database is locked by { $name } (PID { $pid }). Wait for it to finish, then try again.
+pacman-db-stale = Pacman's database is locked ({ $file }), but no running program seems to hold the lock.
+pacman-db-remove = Remove the lock?
+pacman-db-kept = Pacman's database is still locked: { $file }
+pacman-hooks = Hooks that ran:
+pacman-hooks-failed = These hooks reported errors, and may need to be rerun by hand: { $hooks }
 
 # ALPM
 alpm-tx = "An ALPM transaction failed."
@@ -174,6 +563,7 @@ alpm-tx = "An ALPM transaction failed."
 dir-mkdir = Failed to create the directory: { $dir }.
 dir-home = Unable to determine Aura's config directory.
 dir-cache = Unable to determine Aura's cache directory.
+dir-state = Unable to determine Aura's state directory.
 
 # Dependency Resolution
 dep-exist = The package { $pkg } does not exist.
@@ -188,16 +578,19 @@ git-hash = Reading a git hash into Rust failed.
 git-pull = A git pull failed: { $dir }
 git-clone = A git clone failed: { $dir }
 git-io = Calling git somehow failed.
+git-push = A git push failed: { $dir }
+git-checkout = A git checkout failed: { $dir }
 
 # Faur Calls
 faur-fetch = Calling the Faur utterly failed: { $pkg }
 faur-unknown = Unknown package: { $pkg }
 faur-too-many = More results returned from Faur than expected: { $pkg }
+suggest-did-you-mean = Instead of { $pkg }, did you mean: { $suggestions }?
 
 # Common Errors
 err-alpm = Failed to open ALPM handle.
 err-config-path = Failed to determine the path to Aura's config file.
-err-curl = A CURL transaction failed.
+err-curl = A CURL transaction with { $url } failed (attempt { $attempt }).
 err-file-del = Failed to delete: { $file }
 err-file-open = Failed to open file handle to: { $file }
 err-file-write = Failed to write file: { $file }
@@ -208,6 +601,7 @@ err-none-exist = None of the specified packages exist.
 err-pool-create = Failed to create an ALPM connection pool.
 err-pool-get = Failed to get an ALPM handle from the connection pool.
 err-read-dir = Failed to read directory: { $dir }
+err-read-only = A read-only command tried to change the system.
 err-srcinfo = Failed to parse .SRCINFO: { $file }
 err-sudo = Failed to raise privileges.
 err-time-conv = Failed to convert a timestamp.
@@ -228,8 +622,15 @@ common-no-packages = No packages specified.
 common-no-valid = No valid packages specified.
 common-no-work = Nothing to do.
 common-cancelled = Action cancelled.
+common-interrupted = Interrupted.
+common-interrupted-resume = Interrupted. Packages that finished building (or were installed) are kept, so running the same command again picks up where this one left off.
+
+# Package Selection
+select-matched = Your patterns matched { $count } packages:
+select-none = Nothing matched: { $patterns }
 
 # Misc.
 proceed = Proceed?
 proceed-yes = [Y/n]
 proceed-no = [y/N]
+prompt-type-count = Type the number of packages affected to confirm:
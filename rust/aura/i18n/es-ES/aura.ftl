@@ -0,0 +1,115 @@
+language-name = Español
+
+# AUR Packages (-A)
+A-install-unreal = { $pkg } no es un paquete real.
+A-install-cloning = Clonando paquetes nuevos...
+A-install-pulling = Actualizando paquetes conocidos...
+A-install-deps = Resolviendo dependencias...
+A-install-repo-pkgs = Dependencias de los repositorios:
+A-install-aur-pkgs = Paquetes del AUR:
+
+A-build-prep = Preparando los directorios de compilación...
+A-build-pkg = Compilando { $pkg }...
+A-build-diff = ¿Mostrar los cambios en los archivos de compilación?
+A-build-hotedit-pkgbuild = ¿Editar el PKGBUILD?
+A-build-hotedit-install = ¿Editar el archivo .install?
+A-build-fail = No se pudo compilar el paquete:
+A-build-e-makepkg = makepkg falló.
+A-build-continue = ¿Seguir compilando los demás paquetes?
+
+A-i-repo = Repositorio
+A-i-version = Versión
+A-i-status = Estado en el AUR
+A-i-maintainer = Mantenedor
+A-i-proj-url = URL del proyecto
+A-i-aur-url = URL del AUR
+A-i-license = Licencia
+A-i-group = Grupos
+A-i-provides = Provee
+A-i-depends = Depende de
+A-i-make = Dependencias de compilación
+A-i-opt = Dependencias opcionales
+A-i-check = Dependencias de pruebas
+A-i-votes = Votos
+A-i-pop = Popularidad
+A-i-desc = Descripción
+A-i-keywords = Palabras clave
+A-i-submitted = Enviado
+A-i-updated = Actualizado
+
+A-u-fetch-info = Obteniendo información de los paquetes...
+A-u-comparing = Comparando versiones de los paquetes...
+A-u-no-upgrades = Los paquetes del AUR no necesitan actualizarse.
+A-u-to-upgrade = Paquetes del AUR por actualizar:
+
+A-w = Clonando { $package }...
+A-w-fail = No se pudieron clonar los siguientes paquetes:
+
+# Cache (-C)
+C-size = Tamaño actual de la caché: { $size }
+C-c-keep = Se conservarán { $pkgs } archivos de cada paquete. El resto se borrará.
+C-c-freed = Se liberaron { $bytes }.
+C-downgrade-which = ¿Qué versión de { $pkg } quieres?
+
+# Orphans (-O)
+O-abandon = Se eliminarán los siguientes paquetes huérfanos y sus dependencias:
+O-adopt = { $pkg } ahora está marcado como instalado explícitamente.
+O-explicit-err = No se pudo marcar { $pkg } como instalado explícitamente.
+
+# Logs (-L)
+L-first = Primera instalación
+L-upgrades = Actualizaciones
+L-recent = Acciones recientes
+L-view-err = No se pudo abrir tu registro de ALPM.
+
+# Package Information (info)
+info-installed = Instalado
+info-reason = Motivo de instalación
+info-install-date = Fecha de instalación
+info-explicit = Instalado explícitamente
+info-dependency = Instalado como dependencia
+info-unknown = { $pkg } no es un paquete local, de los repositorios ni del AUR.
+
+# Languages (lang)
+lang-load = No se pudieron cargar los datos de idioma.
+lang-unknown = Aura no tiene traducciones para { $lang }.
+lang-missing = A { $lang } le faltan { $count } de los { $total } mensajes:
+lang-complete = { $lang } está traducido por completo.
+
+# Aura-specific Directories
+dir-mkdir = No se pudo crear el directorio: { $dir }.
+
+# Dependency Resolution
+dep-exist = El paquete { $pkg } no existe.
+dep-exist-par = La dependencia { $pkg } de { $par } no existe.
+dep-graph = El grafo de dependencias está mal formado.
+dep-cycle = Hay una dependencia cíclica que involucra a: { $pkg }
+dep-multi = Hubo varios errores al resolver las dependencias.
+
+# Common Errors
+err-alpm = No se pudo abrir el manejador de ALPM.
+err-file-del = No se pudo borrar: { $file }
+err-file-open = No se pudo abrir: { $file }
+err-file-write = No se pudo escribir el archivo: { $file }
+err-none-exist = Ninguno de los paquetes indicados existe.
+err-read-dir = No se pudo leer el directorio: { $dir }
+err-srcinfo = No se pudo analizar el .SRCINFO: { $file }
+err-sudo = No se pudieron elevar los privilegios.
+err-user-input = No se pudo leer la entrada del usuario.
+err-utf8 = Falló una conversión a UTF-8.
+
+# Common Fields
+common-yes = Sí
+common-no = No
+common-none = Ninguno
+common-name = Nombre
+common-done = Hecho.
+common-total = Total
+common-no-packages = No se indicó ningún paquete.
+common-no-valid = No se indicó ningún paquete válido.
+common-no-work = No hay nada que hacer.
+common-cancelled = Acción cancelada.
+common-interrupted = Interrumpido.
+
+# Misc.
+proceed = ¿Continuar?
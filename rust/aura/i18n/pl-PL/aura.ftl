@@ -0,0 +1,115 @@
+language-name = Polski
+
+# AUR Packages (-A)
+A-install-unreal = { $pkg } nie jest prawdziwym pakietem.
+A-install-cloning = Klonowanie nowych pakietów...
+A-install-pulling = Aktualizowanie znanych pakietów...
+A-install-deps = Rozwiązywanie zależności...
+A-install-repo-pkgs = Zależności z repozytoriów:
+A-install-aur-pkgs = Pakiety z AUR:
+
+A-build-prep = Przygotowywanie katalogów budowania...
+A-build-pkg = Budowanie { $pkg }...
+A-build-diff = Pokazać zmiany w plikach budowania?
+A-build-hotedit-pkgbuild = Edytować PKGBUILD?
+A-build-hotedit-install = Edytować plik .install?
+A-build-fail = Nie udało się zbudować pakietu:
+A-build-e-makepkg = makepkg zakończył się błędem.
+A-build-continue = Kontynuować budowanie pozostałych pakietów?
+
+A-i-repo = Repozytorium
+A-i-version = Wersja
+A-i-status = Stan w AUR
+A-i-maintainer = Opiekun
+A-i-proj-url = Strona projektu
+A-i-aur-url = Strona w AUR
+A-i-license = Licencja
+A-i-group = Grupy
+A-i-provides = Dostarcza
+A-i-depends = Zależy od
+A-i-make = Zależności budowania
+A-i-opt = Zależności opcjonalne
+A-i-check = Zależności testów
+A-i-votes = Głosy
+A-i-pop = Popularność
+A-i-desc = Opis
+A-i-keywords = Słowa kluczowe
+A-i-submitted = Dodano
+A-i-updated = Zaktualizowano
+
+A-u-fetch-info = Pobieranie informacji o pakietach...
+A-u-comparing = Porównywanie wersji pakietów...
+A-u-no-upgrades = Pakiety z AUR nie wymagają aktualizacji.
+A-u-to-upgrade = Pakiety z AUR do aktualizacji:
+
+A-w = Klonowanie { $package }...
+A-w-fail = Nie udało się sklonować następujących pakietów:
+
+# Cache (-C)
+C-size = Obecny rozmiar pamięci podręcznej: { $size }
+C-c-keep = Z każdego pakietu zostanie zachowanych { $pkgs } plików. Reszta zostanie usunięta.
+C-c-freed = Zwolniono { $bytes }.
+C-downgrade-which = Którą wersję { $pkg } wybierasz?
+
+# Orphans (-O)
+O-abandon = Następujące osierocone pakiety oraz ich zależności zostaną usunięte:
+O-adopt = { $pkg } oznaczono jako zainstalowany bezpośrednio.
+O-explicit-err = Nie udało się oznaczyć { $pkg } jako zainstalowanego bezpośrednio.
+
+# Logs (-L)
+L-first = Pierwsza instalacja
+L-upgrades = Aktualizacje
+L-recent = Ostatnie działania
+L-view-err = Nie udało się otworzyć dziennika ALPM.
+
+# Package Information (info)
+info-installed = Zainstalowany
+info-reason = Powód instalacji
+info-install-date = Data instalacji
+info-explicit = Zainstalowany bezpośrednio
+info-dependency = Zainstalowany jako zależność
+info-unknown = { $pkg } nie jest pakietem lokalnym, z repozytoriów ani z AUR.
+
+# Languages (lang)
+lang-load = Nie udało się wczytać danych językowych.
+lang-unknown = Aura nie ma tłumaczeń dla { $lang }.
+lang-missing = W { $lang } brakuje { $count } z { $total } komunikatów:
+lang-complete = { $lang } jest w pełni przetłumaczony.
+
+# Aura-specific Directories
+dir-mkdir = Nie udało się utworzyć katalogu: { $dir }.
+
+# Dependency Resolution
+dep-exist = Pakiet { $pkg } nie istnieje.
+dep-exist-par = Zależność { $pkg } pakietu { $par } nie istnieje.
+dep-graph = Graf zależności jest w jakiś sposób uszkodzony.
+dep-cycle = Wykryto cykliczną zależność obejmującą: { $pkg }
+dep-multi = Podczas rozwiązywania zależności wystąpiło wiele błędów.
+
+# Common Errors
+err-alpm = Nie udało się otworzyć uchwytu ALPM.
+err-file-del = Nie udało się usunąć: { $file }
+err-file-open = Nie udało się otworzyć: { $file }
+err-file-write = Nie udało się zapisać pliku: { $file }
+err-none-exist = Żaden z podanych pakietów nie istnieje.
+err-read-dir = Nie udało się odczytać katalogu: { $dir }
+err-srcinfo = Nie udało się przetworzyć .SRCINFO: { $file }
+err-sudo = Nie udało się podnieść uprawnień.
+err-user-input = Nie udało się odczytać danych od użytkownika.
+err-utf8 = Konwersja UTF-8 nie powiodła się.
+
+# Common Fields
+common-yes = Tak
+common-no = Nie
+common-none = Brak
+common-name = Nazwa
+common-done = Gotowe.
+common-total = Razem
+common-no-packages = Nie podano żadnych pakietów.
+common-no-valid = Nie podano żadnych prawidłowych pakietów.
+common-no-work = Nie ma nic do zrobienia.
+common-cancelled = Działanie anulowane.
+common-interrupted = Przerwano.
+
+# Misc.
+proceed = Kontynuować?
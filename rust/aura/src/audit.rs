@@ -0,0 +1,81 @@
+//! A record of every elevated command Aura runs.
+//!
+//! Each `sudo` call is appended as a line of JSON to a log that Aura itself
+//! never rewrites, so that the history of what was run with raised privileges
+//! (and how it went) can be reviewed later with `aura log --audit`.
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process::{Command, ExitStatus};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// A single elevated call.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Entry {
+    /// When the call finished.
+    pub(crate) time: String,
+    /// The full command line, starting with `sudo`.
+    pub(crate) argv: Vec<String>,
+    /// The exit code, if the call ended normally. Absent when it was killed by
+    /// a signal or never returned, as when Aura re-runs itself under `sudo`.
+    pub(crate) status: Option<i32>,
+}
+
+/// Like [`Command::status`], but the call and its outcome are recorded.
+pub(crate) fn status(cmd: &mut Command) -> std::io::Result<ExitStatus> {
+    aura_core::explain::show(cmd);
+    let status = cmd.status();
+    record(cmd, status.as_ref().ok());
+    status
+}
+
+/// Record a call that has already been made.
+pub(crate) fn record(cmd: &Command, status: Option<&ExitStatus>) {
+    let argv = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+
+    write(argv, status.and_then(|s| s.code()));
+}
+
+/// Record that Aura is about to re-run itself under `sudo`.
+pub(crate) fn escalation() {
+    let argv = std::iter::once("sudo".to_string())
+        .chain(std::env::args())
+        .collect();
+
+    write(argv, None);
+}
+
+fn write(argv: Vec<String>, status: Option<i32>) {
+    let entry = Entry {
+        time: OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_default(),
+        argv,
+        status,
+    };
+
+    // A failure to record is reported, but never stops the work itself.
+    match crate::dirs::audit_log() {
+        Err(_) => warn!("Failed to locate the audit log."),
+        Ok(path) => {
+            debug!("Auditing to {}", path.display());
+
+            let json = serde_json::to_string(&entry).unwrap_or_default();
+            let result = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| writeln!(file, "{json}"));
+
+            if let Err(e) = result {
+                warn!("Failed to write to {}: {e}", path.display());
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! Which commands may change the system.
+//!
+//! Anything that raises privileges, like [`sudo`](https://www.sudo.ws/) calls
+//! to `pacman`, requires a [`Mutating`] token as proof that the running command
+//! is allowed to. Only [`mutating`] can create one, and it refuses to for
+//! read-only commands like `-Ss` or `info`. A query can thus never spawn an
+//! elevated process by accident, no matter how the code around it changes.
+
+use crate::flags::SubCmd;
+use std::ops::Not;
+
+/// Permission to change the system: to raise privileges, and to write outside
+/// of Aura's own caches.
+#[derive(Debug)]
+pub struct Mutating {
+    _private: (),
+}
+
+/// Permission to change the system, unless the given command is read-only.
+pub fn mutating(cmd: &SubCmd) -> Option<Mutating> {
+    cmd.is_read_only().not().then(|| Mutating { _private: () })
+}
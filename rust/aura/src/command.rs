@@ -1,13 +1,31 @@
 //! All Aura commands.
 
+pub(crate) mod alias;
 pub(crate) mod aur;
 pub(crate) mod cache;
+pub(crate) mod canary;
 pub(crate) mod check;
+pub(crate) mod clean;
 pub(crate) mod conf;
+pub(crate) mod debug;
 pub(crate) mod deps;
+pub(crate) mod external;
+pub(crate) mod info;
+pub(crate) mod lang;
 pub(crate) mod log;
+pub(crate) mod migrate;
 pub(crate) mod misc;
 pub(crate) mod open;
+pub(crate) mod optdeps;
 pub(crate) mod orphans;
+pub(crate) mod pin;
+pub(crate) mod provides;
+pub(crate) mod report;
+pub(crate) mod search;
+pub(crate) mod select;
+pub(crate) mod setup;
 pub(crate) mod snapshot;
 pub(crate) mod stats;
+pub(crate) mod switch;
+pub(crate) mod upgrade;
+pub(crate) mod verify;
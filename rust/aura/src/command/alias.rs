@@ -0,0 +1,24 @@
+//! User-defined shorthands for longer Aura invocations.
+
+use crate::aura;
+use crate::env::Env;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+
+/// Display each alias and what it expands to.
+pub(crate) fn list(fll: &FluentLanguageLoader, env: &Env) {
+    if env.aliases.is_empty() {
+        aura!(fll, "alias-none");
+    } else {
+        let longest = env
+            .aliases
+            .keys()
+            .map(|k| k.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        for (name, expansion) in env.aliases.iter() {
+            println!(" {:w$} = {}", name.cyan(), expansion, w = longest);
+        }
+    }
+}
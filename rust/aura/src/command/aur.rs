@@ -1,14 +1,31 @@
 //! All functionality involving the `-A` command.
 
-mod build;
+mod binary;
+pub(crate) mod build;
+mod memo;
+mod namcap;
+mod nocheck;
+mod power;
+pub(crate) mod provenance;
+mod push;
+mod sources;
+pub(crate) mod stale;
+pub(crate) mod trend;
+pub(crate) mod variants;
 
-use crate::env::Env;
+use crate::env::{Env, Network};
 use crate::error::Nested;
 use crate::localization::Localised;
+use crate::reporter::Outcome;
 use crate::utils::{Finished, PathStr, ResultVoid, NOTHING};
-use crate::{aura, green, proceed};
+use crate::{aura, green, proceed, red, yellow};
 use alpm::Alpm;
+use aura::caps::Mutating;
+use aura_core::aur::dependencies::{Buildable, Official};
+use aura_core::listing::Listing;
+use aura_core::template::Template;
 use aura_core::Apply;
+use aura_core::Coded;
 use colored::{ColoredString, Colorize};
 use from_variants::FromVariants;
 use i18n_embed::{fluent::FluentLanguageLoader, LanguageLoader};
@@ -16,14 +33,18 @@ use i18n_embed_fl::fl;
 use linya::Progress;
 use log::{debug, error, info};
 use rayon::prelude::*;
-use srcinfo::Srcinfo;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::ops::Not;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use time::OffsetDateTime;
+use ubyte::ToByteUnit;
+
+/// The least disk space assumed to be needed by an AUR package whose size
+/// can't be guessed.
+const MIN_BUILD: u64 = 64 * 1024 * 1024;
 
 #[derive(FromVariants)]
 pub(crate) enum Error {
@@ -31,12 +52,17 @@ pub(crate) enum Error {
     Dirs(crate::dirs::Error),
     Git(aura_core::git::Error),
     Build(build::Error),
+    Push(push::Error),
+    Provenance(provenance::Error),
     Deps(aura_core::aur::dependencies::Error<crate::fetch::Error>),
     Pacman(crate::pacman::Error),
     Env(crate::env::Error),
     Aur(aura_core::aur::Error),
+    Template(aura_core::template::Error),
+    Listing(aura_core::listing::Error),
+    Space(crate::space::Shortfall),
     #[from_variants(skip)]
-    Srcinfo(PathBuf, srcinfo::Error),
+    Srcinfo(PathBuf, aura_core::metadata::Error),
     #[from_variants(skip)]
     PathComponent(PathBuf),
     #[from_variants(skip)]
@@ -46,6 +72,9 @@ pub(crate) enum Error {
     DateConv(time::error::ComponentRange),
     NoPackages,
     Cancelled,
+    Deferred,
+    PowerDeferred,
+    Readline(rustyline::error::ReadlineError),
     Stdout,
 }
 
@@ -56,16 +85,24 @@ impl Nested for Error {
             Error::Dirs(e) => e.nested(),
             Error::Git(e) => e.nested(),
             Error::Build(e) => e.nested(),
+            Error::Push(e) => e.nested(),
+            Error::Provenance(e) => e.nested(),
             Error::Deps(e) => e.nested(),
             Error::Pacman(e) => e.nested(),
             Error::Env(e) => e.nested(),
             Error::Aur(e) => e.nested(),
+            Error::Template(e) => error!("{e}"),
+            Error::Listing(e) => error!("{e}"),
+            Error::Space(_) => {}
             Error::Srcinfo(_, e) => error!("{e}"),
             Error::PathComponent(_) => {}
             Error::FileOpen(_, e) => error!("{e}"),
             Error::FileWrite(_, e) => error!("{e}"),
             Error::NoPackages => {}
             Error::Cancelled => {}
+            Error::Deferred => {}
+            Error::PowerDeferred => {}
+            Error::Readline(e) => error!("{e}"),
             Error::Stdout => {}
             Error::DateConv(e) => error!("{e}"),
         }
@@ -79,13 +116,21 @@ impl Localised for Error {
             Error::Dirs(e) => e.localise(fll),
             Error::Git(e) => e.localise(fll),
             Error::Build(e) => e.localise(fll),
+            Error::Push(e) => e.localise(fll),
+            Error::Provenance(e) => e.localise(fll),
             Error::Deps(e) => e.localise(fll),
             Error::Pacman(e) => e.localise(fll),
             Error::Env(e) => e.localise(fll),
             Error::Aur(e) => e.localise(fll),
+            Error::Template(e) => fl!(fll, "template-parse", err = e.to_string()),
+            Error::Listing(e) => fl!(fll, "listing-parse", err = e.to_string()),
+            Error::Space(s) => s.localise(fll),
             Error::Srcinfo(p, _) => fl!(fll, "err-srcinfo", file = p.utf8()),
             Error::PathComponent(p) => fl!(fll, "A-install-path-comp", path = p.utf8()),
             Error::Cancelled => fl!(fll, "common-cancelled"),
+            Error::Deferred => fl!(fll, "A-metered-deferred"),
+            Error::PowerDeferred => fl!(fll, "A-power-deferred"),
+            Error::Readline(_) => fl!(fll, "err-user-input"),
             Error::NoPackages => fl!(fll, "common-no-packages"),
             Error::Stdout => fl!(fll, "err-write"),
             Error::FileOpen(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
@@ -95,19 +140,145 @@ impl Localised for Error {
     }
 }
 
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Fetch(e) => e.code(),
+            Error::Dirs(e) => e.code(),
+            Error::Git(e) => e.code(),
+            Error::Build(e) => e.code(),
+            Error::Push(e) => e.code(),
+            Error::Provenance(e) => e.code(),
+            Error::Deps(e) => e.code(),
+            Error::Pacman(e) => e.code(),
+            Error::Env(e) => e.code(),
+            Error::Aur(e) => e.code(),
+            Error::Template(e) => e.code(),
+            Error::Listing(e) => e.code(),
+            Error::Space(_) => "aur-space",
+            Error::Srcinfo(_, _) => "aur-srcinfo",
+            Error::PathComponent(_) => "aur-path-component",
+            Error::FileOpen(_, _) => "aur-file-open",
+            Error::FileWrite(_, _) => "aur-file-write",
+            Error::DateConv(_) => "aur-date-conv",
+            Error::NoPackages => "aur-no-packages",
+            Error::Cancelled => "aur-cancelled",
+            Error::Deferred => "aur-deferred",
+            Error::PowerDeferred => "aur-power-deferred",
+            Error::Readline(_) => "aur-readline",
+            Error::Stdout => "aur-stdout",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Fetch(e) => e.context(),
+            Error::Dirs(e) => e.context(),
+            Error::Git(e) => e.context(),
+            Error::Build(e) => e.context(),
+            Error::Push(e) => e.context(),
+            Error::Provenance(e) => e.context(),
+            Error::Deps(e) => e.context(),
+            Error::Pacman(e) => e.context(),
+            Error::Env(e) => e.context(),
+            Error::Aur(e) => e.context(),
+            Error::Template(e) => e.context(),
+            Error::Listing(e) => e.context(),
+            Error::Space(s) => s.context(),
+            Error::Srcinfo(p, _) => vec![("path", p.display().to_string())],
+            Error::PathComponent(p) => vec![("path", p.display().to_string())],
+            Error::FileOpen(p, _) => vec![("path", p.display().to_string())],
+            Error::FileWrite(p, _) => vec![("path", p.display().to_string())],
+            Error::DateConv(_)
+            | Error::NoPackages
+            | Error::Cancelled
+            | Error::Deferred
+            | Error::PowerDeferred
+            | Error::Readline(_)
+            | Error::Stdout => Vec::new(),
+        }
+    }
+}
+
+/// The fields of AUR packages available to `--format`.
+pub(crate) const FIELDS: &[&str] = &[
+    "name",
+    "base",
+    "version",
+    "votes",
+    "popularity",
+    "description",
+    "maintainer",
+    "url",
+    "aur-url",
+    "license",
+    "depends",
+    "provides",
+    "keywords",
+    "submitted",
+    "updated",
+    "out-of-date",
+];
+
+/// The value of one of the [`FIELDS`] of an AUR package.
+pub(crate) fn field(p: &aura_core::faur::Package, field: &str) -> String {
+    let date = |epoch: u64| {
+        OffsetDateTime::from_unix_timestamp(epoch as i64)
+            .map(|t| t.date().to_string())
+            .unwrap_or_else(|_| epoch.to_string())
+    };
+
+    match field {
+        "name" => p.name.clone(),
+        "base" => p.package_base.clone(),
+        "version" => p.version.clone(),
+        "votes" => p.num_votes.to_string(),
+        "popularity" => format!("{:.2}", p.popularity),
+        "description" => p.description.clone().unwrap_or_default(),
+        "maintainer" => p.maintainer.clone().unwrap_or_default(),
+        "url" => p.url.clone().unwrap_or_default(),
+        "aur-url" => package_url(&p.name),
+        "license" => p.license.join(" "),
+        "depends" => p.depends.join(" "),
+        "provides" => p.provides.join(" "),
+        "keywords" => p.keywords.join(" "),
+        "submitted" => date(p.first_submitted),
+        "updated" => date(p.last_modified),
+        "out-of-date" => p.out_of_date.map(date).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
 /// View AUR package information.
-pub(crate) fn info(fll: &FluentLanguageLoader, packages: &[String]) -> Result<(), Error> {
+pub(crate) fn info(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    net: &Network,
+    packages: &[String],
+    format: Option<&str>,
+) -> Result<(), Error> {
     info!("-Ai on {:?}", packages);
-    let r: Vec<aura_core::faur::Package> = aura_core::faur::info(
-        packages.iter().map(|s| s.as_str()),
-        &crate::fetch::fetch_json,
-    )?;
+    let format = format.map(|f| Template::parse(f, FIELDS)).transpose()?;
+    let db = alpm.localdb();
+    let names: Vec<&str> = packages.iter().map(|s| s.as_str()).collect();
+    let r = aura_core::faur::info_batched(&names, &|u: &str| net.json(u))?;
+    trend::record(r.iter().filter_map(|(_, p)| p.as_ref()));
     let mut w = BufWriter::new(std::io::stdout());
 
+    if let Some(t) = format {
+        for p in r.iter().filter_map(|(_, p)| p.as_ref()) {
+            writeln!(w, "{}", t.render(|f| field(p, f))).map_err(|_| Error::Stdout)?;
+        }
+
+        return Ok(());
+    }
+
     let repo = fl!(fll, "A-i-repo");
     let name = fl!(fll, "common-name");
     let ver = fl!(fll, "A-i-version");
     let stat = fl!(fll, "A-i-status");
+    let fresh = fl!(fll, "A-i-up-to-date");
+    let stale = fl!(fll, "A-i-out-of-date");
     let main = fl!(fll, "A-i-maintainer");
     let proj = fl!(fll, "A-i-proj-url");
     let aur = fl!(fll, "A-i-aur-url");
@@ -124,8 +295,28 @@ pub(crate) fn info(fll: &FluentLanguageLoader, packages: &[String]) -> Result<()
     let keys = fl!(fll, "A-i-keywords");
     let sub = fl!(fll, "A-i-submitted");
     let upd = fl!(fll, "A-i-updated");
+    let alt = fl!(fll, "A-i-alternatives");
+    let installed = fl!(fll, "A-i-installed");
+
+    let found: Vec<&aura_core::faur::Package> = r.iter().filter_map(|(_, p)| p.as_ref()).collect();
+    let mut alternatives = variants::alternatives(net, &found).unwrap_or_else(|e| {
+        debug!("Failed to find alternatives");
+        e.nested();
+        HashMap::new()
+    });
+
+    for (n, p) in r {
+        let p = match p {
+            Some(p) => p,
+            None => {
+                let missing = fl!(fll, "A-i-not-found", pkg = n);
+                writeln!(w, "{}\n", missing.red()).map_err(|_| Error::Stdout)?;
+                continue;
+            }
+        };
+
+        let alternatives = alternatives.remove(&p.name).unwrap_or_default();
 
-    for p in r {
         let pairs: Vec<(&str, ColoredString)> = vec![
             (&repo, "aur".magenta()),
             (
@@ -140,8 +331,8 @@ pub(crate) fn info(fll: &FluentLanguageLoader, packages: &[String]) -> Result<()
             (
                 &stat,
                 match p.out_of_date {
-                    None => "Up to Date".green(),
-                    Some(_) => "Out of Date!".red(),
+                    None => fresh.green(),
+                    Some(_) => stale.red(),
                 },
             ),
             (
@@ -176,24 +367,134 @@ pub(crate) fn info(fll: &FluentLanguageLoader, packages: &[String]) -> Result<()
             (&upd, package_date(p.last_modified)?),
         ];
         crate::utils::info(&mut w, fll.current_language(), &pairs).map_err(|_| Error::Stdout)?;
+
+        if alternatives.is_empty().not() {
+            writeln!(w, "{}", alt.bold()).map_err(|_| Error::Stdout)?;
+            for a in alternatives {
+                let ins = match db.pkg(a.name.as_str()) {
+                    Ok(_) => format!(" [{}]", installed).bold(),
+                    Err(_) => "".normal(),
+                };
+                let votes = format!("({})", a.num_votes).yellow();
+                writeln!(w, "  {} {}{}", a.name.cyan(), votes, ins).map_err(|_| Error::Stdout)?;
+            }
+        }
+
         writeln!(w).map_err(|_| Error::Stdout)?;
     }
 
     Ok(())
 }
 
+/// Print the AUR's records of packages as JSON. Nothing is localised or
+/// trimmed, and unless fields are given, the records are exactly as fetched.
+/// Otherwise only those fields are kept, in the order given. Packages that
+/// don't exist are simply absent.
+pub(crate) fn json(net: &Network, packages: &[String], fields: &[String]) -> Result<(), Error> {
+    info!("-A --json on {:?}", packages);
+    let names: Vec<&str> = packages.iter().map(|s| s.as_str()).collect();
+    let mut bodies: Vec<(String, String)> = Vec::new();
+
+    for chunk in names.chunks(aura_core::faur::BATCH_SIZE) {
+        let url = format!(
+            "{}/packages?names={}",
+            aura_core::faur::FAUR_URL,
+            chunk.join(",")
+        );
+        let body = String::from_utf8_lossy(&net.bytes(&url)?).into_owned();
+        bodies.push((url, body));
+    }
+
+    let mut w = BufWriter::new(std::io::stdout());
+
+    if fields.is_empty() {
+        let raw: Vec<&str> = bodies.iter().map(|(_, b)| b.as_str()).collect();
+        let all = splice(&raw);
+        write!(w, "{}", all).map_err(|_| Error::Stdout)?;
+        if all.ends_with('\n').not() {
+            writeln!(w).map_err(|_| Error::Stdout)?;
+        }
+    } else {
+        let mut records = Vec::new();
+        for (url, body) in bodies {
+            let batch: Vec<serde_json::Map<String, serde_json::Value>> =
+                serde_json::from_str(&body)
+                    .map_err(|e| crate::fetch::Error::Json(url.clone(), e))?;
+            records.extend(batch.into_iter().map(|r| Picked::from_record(r, fields)));
+        }
+
+        serde_json::to_writer_pretty(&mut w, &records).map_err(|_| Error::Stdout)?;
+        writeln!(w).map_err(|_| Error::Stdout)?;
+    }
+
+    Ok(())
+}
+
+/// Join the JSON arrays of several responses into one, leaving each record
+/// untouched. A lone response is left as it is.
+fn splice(bodies: &[&str]) -> String {
+    match bodies {
+        [one] => one.to_string(),
+        _ => {
+            let inner: Vec<&str> = bodies
+                .iter()
+                .map(|b| b.trim())
+                .map(|b| b.strip_prefix('[').unwrap_or(b))
+                .map(|b| b.strip_suffix(']').unwrap_or(b))
+                .map(|b| b.trim())
+                .filter(|b| b.is_empty().not())
+                .collect();
+
+            format!("[{}]", inner.join(","))
+        }
+    }
+}
+
+/// Some fields of an AUR record, in the order they were asked for.
+struct Picked(Vec<(String, serde_json::Value)>);
+
+impl Picked {
+    fn from_record(
+        mut record: serde_json::Map<String, serde_json::Value>,
+        fields: &[String],
+    ) -> Picked {
+        let picked = fields
+            .iter()
+            .filter_map(|f| {
+                let key = record.keys().find(|k| k.eq_ignore_ascii_case(f))?.clone();
+                record.remove(&key).map(|v| (key, v))
+            })
+            .collect();
+
+        Picked(picked)
+    }
+}
+
+impl serde::Serialize for Picked {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+    }
+}
+
 /// Search the AUR via a search string.
 ///
 /// Thanks to `clap`, the `terms` slice is guaranteed to be non-empty.
-pub(crate) fn search(
-    alpm: &Alpm,
-    alpha: bool,
-    rev: bool,
-    limit: Option<usize>,
-    quiet: bool,
-    mut terms: Vec<String>,
-) -> Result<(), Error> {
+pub(crate) fn search(alpm: &Alpm, net: &Network, flags: &aura::flags::Aur) -> Result<(), Error> {
+    let mut terms = flags.search.clone();
     debug!("Searching for: {:?}", terms);
+    let format = flags
+        .format
+        .as_deref()
+        .map(|f| Template::parse(f, FIELDS))
+        .transpose()?;
+    let listing = Listing::new(
+        flags.listing.sort.as_deref(),
+        flags.listing.filter.as_deref(),
+        FIELDS,
+    )?;
 
     let db = alpm.localdb();
     let rep = "aur/".magenta();
@@ -207,23 +508,27 @@ pub(crate) fn search(
     debug!("Sanitized terms: {:?}", terms);
 
     let mut matches: Vec<aura_core::faur::Package> =
-        aura_core::faur::search(terms.iter().map(|s| s.as_str()), &crate::fetch::fetch_json)?;
+        aura_core::faur::search(terms.iter().map(|s| s.as_str()), &|u: &str| net.json(u))?;
 
     debug!("Search matches: {}", matches.len());
+    trend::record(matches.iter());
 
     // Sort and filter the results as requested.
-    if alpha {
+    if flags.abc {
         matches.sort_by(|a, b| a.name.cmp(&b.name));
     } else {
         matches.sort_by(|a, b| b.num_votes.cmp(&a.num_votes));
     }
-    if rev {
+    let mut matches = listing.apply(matches, field);
+    if flags.reverse {
         matches.reverse();
     }
-    let to_take = limit.unwrap_or(matches.len());
+    let to_take = flags.limit.unwrap_or(matches.len());
 
     for p in matches.into_iter().take(to_take) {
-        if quiet {
+        if let Some(t) = format.as_ref() {
+            println!("{}", t.render(|f| field(&p, f)));
+        } else if flags.quiet {
             println!("{}", p.name);
         } else {
             let n = p.name.bold();
@@ -248,8 +553,8 @@ pub(crate) fn search(
 }
 
 /// View a package's PKGBUILD.
-pub(crate) fn pkgbuild(pkg: &str, clone_d: &Path) -> Result<(), Error> {
-    let path = aura_core::aur::clone_path_of_pkgbase(clone_d, pkg, &crate::fetch::fetch_json)?
+pub(crate) fn pkgbuild(net: &Network, pkg: &str, clone_d: &Path) -> Result<(), Error> {
+    let path = aura_core::aur::clone_path_of_pkgbase(clone_d, pkg, &|u: &str| net.json(u))?
         .join("PKGBUILD");
 
     let file = BufReader::new(File::open(&path).map_err(|e| Error::FileOpen(path, e))?);
@@ -263,6 +568,31 @@ pub(crate) fn pkgbuild(pkg: &str, clone_d: &Path) -> Result<(), Error> {
     Ok(())
 }
 
+/// Validate a local AUR package repository and push it to the AUR.
+pub(crate) fn push(fll: &FluentLanguageLoader, dir: &Path) -> Result<(), Error> {
+    push::push(fll, dir)?;
+    Ok(())
+}
+
+/// View how the votes and popularity of AUR packages have changed, as far as
+/// Aura has seen.
+pub(crate) fn trend(
+    fll: &FluentLanguageLoader,
+    net: &Network,
+    packages: &[String],
+) -> Result<(), Error> {
+    let names: Vec<&str> = packages.iter().map(|s| s.as_str()).collect();
+    let r = aura_core::faur::info_batched(&names, &|u: &str| net.json(u))?;
+    trend::record(r.iter().filter_map(|(_, p)| p.as_ref()));
+    trend::show(fll, packages);
+    Ok(())
+}
+
+/// View how installed AUR packages were built, according to Aura's records.
+pub(crate) fn local_info(fll: &FluentLanguageLoader, packages: &[String]) -> Result<(), Error> {
+    provenance::show(fll, packages).map_err(Error::Provenance)
+}
+
 /// Open a given package's AUR package in a browser.
 pub(crate) fn open(package: &str) -> Result<(), crate::open::Error> {
     let url = package_url(package);
@@ -303,6 +633,7 @@ pub(crate) fn clone_aur_repos(fll: &FluentLanguageLoader, pkgs: &[String]) -> Re
 pub(crate) fn refresh(
     fll: &FluentLanguageLoader,
     alpm: &Alpm,
+    net: &Network,
     clone_d: &Path,
 ) -> Result<(), Error> {
     aura!(fll, "A-y-refreshing");
@@ -317,7 +648,7 @@ pub(crate) fn refresh(
     let uniques = names
         .into_par_iter()
         .map(|p| {
-            let res = aura_core::aur::clone_path_of_pkgbase(clone_d, p, &crate::fetch::fetch_json);
+            let res = aura_core::aur::clone_path_of_pkgbase(clone_d, p, &|u: &str| net.json(u));
             mtx.lock().unwrap().inc_and_draw(&clone_bar, 1);
             res
         })
@@ -339,11 +670,107 @@ pub(crate) fn refresh(
     Ok(())
 }
 
+/// Settle what each name given to `-A` refers to. Names that match a package
+/// exactly are left alone, but one like `foo` that only partially matches
+/// `foo-git` and `foo-bin` leads to a choice between them, unless the first
+/// (most voted) should simply be taken. A sole match is taken without asking.
+pub(crate) fn disambiguate(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    pkgs: Vec<String>,
+    pick_first: bool,
+) -> Result<Vec<String>, Error> {
+    let alpm = env.alpm()?;
+    let fetch = |u: &str| env.network.json(u);
+    let known: HashSet<String> = aura_core::faur::info(pkgs.iter().map(|p| p.as_str()), &fetch)?
+        .into_iter()
+        .map(|p| p.name)
+        .collect();
+
+    let mut chosen = Vec::with_capacity(pkgs.len());
+
+    for pkg in pkgs {
+        if known.contains(&pkg) || alpm.syncdbs().find_satisfier(pkg.as_str()).is_some() {
+            chosen.push(pkg);
+            continue;
+        }
+
+        let mut candidates: Vec<_> = aura_core::faur::search([pkg.as_str()], &fetch)?
+            .into_iter()
+            .filter(|c| c.name.contains(pkg.as_str()))
+            .collect();
+        candidates.sort_by(|a, b| b.num_votes.cmp(&a.num_votes));
+
+        match candidates.first() {
+            // Resolution will report it as missing.
+            None => chosen.push(pkg),
+            Some(c) if pick_first || candidates.len() == 1 => {
+                aura!(
+                    fll,
+                    "A-pick-first",
+                    pkg = pkg.as_str(),
+                    chosen = c.name.as_str()
+                );
+                chosen.push(c.name.clone());
+            }
+            Some(_) => {
+                aura!(fll, "A-pick-which", pkg = pkg.as_str());
+                let digits = candidates.len().to_string().len();
+
+                for (i, c) in candidates.iter().enumerate() {
+                    let vot = format!("{}", c.num_votes).yellow();
+                    let pop = format!("{:.2}", c.popularity).yellow();
+                    println!(
+                        " {:w$}) {} ({} | {})",
+                        i,
+                        c.name.bold(),
+                        vot,
+                        pop,
+                        w = digits
+                    );
+                    println!(
+                        " {:w$}  {}",
+                        "",
+                        c.description.as_deref().unwrap_or_default(),
+                        w = digits
+                    );
+                }
+
+                let ix = crate::utils::select(">>> ", candidates.len() - 1)?;
+                chosen.push(candidates.swap_remove(ix).name);
+            }
+        }
+    }
+
+    Ok(chosen)
+}
+
+/// What became of the packages that an install or upgrade set out to build.
+#[derive(Default)]
+pub(crate) struct Summary {
+    /// Upgraded packages, with their old and new versions. The new version of a
+    /// VCS package isn't known until it's built.
+    pub(crate) upgraded: Vec<(String, String, Option<String>)>,
+    /// Builds that failed, but which the user chose to skip past.
+    pub(crate) failed: Vec<build::Failed>,
+    /// Packages that weren't built, and the failed dependency that kept them
+    /// from it.
+    pub(crate) skipped: Vec<(String, String)>,
+}
+
+impl Summary {
+    /// How many packages that were set out to be built weren't.
+    pub(crate) fn unbuilt(&self) -> usize {
+        self.failed.len() + self.skipped.len()
+    }
+}
+
 pub(crate) fn install<'a, I>(
+    m: &Mutating,
     fll: &FluentLanguageLoader,
     env: &Env,
     raw_pkgs: I,
-) -> Result<(), Error>
+) -> Result<Summary, Error>
 where
     I: IntoIterator<Item = &'a str>,
 {
@@ -354,38 +781,143 @@ where
         return Err(Error::NoPackages);
     }
 
+    let watch = crate::reasons::Watch::start(env);
+    let etc = crate::etc::Watch::start(env);
+    let dkms = crate::kernel::Watch::start(env);
+    let result = install_inner(m, fll, env, pkgs);
+    if let Some(w) = watch {
+        w.finish(env);
+    }
+    if let Some(w) = etc {
+        w.finish(fll, env);
+    }
+    if let Some(w) = dkms {
+        w.finish(fll, env);
+    }
+
+    result
+}
+
+fn install_inner(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    pkgs: Vec<&str>,
+) -> Result<Summary, Error> {
     // `-a` was used, or was otherwise specified in config.
     if env.aur.delmakedeps {
         let alpm = env.alpm()?;
         let before: HashSet<_> = alpm_utils::alpm::orphans(&alpm).map(|p| p.name()).collect();
-        install_work(fll, env, pkgs)?;
+        let summary = install_work(m, fll, env, pkgs)?;
         // Another handle must be opened, or else the change in orphan packages won't be detected.
         let alpm = env.alpm()?;
         let after: HashSet<_> = alpm_utils::alpm::orphans(&alpm).map(|p| p.name()).collect();
-        crate::pacman::sudo_pacman("-Rsu", NOTHING, after.difference(&before))?;
+        crate::pacman::sudo_pacman(m, "-Rsu", NOTHING, after.difference(&before))?;
+        Ok(summary)
     } else {
-        install_work(fll, env, pkgs)?;
+        install_work(m, fll, env, pkgs)
     }
+}
 
-    Ok(())
+/// Build and install every package named in a list, like the output of
+/// `pacman -Qqm` on another machine. Packages that are no longer in the AUR are
+/// reported and skipped.
+pub(crate) fn rebuild_from(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    list: &Path,
+) -> Result<(), Error> {
+    let raw = std::fs::read_to_string(list).map_err(|e| Error::FileOpen(list.to_path_buf(), e))?;
+    let names = pkglist(&raw);
+    info!("Rebuilding from {}: {:?}", list.display(), names);
+
+    let fetch = |u: &str| env.network.json(u);
+    let (found, missing): (Vec<_>, Vec<_>) = aura_core::faur::info_batched(&names, &fetch)?
+        .into_iter()
+        .partition(|(_, p)| p.is_some());
+    let found: Vec<&str> = found.into_iter().map(|(n, _)| n).collect();
+    let missing: Vec<&str> = missing.into_iter().map(|(n, _)| n).collect();
+
+    if missing.is_empty().not() {
+        // Some may have since moved to the official repos.
+        let alpm = env.alpm()?;
+        let (official, gone): (Vec<&str>, Vec<&str>) = missing
+            .into_iter()
+            .partition(|n| alpm.syncdbs().pkg(*n).is_ok());
+
+        if official.is_empty().not() {
+            yellow!(fll, "A-rebuild-official", pkgs = official.join(", "));
+        }
+        if gone.is_empty().not() {
+            yellow!(fll, "A-rebuild-missing", pkgs = gone.join(", "));
+        }
+    }
+
+    install(m, fll, env, found).map(|_| ())
 }
 
-fn install_work<'a, I>(fll: &FluentLanguageLoader, env: &Env, pkgs: I) -> Result<(), Error>
+/// The package names of a list, one per line. Like `pacman -Qm`, lines may
+/// also carry a version after the name, and blank lines and `#` comments are
+/// ignored.
+fn pkglist(raw: &str) -> Vec<&str> {
+    let mut names: Vec<&str> = raw
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or_default())
+        .filter_map(|l| l.split_whitespace().next())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+fn install_work<'a, I>(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    pkgs: I,
+) -> Result<Summary, Error>
 where
     I: IntoIterator<Item = &'a str>,
 {
+    // From here on, a Ctrl-C lets builds and clones fail and clean up rather
+    // than killing Aura on the spot.
+    let _guard = crate::signal::Guard::new();
+
+    let requested: HashSet<&str> = pkgs.into_iter().collect();
     let pool = env.alpm_pool()?;
-    aura!(fll, "A-install-deps");
-    let rslv = aura_core::aur::dependencies::resolve(
-        pool,
-        &crate::fetch::fetch_json,
-        &env.aur.clones,
-        pkgs,
-    )?;
+    let report = env.reporter();
+    report.stage(fl!(fll, "A-install-deps"));
+    let rslv = {
+        let _timer = aura_core::timings::time(aura_core::timings::Phase::Resolution);
+
+        match memo::load(env, &requested) {
+            Some(r) => {
+                report.detail(fl!(fll, "A-install-memo"));
+                r
+            }
+            None => {
+                let r = aura_core::aur::dependencies::resolve(
+                    pool,
+                    &|u: &str| env.network.json(u),
+                    &env.aur.clones,
+                    env.carch(),
+                    requested.iter().copied(),
+                )?;
+                memo::save(env, &requested, &r);
+                r
+            }
+        }
+    };
 
     debug!("Satisfied: {:?}", rslv.satisfied);
     debug!("To install: {:?}", rslv.to_install);
     debug!("To build: {:?}", rslv.to_build);
+    if rslv.satisfied.is_empty().not() {
+        let mut v: Vec<_> = rslv.satisfied.iter().map(|s| s.as_str()).collect();
+        v.sort_unstable();
+        report.detail(fl!(fll, "report-satisfied", pkgs = v.join(", ")));
+    }
 
     // --- Sort package names alphabetically --- //
     let to_install = {
@@ -399,56 +931,468 @@ where
         v
     };
 
-    if to_install.is_empty().not() {
+    // --- Install pre-built versions from trusted repos instead, if offered --- //
+    let binaries = binary::offer(
+        fll,
+        &env.aur.binaries,
+        &env.alpm()?,
+        &env.aur.clones,
+        &to_build,
+    );
+    let to_build: Vec<Buildable> = to_build
+        .into_iter()
+        .filter(|b| binaries.iter().any(|bin| bin.base == b.name).not())
+        .collect();
+
+    if to_install.is_empty().not() && report.is_quiet().not() {
         aura!(fll, "A-install-repo-pkgs");
         to_install.iter().for_each(|p| println!(" {p}"));
     }
-    aura!(fll, "A-install-aur-pkgs");
-    to_build.iter().for_each(|p| println!(" {p}"));
+    if to_build.is_empty().not() && report.is_quiet().not() {
+        aura!(fll, "A-install-aur-pkgs");
+        to_build.iter().for_each(|p| println!(" {p}"));
+    }
+    if binaries.is_empty().not() && report.is_quiet().not() {
+        aura!(fll, "A-install-binaries");
+        for b in binaries.iter() {
+            println!(" {} {}", b.base, format!("({})", b.source()).yellow());
+        }
+    }
 
-    // Proceed if the user accepts.
-    proceed!(fll, "proceed").ok_or(Error::Cancelled)?;
+    if rslv.unsupported.is_empty().not() {
+        let mut v: Vec<_> = rslv.unsupported.iter().map(|s| s.as_str()).collect();
+        v.sort_unstable();
+        yellow!(
+            fll,
+            "A-install-arch",
+            arch = env.carch(),
+            pkgs = v.join(", ")
+        );
+    }
+
+    // Like `pacman`, installing an ignored package is allowed, but only after a
+    // warning.
+    let ignored: Vec<&str> = to_install
+        .iter()
+        .map(|p| p.as_ref())
+        .chain(to_build.iter().map(|p| p.name.as_str()))
+        .filter(|p| env.is_ignored(p, &[]))
+        .collect();
+    if ignored.is_empty().not() {
+        yellow!(fll, "A-install-ignored", pkgs = ignored.join(", "));
+    }
+
+    if env.aur.sources {
+        sources::report(fll, &env.aur.clones, &to_build);
+    }
+
+    let too_large = env.network.metered && metered_check(fll, env, &to_install, &to_build)?;
+
+    space_check(env, &to_install, &to_build)?;
+    power::guard(fll, &env.aur.power, &to_build).ok_or(Error::PowerDeferred)?;
+
+    // Proceed if the user accepts. Declining what's too large for a metered
+    // connection only defers it, and is remembered apart from other installs.
+    if too_large {
+        proceed!(fll, "proceed", "A-metered").ok_or(Error::Deferred)?;
+    } else {
+        proceed!(fll, "proceed", "A-install").ok_or(Error::Cancelled)?;
+    }
 
     // --- Determine the best build order --- //
     let order: Vec<Vec<&str>> = aura_core::aur::dependencies::build_order(&to_build)?;
     debug!("Build order: {:?}", order);
+    let layers: Vec<String> = order.iter().map(|l| l.join(", ")).collect();
+    report.detail(fl!(fll, "report-order", order = layers.join(" → ")));
 
     // --- Install repo dependencies --- //
     if to_install.is_empty().not() {
+        crate::command::check::partial_upgrade_warning(fll, env, false);
+        crate::pacman::ensure_unlocked(m, fll, &env.pacman.db_path)?;
         crate::pacman::pacman_install_from_repos(
+            m,
+            fll,
+            env.alpm_log(),
             ["--asdeps", "--noconfirm"],
             to_install.iter().map(|o| o.as_ref()),
         )?;
     }
 
+    // --- Install pre-built AUR packages --- //
+    if binaries.is_empty().not() {
+        install_binaries(m, fll, env, &requested, &binaries)?;
+    }
+
     // --- Build and install each layer of AUR packages --- //
     let is_single = to_build.len() == 1;
-    for raw_layer in order.into_iter().apply(Finished::new) {
+    let mut staged: Vec<(build::Built, bool)> = Vec::new();
+    let mut failed: Vec<build::Failed> = Vec::new();
+    let mut skipped: Vec<(&str, String)> = Vec::new();
+    for (i, raw_layer) in order.iter().enumerate().apply(Finished::new) {
         let done = raw_layer.is_last();
-        let layer = raw_layer.inner();
-        let clone_paths = layer.into_iter().map(|pkg| env.aur.clones.join(pkg));
-        let builts = build::build(fll, &env.aur, &env.general.editor, is_single, clone_paths)?;
-
-        if builts.is_empty().not() {
-            // FIXME Tue Jun 28 15:04:10 2022
-            //
-            // Chances are that this condition is wrong. It's conceivable that a
-            // binary package could slip into an early installation layer. This
-            // needs to be confirmed, though.
-            let flags = (!done).then(|| ["--asdeps"].as_slice()).unwrap_or_default();
-            let tarballs = builts.iter().flat_map(|b| &b.tarballs);
-            crate::pacman::pacman_install_from_tarball(flags, tarballs)?;
-
-            builts
+        let (_, layer) = raw_layer.inner();
+
+        // Packages that depend on a failed (or skipped) build can't be built.
+        let layer: Vec<&str> = layer
+            .iter()
+            .copied()
+            .filter(|pkg| match broken_dep(&to_build, &failed, &skipped, pkg) {
+                None => true,
+                Some(dep) => {
+                    skipped.push((*pkg, dep));
+                    false
+                }
+            })
+            .collect();
+
+        let clone_paths = layer.iter().map(|pkg| env.aur.clones.join(pkg));
+        let (builts, fails) = build::build(fll, env, is_single, clone_paths)?;
+        failed.extend(fails);
+
+        // When staging, only what later layers need in order to build is
+        // installed right away. Everything else waits for the final transaction.
+        let (now, later): (Vec<_>, Vec<_>) = if env.aur.stage {
+            let needed = needed_later(&to_build, &order[i + 1..]);
+            builts.into_iter().partition(|b| {
+                b.clone
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .map(|f| needed.contains(f))
+                    .unwrap_or(false)
+            })
+        } else {
+            (builts, Vec::new())
+        };
+
+        // FIXME Tue Jun 28 15:04:10 2022
+        //
+        // Chances are that this condition is wrong. It's conceivable that a
+        // binary package could slip into an early installation layer. This
+        // needs to be confirmed, though.
+        install_built(m, fll, env, &now, !done)?;
+        staged.extend(later.into_iter().map(|b| (b, !done)));
+    }
+
+    // --- Install all staged packages at once --- //
+    if staged.is_empty().not() {
+        report.stage(fl!(fll, "A-stage-install", count = staged.len()));
+        let (builts, asdeps): (Vec<_>, Vec<_>) = staged.into_iter().unzip();
+        let deps: Vec<String> = builts
+            .iter()
+            .zip(asdeps)
+            .filter(|(_, asdeps)| *asdeps)
+            .flat_map(|(b, _)| &b.tarballs)
+            .filter_map(|tb| aura_core::cache::PkgPath::new(tb.clone()))
+            .map(|pp| pp.as_package().name.to_string())
+            .collect();
+
+        install_built(m, fll, env, &builts, false)?;
+
+        if deps.is_empty().not() {
+            crate::pacman::sudo_pacman(m, "-D", ["--asdeps"], &deps)?;
+        }
+    }
+
+    // --- Report what couldn't be built --- //
+    // Only builds the user chose to skip past end up here, so they aren't
+    // errors. Stopping instead would have already ended the install.
+    if failed.is_empty() && skipped.is_empty() {
+        green!(fll, "common-done");
+    } else if report.is_quiet() {
+        // Failures were reported as they happened.
+        for (pkg, _) in skipped.iter() {
+            report.outcome(fll, pkg, Outcome::Skipped);
+        }
+    } else {
+        red!(fll, "A-keep-going-failed");
+        for f in failed.iter() {
+            println!(" {}", f.base.cyan());
+            println!("    {}", f.reason);
+        }
+
+        if skipped.is_empty().not() {
+            yellow!(fll, "A-keep-going-skipped");
+            for (pkg, dep) in skipped.iter() {
+                println!(" {} ({})", pkg.cyan(), dep);
+            }
+        }
+    }
+
+    Ok(Summary {
+        upgraded: Vec::new(),
+        failed,
+        skipped: skipped
+            .into_iter()
+            .map(|(pkg, dep)| (pkg.to_string(), dep))
+            .collect(),
+    })
+}
+
+/// If any of a package's AUR dependencies failed to build or was itself
+/// skipped, yield the name of that dependency.
+fn broken_dep(
+    to_build: &[Buildable],
+    failed: &[build::Failed],
+    skipped: &[(&str, String)],
+    pkg: &str,
+) -> Option<String> {
+    let deps = &to_build.iter().find(|b| b.name == pkg)?.deps;
+
+    failed
+        .iter()
+        .map(|f| f.base.as_str())
+        .chain(skipped.iter().map(|(s, _)| *s))
+        .find(|broken| deps.contains(*broken))
+        .map(|broken| broken.to_string())
+}
+
+/// Install freshly built packages, and record what they were built from.
+fn install_built(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    builts: &[build::Built],
+    asdeps: bool,
+) -> Result<(), Error> {
+    if builts.is_empty() {
+        return Ok(());
+    }
+
+    let flags = asdeps.then(|| ["--asdeps"].as_slice()).unwrap_or_default();
+    conflict_check(fll, env, builts)?;
+    let tarballs = builts.iter().flat_map(|b| &b.tarballs);
+    crate::pacman::ensure_unlocked(m, fll, &env.pacman.db_path)?;
+    crate::pacman::pacman_install_from_tarball(m, fll, env.alpm_log(), flags, tarballs)?;
+
+    if env.aur.fileindex {
+        index_files(fll, env, builts);
+    }
+
+    builts
+        .iter()
+        .try_for_each(|b| update_hash(&env.aur.hashes, &b.clone))?;
+
+    // Like the file index, provenance is a nicety that shouldn't fail an install.
+    if let Err(e) = provenance::record(builts) {
+        e.nested();
+        yellow!(fll, "A-i-record-fail");
+    }
+
+    let report = env.reporter();
+    builts
+        .iter()
+        .filter_map(|b| b.clone.file_name().and_then(|f| f.to_str()))
+        .for_each(|base| report.outcome(fll, base, Outcome::Installed));
+
+    Ok(())
+}
+
+/// The AUR packages that some package in a later build layer depends on. These
+/// must be installed before that layer can be built.
+fn needed_later<'a>(to_build: &'a [Buildable], later: &[Vec<&str>]) -> HashSet<&'a str> {
+    let later: HashSet<&str> = later.iter().flatten().copied().collect();
+
+    to_build
+        .iter()
+        .filter(|b| later.contains(b.name.as_str()))
+        .flat_map(|b| b.deps.iter().map(|d| d.as_str()))
+        .collect()
+}
+
+/// On a metered connection, report how much is about to be downloaded, and
+/// warn if it's more than the user is willing to pay for. Yields whether it is.
+fn metered_check(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    to_install: &[Official],
+    to_build: &[Buildable],
+) -> Result<bool, Error> {
+    let alpm = env.alpm()?;
+    let repo: u64 = to_install
+        .iter()
+        .filter_map(|o| alpm.syncdbs().find_satisfier(o.as_ref()))
+        .map(|p| p.download_size().max(0) as u64)
+        .sum();
+
+    // Remote sources whose size can't be known in advance (like `git` repos or
+    // servers that don't report a length) are counted separately.
+    let (aur, unknown) = to_build
+        .iter()
+        .filter_map(|b| {
+            aura_core::metadata::srcinfo(&env.aur.clones.join(&b.name).join(".SRCINFO")).ok()
+        })
+        .flat_map(|info| {
+            build::remote_sources(&info.base.source, env.carch())
                 .into_iter()
-                .try_for_each(|b| update_hash(&env.aur.hashes, &b.clone))?;
+                .map(|s| env.network.content_length(s.url))
+                .collect::<Vec<_>>()
+        })
+        .fold((0, 0), |(known, unknown), len| match len {
+            Some(l) => (known + l, unknown),
+            None => (known, unknown + 1),
+        });
+
+    let total = repo + aur;
+    aura!(fll, "A-metered-total", size = total.bytes().to_string());
+    if unknown > 0 {
+        yellow!(fll, "A-metered-unknown", count = unknown);
+    }
+
+    let too_large = total > env.network.metered_threshold * 1024 * 1024;
+    if too_large {
+        yellow!(
+            fll,
+            "A-metered-large",
+            limit = env.network.metered_threshold.mebibytes().to_string()
+        );
+    }
+
+    Ok(too_large)
+}
+
+/// Install AUR packages pre-built by a third-party repo, and record where they
+/// came from.
+fn install_binaries(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    requested: &HashSet<&str>,
+    binaries: &[binary::Binary],
+) -> Result<(), Error> {
+    crate::pacman::ensure_unlocked(m, fll, &env.pacman.db_path)?;
+    crate::pacman::pacman_install_from_repos(
+        m,
+        fll,
+        env.alpm_log(),
+        ["--noconfirm"],
+        binaries
+            .iter()
+            .flat_map(|b| b.pkgs.iter().map(move |(n, _)| format!("{}/{}", b.repo, n))),
+    )?;
+
+    let deps: Vec<&str> = binaries
+        .iter()
+        .filter(|b| requested.contains(b.base.as_str()).not())
+        .flat_map(|b| b.pkgs.iter().map(|(n, _)| n.as_str()))
+        .filter(|n| requested.contains(n).not())
+        .collect();
+    if deps.is_empty().not() {
+        crate::pacman::sudo_pacman(m, "-D", ["--asdeps"], &deps)?;
+    }
+
+    let pkgs: Vec<(aura_core::Package, String)> = binaries
+        .iter()
+        .flat_map(|b| {
+            b.pkgs.iter().map(|(n, v)| {
+                let p = aura_core::Package::new(n.as_str(), v.as_str());
+                (p, b.source())
+            })
+        })
+        .collect();
+    let installed: Vec<(&aura_core::Package, &str)> =
+        pkgs.iter().map(|(p, s)| (p, s.as_str())).collect();
+    provenance::record_installed(&installed)?;
+
+    let report = env.reporter();
+    binaries
+        .iter()
+        .for_each(|b| report.outcome(fll, &b.base, Outcome::Prebuilt));
+
+    Ok(())
+}
+
+/// Abort before anything is downloaded or built if some filesystem wouldn't
+/// have room for it all.
+fn space_check(env: &Env, to_install: &[Official], to_build: &[Buildable]) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let db = alpm.localdb();
+    let installed = |name: &str| db.pkg(name).map(|p| p.isize().max(0) as u64).ok();
+
+    // Repo packages report exactly what they'll download and unpack to.
+    let (download, unpacked) = to_install
+        .iter()
+        .filter_map(|o| alpm.syncdbs().find_satisfier(o.as_ref()))
+        .fold((0, 0), |(d, u), p| {
+            let new = p.isize().max(0) as u64;
+            let old = installed(p.name()).unwrap_or(0);
+            (
+                d + p.download_size().max(0) as u64,
+                u + new.saturating_sub(old),
+            )
+        });
+
+    // The size of an AUR package can only be guessed at from the version
+    // that's already installed, if any. Its build usually needs a few times
+    // that in scratch space, for the sources and what they compile to.
+    let (scratch, tarballs, fresh) =
+        to_build
+            .iter()
+            .fold((0, 0, 0), |(s, t, f), b| match installed(&b.name) {
+                Some(size) => (s + size.max(MIN_BUILD) * 3, t + size, f),
+                None => (s + MIN_BUILD * 3, t + MIN_BUILD, f + MIN_BUILD),
+            });
+
+    let root = Path::new(&env.pacman.root_dir);
+
+    crate::space::check(&[
+        (crate::space::pacman_cache(&env.pacman), download),
+        (root, unpacked + fresh),
+        (&env.aur.build, scratch),
+        (&env.aur.cache, tarballs),
+    ])?;
+
+    Ok(())
+}
+
+/// Warn about any files within freshly built tarballs that already exist on the
+/// filesystem, before `pacman -U` has a chance to fail halfway through.
+fn conflict_check(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    builts: &[build::Built],
+) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let conflicts: Vec<(&Path, Vec<PathBuf>)> = builts
+        .iter()
+        .flat_map(|b| &b.tarballs)
+        .map(|tb| {
+            let mut cs = aura_core::files::conflicts(&alpm, tb);
+            cs.retain(|c| env.is_noextract(c).not());
+            (tb.as_path(), cs)
+        })
+        .filter(|(_, cs)| cs.is_empty().not())
+        .collect();
+
+    if conflicts.is_empty().not() {
+        yellow!(fll, "A-install-conflicts");
+        for (tarball, files) in conflicts {
+            println!(" {}", tarball.display().to_string().cyan());
+            for file in files {
+                println!("    {}", file.display());
+            }
         }
+
+        proceed!(fll, "proceed", "A-conflicts").ok_or(Error::Cancelled)?;
     }
 
-    green!(fll, "common-done");
     Ok(())
 }
 
+/// Record the file lists of freshly built tarballs for `aura provides`. Failure
+/// here shouldn't interrupt an installation, so errors are only reported.
+fn index_files(fll: &FluentLanguageLoader, env: &Env, builts: &[build::Built]) {
+    let tarballs: Vec<PathBuf> = builts.iter().flat_map(|b| b.tarballs.clone()).collect();
+    let result = env
+        .alpm()
+        .map_err(crate::command::provides::Error::Env)
+        .and_then(|alpm| crate::command::provides::update(&alpm, &tarballs));
+
+    if let Err(e) = result {
+        e.nested();
+        yellow!(fll, "provides-update-fail");
+    }
+}
+
 fn update_hash(hashes: &Path, clone: &Path) -> Result<(), Error> {
     let hash = aura_core::git::hash(clone)?;
     let base = clone
@@ -464,47 +1408,66 @@ fn update_hash(hashes: &Path, clone: &Path) -> Result<(), Error> {
 
 /// Upgrade all installed AUR packages.
 pub(crate) fn upgrade<'a>(
+    m: &Mutating,
     fll: &FluentLanguageLoader,
     alpm: &'a Alpm,
     env: Env,
-) -> Result<(), Error> {
+) -> Result<Summary, Error> {
     info!("Upgrading all AUR packages.");
-    debug!("Will ignore: {:?}", env.aur.ignores);
+    debug!(
+        "Will ignore: {:?}, {:?}, groups {:?}",
+        env.aur.ignores, env.pacman.ignore_pkg, env.pacman.ignore_group
+    );
 
     // --- Query database for all non-repo packages --- //
-    let mut foreigns: Vec<aura_core::Package<'a>> =
-        alpm_utils::alpm::foreigns(alpm).map(|p| p.into()).collect();
-    debug!("Foreign packages: {}", foreigns.len());
-    foreigns.retain(|p| env.aur.ignores.contains(p.name.as_ref()).not());
-    debug!("After excluding ignores: {}", foreigns.len());
+    let foreigns: Vec<aura_core::Package<'a>> = alpm_utils::alpm::foreigns(alpm)
+        .filter(|p| {
+            let groups: Vec<&str> = p.groups().iter().collect();
+            env.is_ignored(p.name(), &groups).not()
+        })
+        .map(|p| p.into())
+        .collect();
+    debug!("Foreign packages, excluding ignores: {}", foreigns.len());
 
     // --- Ensure they all have local clones --- //
-    aura!(fll, "A-u-fetch-info");
+    let report = env.reporter();
+    report.stage(fl!(fll, "A-u-fetch-info"));
     let clones: HashSet<PathBuf> = foreigns
         .par_iter()
         .map(|p| p.name.as_ref())
         .map(|p| {
-            aura_core::aur::clone_path_of_pkgbase(&env.aur.clones, p, &crate::fetch::fetch_json)
+            aura_core::aur::clone_path_of_pkgbase(&env.aur.clones, p, &|u: &str| {
+                env.network.json(u)
+            })
         })
         .collect::<Result<HashSet<_>, aura_core::aur::Error>>()?;
     debug!("Unique clones: {}", clones.len());
 
     // --- Compare versions to determine what to upgrade --- //
-    aura!(fll, "A-u-comparing");
+    report.stage(fl!(fll, "A-u-comparing"));
     info!("Reading .SRCINFO files...");
     let srcinfos = clones
         .into_par_iter()
         .map(|path| {
             let full = path.join(".SRCINFO");
-            Srcinfo::parse_file(&full).map_err(|e| Error::Srcinfo(full, e))
+            aura_core::metadata::srcinfo(&full).map_err(|e| Error::Srcinfo(full, e))
         })
         .collect::<Result<Vec<_>, Error>>()?;
+    let bases: HashMap<&str, &str> = srcinfos
+        .iter()
+        .flat_map(|info| {
+            info.pkgs
+                .iter()
+                .map(|p| (p.pkgname.as_str(), info.base.pkgbase.as_str()))
+        })
+        .collect();
     info!("Pulling AUR data...");
     let from_api: Vec<aura_core::faur::Package> = aura_core::faur::info(
         srcinfos.iter().map(|p| p.base.pkgbase.as_str()),
-        &crate::fetch::fetch_json,
+        &|u: &str| env.network.json(u),
     )?;
     debug!("Packages pulled: {}", from_api.len());
+    trend::record(from_api.iter());
     let db = alpm.localdb();
     let mut to_upgrade: Vec<(aura_core::Package<'_>, aura_core::Package<'_>)> = from_api
         .into_iter()
@@ -512,17 +1475,40 @@ pub(crate) fn upgrade<'a>(
         .map(|(old, new)| (aura_core::Package::from(old), aura_core::Package::from(new)))
         .filter(|(old, new)| old < new)
         .collect();
+
+    let pinned: Vec<String> = to_upgrade
+        .iter()
+        .filter(|(_, new)| env.is_pinned(&new.name, "aur", &new.version))
+        .map(|(_, new)| new.name.to_string())
+        .collect();
+    if pinned.is_empty().not() {
+        yellow!(fll, "pin-holding", pkgs = pinned.join(", "));
+        to_upgrade.retain(|(_, new)| pinned.iter().all(|p| *p != new.name));
+    }
     debug!("Packages to upgrade: {}", to_upgrade.len());
 
     // --- Account for VCS packages --- //
     let vcs: Vec<_> = if env.aur.git {
+        // Packages Aura built itself are known to be VCS packages or not, even
+        // if their names don't say so.
+        let prov = provenance::load();
+
         foreigns
             .iter()
             .filter(|p| {
                 let n = p.name.as_ref();
-                n.ends_with("-git") || n.ends_with("-hg") || n.ends_with("-svn")
+                n.ends_with("-git")
+                    || n.ends_with("-hg")
+                    || n.ends_with("-svn")
+                    || prov.get(n).map(|r| r.vcs).unwrap_or(false)
             })
             .filter(|p| to_upgrade.iter().all(|(old, _)| p.name != old.name))
+            // The new version of a VCS package isn't known until it's built,
+            // so any pin on it holds it.
+            .filter(|p| {
+                env.pins.repos.contains("aur").not()
+                    && env.pins.packages.contains_key(p.name.as_ref()).not()
+            })
             .collect()
     } else {
         Vec::new()
@@ -533,44 +1519,186 @@ pub(crate) fn upgrade<'a>(
     // --- Report --- //
     if to_upgrade.is_empty() && (env.aur.git.not() || (env.aur.git && vcs.is_empty())) {
         aura!(fll, "A-u-no-upgrades");
+        Ok(Summary::default())
     } else {
-        aura!(fll, "A-u-to-upgrade");
         to_upgrade.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
-        let longest_name = to_upgrade
-            .iter()
-            .map(|(old, _)| old.name.chars().count())
-            .max()
-            .unwrap_or(0);
-        let longest_version = to_upgrade
-            .iter()
-            .map(|(old, _)| old.version.chars().count())
-            .max()
-            .unwrap_or(0);
-
-        for (old, new) in to_upgrade.iter() {
-            println!(
-                " {:n$} :: {:v$} -> {}",
-                old.name.cyan(),
-                old.version.truecolor(128, 128, 128),
-                new.version.bold(),
-                n = longest_name,
-                v = longest_version,
-            );
-        }
 
-        if env.aur.git && vcs.is_empty().not() {
-            aura!(fll, "A-u-git");
-            for p in vcs.iter() {
-                println!(" {}", p.name.cyan());
+        if report.is_quiet().not() {
+            aura!(fll, "A-u-to-upgrade");
+            let longest_name = to_upgrade
+                .iter()
+                .map(|(old, _)| old.name.chars().count())
+                .max()
+                .unwrap_or(0);
+            let longest_version = to_upgrade
+                .iter()
+                .map(|(old, _)| old.version.chars().count())
+                .max()
+                .unwrap_or(0);
+
+            // Fetching each repo's history is slow, so it's done all at once.
+            let changes: Vec<Option<Vec<String>>> = if env.aur.changes {
+                to_upgrade
+                    .par_iter()
+                    .map(|(old, _)| {
+                        let base = bases
+                            .get(old.name.as_ref())
+                            .copied()
+                            .unwrap_or(old.name.as_ref());
+                        changes_since_install(&env, base)
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            for (i, (old, new)) in to_upgrade.iter().enumerate() {
+                println!(
+                    " {:n$} :: {:v$} -> {}",
+                    old.name.cyan(),
+                    old.version.truecolor(128, 128, 128),
+                    new.version.bold(),
+                    n = longest_name,
+                    v = longest_version,
+                );
+
+                match changes.get(i) {
+                    None => {}
+                    Some(Some(commits)) => {
+                        for c in commits {
+                            println!("     {}", c.truecolor(128, 128, 128));
+                        }
+                    }
+                    Some(None) => println!("     {}", fl!(fll, "A-u-changes-unknown").yellow()),
+                }
+            }
+
+            if env.aur.git && vcs.is_empty().not() {
+                aura!(fll, "A-u-git");
+                for p in vcs.iter() {
+                    println!(" {}", p.name.cyan());
+                }
             }
         }
 
-        let names = to_upgrade
+        let mut names: Vec<&str> = to_upgrade
             .iter()
             .map(|(old, _)| old.name.as_ref())
-            .chain(vcs.iter().map(|p| p.name.as_ref()));
-        install(fll, &env, names)?;
+            .chain(vcs.iter().map(|p| p.name.as_ref()))
+            .collect();
+
+        // --- Let the user exclude upgrades for this run only --- //
+        if env.aur.pick {
+            aura!(fll, "A-u-pick");
+            let mut chosen = vec![true; names.len()];
+            crate::utils::toggle(">>> ", &names, &mut chosen)?;
+            let mut chosen = chosen.into_iter();
+            names.retain(|_| chosen.next().unwrap_or(false));
+
+            if names.is_empty() {
+                aura!(fll, "common-no-work");
+                return Ok(Summary::default());
+            }
+        }
+
+        let upgrades: Vec<_> = to_upgrade
+            .iter()
+            .filter(|(old, _)| names.contains(&old.name.as_ref()))
+            .map(|(old, new)| {
+                (
+                    old.name.as_ref(),
+                    old.version.as_ref(),
+                    new.version.as_ref(),
+                )
+            })
+            .collect();
+        crate::state::before(fll, &env, &upgrades);
+        crate::kernel::before(fll, alpm, &upgrades);
+
+        let mut summary = install(m, fll, &env, names.iter().copied())?;
+
+        // Failures and skips are by package base.
+        let base = |name: &str| bases.get(name).copied().unwrap_or(name).to_string();
+        let done = |name: &str| {
+            let b = base(name);
+            summary.failed.iter().all(|f| f.base != b)
+                && summary.skipped.iter().all(|(s, _)| *s != b)
+        };
+        let upgraded = upgrades
+            .iter()
+            .filter(|(name, _, _)| done(name))
+            .map(|(name, old, new)| (name.to_string(), old.to_string(), Some(new.to_string())))
+            .chain(
+                vcs.iter()
+                    .filter(|p| names.contains(&p.name.as_ref()) && done(p.name.as_ref()))
+                    .map(|p| (p.name.to_string(), p.version.to_string(), None)),
+            )
+            .collect();
+        summary.upgraded = upgraded;
+
+        Ok(summary)
     }
+}
 
-    Ok(())
+/// The AUR commits made to a package since it was last installed, if they can
+/// be determined.
+fn changes_since_install(env: &Env, base: &str) -> Option<Vec<String>> {
+    let hash = std::fs::read_to_string(env.aur.hashes.join(base)).ok()?;
+    aura_core::git::log_since(&env.aur.clones.join(base), hash.trim())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn spliced_responses() {
+        let one = "[{\"Name\":\"aura\",\"Version\":\"4.0.0-1\"}]\n";
+        assert_eq!(one, splice(&[one]));
+
+        let two = "[ {\"Name\":\"paru\"} ]";
+        let none = "[]";
+        assert_eq!(
+            "[{\"Name\":\"aura\",\"Version\":\"4.0.0-1\"},{\"Name\":\"paru\"}]",
+            splice(&[one, none, two])
+        );
+        assert_eq!("[]", splice(&[]));
+    }
+
+    #[test]
+    fn picked_in_order() {
+        let record =
+            serde_json::from_str(r#"{"Name":"aura","Version":"4.0.0-1","URL":null}"#).unwrap();
+        let fields = vec![
+            "version".to_string(),
+            "Name".to_string(),
+            "Votes".to_string(),
+        ];
+        let picked = Picked::from_record(record, &fields);
+
+        assert_eq!(
+            r#"{"Version":"4.0.0-1","Name":"aura"}"#,
+            serde_json::to_string(&picked).unwrap()
+        );
+    }
+
+    #[test]
+    fn pkglist_names() {
+        let raw = "\
+# Exported from another machine
+aura 4.0.0-1
+yay-bin
+
+  paru 2.0.0-1  # An inline comment
+aura
+";
+
+        assert_eq!(vec!["aura", "paru", "yay-bin"], pkglist(raw));
+    }
+
+    #[test]
+    fn pkglist_empty() {
+        assert!(pkglist("").is_empty());
+        assert!(pkglist("# Nothing\n\n   \n").is_empty());
+    }
 }
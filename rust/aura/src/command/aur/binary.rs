@@ -0,0 +1,210 @@
+//! Installing pre-built AUR packages from trusted third-party repositories,
+//! like `chaotic-aur`, instead of building them.
+//!
+//! Such repositories must already be configured in `pacman.conf`. Aura only
+//! offers their packages; whether to trust them is up to the user. Each answer
+//! is remembered in the `[aur.binaries]` section of `aura.toml`, so that the
+//! same package isn't asked about again.
+
+use crate::env::Binaries;
+use crate::green;
+use alpm::Alpm;
+use aura_core::aur::dependencies::Buildable;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::warn;
+use std::collections::HashSet;
+use std::ops::Not;
+use std::path::Path;
+use toml_edit::{Array, Document};
+
+/// A package base whose packages will all be installed pre-built.
+pub(super) struct Binary {
+    /// The package base it stands in for.
+    pub(super) base: String,
+    /// The packages of that base, with their versions.
+    pub(super) pkgs: Vec<(String, String)>,
+    /// The repository they come from.
+    pub(super) repo: String,
+}
+
+impl Binary {
+    /// The label to record as where this package came from.
+    pub(super) fn source(&self) -> String {
+        format!("{} (pre-built)", self.repo)
+    }
+}
+
+/// Which of the given package bases to install pre-built. Unless the config
+/// already has a preference for one, the user is asked, and the answer
+/// remembered.
+pub(super) fn offer(
+    fll: &FluentLanguageLoader,
+    binaries: &Binaries,
+    alpm: &Alpm,
+    clones: &Path,
+    to_build: &[Buildable],
+) -> Vec<Binary> {
+    if binaries.repos.is_empty() {
+        return Vec::new();
+    }
+
+    let dbs: Vec<_> = alpm
+        .syncdbs()
+        .iter()
+        .filter(|db| binaries.repos.iter().any(|r| r == db.name()))
+        .collect();
+
+    to_build
+        .iter()
+        .map(|b| b.name.as_str())
+        .filter_map(|base| {
+            // Repos hold packages, not bases, so a preference may name either.
+            let names = pkgnames(clones, base);
+            let listed =
+                |set: &HashSet<String>| set.contains(base) || names.iter().any(|n| set.contains(n));
+
+            if listed(&binaries.never) {
+                return None;
+            }
+
+            // Repos are searched in the order they were given, and all the
+            // packages of a base must come from the same one.
+            let (db, pkgs) = binaries.repos.iter().find_map(|r| {
+                let db = dbs.iter().find(|db| db.name() == r)?;
+                let pkgs: Option<Vec<(String, String)>> = names
+                    .iter()
+                    .map(|n| {
+                        db.pkg(n.as_str())
+                            .ok()
+                            .map(|p| (n.clone(), p.version().to_string()))
+                    })
+                    .collect();
+
+                pkgs.map(|ps| (db, ps))
+            })?;
+
+            let bin = Binary {
+                base: base.to_string(),
+                pkgs,
+                repo: db.name().to_string(),
+            };
+
+            let wanted = listed(&binaries.always) || {
+                let msg = fl!(
+                    fll,
+                    "A-binary-offer",
+                    pkg = base,
+                    version = bin
+                        .pkgs
+                        .first()
+                        .map(|(_, v)| v.as_str())
+                        .unwrap_or_default(),
+                    repo = bin.repo.as_str()
+                );
+                let wanted = crate::prompts::ask(fll, "A-binary-offer", msg).is_some();
+
+                match remember(base, wanted) {
+                    Some(()) => green!(fll, "A-binary-remembered", pkg = base.cyan().to_string()),
+                    None => warn!("Couldn't remember the answer for {base} in aura.toml"),
+                }
+
+                wanted
+            };
+
+            wanted.then(|| bin)
+        })
+        .collect()
+}
+
+/// The packages a base builds, according to its clone.
+fn pkgnames(clones: &Path, base: &str) -> Vec<String> {
+    aura_core::metadata::srcinfo(&clones.join(base).join(".SRCINFO"))
+        .map(|info| info.pkgs.into_iter().map(|p| p.pkgname).collect())
+        .unwrap_or_else(|_| vec![base.to_string()])
+}
+
+/// Record in `aura.toml` whether a package base should always be installed
+/// pre-built, or always be built.
+fn remember(base: &str, wanted: bool) -> Option<()> {
+    let path = crate::dirs::aura_config().ok()?;
+    let raw = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut doc: Document = raw.parse().ok()?;
+
+    prefer(&mut doc, base, wanted)?;
+    std::fs::write(&path, doc.to_string()).ok()
+}
+
+/// Move a package base into `always` or `never`, leaving the rest of the
+/// document as it was.
+fn prefer(doc: &mut Document, base: &str, wanted: bool) -> Option<()> {
+    let (add, drop) = if wanted {
+        ("always", "never")
+    } else {
+        ("never", "always")
+    };
+
+    let table = doc
+        .entry("aur")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()?
+        .entry("binaries")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()?;
+
+    if let Some(arr) = table.get_mut(drop).and_then(|i| i.as_array_mut()) {
+        if let Some(ix) = arr.iter().position(|v| v.as_str() == Some(base)) {
+            arr.remove(ix);
+        }
+    }
+
+    let arr = table
+        .entry(add)
+        .or_insert_with(|| toml_edit::value(Array::new()))
+        .as_array_mut()?;
+
+    if arr.iter().all(|v| v.as_str() != Some(base)) {
+        arr.push(base);
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn preferences_moved() {
+        let mut doc: Document =
+            "# Mine\n[aur.binaries]\nrepos = [\"chaotic-aur\"]\nnever = [\"foo\", \"bar\"]\n"
+                .parse()
+                .unwrap();
+
+        prefer(&mut doc, "foo", true).unwrap();
+        prefer(&mut doc, "foo", true).unwrap();
+        prefer(&mut doc, "baz", false).unwrap();
+
+        let text = doc.to_string();
+        assert!(text.starts_with("# Mine\n"));
+
+        let back: toml::Value = toml::from_str(&text).unwrap();
+        let bins = &back["aur"]["binaries"];
+        assert_eq!(toml::Value::from(vec!["foo"]), bins["always"]);
+        assert_eq!(toml::Value::from(vec!["bar", "baz"]), bins["never"]);
+        assert_eq!(toml::Value::from(vec!["chaotic-aur"]), bins["repos"]);
+    }
+
+    #[test]
+    fn new_section() {
+        let mut doc = Document::new();
+        prefer(&mut doc, "foo", false).unwrap();
+
+        let back: toml::Value = toml::from_str(&doc.to_string()).unwrap();
+        assert_eq!(
+            toml::Value::from(vec!["foo"]),
+            back["aur"]["binaries"]["never"]
+        );
+    }
+}
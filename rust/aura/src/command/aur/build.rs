@@ -1,20 +1,36 @@
+use super::namcap::{Complaint, Severity};
+use super::nocheck;
+use crate::download::download_with_progress;
+use crate::env::{Downloader, Env, Keep, Limits, Network};
 use crate::error::Nested;
 use crate::localization::Localised;
+use crate::reporter::{Outcome, Reporter};
 use crate::utils::{PathStr, ResultVoid};
 use crate::{aura, proceed, red, yellow};
+use aura_core::explain::Explain;
+use aura_core::timings::{self, Phase};
+use aura_core::Coded;
 use colored::Colorize;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
+use itertools::{Either, Itertools};
+use linya::Progress;
 use log::{debug, error, warn};
 use nonempty::NonEmpty;
-use srcinfo::Srcinfo;
+use rayon::prelude::*;
+use srcinfo::ArchVec;
+use std::collections::HashSet;
+use std::io::Write;
 use std::ops::Not;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+use ubyte::ToByteUnit;
 use validated::Validated;
 
 pub(crate) enum Error {
-    Srcinfo(PathBuf, srcinfo::Error),
+    Srcinfo(PathBuf, aura_core::metadata::Error),
     GitDiff(aura_core::git::Error),
     CopyBuildFiles(NonEmpty<std::io::Error>),
     Utf8(std::str::Utf8Error),
@@ -25,7 +41,14 @@ pub(crate) enum Error {
     ReadDir(PathBuf, std::io::Error),
     Pkglist(PathBuf, std::io::Error),
     Makepkg,
+    Timeout(u64),
+    Aria2,
+    SourceDownload(String),
+    RootBuild,
+    Chown(PathBuf, String),
+    Namcap(Vec<String>),
     Cancelled,
+    Interrupted,
 }
 
 impl Nested for Error {
@@ -46,7 +69,14 @@ impl Nested for Error {
             Error::ReadDir(_, e) => error!("{e}"),
             Error::Pkglist(_, e) => error!("{e}"),
             Error::Makepkg => {}
+            Error::Timeout(_) => {}
+            Error::Aria2 => {}
+            Error::SourceDownload(_) => {}
+            Error::RootBuild => {}
+            Error::Chown(_, _) => {}
+            Error::Namcap(_) => {}
             Error::Cancelled => {}
+            Error::Interrupted => {}
         }
     }
 }
@@ -61,8 +91,15 @@ impl Localised for Error {
             Error::FilenameExtraction(p) => fl!(fll, "A-build-e-filename", file = p.utf8()),
             Error::TarballMove(p) => fl!(fll, "A-build-e-tarball", file = p.utf8()),
             Error::Cancelled => fl!(fll, "common-cancelled"),
+            Error::Interrupted => fl!(fll, "common-interrupted"),
             Error::EditFail(p) => fl!(fll, "A-build-e-edit", file = p.utf8()),
             Error::Makepkg => fl!(fll, "A-build-e-makepkg"),
+            Error::Timeout(m) => fl!(fll, "A-build-e-timeout", minutes = m),
+            Error::Aria2 => fl!(fll, "A-build-e-aria2"),
+            Error::SourceDownload(url) => fl!(fll, "A-build-e-source", url = url.as_str()),
+            Error::RootBuild => fl!(fll, "A-build-e-root"),
+            Error::Namcap(ls) => fl!(fll, "A-build-e-namcap", lints = ls.join("\n  ")),
+            Error::Chown(p, u) => fl!(fll, "A-build-e-chown", dir = p.utf8(), user = u.as_str()),
             Error::CreateDir(p, _) => fl!(fll, "dir-mkdir", dir = p.utf8()),
             Error::ReadDir(p, _) => fl!(fll, "err-read-dir", dir = p.utf8()),
             Error::Pkglist(p, _) => fl!(fll, "A-build-pkglist", dir = p.utf8()),
@@ -70,45 +107,125 @@ impl Localised for Error {
     }
 }
 
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Srcinfo(_, _) => "build-srcinfo",
+            Error::GitDiff(e) => e.code(),
+            Error::CopyBuildFiles(_) => "build-copy-build-files",
+            Error::Utf8(_) => "build-utf8",
+            Error::FilenameExtraction(_) => "build-filename-extraction",
+            Error::TarballMove(_) => "build-tarball-move",
+            Error::EditFail(_) => "build-edit-fail",
+            Error::CreateDir(_, _) => "build-create-dir",
+            Error::ReadDir(_, _) => "build-read-dir",
+            Error::Pkglist(_, _) => "build-pkglist",
+            Error::Makepkg => "build-makepkg",
+            Error::Timeout(_) => "build-timeout",
+            Error::Aria2 => "build-aria2",
+            Error::SourceDownload(_) => "build-source-download",
+            Error::RootBuild => "build-root-build",
+            Error::Chown(_, _) => "build-chown",
+            Error::Namcap(_) => "build-namcap",
+            Error::Cancelled => "build-cancelled",
+            Error::Interrupted => "build-interrupted",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Srcinfo(p, _) => vec![("path", p.display().to_string())],
+            Error::GitDiff(e) => e.context(),
+            Error::FilenameExtraction(p) => vec![("path", p.display().to_string())],
+            Error::TarballMove(p) => vec![("path", p.display().to_string())],
+            Error::EditFail(p) => vec![("path", p.display().to_string())],
+            Error::CreateDir(p, _) => vec![("path", p.display().to_string())],
+            Error::ReadDir(p, _) => vec![("path", p.display().to_string())],
+            Error::Pkglist(p, _) => vec![("path", p.display().to_string())],
+            Error::SourceDownload(url) => vec![("url", url.to_string())],
+            Error::Chown(p, user) => {
+                vec![
+                    ("path", p.display().to_string()),
+                    ("user", user.to_string()),
+                ]
+            }
+            Error::Namcap(tags) => vec![("tags", tags.join(", "))],
+            Error::Timeout(m) => vec![("minutes", m.to_string())],
+            Error::CopyBuildFiles(_)
+            | Error::Utf8(_)
+            | Error::Makepkg
+            | Error::Aria2
+            | Error::RootBuild
+            | Error::Cancelled
+            | Error::Interrupted => Vec::new(),
+        }
+    }
+}
+
+// TODO Remove or rethink `-f`.
+/// The flags that `makepkg` is always called with.
+pub(super) const MAKEPKG_FLAGS: &[&str] = &["-f"];
+
 /// The results of a successful build.
 pub(crate) struct Built {
     pub(crate) clone: PathBuf,
     pub(crate) tarballs: Vec<PathBuf>,
+    /// Any complaints `namcap` had about the build.
+    pub(super) lints: Vec<Complaint>,
+    /// Flags passed to `makepkg` beyond the usual ones.
+    pub(super) flags: Vec<String>,
+}
+
+/// A package whose build failed, but which the user chose to skip past.
+pub(crate) struct Failed {
+    /// The name of the package's clone.
+    pub(crate) base: String,
+    /// Why the build failed.
+    pub(crate) reason: String,
 }
 
 // TODO Thu Jan 20 16:13:54 2022
 //
 // Consider parallel builds, but make it opt-in.
-/// Build the given packages and yield paths to their built tarballs.
+/// Build the given packages and yield paths to their built tarballs, as well as
+/// any packages that failed to build but were skipped.
 pub(crate) fn build<I>(
     fll: &FluentLanguageLoader,
-    aur: &crate::env::Aur,
-    editor: &str,
+    env: &Env,
     is_single: bool,
     pkg_clones: I,
-) -> Result<Vec<Built>, Error>
+) -> Result<(Vec<Built>, Vec<Failed>), Error>
 where
     I: Iterator<Item = PathBuf>,
 {
-    aura!(fll, "A-build-prep");
+    let _timer = timings::time(Phase::Builds);
+    let report = env.reporter();
+    report.stage(fl!(fll, "A-build-prep"));
 
-    let to_install = pkg_clones
-        .map(|path| build_one(fll, aur, editor, path))
-        .map(|r| build_check(fll, is_single, r))
-        .collect::<Result<Vec<Option<Built>>, Error>>()?
+    let (to_install, failed): (Vec<Built>, Vec<Failed>) = pkg_clones
+        .map(|path| {
+            let base = path
+                .file_name()
+                .and_then(|f| f.to_str())
+                .unwrap_or_default()
+                .to_string();
+            let r = build_one(fll, env, path);
+            build_check(fll, &report, is_single, env.aur.keep_going, base, r)
+        })
+        .collect::<Result<Vec<Either<Built, Failed>>, Error>>()?
         .into_iter()
-        .flatten()
-        .collect();
+        .partition_map(|e| e);
+
+    namcap_summary(fll, &to_install);
 
-    Ok(to_install)
+    Ok((to_install, failed))
 }
 
-fn build_one(
-    fll: &FluentLanguageLoader,
-    aur: &crate::env::Aur,
-    editor: &str,
-    clone: PathBuf,
-) -> Result<Built, Error> {
+fn build_one(fll: &FluentLanguageLoader, env: &Env, clone: PathBuf) -> Result<Built, Error> {
+    let aur = &env.aur;
+    let net = &env.network;
+    let editor = env.general.editor.as_str();
+
     // Attempt a quick `git pull` to avoid the issue of building stale versions
     // of a package if the user forgot to `-Ay` recently.
     if let Err(e) = aura_core::git::pull(&clone) {
@@ -118,13 +235,19 @@ fn build_one(
 
     // --- Parse the .SRCINFO for metadata --- //
     let path = clone.join(".SRCINFO");
-    let info = Srcinfo::parse_file(&path).map_err(|e| Error::Srcinfo(path, e))?;
+    let info = aura_core::metadata::srcinfo(&path).map_err(|e| Error::Srcinfo(path, e))?;
     let base = info.base.pkgbase;
 
-    aura!(fll, "A-build-pkg", pkg = base.cyan().bold().to_string());
+    let report = env.reporter();
+    report.stage(fl!(
+        fll,
+        "A-build-pkg",
+        pkg = base.cyan().bold().to_string()
+    ));
 
     // --- Prepare the Build Directory --- //
-    let build = aur.build.join(&base);
+    let names: Vec<&str> = info.pkgs.iter().map(|p| p.pkgname.as_str()).collect();
+    let build = build_root(fll, env, &names).join(&base);
     std::fs::create_dir_all(&build).map_err(|e| Error::CreateDir(build.clone(), e))?;
 
     // --- Copy non-downloadable `source` files and PKGBUILD --- //
@@ -168,21 +291,246 @@ fn build_one(
         overwrite_build_files(fll, editor, &build, &base)?;
     }
 
-    let tarballs = {
-        let tarballs = makepkg(&build)?;
+    // --- Fetch remote sources ahead of `makepkg`, if requested --- //
+    if aur.downloader != Downloader::Makepkg {
+        let sources = remote_sources(&info.base.source, env.carch());
+
+        if sources.is_empty().not() {
+            report.stage(fl!(fll, "A-build-sources", count = sources.len()));
+
+            match aur.downloader {
+                Downloader::Aria2 => aria2(net, &build, &sources)?,
+                Downloader::Aura => parallel_download(net, &build, &sources)?,
+                Downloader::Makepkg => {}
+            }
+        }
+    }
+
+    // --- Hand the build directory over to an unprivileged user, if need be --- //
+    let user = build_user(aur)?;
+    if let Some(u) = user.as_deref() {
+        chown(&build, u)?;
+    }
+
+    let (tarballs, lints, flags) = {
+        let mut mk = Makepkg::new(env, user.as_deref());
+        let skip_tests = nocheck::skip(fll, &aur.nocheck, &base);
+        let timed = aur.nocheck.ask_after.is_some() && skip_tests.not();
+        if skip_tests {
+            mk.flags.push("--nocheck");
+        }
+        if timed {
+            mk.flags.push("--log");
+        }
+
+        let tarballs = match makepkg(&mk, &report, &base, &build, aur.timeout_for(&base)) {
+            // A hung build leaves its half-finished `src/` and `pkg/` behind,
+            // which would only get in the way of the next attempt.
+            Err(Error::Timeout(m)) => {
+                cleanup(Keep::Sources, &build);
+                return Err(Error::Timeout(m));
+            }
+            r => r?,
+        };
 
         for tb in tarballs.iter() {
             debug!("Built: {}", tb.display());
         }
 
+        if timed {
+            nocheck::record(&build, &base);
+        }
+
+        // --- Lint the results --- //
+        let lints = if aur.namcap {
+            lint(&aur.namcap_strict, &build, &tarballs)?
+        } else {
+            Vec::new()
+        };
+
         // --- Copy all build artifacts to the cache, and then ignore any sig files --- //
-        copy_to_cache(&aur.cache, &tarballs)?
+        let moved = copy_to_cache(&aur.cache, &tarballs)?
             .into_iter()
             .filter(|path| path.extension().map(|ex| ex != "sig").unwrap_or(false))
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+
+        // Only what affects the result is worth recording.
+        let flags = skip_tests
+            .then(|| "--nocheck".to_string())
+            .into_iter()
+            .collect();
+
+        (moved, lints, flags)
     };
 
-    Ok(Built { clone, tarballs })
+    if aur.keeps.contains(&base).not() {
+        cleanup(aur.keep, &build);
+    }
+
+    Ok(Built {
+        clone,
+        tarballs,
+        lints,
+        flags,
+    })
+}
+
+/// Where a package should be built. If the configured build root is a tmpfs
+/// that looks too small for the package, fall back to the usual on-disk
+/// location instead.
+fn build_root(fll: &FluentLanguageLoader, env: &Env, pkgnames: &[&str]) -> PathBuf {
+    let root = env.aur.build.clone();
+
+    if let Some(avail) = tmpfs_space(&root) {
+        // The extracted `pkg/` tree alone is as large as the installed package,
+        // and `src/` is usually at least as large again. Packages that have
+        // never been installed can't be estimated, and are built as normal.
+        let needed: u64 = env
+            .alpm()
+            .map(|alpm| {
+                let db = alpm.localdb();
+                pkgnames
+                    .iter()
+                    .filter_map(|p| db.pkg(*p).ok())
+                    .map(|p| 2 * p.isize().max(0) as u64)
+                    .sum()
+            })
+            .unwrap_or(0);
+
+        debug!("tmpfs build: {} needed, {} available", needed, avail);
+
+        if needed > avail {
+            if let Ok(disk) = crate::dirs::builds() {
+                let size = needed.bytes().to_string();
+                yellow!(fll, "A-build-tmpfs", size = size, dir = disk.utf8());
+                return disk;
+            }
+        }
+    }
+
+    root
+}
+
+/// If the given path is on a tmpfs, how many bytes can actually be written
+/// there? This is limited by both the size of the tmpfs and by available RAM.
+fn tmpfs_space(path: &Path) -> Option<u64> {
+    let out = Command::new("df")
+        .arg("--output=fstype,avail")
+        .arg("-B1")
+        .arg(path)
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8(out.stdout).ok()?;
+    let mut fields = stdout.lines().nth(1)?.split_whitespace();
+
+    (fields.next()? == "tmpfs").then(|| ())?;
+    let avail: u64 = fields.next()?.parse().ok()?;
+    let ram = mem_available().unwrap_or(avail);
+
+    Some(avail.min(ram))
+}
+
+/// Available memory in bytes, as reported by `/proc/meminfo`.
+fn mem_available() -> Option<u64> {
+    std::fs::read_to_string("/proc/meminfo")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))
+        .and_then(|kb| kb.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// Run `namcap` over a PKGBUILD and the packages built from it. Complaints the
+/// user has deemed fatal fail the build.
+fn lint(
+    strict: &HashSet<String>,
+    build: &Path,
+    tarballs: &[PathBuf],
+) -> Result<Vec<Complaint>, Error> {
+    let targets: Vec<&Path> = std::iter::once(Path::new("PKGBUILD"))
+        .chain(tarballs.iter().map(|tb| tb.as_path()))
+        .collect();
+
+    // Whether `namcap` is installed was confirmed when the `Env` was validated.
+    let lints = super::namcap::run(build, &targets).unwrap_or_default();
+    let fatal: Vec<String> = lints
+        .iter()
+        .filter(|c| c.is_fatal(strict))
+        .map(|c| c.to_string())
+        .collect();
+
+    if fatal.is_empty() {
+        Ok(lints)
+    } else {
+        Err(Error::Namcap(fatal))
+    }
+}
+
+/// Display every complaint `namcap` had across a set of builds, most severe
+/// first.
+fn namcap_summary(fll: &FluentLanguageLoader, builts: &[Built]) {
+    let mut lints: Vec<&Complaint> = builts.iter().flat_map(|b| &b.lints).collect();
+
+    if lints.is_empty().not() {
+        lints.sort_by_key(|c| c.severity);
+        aura!(fll, "A-build-namcap");
+
+        for (severity, group) in &lints.into_iter().group_by(|c| c.severity) {
+            let label = match severity {
+                Severity::Error => fl!(fll, "A-build-namcap-errors").red(),
+                Severity::Warning => fl!(fll, "A-build-namcap-warnings").yellow(),
+                Severity::Info => fl!(fll, "A-build-namcap-info").normal(),
+            };
+
+            println!("  {}", label.bold());
+            group.for_each(|c| println!("    {}", c));
+        }
+    }
+}
+
+/// Rebuild a package in an already prepared directory, just as `-A` would have
+/// originally, but with extra variables set to mimic an earlier build. Yields
+/// the paths of the built tarballs.
+pub(crate) fn rebuild(
+    env: &Env,
+    base: &str,
+    within: &Path,
+    vars: &[(&str, &str)],
+) -> Result<Vec<PathBuf>, Error> {
+    let user = build_user(&env.aur)?;
+    if let Some(u) = user.as_deref() {
+        chown(within, u)?;
+    }
+
+    let mut mk = Makepkg::new(env, user.as_deref());
+    mk.vars.extend(vars.iter().copied());
+    mk.flags.extend(["--cleanbuild", "--noconfirm"]);
+
+    makepkg(
+        &mk,
+        &env.reporter(),
+        base,
+        within,
+        env.aur.timeout_for(base),
+    )
+}
+
+/// Tidy up a build directory after a successful build, as per the user's
+/// retention policy. Failure here isn't worth interrupting anything over.
+fn cleanup(keep: Keep, build: &Path) {
+    let res = match keep {
+        Keep::All => Ok(()),
+        Keep::Sources => ["src", "pkg"]
+            .iter()
+            .map(|d| build.join(d))
+            .filter(|d| d.is_dir())
+            .try_for_each(std::fs::remove_dir_all),
+        Keep::None => std::fs::remove_dir_all(build),
+    };
+
+    if let Err(e) = res {
+        warn!("Failed to clean {}: {}", build.display(), e);
+    }
 }
 
 fn show_diffs(
@@ -260,25 +608,341 @@ fn edit(editor: &str, file: PathBuf) -> Result<(), Error> {
     Ok(())
 }
 
-/// Build each package specified by the `PKGBUILD` and yield a list of the built
-/// tarballs.
-fn makepkg(within: &Path) -> Result<Vec<PathBuf>, Error> {
-    Command::new("makepkg")
-        .arg("-f") // TODO Remove or rethink
-        .current_dir(within)
+/// A remote source file of a PKGBUILD, and the filename `makepkg` expects to
+/// find it under.
+pub(super) struct Source<'a> {
+    pub(super) url: &'a str,
+    pub(super) file: &'a str,
+}
+
+impl<'a> Source<'a> {
+    /// Parse a `source` entry, like `foo.tar.gz::https://example.com/v1.tar.gz`.
+    /// VCS sources like `git+https://` are left for `makepkg` to handle.
+    fn parse(raw: &'a str) -> Option<Source<'a>> {
+        let (file, url) = match raw.split_once("::") {
+            Some((file, url)) => (Some(file), url),
+            None => (None, raw),
+        };
+
+        ["https://", "http://", "ftp://"]
+            .iter()
+            .any(|scheme| url.starts_with(scheme))
+            .then(|| {
+                let url = url.split('#').next().unwrap_or(url);
+                let file = file.unwrap_or_else(|| url.rsplit('/').next().unwrap_or(url));
+                Source { url, file }
+            })
+    }
+}
+
+/// All remote sources relevant to the given architecture.
+pub(super) fn remote_sources<'a>(sources: &'a [ArchVec], arch: &str) -> Vec<Source<'a>> {
+    sources
+        .iter()
+        .filter(|av| aura_core::aur::arch_applies(av, arch))
+        .flat_map(|av| av.vec.iter())
+        .filter_map(|s| Source::parse(s))
+        .collect()
+}
+
+/// Download sources into the build directory with `aria2c`, which resumes
+/// partial downloads and uses multiple connections per file.
+fn aria2(net: &Network, within: &Path, sources: &[Source]) -> Result<(), Error> {
+    let input: String = sources
+        .iter()
+        .map(|s| format!("{}\n  out={}\n", s.url, s.file))
+        .collect();
+
+    let mut aria = Command::new("aria2c");
+
+    if let Some(rate) = net.limit_rate {
+        aria.arg(format!("--max-overall-download-limit={rate}K"));
+    }
+
+    let mut child = aria
+        .arg("--continue=true")
+        .arg("--auto-file-renaming=false")
+        .arg("--max-connection-per-server=8")
+        .arg("--split=8")
+        .arg("--input-file=-")
+        .arg(format!("--dir={}", within.display()))
+        .stdin(Stdio::piped())
+        .explained()
+        .spawn()
+        .map_err(|_| Error::Aria2)?;
+
+    // The handle is dropped immediately, closing `stdin` so that `aria2c`
+    // knows the input is complete.
+    child
+        .stdin
+        .take()
+        .ok_or(Error::Aria2)?
+        .write_all(input.as_bytes())
+        .map_err(|_| Error::Aria2)?;
+
+    child
+        .wait()
+        .map_err(|_| Error::Aria2)?
+        .success()
+        .then(|| ())
+        .ok_or(Error::Aria2)
+}
+
+/// Download sources into the build directory concurrently. Files that are
+/// already present from a previous build are skipped.
+fn parallel_download(net: &Network, within: &Path, sources: &[Source]) -> Result<(), Error> {
+    let todo: Vec<_> = sources
+        .iter()
+        .filter(|s| within.join(s.file).is_file().not())
+        .collect();
+
+    let progress = Mutex::new(Progress::new());
+    let bar = progress
+        .lock()
+        .unwrap()
+        .bar(todo.len(), "Downloading sources");
+
+    todo.into_par_iter()
+        .map(|s| {
+            // Partial downloads are never mistaken for complete ones.
+            let target = within.join(s.file);
+            let part = within.join(format!("{}.part", s.file));
+            let res = download_with_progress(net, s.url, &part, None)
+                .ok()
+                .and_then(|_| std::fs::rename(&part, &target).ok())
+                .ok_or_else(|| Error::SourceDownload(s.url.to_string()));
+
+            progress.lock().unwrap().inc_and_draw(&bar, 1);
+            res
+        })
+        .collect::<Result<(), Error>>()
+}
+
+/// `makepkg` refuses to run as root. When Aura itself is root, which user
+/// should builds be performed as instead? Yields `None` when no switch is
+/// necessary.
+///
+/// If no `builduser` is configured, the user who invoked `sudo` is used.
+fn build_user(aur: &crate::env::Aur) -> Result<Option<String>, Error> {
+    match sudo::check() {
+        sudo::RunningAs::Root => aur
+            .builduser
+            .clone()
+            .or_else(|| std::env::var("SUDO_USER").ok())
+            .filter(|u| u != "root")
+            .map(Some)
+            .ok_or(Error::RootBuild),
+        _ => Ok(None),
+    }
+}
+
+/// Give ownership of a build directory to the build user.
+fn chown(dir: &Path, user: &str) -> Result<(), Error> {
+    Command::new("chown")
+        .arg("-R")
+        .arg(format!("{user}:"))
+        .arg(dir)
+        .explained()
         .status()
-        // FIXME Tue Jun 21 14:00:15 2022
-        //
-        // This should probably collect the error.
-        .map_err(|_| Error::Makepkg)?
+        .map_err(|_| Error::Chown(dir.to_path_buf(), user.to_string()))?
+        .success()
+        .then(|| ())
+        .ok_or_else(|| Error::Chown(dir.to_path_buf(), user.to_string()))
+}
+
+/// How `makepkg` is called: as whom, with what environment, and whether within
+/// a chroot, as set by the active build profile.
+struct Makepkg<'a> {
+    user: Option<&'a str>,
+    vars: Vec<(&'a str, &'a str)>,
+    config: Option<&'a Path>,
+    chroot: Option<&'a Path>,
+    limits: &'a Limits,
+    /// Flags for this build beyond [`MAKEPKG_FLAGS`].
+    flags: Vec<&'static str>,
+}
+
+impl<'a> Makepkg<'a> {
+    fn new(env: &'a Env, user: Option<&'a str>) -> Self {
+        let profile = env.aur.active_profile();
+        let carch = profile
+            .and_then(|p| p.carch.as_deref())
+            .or(env.aur.carch.as_deref());
+        let vars = carch
+            .map(|a| ("CARCH", a))
+            .into_iter()
+            .chain(
+                profile
+                    .into_iter()
+                    .flat_map(|p| p.env.iter().map(|(k, v)| (k.as_str(), v.as_str()))),
+            )
+            .collect();
+
+        Makepkg {
+            user,
+            vars,
+            config: profile.and_then(|p| p.makepkg_conf.as_deref()),
+            chroot: profile.and_then(|p| p.chroot.as_deref()),
+            limits: &env.aur.limits,
+            flags: Vec::new(),
+        }
+    }
+
+    /// A plain `makepkg` call, run as the build user if there is one.
+    fn makepkg(&self) -> Command {
+        let mut cmd = match self.user {
+            None => {
+                let mut cmd = Command::new("makepkg");
+                cmd.envs(self.vars.iter().copied());
+                cmd
+            }
+            Some(u) => {
+                // `sudo` would otherwise clear them.
+                let mut cmd = Command::new("sudo");
+                cmd.arg("-u").arg(u).arg("env");
+                cmd.args(self.vars.iter().map(|(k, v)| format!("{k}={v}")));
+                cmd.arg("makepkg");
+                cmd
+            }
+        };
+
+        if let Some(c) = self.config {
+            cmd.arg("--config").arg(c);
+        }
+
+        cmd
+    }
+
+    /// The call that lists the tarballs a build will have made. Their names
+    /// depend on `CARCH` and `PKGEXT`, so for a chroot build, its own
+    /// `makepkg.conf` must be read instead of the host's.
+    fn packagelist(&self) -> Command {
+        let mut cmd = match self.chroot {
+            None => self.makepkg(),
+            Some(root) => {
+                let bare = Makepkg {
+                    vars: Vec::new(),
+                    config: None,
+                    chroot: None,
+                    flags: Vec::new(),
+                    ..*self
+                };
+                let mut cmd = bare.makepkg();
+                cmd.arg("--config").arg(root.join("root/etc/makepkg.conf"));
+                cmd
+            }
+        };
+
+        cmd.arg("--packagelist");
+        cmd
+    }
+
+    /// The call that actually builds, within the chroot if there is one, and
+    /// under whatever resource limits were asked for.
+    fn build(&self) -> Command {
+        let cmd = match self.chroot {
+            None => {
+                let mut cmd = self.makepkg();
+                cmd.args(MAKEPKG_FLAGS).args(&self.flags);
+                cmd
+            }
+            Some(root) => {
+                let mut cmd = Command::new("makechrootpkg");
+                cmd.arg("-c").arg("-r").arg(root);
+                if let Some(u) = self.user {
+                    cmd.arg("-U").arg(u);
+                }
+                // The chroot's own `makepkg.conf` applies, and no variables
+                // are passed through to it.
+                cmd.arg("--").args(MAKEPKG_FLAGS).args(&self.flags);
+                cmd
+            }
+        };
+
+        self.limit(cmd)
+    }
+
+    /// Wrap a command in `systemd-run`, `nice`, and `ionice` as necessary.
+    fn limit(&self, inner: Command) -> Command {
+        let lim = self.limits;
+        let mut prefix: Vec<String> = Vec::new();
+
+        if lim.is_scoped() {
+            prefix.extend(["systemd-run", "--scope", "--quiet"].map(String::from));
+            // Without a build user, Aura isn't root, and only the user's own
+            // service manager will create scopes for it.
+            if self.user.is_none() {
+                prefix.push("--user".to_string());
+            }
+            if let Some(m) = lim.memory_max.as_deref() {
+                prefix.push(format!("--property=MemoryMax={m}"));
+            }
+            if let Some(q) = lim.cpu_quota.as_deref() {
+                prefix.push(format!("--property=CPUQuota={q}"));
+            }
+        }
+
+        if let Some(n) = lim.nice {
+            prefix.extend(["nice".to_string(), "-n".to_string(), n.to_string()]);
+        }
+
+        if let Some(c) = lim.ionice.as_deref() {
+            prefix.extend(["ionice", "-c", c].map(String::from));
+        }
+
+        match prefix.split_first() {
+            None => inner,
+            Some((program, rest)) => {
+                let mut cmd = Command::new(program);
+                cmd.args(rest)
+                    .arg(inner.get_program())
+                    .args(inner.get_args());
+                for (k, v) in inner.get_envs() {
+                    match v {
+                        Some(v) => cmd.env(k, v),
+                        None => cmd.env_remove(k),
+                    };
+                }
+                cmd
+            }
+        }
+    }
+}
+
+/// Build each package specified by the `PKGBUILD` and yield a list of the built
+/// tarballs. The build is stopped if it runs longer than the given limit.
+fn makepkg(
+    mk: &Makepkg,
+    report: &Reporter,
+    base: &str,
+    within: &Path,
+    limit: Option<u64>,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut cmd = mk.build();
+    cmd.current_dir(within);
+    report.command(&cmd);
+    report.build_output(base, &mut cmd);
+
+    let status = match limit {
+        None => crate::signal::status(&mut cmd).map(Some),
+        Some(m) => crate::signal::status_within(&mut cmd, Duration::from_secs(m * 60)),
+    }
+    // FIXME Tue Jun 21 14:00:15 2022
+    //
+    // This should probably collect the error.
+    .map_err(|_| Error::Makepkg)?;
+
+    status
+        .ok_or(Error::Timeout(limit.unwrap_or_default()))?
         .success()
         .then(|| ())
         .ok_or(Error::Makepkg)?;
 
     // NOTE Outputs absolute paths.
-    let bytes = Command::new("makepkg")
-        .arg("--packagelist")
+    let bytes = mk
+        .packagelist()
         .current_dir(within)
+        .explained()
         .output()
         .map_err(|e| Error::Pkglist(within.to_path_buf(), e))?
         .stdout;
@@ -316,6 +980,7 @@ fn move_tarball(source: &Path, target: &Path) -> Result<(), Error> {
     Command::new("mv")
         .arg(source)
         .arg(target)
+        .explained()
         .status()
         // FIXME Tue Jun 21 14:04:58 2022
         //
@@ -328,19 +993,30 @@ fn move_tarball(source: &Path, target: &Path) -> Result<(), Error> {
 
 fn build_check(
     fll: &FluentLanguageLoader,
+    report: &Reporter,
     is_single: bool,
+    keep_going: bool,
+    base: String,
     r: Result<Built, Error>,
-) -> Result<Option<Built>, Error> {
+) -> Result<Either<Built, Failed>, Error> {
     match r {
-        Ok(tbs) => Ok(Some(tbs)),
+        Ok(tbs) => Ok(Either::Left(tbs)),
+        // Nothing else should be attempted once the user has asked to stop.
+        Err(_) if crate::signal::interrupted() => Err(Error::Interrupted),
         Err(e) => {
-            red!(fll, "A-build-fail");
-            eprintln!("\n  {}\n", e.localise(fll));
+            if report.is_quiet() {
+                report.outcome(fll, &base, Outcome::Failed);
+            } else {
+                red!(fll, "A-build-fail");
+            }
+            let reason = e.localise(fll);
+            eprintln!("\n  {}\n", reason);
 
-            if is_single || proceed!(fll, "A-build-continue").is_none() {
+            // With `--keep-going`, there's no need to ask.
+            if is_single || (keep_going.not() && proceed!(fll, "A-build-continue").is_none()) {
                 Err(Error::Cancelled)
             } else {
-                Ok(None)
+                Ok(Either::Right(Failed { base, reason }))
             }
         }
     }
@@ -0,0 +1,296 @@
+//! Reusing the dependency resolution of an earlier, identical `-A` command.
+//!
+//! Resolving a large set of AUR packages means fetching the metadata and
+//! reading the `.SRCINFO` of every package involved. When the same packages
+//! are asked for again within a day, say after fixing one failed build, the
+//! last result is reused instead. A single batched request first confirms that
+//! nothing to be built has been pushed to the AUR since, and its `.SRCINFO`
+//! mustn't have changed locally either. Whatever has been installed in the
+//! meantime is counted as satisfied.
+//!
+//! A resolution is also dropped once the repos it was judged against change,
+//! by a database sync, or once something it had counted as satisfied has been
+//! removed.
+
+use crate::env::Env;
+use aura_core::aur::dependencies::Resolution;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long a resolution may be reused at all.
+const MAX_AGE: Duration = Duration::from_secs(crate::utils::SECS_IN_DAY);
+
+/// A past resolution, and what it was asked to resolve.
+#[derive(Serialize, Deserialize)]
+struct Entry<R> {
+    packages: Vec<String>,
+    arch: String,
+    resolution: R,
+}
+
+/// The last resolution of exactly these packages, if it's still good.
+pub(super) fn load(env: &Env, requested: &HashSet<&str>) -> Option<Resolution> {
+    let packages = sorted(requested);
+    let path = path(&packages, env.carch())?;
+    let saved = modified(&path)?;
+    let raw = std::fs::read_to_string(&path).ok()?;
+    let entry: Entry<Resolution> = serde_json::from_str(&raw).ok()?;
+
+    if current(&entry, &packages, env.carch(), saved, SystemTime::now()).not() {
+        return None;
+    }
+
+    let changed = entry.resolution.to_build.iter().any(|b| {
+        let srcinfo = env.aur.clones.join(&b.name).join(".SRCINFO");
+        modified(&srcinfo).map(|m| m > saved).unwrap_or(true)
+    });
+    if changed {
+        debug!("A .SRCINFO has changed since the last resolution.");
+        return None;
+    }
+
+    // Which repo packages to install depends on the sync databases.
+    if synced_since(&Path::new(&env.pacman.db_path).join("sync"), saved) {
+        debug!("The databases have been synced since the last resolution.");
+        return None;
+    }
+
+    let alpm = env.alpm().ok()?;
+    let db = alpm.localdb();
+    let is_installed =
+        |name: &str| db.pkg(name).is_ok() || db.pkgs().find_satisfier(name).is_some();
+    if lost(&is_installed, &entry.resolution.satisfied) {
+        debug!("Something satisfied last time has since been removed.");
+        return None;
+    }
+
+    // The clones aren't pulled when a resolution is reused, so the AUR itself
+    // is asked whether anything to be built has been pushed to since.
+    let names: Vec<String> = entry
+        .resolution
+        .to_build
+        .iter()
+        .filter_map(|b| {
+            let path = env.aur.clones.join(&b.name).join(".SRCINFO");
+            let info = aura_core::metadata::srcinfo(&path).ok()?;
+            info.pkgs.into_iter().next().map(|p| p.pkgname)
+        })
+        .collect();
+    if names.len() < entry.resolution.to_build.len() {
+        return None;
+    }
+
+    let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+    let found = aura_core::faur::info_batched(&names, &|u: &str| env.network.json(u)).ok()?;
+    let pushed: Vec<Option<u64>> = found
+        .iter()
+        .map(|(_, p)| p.as_ref().map(|p| p.last_modified))
+        .collect();
+    if untouched(saved, &pushed).not() {
+        debug!("Something to be built has been pushed to since the last resolution.");
+        return None;
+    }
+
+    Some(settle(is_installed, requested, entry.resolution))
+}
+
+/// Remember a resolution of these packages.
+pub(super) fn save(env: &Env, requested: &HashSet<&str>, resolution: &Resolution) {
+    let packages = sorted(requested);
+
+    if let Some(path) = path(&packages, env.carch()) {
+        let entry = Entry {
+            packages,
+            arch: env.carch().to_string(),
+            resolution,
+        };
+
+        if let Ok(json) = serde_json::to_string(&entry) {
+            if let Err(e) = std::fs::write(&path, json) {
+                debug!("Failed to save the resolution to {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+/// Is this the entry of the given packages, and young enough to be reused?
+fn current<R>(
+    entry: &Entry<R>,
+    packages: &[String],
+    arch: &str,
+    saved: SystemTime,
+    now: SystemTime,
+) -> bool {
+    // Different sets of packages could still share a file.
+    let same = entry.packages == packages && entry.arch == arch;
+    let young = now
+        .duration_since(saved)
+        .map(|age| age < MAX_AGE)
+        .unwrap_or(true);
+
+    same && young
+}
+
+/// Were none of the given packages pushed to the AUR after the entry was
+/// saved? Each is the time of its last push, if it's still on the AUR at all.
+fn untouched(saved: SystemTime, pushed: &[Option<u64>]) -> bool {
+    let saved = saved
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    pushed
+        .iter()
+        .all(|p| p.map(|p| p <= saved).unwrap_or(false))
+}
+
+/// Has any of the sync databases in this directory changed since the given
+/// time?
+fn synced_since(dir: &Path, saved: SystemTime) -> bool {
+    dir.read_dir()
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().map(|x| x == "db").unwrap_or(false))
+                .any(|p| modified(&p).map(|m| m > saved).unwrap_or(true))
+        })
+        .unwrap_or(false)
+}
+
+/// Is any of what was counted as satisfied no longer installed?
+fn lost<F>(is_installed: F, satisfied: &HashSet<String>) -> bool
+where
+    F: Fn(&str) -> bool,
+{
+    satisfied.iter().any(|s| is_installed(s).not())
+}
+
+/// Count anything installed since the resolution was made as satisfied, unless
+/// it was asked for by name.
+fn settle<F>(is_installed: F, requested: &HashSet<&str>, mut res: Resolution) -> Resolution
+where
+    F: Fn(&str) -> bool,
+{
+    let installed = |name: &str| requested.contains(name).not() && is_installed(name);
+
+    let (done, to_install): (HashSet<_>, HashSet<_>) = res
+        .to_install
+        .into_iter()
+        .partition(|o| installed(o.as_ref()));
+    res.satisfied.extend(done.iter().map(|o| o.to_string()));
+    res.to_install = to_install;
+
+    let (done, to_build): (HashSet<_>, HashSet<_>) =
+        res.to_build.into_iter().partition(|b| installed(&b.name));
+    res.satisfied.extend(done.into_iter().map(|b| b.name));
+    res.to_build = to_build;
+
+    res
+}
+
+fn sorted(requested: &HashSet<&str>) -> Vec<String> {
+    let mut packages: Vec<String> = requested.iter().map(|p| p.to_string()).collect();
+    packages.sort_unstable();
+    packages
+}
+
+fn path(packages: &[String], arch: &str) -> Option<PathBuf> {
+    let key = format!("{arch} {}", packages.join(" "));
+
+    crate::dirs::resolutions()
+        .ok()
+        .map(|dir| dir.join(format!("{:016x}.json", crate::fetch::fnv(&key))))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resolution() -> Resolution {
+        let raw = r#"{
+            "to_install": ["glibc", "gtk3"],
+            "to_build": [{"name": "aura", "deps": ["gtk3"]}, {"name": "yay", "deps": []}],
+            "satisfied": ["bash"],
+            "unsupported": [],
+            "provided": []
+        }"#;
+
+        serde_json::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn installed_since() {
+        let requested: HashSet<&str> = ["aura", "glibc"].into_iter().collect();
+        let installed = |name: &str| ["glibc", "gtk3", "yay"].contains(&name);
+        let res = settle(installed, &requested, resolution());
+
+        let mut satisfied: Vec<_> = res.satisfied.iter().map(|s| s.as_str()).collect();
+        satisfied.sort_unstable();
+        assert_eq!(vec!["bash", "gtk3", "yay"], satisfied);
+
+        // Asked for by name, so still installed or built again.
+        assert!(res.to_install.contains("glibc"));
+        assert!(res.to_build.iter().any(|b| b.name == "aura"));
+        assert_eq!(1, res.to_install.len());
+        assert_eq!(1, res.to_build.len());
+    }
+
+    #[test]
+    fn removed_since() {
+        let res = resolution();
+
+        assert!(lost(|name: &str| name == "bash", &res.satisfied).not());
+        assert!(lost(|_: &str| false, &res.satisfied));
+    }
+
+    #[test]
+    fn synced() {
+        let dir = std::env::temp_dir().join(format!("aura-memo-sync-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("core.db"), "").unwrap();
+        let written = modified(&dir.join("core.db")).unwrap();
+
+        assert!(synced_since(&dir, written - Duration::from_secs(60)));
+        assert!(synced_since(&dir, written + Duration::from_secs(60)).not());
+        assert!(synced_since(&dir.join("missing"), written).not());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reusable() {
+        let packages = vec!["aura".to_string()];
+        let entry = Entry {
+            packages: packages.clone(),
+            arch: "x86_64".to_string(),
+            resolution: (),
+        };
+        let saved = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let soon = saved + Duration::from_secs(60);
+        let later = saved + MAX_AGE + Duration::from_secs(1);
+
+        assert!(current(&entry, &packages, "x86_64", saved, soon));
+        assert!(current(&entry, &packages, "x86_64", saved, later).not());
+        assert!(current(&entry, &packages, "aarch64", saved, soon).not());
+        assert!(current(&entry, &["yay".to_string()], "x86_64", saved, soon).not());
+    }
+
+    #[test]
+    fn pushed_since() {
+        let saved = UNIX_EPOCH + Duration::from_secs(1_000);
+
+        assert!(untouched(saved, &[Some(999), Some(1_000)]));
+        assert!(untouched(saved, &[]));
+        assert!(untouched(saved, &[Some(999), Some(1_001)]).not());
+        assert!(untouched(saved, &[Some(999), None]).not());
+    }
+}
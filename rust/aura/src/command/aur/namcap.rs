@@ -0,0 +1,101 @@
+//! Linting PKGBUILDs and built packages with `namcap`.
+
+use std::ops::Not;
+use std::path::Path;
+use std::process::Command;
+
+/// How serious a complaint from `namcap` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    fn from_letter(s: &str) -> Option<Severity> {
+        match s {
+            "E" => Some(Severity::Error),
+            "W" => Some(Severity::Warning),
+            "I" => Some(Severity::Info),
+            _ => None,
+        }
+    }
+
+    /// The single letter `namcap` uses to mark this severity.
+    pub(super) fn letter(&self) -> &'static str {
+        match self {
+            Severity::Error => "E",
+            Severity::Warning => "W",
+            Severity::Info => "I",
+        }
+    }
+}
+
+/// A single complaint from `namcap`, as reported by its machine-readable mode.
+pub(super) struct Complaint {
+    /// The PKGBUILD or package that was complained about.
+    pub(super) target: String,
+    pub(super) severity: Severity,
+    /// A stable identifier for the kind of complaint, like
+    /// `dependency-detected-not-included`.
+    pub(super) tag: String,
+    /// Any further detail, like the file or dependency concerned.
+    pub(super) detail: String,
+}
+
+impl Complaint {
+    /// Parse a line like `foo W: elffile-without-relro usr/bin/foo`.
+    fn parse(line: &str) -> Option<Complaint> {
+        let (target, rest) = line.split_once(' ')?;
+        let (letter, rest) = rest.split_once(": ")?;
+        let severity = Severity::from_letter(letter)?;
+        let (tag, detail) = rest.split_once(' ').unwrap_or((rest, ""));
+
+        Some(Complaint {
+            target: target.to_string(),
+            severity,
+            tag: tag.to_string(),
+            detail: detail.to_string(),
+        })
+    }
+
+    /// Should this complaint fail the build, given a set of tags and/or
+    /// severity letters that the user considers fatal?
+    pub(super) fn is_fatal<'a, I>(&self, strict: I) -> bool
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        strict
+            .into_iter()
+            .any(|s| s == &self.tag || s == self.severity.letter())
+    }
+}
+
+impl std::fmt::Display for Complaint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} {}", self.target, self.tag, self.detail)
+    }
+}
+
+/// Run `namcap` over the given files. Yields `None` if `namcap` isn't
+/// installed.
+pub(super) fn run<P>(within: &Path, targets: &[P]) -> Option<Vec<Complaint>>
+where
+    P: AsRef<Path>,
+{
+    let out = Command::new("namcap")
+        .arg("-m")
+        .args(targets.iter().map(|t| t.as_ref()))
+        .current_dir(within)
+        .output()
+        .ok()?;
+
+    let complaints = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter(|l| l.trim().is_empty().not())
+        .filter_map(Complaint::parse)
+        .collect();
+
+    Some(complaints)
+}
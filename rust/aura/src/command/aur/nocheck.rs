@@ -0,0 +1,92 @@
+//! Deciding whether to run a package's test suite.
+//!
+//! When asking about slow test suites is enabled, `makepkg` is told to log
+//! each of its phases. The gap between the final writes to the log of the
+//! phase before `check()` and to the log of `check()` itself is how long the
+//! tests took. That's recorded per package base, for next time.
+
+use crate::env::Nocheck;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Should the tests of the given package base be skipped?
+pub(super) fn skip(fll: &FluentLanguageLoader, policy: &Nocheck, base: &str) -> bool {
+    if policy.skip.contains(base) {
+        return true;
+    }
+
+    if policy.run.contains(base) {
+        return false;
+    }
+
+    if policy.default {
+        return true;
+    }
+
+    let last = load().get(base).copied();
+
+    match (policy.ask_after, last) {
+        (Some(limit), Some(secs)) if secs > limit * 60 => {
+            let msg = fl!(
+                fll,
+                "A-build-nocheck",
+                pkg = base,
+                minutes = (secs + 59) / 60
+            );
+            crate::prompts::ask(fll, "A-build-nocheck", msg).is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Record how long the tests of a completed build took, if `makepkg` left
+/// logs behind to tell.
+pub(super) fn record(build: &Path, base: &str) {
+    let logs: Vec<(String, SystemTime)> = match std::fs::read_dir(build) {
+        Err(_) => return,
+        Ok(rd) => rd
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                let time = e.metadata().and_then(|m| m.modified()).ok()?;
+                name.ends_with(".log").then(|| (name, time))
+            })
+            .collect(),
+    };
+
+    let check = logs
+        .iter()
+        .filter(|(n, _)| n.ends_with("-check.log"))
+        .map(|(_, t)| *t)
+        .max();
+
+    let secs = check.and_then(|c| {
+        let before = logs.iter().map(|(_, t)| *t).filter(|t| *t < c).max()?;
+        c.duration_since(before).ok()
+    });
+
+    if let Some(d) = secs {
+        debug!("The tests of {base} took {}s", d.as_secs());
+
+        let mut times = load();
+        times.insert(base.to_string(), d.as_secs());
+
+        if let Ok(path) = crate::dirs::check_times() {
+            let json = serde_json::to_string(&times).unwrap_or_default();
+            let _ = std::fs::write(path, json);
+        }
+    }
+}
+
+/// How long the tests of each package base last took, in seconds.
+fn load() -> BTreeMap<String, u64> {
+    crate::dirs::check_times()
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,163 @@
+//! Holding back heavy builds on laptops that aren't up to them right now.
+
+use crate::env::Power;
+use crate::yellow;
+use aura_core::aur::dependencies::Buildable;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use std::ops::Not;
+use std::path::Path;
+
+const POWER_SUPPLIES: &str = "/sys/class/power_supply";
+const THERMAL_ZONES: &str = "/sys/class/thermal";
+
+/// Warn about building heavy packages while on battery or while the CPU is
+/// already hot. Yields `None` if the builds should be deferred instead.
+pub(crate) fn guard(
+    fll: &FluentLanguageLoader,
+    power: &Power,
+    to_build: &[Buildable],
+) -> Option<()> {
+    if power.guard.not() {
+        return Some(());
+    }
+
+    let heavy: Vec<&str> = to_build
+        .iter()
+        .map(|b| b.name.as_str())
+        .filter(|n| {
+            power.heavy.is_empty() || power.heavy.iter().any(|p| aura_core::glob::matches(p, n))
+        })
+        .collect();
+
+    if heavy.is_empty() {
+        return Some(());
+    }
+
+    let mut concerned = false;
+
+    if on_battery(Path::new(POWER_SUPPLIES)) {
+        yellow!(fll, "A-power-battery", pkgs = heavy.join(", "));
+        concerned = true;
+    }
+
+    if let Some(temp) = cpu_temp(Path::new(THERMAL_ZONES)).filter(|t| *t >= power.max_temp) {
+        yellow!(
+            fll,
+            "A-power-hot",
+            temp = temp,
+            limit = power.max_temp,
+            pkgs = heavy.join(", ")
+        );
+        concerned = true;
+    }
+
+    (concerned && power.defer).not().then(|| ())
+}
+
+/// Is the machine running from a battery? Only true when some battery is
+/// discharging, so desktops and plugged-in laptops are never concerned.
+fn on_battery(supplies: &Path) -> bool {
+    let read = |p: &Path, f: &str| {
+        std::fs::read_to_string(p.join(f))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let supplies: Vec<_> = match std::fs::read_dir(supplies) {
+        Err(_) => return false,
+        Ok(rd) => rd.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+    };
+
+    let plugged = supplies
+        .iter()
+        .any(|p| read(p, "type") == "Mains" && read(p, "online") == "1");
+
+    plugged.not()
+        && supplies
+            .iter()
+            .any(|p| read(p, "type") == "Battery" && read(p, "status") == "Discharging")
+}
+
+/// The hottest reading of any thermal zone, in degrees Celsius.
+fn cpu_temp(zones: &Path) -> Option<u32> {
+    std::fs::read_dir(zones)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with("thermal_zone"))
+        .filter_map(|e| std::fs::read_to_string(e.path().join("temp")).ok())
+        .filter_map(|s| s.trim().parse::<i64>().ok())
+        // Reported in millidegrees.
+        .map(|m| (m.max(0) / 1000) as u32)
+        .max()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A fake sysfs class directory, with the given files per device.
+    fn sysfs(name: &str, devices: &[(&str, &[(&str, &str)])]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("aura-power-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (device, files) in devices {
+            let dir = root.join(device);
+            fs::create_dir_all(&dir).unwrap();
+            for (file, contents) in files.iter() {
+                fs::write(dir.join(file), format!("{contents}\n")).unwrap();
+            }
+        }
+        root
+    }
+
+    #[test]
+    fn battery() {
+        let discharging: &[(&str, &str)] = &[("type", "Battery"), ("status", "Discharging")];
+        let charging: &[(&str, &str)] = &[("type", "Battery"), ("status", "Charging")];
+        let online: &[(&str, &str)] = &[("type", "Mains"), ("online", "1")];
+        let offline: &[(&str, &str)] = &[("type", "Mains"), ("online", "0")];
+
+        let unplugged = sysfs("unplugged", &[("AC", offline), ("BAT0", discharging)]);
+        assert!(on_battery(&unplugged));
+
+        let plugged = sysfs("plugged", &[("AC", online), ("BAT0", charging)]);
+        assert!(!on_battery(&plugged));
+
+        // Some batteries report discharging even while on mains.
+        let confused = sysfs("confused", &[("AC", online), ("BAT0", discharging)]);
+        assert!(!on_battery(&confused));
+
+        let desktop = sysfs("desktop", &[("AC", online)]);
+        assert!(!on_battery(&desktop));
+        assert!(!on_battery(&desktop.join("missing")));
+
+        for dir in [unplugged, plugged, confused, desktop] {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn temperature() {
+        let zones = sysfs(
+            "thermal",
+            &[
+                ("thermal_zone0", &[("temp", "45000")]),
+                ("thermal_zone1", &[("temp", "81500")]),
+                ("thermal_zone2", &[("temp", "garbage")]),
+                ("cooling_device0", &[("temp", "99000")]),
+            ],
+        );
+        assert_eq!(Some(81), cpu_temp(&zones));
+
+        let empty = sysfs("thermal-empty", &[]);
+        fs::create_dir_all(&empty).unwrap();
+        assert_eq!(None, cpu_temp(&empty));
+        assert_eq!(None, cpu_temp(&empty.join("missing")));
+
+        for dir in [zones, empty] {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Recording and viewing how Aura-built packages were built.
+
+use super::build::{Built, MAKEPKG_FLAGS};
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::yellow;
+use aura_core::cache::PkgPath;
+use aura_core::provenance::{is_vcs_source, Provenance, Record};
+use aura_core::Coded;
+use colored::{ColoredString, Colorize};
+use from_variants::FromVariants;
+use i18n_embed::{fluent::FluentLanguageLoader, LanguageLoader};
+use i18n_embed_fl::fl;
+use log::error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use time::OffsetDateTime;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    Git(aura_core::git::Error),
+    #[from_variants(skip)]
+    FileOpen(PathBuf, std::io::Error),
+    #[from_variants(skip)]
+    JsonWrite(PathBuf, serde_json::Error),
+    Stdout,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Dirs(e) => e.nested(),
+            Error::Git(e) => e.nested(),
+            Error::FileOpen(_, e) => error!("{e}"),
+            Error::JsonWrite(_, e) => error!("{e}"),
+            Error::Stdout => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Dirs(e) => e.localise(fll),
+            Error::Git(e) => e.localise(fll),
+            Error::FileOpen(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
+            Error::JsonWrite(p, _) => fl!(fll, "err-json-write", file = p.utf8()),
+            Error::Stdout => fl!(fll, "err-write"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::Git(e) => e.code(),
+            Error::FileOpen(_, _) => "provenance-file-open",
+            Error::JsonWrite(_, _) => "provenance-json-write",
+            Error::Stdout => "provenance-stdout",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::Git(e) => e.context(),
+            Error::FileOpen(p, _) => vec![("path", p.display().to_string())],
+            Error::JsonWrite(p, _) => vec![("path", p.display().to_string())],
+            Error::Stdout => Vec::new(),
+        }
+    }
+}
+
+/// The provenance database, or an empty one if none has been written yet.
+pub(crate) fn load() -> Provenance {
+    crate::dirs::provenance()
+        .ok()
+        .and_then(|path| Provenance::from_path(&path))
+        .unwrap_or_default()
+}
+
+/// Record how each of the given packages was built.
+pub(super) fn record(builts: &[Built]) -> Result<(), Error> {
+    let path = crate::dirs::provenance()?;
+    let mut prov = Provenance::from_path(&path).unwrap_or_default();
+    let host = crate::utils::hostname();
+    let built = OffsetDateTime::now_utc().unix_timestamp().max(0) as u64;
+    for b in builts {
+        let flags: Vec<String> = MAKEPKG_FLAGS
+            .iter()
+            .map(|f| f.to_string())
+            .chain(b.flags.iter().cloned())
+            .collect();
+        let commit = aura_core::git::hash(&b.clone)?.trim().to_string();
+        let (pkgbase, vcs) = clone_facts(&b.clone);
+
+        for pp in b.tarballs.iter().filter_map(|t| PkgPath::new(t.clone())) {
+            let record = Record {
+                pkgbase: pkgbase.clone(),
+                version: pp.as_package().version.to_string(),
+                commit: commit.clone(),
+                built,
+                flags: flags.clone(),
+                host: host.clone(),
+                vcs,
+                source: None,
+            };
+
+            prov.insert(pp.as_package().name.to_string(), record);
+        }
+    }
+
+    write(&path, &prov)
+}
+
+/// Record packages that were built by some other tool, given the clones that
+/// tool left behind. Existing records are never replaced. Yields how many
+/// packages were newly recorded.
+pub(crate) fn adopt(packages: &[(alpm::Package<'_>, PathBuf)]) -> Result<usize, Error> {
+    let path = crate::dirs::provenance()?;
+    let mut prov = Provenance::from_path(&path).unwrap_or_default();
+    let host = crate::utils::hostname();
+    let mut added = 0;
+
+    for (p, clone) in packages {
+        if prov.get(p.name()).is_some() {
+            continue;
+        }
+
+        let commit = aura_core::git::hash(clone)?.trim().to_string();
+        let (pkgbase, vcs) = clone_facts(clone);
+        let record = Record {
+            pkgbase,
+            version: p.version().to_string(),
+            commit,
+            built: p.install_date().unwrap_or(0).max(0) as u64,
+            flags: Vec::new(),
+            host: host.clone(),
+            vcs,
+            source: None,
+        };
+
+        prov.insert(p.name().to_string(), record);
+        added += 1;
+    }
+
+    write(&path, &prov)?;
+    Ok(added)
+}
+
+/// Record packages installed directly from tarballs with `-U`, given where
+/// each tarball came from.
+pub(crate) fn record_installed(tarballs: &[(&aura_core::Package, &str)]) -> Result<(), Error> {
+    let path = crate::dirs::provenance()?;
+    let mut prov = Provenance::from_path(&path).unwrap_or_default();
+    let host = crate::utils::hostname();
+    let now = OffsetDateTime::now_utc().unix_timestamp().max(0) as u64;
+
+    for (pkg, source) in tarballs {
+        let record = Record {
+            pkgbase: pkg.name.to_string(),
+            version: pkg.version.to_string(),
+            commit: String::new(),
+            built: now,
+            flags: Vec::new(),
+            host: host.clone(),
+            vcs: false,
+            source: Some(source.to_string()),
+        };
+
+        prov.insert(pkg.name.to_string(), record);
+    }
+
+    write(&path, &prov)
+}
+
+/// The package base of a clone, and whether its sources come from a VCS.
+fn clone_facts(clone: &Path) -> (String, bool) {
+    let info = aura_core::metadata::srcinfo(&clone.join(".SRCINFO")).ok();
+    let vcs = info
+        .as_ref()
+        .map(|i| {
+            i.base
+                .source
+                .iter()
+                .flat_map(|av| av.vec.iter())
+                .any(|s| is_vcs_source(s))
+        })
+        .unwrap_or(false);
+    let pkgbase = info
+        .map(|i| i.base.pkgbase)
+        .or_else(|| {
+            clone
+                .file_name()
+                .and_then(|f| f.to_str())
+                .map(|f| f.to_string())
+        })
+        .unwrap_or_default();
+
+    (pkgbase, vcs)
+}
+
+fn write(path: &Path, prov: &Provenance) -> Result<(), Error> {
+    let file = File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| Error::FileOpen(path.to_path_buf(), e))?;
+    serde_json::to_writer(file, prov).map_err(|e| Error::JsonWrite(path.to_path_buf(), e))
+}
+
+/// Display what Aura knows about how some installed packages were built.
+pub(super) fn show(fll: &FluentLanguageLoader, packages: &[String]) -> Result<(), Error> {
+    let prov = load();
+    let mut w = BufWriter::new(std::io::stdout());
+
+    let name = fl!(fll, "common-name");
+    let ver = fl!(fll, "A-i-version");
+    let base = fl!(fll, "A-i-pkgbase");
+    let commit = fl!(fll, "A-i-commit");
+    let built = fl!(fll, "A-i-built");
+    let flags = fl!(fll, "A-i-flags");
+    let host = fl!(fll, "A-i-host");
+    let vcs = fl!(fll, "A-i-vcs");
+    let source = fl!(fll, "A-i-source");
+    let yes = fl!(fll, "common-yes");
+    let no = fl!(fll, "common-no");
+
+    for p in packages {
+        match prov.get(p) {
+            None => {
+                yellow!(fll, "A-i-no-record", pkg = p.as_str());
+            }
+            Some(r) => {
+                let date: ColoredString = OffsetDateTime::from_unix_timestamp(r.built as i64)
+                    .map(|d| d.date().to_string().normal())
+                    .unwrap_or_else(|_| r.built.to_string().red());
+
+                let mut pairs: Vec<(&str, ColoredString)> = vec![
+                    (&name, p.bold()),
+                    (&ver, r.version.normal()),
+                    (&base, r.pkgbase.cyan()),
+                    (&commit, r.commit.normal()),
+                    (&built, date),
+                    (&flags, r.flags.join(" ").normal()),
+                    (&host, r.host.normal()),
+                    (&vcs, if r.vcs { yes.yellow() } else { no.normal() }),
+                ];
+                if let Some(s) = r.source.as_deref() {
+                    pairs.push((&source, s.normal()));
+                }
+                crate::utils::info(&mut w, fll.current_language(), &pairs)
+                    .map_err(|_| Error::Stdout)?;
+                writeln!(w).map_err(|_| Error::Stdout)?;
+            }
+        }
+    }
+
+    Ok(())
+}
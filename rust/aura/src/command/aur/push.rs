@@ -0,0 +1,153 @@
+//! Publishing to the AUR, for package maintainers.
+
+use crate::command::check::{BAD, GOOD, WARN};
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::{aura, green, proceed};
+use aura_core::Coded;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub(crate) enum Error {
+    Git(aura_core::git::Error),
+    Srcinfo(PathBuf),
+    FileWrite(PathBuf, std::io::Error),
+    Checklist,
+    Cancelled,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Git(e) => e.nested(),
+            Error::Srcinfo(_) => {}
+            Error::FileWrite(_, e) => error!("{e}"),
+            Error::Checklist => {}
+            Error::Cancelled => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Git(e) => e.localise(fll),
+            Error::Srcinfo(p) => fl!(fll, "A-push-e-srcinfo", dir = p.utf8()),
+            Error::FileWrite(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+            Error::Checklist => fl!(fll, "A-push-e-checklist"),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Git(e) => e.code(),
+            Error::Srcinfo(_) => "push-srcinfo",
+            Error::FileWrite(_, _) => "push-file-write",
+            Error::Checklist => "push-checklist",
+            Error::Cancelled => "push-cancelled",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Git(e) => e.context(),
+            Error::Srcinfo(p) => vec![("path", p.display().to_string())],
+            Error::FileWrite(p, _) => vec![("path", p.display().to_string())],
+            Error::Checklist | Error::Cancelled => Vec::new(),
+        }
+    }
+}
+
+/// Validate a local AUR package repository, then push it to the AUR.
+pub(crate) fn push(fll: &FluentLanguageLoader, dir: &Path) -> Result<(), Error> {
+    aura!(fll, "A-push-checking", dir = dir.utf8());
+
+    // --- The PKGBUILD itself --- //
+    let has_pkgbuild = dir.join("PKGBUILD").is_file();
+    line(has_pkgbuild, fl!(fll, "A-push-pkgbuild"));
+    if has_pkgbuild.not() {
+        return Err(Error::Checklist);
+    }
+
+    // --- A fresh .SRCINFO --- //
+    regenerate_srcinfo(dir)?;
+    line(true, fl!(fll, "A-push-srcinfo"));
+
+    // --- namcap, if available --- //
+    match super::namcap::run(dir, &["PKGBUILD"]) {
+        None => println!(
+            "  [{}] {}",
+            WARN.yellow(),
+            fl!(fll, "A-push-namcap-missing")
+        ),
+        Some(cs) if cs.is_empty() => line(true, fl!(fll, "A-push-namcap")),
+        Some(cs) => {
+            println!("  [{}] {}", WARN.yellow(), fl!(fll, "A-push-namcap"));
+            cs.iter().for_each(|c| println!("      └─ {}", c));
+        }
+    }
+
+    // --- Everything committed --- //
+    let clean = aura_core::git::is_clean(dir).map_err(Error::Git)?;
+    line(clean, fl!(fll, "A-push-committed"));
+    if clean.not() {
+        println!("      └─ {}", fl!(fll, "A-push-committed-fix"));
+    }
+
+    // --- Pushing to the AUR over SSH --- //
+    let remote = aura_core::git::remote_url(dir);
+    let good_remote = remote.as_deref().map(is_aur_ssh).unwrap_or(false);
+    line(good_remote, fl!(fll, "A-push-remote"));
+    if good_remote.not() {
+        let url = remote.unwrap_or_default();
+        println!("      └─ {}", fl!(fll, "A-push-remote-fix", url = url));
+    }
+
+    if (clean && good_remote).not() {
+        return Err(Error::Checklist);
+    }
+
+    proceed!(fll, "A-push-proceed").ok_or(Error::Cancelled)?;
+    aura_core::git::push(dir).map_err(Error::Git)?;
+    green!(fll, "common-done");
+
+    Ok(())
+}
+
+fn line(good: bool, msg: String) {
+    let symb = if good { GOOD.green() } else { BAD.red() };
+    println!("  [{}] {}", symb, msg);
+}
+
+/// Overwrite the `.SRCINFO` with what `makepkg` generates from the current
+/// PKGBUILD, and confirm that the result can be parsed.
+fn regenerate_srcinfo(dir: &Path) -> Result<(), Error> {
+    let out = Command::new("makepkg")
+        .arg("--printsrcinfo")
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .ok_or_else(|| Error::Srcinfo(dir.to_path_buf()))?;
+
+    let path = dir.join(".SRCINFO");
+    std::fs::write(&path, out.stdout).map_err(|e| Error::FileWrite(path.clone(), e))?;
+    aura_core::metadata::srcinfo(&path).map_err(|_| Error::Srcinfo(dir.to_path_buf()))?;
+
+    Ok(())
+}
+
+/// Does a git remote URL point to the AUR over SSH? Pushing over HTTPS isn't
+/// supported by the AUR.
+fn is_aur_ssh(url: &str) -> bool {
+    url.starts_with("ssh://aur@aur.archlinux.org/") || url.starts_with("aur@aur.archlinux.org:")
+}
@@ -0,0 +1,150 @@
+//! Reporting what a package will download, and from where.
+
+use crate::command::check::{GOOD, WARN};
+use crate::{aura, yellow};
+use aura_core::aur::dependencies::Buildable;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use srcinfo::{ArchVec, Srcinfo};
+use std::ops::Not;
+use std::path::Path;
+
+/// A single `source=` entry, broken into its parts.
+struct Entry<'a> {
+    protocol: &'a str,
+    domain: &'a str,
+    file: &'a str,
+    /// Will `makepkg` verify this source against a checksum?
+    verified: bool,
+}
+
+impl<'a> Entry<'a> {
+    fn parse(raw: &'a str, verified: bool) -> Entry<'a> {
+        let (name, url) = match raw.split_once("::") {
+            Some((name, url)) => (Some(name), url),
+            None => (None, raw),
+        };
+
+        let (protocol, domain) = match url.split_once("://") {
+            None => ("local", ""),
+            Some((protocol, rest)) => {
+                let host = rest.split('/').next().unwrap_or(rest);
+                let host = host.rsplit_once('@').map(|(_, h)| h).unwrap_or(host);
+                (protocol, host)
+            }
+        };
+
+        let file = name.unwrap_or_else(|| {
+            let path = url.split('#').next().unwrap_or(url);
+            path.trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or(path)
+        });
+
+        Entry {
+            protocol,
+            domain,
+            file,
+            verified,
+        }
+    }
+
+    /// Are the contents of this source sent in the clear?
+    fn is_plaintext(&self) -> bool {
+        matches!(self.protocol, "http" | "ftp" | "git" | "git+http")
+    }
+}
+
+/// Every source of a package, in order, paired with whether or not it's
+/// protected by a checksum.
+fn entries(info: &Srcinfo) -> Vec<Entry<'_>> {
+    let base = &info.base;
+    let sums: [&Vec<ArchVec>; 7] = [
+        &base.md5sums,
+        &base.sha1sums,
+        &base.sha224sums,
+        &base.sha256sums,
+        &base.sha384sums,
+        &base.sha512sums,
+        &base.b2sums,
+    ];
+
+    base.source
+        .iter()
+        .flat_map(|av| {
+            av.vec.iter().enumerate().map(move |(i, raw)| {
+                // A source is verified if any algorithm gives it a real
+                // checksum, rather than `SKIP`.
+                let verified = sums
+                    .iter()
+                    .flat_map(|s| s.iter())
+                    .filter(|sav| sav.arch == av.arch)
+                    .filter_map(|sav| sav.vec.get(i))
+                    .any(|sum| sum != "SKIP");
+
+                Entry::parse(raw, verified)
+            })
+        })
+        .collect()
+}
+
+/// List what each package to be built will download, and from where. Plain
+/// HTTP and unchecked sources are highlighted.
+pub(super) fn report(fll: &FluentLanguageLoader, clones: &Path, to_build: &[Buildable]) {
+    let infos: Vec<Srcinfo> = to_build
+        .iter()
+        .filter_map(|b| aura_core::metadata::srcinfo(&clones.join(&b.name).join(".SRCINFO")).ok())
+        .collect();
+
+    aura!(fll, "A-sources");
+
+    for info in infos.iter() {
+        let entries = entries(info);
+        println!(" {}", info.base.pkgbase.cyan().bold());
+
+        if entries.is_empty() {
+            println!("   {}", fl!(fll, "A-sources-none"));
+            continue;
+        }
+
+        let long_p = entries.iter().map(|e| e.protocol.len()).max().unwrap_or(0);
+        let long_d = entries.iter().map(|e| e.domain.len()).max().unwrap_or(0);
+
+        for e in entries.iter() {
+            let mut notes = Vec::new();
+
+            // Local files are part of the AUR repo itself, and so are
+            // already covered by the PKGBUILD review.
+            if e.protocol != "local" && e.verified.not() {
+                notes.push(fl!(fll, "A-sources-skip"));
+            }
+
+            if e.is_plaintext() {
+                notes.push(fl!(fll, "A-sources-plain"));
+            }
+
+            let symb = if notes.is_empty() {
+                GOOD.green()
+            } else {
+                WARN.yellow()
+            };
+
+            println!(
+                "  [{}] {:p$} {:d$} {} {}",
+                symb,
+                e.protocol,
+                e.domain,
+                e.file,
+                notes.join(", ").yellow(),
+                p = long_p,
+                d = long_d
+            );
+        }
+    }
+
+    if infos.len() < to_build.len() {
+        yellow!(fll, "A-sources-unreadable");
+    }
+}
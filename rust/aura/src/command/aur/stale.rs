@@ -0,0 +1,186 @@
+//! AUR packages left behind by a new version of a language runtime.
+//!
+//! Python, Perl, and GHC each keep their libraries in a directory named for
+//! their version, like `/usr/lib/python3.11/site-packages`. When a new version
+//! arrives, the official packages are rebuilt against it, but AUR packages
+//! aren't, and their files stay in a directory that nothing reads anymore. Such
+//! packages are found by their files, and rebuilt together as one batch.
+
+use super::Error;
+use crate::env::Env;
+use crate::{aura, yellow};
+use alpm::Alpm;
+use aura::caps::Mutating;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use std::collections::BTreeSet;
+use std::ops::Not;
+
+/// A language runtime whose libraries are tied to its version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Runtime {
+    Python,
+    Perl,
+    Ghc,
+}
+
+impl Runtime {
+    const ALL: [Runtime; 3] = [Runtime::Python, Runtime::Perl, Runtime::Ghc];
+
+    fn name(self) -> &'static str {
+        match self {
+            Runtime::Python => "Python",
+            Runtime::Perl => "Perl",
+            Runtime::Ghc => "GHC",
+        }
+    }
+
+    /// The package that provides the runtime.
+    fn package(self) -> &'static str {
+        match self {
+            Runtime::Python => "python",
+            Runtime::Perl => "perl",
+            Runtime::Ghc => "ghc-libs",
+        }
+    }
+
+    /// The start of the paths of versioned library directories.
+    fn prefix(self) -> &'static str {
+        match self {
+            Runtime::Python => "usr/lib/python",
+            Runtime::Perl => "usr/lib/perl5/",
+            Runtime::Ghc => "usr/lib/ghc-",
+        }
+    }
+
+    /// The part of a package version that names its library directory, like
+    /// `3.11` of Python `3.11.5-2`.
+    fn abi(self, version: &str) -> String {
+        let (_, v, _) = aura_core::version::parse_evr(version);
+
+        match self {
+            Runtime::Python | Runtime::Perl => v.split('.').take(2).collect::<Vec<_>>().join("."),
+            Runtime::Ghc => v.to_string(),
+        }
+    }
+
+    /// The version of the library directory that a file lives in, if any.
+    fn dir_version(self, path: &str) -> Option<&str> {
+        let rest = path.strip_prefix(self.prefix())?;
+        let (dir, _) = rest.split_once('/')?;
+
+        dir.starts_with(|c: char| c.is_ascii_digit()).then(|| dir)
+    }
+
+    /// Was a file installed for some other version of this runtime than the
+    /// current one? Python 2 and 3 are separate runtimes, and live side by
+    /// side.
+    fn is_stale(self, path: &str, current: &str) -> bool {
+        match self.dir_version(path) {
+            None => false,
+            Some(v) if v == current => false,
+            Some(v) => match self {
+                Runtime::Python => v.split('.').next() == current.split('.').next(),
+                Runtime::Perl | Runtime::Ghc => true,
+            },
+        }
+    }
+}
+
+/// For each runtime that has moved on, its current version and the foreign
+/// packages still built for an older one.
+pub(crate) fn stale(alpm: &Alpm) -> Vec<(Runtime, String, Vec<&str>)> {
+    let db = alpm.localdb();
+
+    Runtime::ALL
+        .iter()
+        .filter_map(|r| {
+            let pkg = db.pkg(r.package()).ok()?;
+            Some((*r, r.abi(pkg.version().as_str())))
+        })
+        .filter_map(|(r, current)| {
+            let pkgs: Vec<&str> = alpm_utils::alpm::foreigns(alpm)
+                .filter(|p| {
+                    p.files()
+                        .files()
+                        .iter()
+                        .any(|f| r.is_stale(f.name(), &current))
+                })
+                .map(|p| p.name())
+                .collect();
+
+            (pkgs.is_empty().not()).then(|| (r, current, pkgs))
+        })
+        .collect()
+}
+
+/// Rebuild every foreign package left behind by a runtime, as one batch.
+pub(crate) fn rebuild(m: &Mutating, fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let groups = stale(&alpm);
+
+    if groups.is_empty() {
+        aura!(fll, "A-stale-none");
+        return Ok(());
+    }
+
+    let mut names = BTreeSet::new();
+    for (runtime, current, pkgs) in groups {
+        aura!(
+            fll,
+            "A-stale-found",
+            runtime = runtime.name(),
+            version = current
+        );
+        for p in pkgs {
+            println!(" {}", p.cyan());
+            names.insert(p);
+        }
+    }
+
+    super::install(m, fll, env, names).map(|_| ())
+}
+
+/// Mention the foreign packages that a runtime upgrade left behind, if any.
+pub(crate) fn advice(fll: &FluentLanguageLoader, env: &Env) {
+    let alpm = match env.alpm() {
+        Ok(a) => a,
+        Err(_) => return,
+    };
+
+    let pkgs: BTreeSet<&str> = stale(&alpm).into_iter().flat_map(|(_, _, ps)| ps).collect();
+
+    if pkgs.is_empty().not() {
+        let pkgs = pkgs.into_iter().collect::<Vec<_>>().join(", ");
+        let cmd = "aura -A --stale".bold().cyan().to_string();
+        yellow!(fll, "A-stale-advice", pkgs = pkgs, command = cmd);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn staleness() {
+        let py = Runtime::Python;
+        assert_eq!("3.11", py.abi("3.11.5-2"));
+        assert!(py.is_stale("usr/lib/python3.10/site-packages/foo.py", "3.11"));
+        assert!(py
+            .is_stale("usr/lib/python3.11/site-packages/foo.py", "3.11")
+            .not());
+        assert!(py
+            .is_stale("usr/lib/python2.7/site-packages/foo.py", "3.11")
+            .not());
+        assert!(py.is_stale("usr/lib/pythonfoo/bar", "3.11").not());
+
+        let perl = Runtime::Perl;
+        assert_eq!("5.38", perl.abi("5.38.0-1"));
+        assert!(perl.is_stale("usr/lib/perl5/5.36/vendor_perl/Foo.pm", "5.38"));
+
+        let ghc = Runtime::Ghc;
+        assert_eq!("9.2.8", ghc.abi("9.2.8-1"));
+        assert!(ghc.is_stale("usr/lib/ghc-9.0.2/site-local/foo", "9.2.8"));
+    }
+}
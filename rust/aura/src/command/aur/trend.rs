@@ -0,0 +1,230 @@
+//! How the votes and popularity of AUR packages change over time.
+//!
+//! The AUR only reports current figures, so whenever Aura fetches the metadata
+//! of packages anyway, as for `-Ai`, `-As`, `-Au`, `info`, and `search`, it
+//! keeps a sample of each, at most one per day. Over time these show which of
+//! several competing packages, like a `-bin` and a `-git` variant, is gaining
+//! users.
+//!
+//! Both the samples kept of each package and the packages kept at all are
+//! capped, so that many broad searches don't grow the history without end.
+
+use crate::utils::SECS_IN_DAY;
+use crate::yellow;
+use aura_core::faur::Package;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Not;
+use time::OffsetDateTime;
+
+/// The most samples kept of any one package. Beyond this the oldest are
+/// dropped.
+const MAX_SAMPLES: usize = 365;
+
+/// The most packages whose samples are kept. Beyond this, those sampled least
+/// recently are dropped.
+const MAX_PACKAGES: usize = 1000;
+
+/// How many of the latest samples to display.
+const SHOWN: usize = 10;
+
+/// A package's figures on some day.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Sample {
+    /// Days since the Unix epoch.
+    day: i64,
+    votes: u64,
+    popularity: f64,
+}
+
+/// Note the current figures of some packages.
+pub(crate) fn record<'a, I>(packages: I)
+where
+    I: IntoIterator<Item = &'a Package>,
+{
+    let day = OffsetDateTime::now_utc().unix_timestamp() / SECS_IN_DAY as i64;
+    let mut history = load();
+    let mut changed = false;
+
+    for p in packages {
+        let sample = Sample {
+            day,
+            votes: p.num_votes,
+            popularity: p.popularity,
+        };
+        changed |= add(history.entry(p.name.clone()).or_default(), sample);
+    }
+
+    if changed.not() {
+        return;
+    }
+
+    trim(&mut history);
+
+    if let Ok(path) = crate::dirs::trends() {
+        // Written whole and then moved, so that an interrupted write never
+        // loses the history.
+        let tmp = path.with_extension("json.tmp");
+        let json = serde_json::to_string(&history).unwrap_or_default();
+        if let Err(e) = std::fs::write(&tmp, json).and_then(|_| std::fs::rename(&tmp, &path)) {
+            debug!("Failed to write {}: {e}", path.display());
+        }
+    }
+}
+
+/// Add a sample, replacing any other from the same day. Did anything change?
+fn add(samples: &mut Vec<Sample>, sample: Sample) -> bool {
+    match samples.last_mut() {
+        Some(last) if *last == sample => return false,
+        Some(last) if last.day == sample.day => *last = sample,
+        _ => samples.push(sample),
+    }
+
+    if samples.len() > MAX_SAMPLES {
+        samples.drain(..samples.len() - MAX_SAMPLES);
+    }
+
+    true
+}
+
+/// Drop the packages sampled least recently, beyond the most that are kept.
+fn trim(history: &mut BTreeMap<String, Vec<Sample>>) {
+    if history.len() <= MAX_PACKAGES {
+        return;
+    }
+
+    let mut latest: Vec<(i64, String)> = history
+        .iter()
+        .map(|(name, s)| (s.last().map(|s| s.day).unwrap_or(0), name.clone()))
+        .collect();
+    latest.sort_unstable_by(|a, b| b.cmp(a));
+
+    for (_, name) in latest.into_iter().skip(MAX_PACKAGES) {
+        history.remove(&name);
+    }
+}
+
+/// Display how the figures of some packages have changed.
+pub(super) fn show(fll: &FluentLanguageLoader, packages: &[String]) {
+    let history = load();
+    let votes = fl!(fll, "A-i-votes");
+    let pop = fl!(fll, "A-i-pop");
+
+    for p in packages {
+        let samples = match history.get(p) {
+            Some(s) if s.len() > 1 => s,
+            _ => {
+                yellow!(fll, "A-trend-few", pkg = p.as_str());
+                continue;
+            }
+        };
+
+        println!("{}", p.bold());
+        let start = samples.len().saturating_sub(SHOWN);
+        let mut prev: Option<&Sample> = (start > 0).then(|| &samples[start - 1]);
+
+        for s in samples[start..].iter() {
+            let (dv, dp) = match prev {
+                Some(b) => (
+                    delta(s.votes as f64 - b.votes as f64, 0),
+                    delta(s.popularity - b.popularity, 2),
+                ),
+                None => ("".normal(), "".normal()),
+            };
+
+            println!(
+                "  {}  {} {:>6} {:7}  {} {:>6.2} {}",
+                date(s.day),
+                votes,
+                s.votes,
+                dv,
+                pop,
+                s.popularity,
+                dp
+            );
+            prev = Some(s);
+        }
+
+        if let (Some(first), Some(last)) = (samples.first(), samples.last()) {
+            let since = date(first.day);
+            let dv = delta(last.votes as f64 - first.votes as f64, 0).to_string();
+            let dp = delta(last.popularity - first.popularity, 2).to_string();
+            println!(
+                "  {}\n",
+                fl!(fll, "A-trend-since", date = since, votes = dv, pop = dp)
+            );
+        }
+    }
+}
+
+/// A signed change, coloured by its direction.
+fn delta(d: f64, precision: usize) -> ColoredString {
+    let s = format!("({:+.*})", precision, d);
+
+    if d > 0.0 {
+        s.green()
+    } else if d < 0.0 {
+        s.red()
+    } else {
+        s.normal()
+    }
+}
+
+fn date(day: i64) -> String {
+    OffsetDateTime::from_unix_timestamp(day * SECS_IN_DAY as i64)
+        .map(|d| d.date().to_string())
+        .unwrap_or_default()
+}
+
+fn load() -> BTreeMap<String, Vec<Sample>> {
+    crate::dirs::trends()
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn one_sample_per_day() {
+        let sample = |day, votes| Sample {
+            day,
+            votes,
+            popularity: 0.0,
+        };
+
+        let mut samples = Vec::new();
+        assert!(add(&mut samples, sample(1, 10)));
+        assert!(add(&mut samples, sample(1, 11)));
+        assert!(add(&mut samples, sample(2, 12)));
+        assert!(add(&mut samples, sample(2, 12)).not());
+
+        assert_eq!(vec![sample(1, 11), sample(2, 12)], samples);
+    }
+
+    #[test]
+    fn least_recent_dropped() {
+        let sample = |day| Sample {
+            day,
+            votes: 0,
+            popularity: 0.0,
+        };
+
+        let mut history: BTreeMap<String, Vec<Sample>> = (0..MAX_PACKAGES as i64 + 2)
+            .map(|d| (format!("pkg-{d}"), vec![sample(d)]))
+            .collect();
+        trim(&mut history);
+
+        assert_eq!(MAX_PACKAGES, history.len());
+        assert!(history.contains_key("pkg-0").not());
+        assert!(history.contains_key("pkg-1").not());
+        assert!(history.contains_key("pkg-2"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Packages that stand in for one another, like `foo`, `foo-bin`, and
+//! `foo-git`.
+
+use crate::env::Network;
+use aura_core::faur::Package;
+use std::collections::{HashMap, HashSet};
+use std::ops::Not;
+
+/// Suffixes that mark a package as a variant of some other.
+pub(crate) const SUFFIXES: &[&str] = &[
+    "-bin",
+    "-git",
+    "-hg",
+    "-svn",
+    "-bzr",
+    "-beta",
+    "-nightly",
+    "-appimage",
+    "-latest",
+    "-stable",
+];
+
+/// The name that a variant is a variant of. Names without a known suffix are
+/// their own stem.
+pub(crate) fn stem(name: &str) -> &str {
+    let mut stem = name;
+
+    while let Some(s) = SUFFIXES
+        .iter()
+        .find_map(|suffix| stem.strip_suffix(suffix))
+        .filter(|s| s.is_empty().not())
+    {
+        stem = s;
+    }
+
+    stem
+}
+
+/// Does a package provide the given name?
+fn provides(pkg: &Package, name: &str) -> bool {
+    pkg.provides
+        .iter()
+        .any(|p| p.split(|c| c == '=' || c == '<' || c == '>').next() == Some(name))
+}
+
+/// Do two packages share most of their keywords? Half of the smaller set must
+/// be shared, and at least two keywords unless one has only the one.
+fn similar_keywords(a: &Package, b: &Package) -> bool {
+    let words =
+        |p: &Package| -> HashSet<String> { p.keywords.iter().map(|k| k.to_lowercase()).collect() };
+    let (a, b) = (words(a), words(b));
+    let smaller = a.len().min(b.len());
+    let shared = a.intersection(&b).count();
+
+    smaller > 0 && shared * 2 >= smaller && shared >= smaller.min(2)
+}
+
+/// Of the packages found, those that could stand in for the given one: those
+/// sharing its stem, providing it, or mostly sharing its keywords. The most
+/// voted come first.
+fn alternatives_to(pkg: &Package, found: &[Package]) -> Vec<Package> {
+    let stem = stem(&pkg.name);
+    let mut seen = HashSet::new();
+    let mut alts: Vec<Package> = found
+        .iter()
+        .filter(|p| p.name != pkg.name)
+        .filter(|p| self::stem(&p.name) == stem || provides(p, stem) || similar_keywords(p, pkg))
+        .filter(|p| seen.insert(p.name.as_str()))
+        .cloned()
+        .collect();
+    alts.sort_by(|a, b| b.num_votes.cmp(&a.num_votes));
+
+    alts
+}
+
+/// Other AUR packages that could stand in for each of the given ones, keyed by
+/// name. The variants of every package are asked for at once, and then
+/// whatever provides each stem or mentions it.
+pub(crate) fn alternatives(
+    net: &Network,
+    pkgs: &[&Package],
+) -> Result<HashMap<String, Vec<Package>>, crate::fetch::Error> {
+    let fetch = |u: &str| net.json(u);
+    let mut seen = HashSet::new();
+    let stems: Vec<&str> = pkgs
+        .iter()
+        .map(|p| stem(&p.name))
+        .filter(|s| seen.insert(*s))
+        .collect();
+
+    let names: Vec<String> = stems
+        .iter()
+        .flat_map(|stem| {
+            std::iter::once(stem.to_string())
+                .chain(SUFFIXES.iter().map(move |s| format!("{stem}{s}")))
+        })
+        .collect();
+    let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+    let mut found: Vec<Package> = aura_core::faur::info_batched(&names, &fetch)?
+        .into_iter()
+        .filter_map(|(_, p)| p)
+        .collect();
+
+    for stem in stems {
+        found.extend(aura_core::faur::provides(stem, &fetch)?);
+        found.extend(aura_core::faur::search([stem], &fetch)?);
+    }
+
+    let alts = pkgs
+        .iter()
+        .map(|p| (p.name.clone(), alternatives_to(p, &found)))
+        .collect();
+
+    Ok(alts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stems() {
+        assert_eq!("foo", stem("foo-bin"));
+        assert_eq!("foo", stem("foo-beta-git"));
+        assert_eq!("foo", stem("foo"));
+        assert_eq!("foo-bar", stem("foo-bar-git"));
+        assert_eq!("-git", stem("-git"));
+    }
+
+    fn package(name: &str, votes: u64, provides: &[&str], keywords: &[&str]) -> Package {
+        serde_json::from_value(serde_json::json!({
+            "CheckDepends": [],
+            "Conflicts": [],
+            "Depends": [],
+            "FirstSubmitted": 0,
+            "Groups": [],
+            "ID": 0,
+            "Keywords": keywords,
+            "LastModified": 0,
+            "License": [],
+            "MakeDepends": [],
+            "Name": name,
+            "NumVotes": votes,
+            "OptDepends": [],
+            "PackageBase": name,
+            "PackageBaseID": 0,
+            "Popularity": 0.0,
+            "Provides": provides,
+            "Replaces": [],
+            "URLPath": "",
+            "Version": "1.0-1",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn keywords() {
+        let brave = package("brave", 0, &[], &["browser", "chromium", "privacy"]);
+        let alike = package("brave-browser", 0, &[], &["Browser", "Privacy"]);
+        let other = package("firefox", 0, &[], &["browser", "gecko", "mozilla"]);
+        let none = package("brave-tools", 0, &[], &[]);
+
+        assert!(similar_keywords(&brave, &alike));
+        assert!(similar_keywords(&brave, &other).not());
+        assert!(similar_keywords(&brave, &none).not());
+    }
+
+    #[test]
+    fn alternatives_found() {
+        let foo = package("foo", 10, &[], &["editor", "terminal"]);
+        let found = vec![
+            package("foo", 10, &[], &["editor", "terminal"]),
+            package("foo-git", 5, &["foo"], &[]),
+            package("foo-bin", 20, &["foo=1.0"], &[]),
+            package("foo-bin", 20, &["foo=1.0"], &[]),
+            package("foofighter", 50, &[], &["music"]),
+            package("bar", 1, &[], &["Editor", "terminal", "tui"]),
+            package("baz", 1, &["foobar"], &[]),
+        ];
+        let names: Vec<String> = alternatives_to(&foo, &found)
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+
+        assert_eq!(vec!["foo-bin", "foo-git", "bar"], names);
+    }
+}
@@ -1,13 +1,17 @@
 //! All functionality involving the `-C` command.
 
 use crate::download::download_with_progress;
-use crate::env::Env;
+use crate::env::{Env, Network};
 use crate::error::Nested;
 use crate::localization::Localised;
 use crate::utils::{PathStr, NOTHING};
 use crate::{aura, green, proceed, yellow};
 use alpm::Alpm;
+use aura::caps::Mutating;
+use aura::flags;
 use aura_core::cache::{CacheSize, PkgPath};
+use aura_core::listing::Listing;
+use aura_core::Coded;
 use colored::*;
 use from_variants::FromVariants;
 use i18n_embed::{fluent::FluentLanguageLoader, LanguageLoader};
@@ -15,6 +19,8 @@ use i18n_embed_fl::fl;
 use itertools::Itertools;
 use linya::Progress;
 use log::{debug, error};
+use r2d2::Pool;
+use r2d2_alpm::AlpmManager;
 use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
@@ -25,6 +31,10 @@ use time::macros::format_description;
 use time::OffsetDateTime;
 use ubyte::ToByteUnit;
 
+mod recompress;
+
+pub(crate) use recompress::recompress;
+
 const FIFTY_MB: i64 = 52_428_800;
 
 const FIVE_HUNDRED_MB: i64 = 524_288_000;
@@ -40,6 +50,7 @@ pub(crate) enum Error {
     Cancelled,
     NoPackages,
     NothingToDo,
+    MissingZstd,
     #[from_variants(skip)]
     AlreadyExists(PathBuf),
     #[from_variants(skip)]
@@ -53,6 +64,7 @@ pub(crate) enum Error {
     #[from_variants(skip)]
     Mkdir(PathBuf, std::io::Error),
     Date(time::error::Format),
+    Listing(aura_core::listing::Error),
 }
 
 impl Nested for Error {
@@ -64,6 +76,7 @@ impl Nested for Error {
             Error::Cancelled => {}
             Error::NoPackages => {}
             Error::NothingToDo => {}
+            Error::MissingZstd => {}
             Error::AlreadyExists(_) => {}
             Error::Delete(_) => {}
             Error::ReadDir(_) => {}
@@ -71,6 +84,7 @@ impl Nested for Error {
             Error::CurrDir(e) => error!("{e}"),
             Error::Mkdir(_, e) => error!("{e}"),
             Error::Date(e) => error!("{e}"),
+            Error::Listing(e) => error!("{e}"),
         }
     }
 }
@@ -84,6 +98,7 @@ impl Localised for Error {
             Error::Cancelled => fl!(fll, "common-cancelled"),
             Error::NoPackages => fl!(fll, "common-no-packages"),
             Error::NothingToDo => fl!(fll, "common-no-work"),
+            Error::MissingZstd => fl!(fll, "C-z-missing"),
             Error::AlreadyExists(p) => fl!(fll, "C-b-file", target = p.utf8()),
             Error::Delete(p) => fl!(fll, "err-file-del", file = p.utf8()),
             Error::ReadDir(p) => fl!(fll, "err-read-dir", dir = p.utf8()),
@@ -91,12 +106,56 @@ impl Localised for Error {
             Error::CurrDir(_) => fl!(fll, "C-b-curr"),
             Error::Mkdir(p, _) => fl!(fll, "dir-mkdir", dir = p.utf8()),
             Error::Date(_) => fl!(fll, "err-time-format"),
+            Error::Listing(e) => fl!(fll, "listing-parse", err = e.to_string()),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Readline(_) => "cache-readline",
+            Error::Sudo(_) => "cache-sudo",
+            Error::Pacman(e) => e.code(),
+            Error::Cancelled => "cache-cancelled",
+            Error::NoPackages => "cache-no-packages",
+            Error::NothingToDo => "cache-nothing-to-do",
+            Error::MissingZstd => "cache-missing-zstd",
+            Error::AlreadyExists(_) => "cache-already-exists",
+            Error::Delete(_) => "cache-delete",
+            Error::ReadDir(_) => "cache-read-dir",
+            Error::Stdout(_) => "cache-stdout",
+            Error::CurrDir(_) => "cache-curr-dir",
+            Error::Mkdir(_, _) => "cache-mkdir",
+            Error::Date(_) => "cache-date",
+            Error::Listing(e) => e.code(),
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Pacman(e) => e.context(),
+            Error::Listing(e) => e.context(),
+            Error::AlreadyExists(p) => vec![("path", p.display().to_string())],
+            Error::Delete(p) => vec![("path", p.display().to_string())],
+            Error::ReadDir(p) => vec![("path", p.display().to_string())],
+            Error::Mkdir(p, _) => vec![("path", p.display().to_string())],
+            Error::Readline(_)
+            | Error::Sudo(_)
+            | Error::Cancelled
+            | Error::NoPackages
+            | Error::NothingToDo
+            | Error::MissingZstd
+            | Error::Stdout(_)
+            | Error::CurrDir(_)
+            | Error::Date(_) => Vec::new(),
         }
     }
 }
 
 /// Downgrade the given packages.
 pub(crate) fn downgrade(
+    m: &Mutating,
     fll: &FluentLanguageLoader,
     caches: &[&Path],
     packages: Vec<String>,
@@ -126,7 +185,7 @@ pub(crate) fn downgrade(
         return Err(Error::NothingToDo);
     }
 
-    crate::pacman::sudo_pacman("-U", NOTHING, to_downgrade)?;
+    crate::pacman::sudo_pacman(m, "-U", NOTHING, to_downgrade)?;
     green!(fll, "common-done");
     Ok(())
 }
@@ -153,11 +212,12 @@ fn downgrade_one<'a>(
 
 /// Delete invalid tarballs from the cache.
 pub(crate) fn invalid(
+    m: &Mutating,
     fll: &FluentLanguageLoader,
     alpm: &Alpm,
     caches: &[&Path],
 ) -> Result<(), Error> {
-    crate::utils::sudo()?;
+    crate::utils::sudo(m)?;
     aura!(fll, "C-t-invalids");
 
     // FIXME Thu Jan 27 15:24:44 2022
@@ -174,17 +234,54 @@ pub(crate) fn invalid(
 }
 
 /// Print the contents of the package caches.
-pub(crate) fn list(caches: &[&Path]) -> Result<(), Error> {
+pub(crate) fn list(caches: &[&Path], listing: &flags::Listing) -> Result<(), Error> {
+    let listing = Listing::new(listing.sort.as_deref(), listing.filter.as_deref(), FIELDS)?;
+    let mut files = Vec::new();
     for path in caches {
-        path.read_dir()
-            .map_err(|_| Error::ReadDir(path.to_path_buf()))?
-            .filter_map(|de| de.ok())
-            .for_each(|de| println!("{}", de.path().display()));
+        files.extend(
+            path.read_dir()
+                .map_err(|_| Error::ReadDir(path.to_path_buf()))?
+                .filter_map(|de| de.ok())
+                .map(|de| de.path()),
+        );
+    }
+
+    for file in listing.apply(files, |f, name| field(f, name)) {
+        println!("{}", file.display());
     }
 
     Ok(())
 }
 
+/// The fields of cached files that listings can sort and filter by.
+const FIELDS: &[&str] = &["path", "name", "version", "size", "modified"];
+
+/// The value of one of the [`FIELDS`] of a cached file. Files that aren't
+/// tarballs have no name or version.
+fn field(path: &Path, field: &str) -> String {
+    let meta = || path.metadata().ok();
+
+    match field {
+        "path" => path.display().to_string(),
+        "name" | "version" => PkgPath::new(path.to_path_buf())
+            .map(|pp| {
+                let p = pp.as_package();
+                match field {
+                    "name" => p.name.to_string(),
+                    _ => p.version.to_string(),
+                }
+            })
+            .unwrap_or_default(),
+        "size" => meta().map(|m| m.len().to_string()).unwrap_or_default(),
+        "modified" => meta()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs().to_string())
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
 /// Print cache data for given packages.
 pub(crate) fn info(
     fll: &FluentLanguageLoader,
@@ -192,6 +289,8 @@ pub(crate) fn info(
     caches: &[&Path],
     packages: Vec<String>,
 ) -> Result<(), Error> {
+    let packages =
+        crate::utils::expand(fll, packages, cached_names(caches)).ok_or(Error::Cancelled)?;
     let db = alpm.localdb();
     let mut w = BufWriter::new(std::io::stdout());
 
@@ -243,8 +342,10 @@ pub(crate) fn info(
 }
 
 /// Print all package filepaths from the cache that match some search term.
-pub(crate) fn search(caches: &[&Path], term: &str) -> Result<(), Error> {
-    for file in aura_core::cache::search(caches, term) {
+pub(crate) fn search(caches: &[&Path], term: &str, listing: &flags::Listing) -> Result<(), Error> {
+    let listing = Listing::new(listing.sort.as_deref(), listing.filter.as_deref(), FIELDS)?;
+    let files: Vec<PathBuf> = aura_core::cache::search(caches, term).collect();
+    for file in listing.apply(files, |f, name| field(f, name)) {
         println!("{}", file.display());
     }
     Ok(())
@@ -252,11 +353,19 @@ pub(crate) fn search(caches: &[&Path], term: &str) -> Result<(), Error> {
 
 /// Delete all but `keep`-many old tarballs for each package in the cache.
 pub(crate) fn clean(
+    m: &Mutating,
     fll: &FluentLanguageLoader,
     caches: &[&Path],
     keep: usize,
+    packages: Vec<String>,
 ) -> Result<(), Error> {
-    crate::utils::sudo()?;
+    crate::utils::sudo(m)?;
+
+    // Cleaning may be restricted to only certain packages.
+    let only: HashSet<String> = crate::utils::expand(fll, packages, cached_names(caches))
+        .ok_or(Error::Cancelled)?
+        .into_iter()
+        .collect();
 
     let size_before = aura_core::cache::size(caches);
     let human = format!("{}", size_before.bytes.bytes());
@@ -264,10 +373,11 @@ pub(crate) fn clean(
     yellow!(fll, "C-c-keep", pkgs = keep);
 
     // Proceed if the user accepts.
-    proceed!(fll, "proceed").ok_or(Error::Cancelled)?;
+    proceed!(fll, "proceed", "C-clean").ok_or(Error::Cancelled)?;
 
     // Get all the tarball paths, sort and group them by name, and then remove them.
     aura_core::cache::package_paths(caches)
+        .filter(|pp| only.is_empty() || only.contains(pp.as_package().name.as_ref()))
         .sorted_by(|p0, p1| p1.cmp(p0)) // Forces a `collect` underneath.
         .group_by(|pp| pp.as_package().name.clone()) // TODO Naughty clone.
         .into_iter()
@@ -282,6 +392,13 @@ pub(crate) fn clean(
     Ok(())
 }
 
+/// The unique names of every package with a tarball in the cache.
+fn cached_names(caches: &[&Path]) -> HashSet<String> {
+    aura_core::cache::package_paths(caches)
+        .map(|pp| pp.as_package().name.to_string())
+        .collect()
+}
+
 /// Delete only those tarballs which aren't present in a snapshot.
 pub(crate) fn clean_not_saved(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
     let caches = env.caches();
@@ -292,7 +409,7 @@ pub(crate) fn clean_not_saved(fll: &FluentLanguageLoader, env: &Env) -> Result<(
     aura!(fll, "C-size", size = human);
 
     // Proceed if the user accepts.
-    proceed!(fll, "proceed").ok_or(Error::Cancelled)?;
+    proceed!(fll, "proceed", "C-notsaved").ok_or(Error::Cancelled)?;
 
     let tarballs = aura_core::cache::package_paths(&caches);
 
@@ -331,11 +448,13 @@ pub(crate) fn clean_not_saved(fll: &FluentLanguageLoader, env: &Env) -> Result<(
 
 /// Download tarballs of installed packages that are missing from the cache.
 pub(crate) fn refresh(
+    m: &Mutating,
     fll: &FluentLanguageLoader,
     alpm: &Alpm,
+    net: &Network,
     caches: &[&Path],
 ) -> Result<(), Error> {
-    crate::utils::sudo()?;
+    crate::utils::sudo(m)?;
 
     // All installed packages that are missing a tarball in the cache.
     let ps: Vec<alpm::Package> = {
@@ -377,7 +496,7 @@ pub(crate) fn refresh(
         );
 
         // Proceed if the user accepts.
-        proceed!(fll, "proceed").ok_or(Error::Cancelled)?;
+        proceed!(fll, "proceed", "C-refresh").ok_or(Error::Cancelled)?;
 
         // Determine target cache.
         aura!(fll, "C-y-which-cache");
@@ -423,7 +542,7 @@ pub(crate) fn refresh(
                 let mut res = ms.iter().filter_map(|m| {
                     let url = format!("{}/{}", m, tarball);
                     let target = target_cache.join(&tarball);
-                    download_with_progress(&url, &target, Some((pr.clone(), &bar))).ok()
+                    download_with_progress(net, &url, &target, Some((pr.clone(), &bar))).ok()
                 });
 
                 // If the download failed from every mirror, cancel the progress bar.
@@ -495,7 +614,7 @@ pub(crate) fn backup(fll: &FluentLanguageLoader, env: &Env, target: &Path) -> Re
     }
 
     // Proceed if the user accepts.
-    proceed!(fll, "proceed").ok_or(Error::Cancelled)?;
+    proceed!(fll, "proceed", "C-backup").ok_or(Error::Cancelled)?;
 
     copy(&sources, &full, cache_size.files)
 }
@@ -531,6 +650,39 @@ fn copy(sources: &[&Path], target: &Path, file_count: usize) -> Result<(), Error
     Ok(())
 }
 
+/// Display which cached tarballs would own the given file, were they
+/// installed. Only the newest tarball of each package is considered.
+pub(crate) fn owns(
+    fll: &FluentLanguageLoader,
+    pool: Pool<AlpmManager>,
+    caches: &[&Path],
+    file: &Path,
+) {
+    let mut hits: Vec<PkgPath> = aura_core::cache::newest_package_paths(caches)
+        .into_par_iter()
+        .filter(|pp| {
+            pool.get()
+                .map(|alpm| aura_core::files::tarball_owns(&alpm, pp.as_path(), file))
+                .unwrap_or(false)
+        })
+        .collect();
+    hits.sort();
+
+    if hits.is_empty() {
+        yellow!(fll, "C-o-none", file = file.utf8());
+    } else {
+        for pp in hits {
+            let p = pp.as_package();
+            println!(
+                "{} {} :: {}",
+                p.name.bold(),
+                p.version,
+                pp.as_path().display()
+            );
+        }
+    }
+}
+
 /// Display packages that don't have a tarball in any cache.
 pub(crate) fn missing(alpm: &Alpm, caches: &[&Path]) {
     for pkg in aura_core::cache::missing_tarballs(alpm, caches) {
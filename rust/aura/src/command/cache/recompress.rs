@@ -0,0 +1,144 @@
+//! Rewriting old cached tarballs with stronger compression.
+//!
+//! Tarballs from before Arch's switch to `zstd` are `xz`, and others may have
+//! been compressed at a low level. Each is decompressed and compressed again at
+//! the chosen `zstd` level, and kept only if that made it smaller.
+//!
+//! The contents stay the same, but the file doesn't, so any signature of it is
+//! no longer valid and is deleted. The versions currently in the sync
+//! databases are left alone, since `pacman` checks their tarballs against the
+//! databases' checksums.
+
+use super::Error;
+use crate::{aura, green, proceed, yellow};
+use alpm::Alpm;
+use aura::caps::Mutating;
+use aura_core::cache::PkgPath;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use ubyte::ToByteUnit;
+
+/// Recompress old tarballs in the given caches at some `zstd` level.
+pub(crate) fn recompress(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    caches: &[&Path],
+    level: u8,
+) -> Result<(), Error> {
+    which::which("zstd").map_err(|_| Error::MissingZstd)?;
+    crate::utils::sudo(m)?;
+
+    let syncs = alpm.syncdbs();
+    let tarballs: Vec<PkgPath> = aura_core::cache::package_paths(caches)
+        .filter(|pp| decompressor(pp.as_path()).is_some())
+        .filter(|pp| {
+            let p = pp.as_package();
+            syncs
+                .pkg(p.name.as_ref())
+                .map(|s| s.version().as_str() != p.version.as_ref())
+                .unwrap_or(true)
+        })
+        .collect();
+
+    if tarballs.is_empty() {
+        return Err(Error::NothingToDo);
+    }
+
+    aura!(fll, "C-z-count", count = tarballs.len(), level = level);
+    let signed = tarballs.iter().filter(|pp| pp.sig_file().exists()).count();
+    if signed > 0 {
+        yellow!(fll, "C-z-sigs", count = signed);
+    }
+
+    proceed!(fll, "proceed", "C-recompress").ok_or(Error::Cancelled)?;
+
+    let mut saved = 0;
+    for pp in tarballs {
+        let path = pp.as_path();
+        match shrink(path, level) {
+            Some(s) => {
+                debug!("Recompressed {}, saving {s} bytes", path.display());
+                let _ = std::fs::remove_file(pp.sig_file());
+                saved += s;
+            }
+            None => debug!("Left {} as it was", path.display()),
+        }
+    }
+
+    let bytes = format!("{}", saved.bytes());
+    green!(fll, "C-z-saved", bytes = bytes);
+    Ok(())
+}
+
+/// The program that decompresses a tarball, if it's one worth recompressing.
+fn decompressor(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()? {
+        "xz" => Some("xz"),
+        "zst" => Some("zstd"),
+        _ => None,
+    }
+}
+
+/// Recompress a tarball, replacing it if the result is smaller. Yields the
+/// bytes saved.
+fn shrink(path: &Path, level: u8) -> Option<u64> {
+    let before = path.metadata().ok()?.len();
+    let target = path.with_extension("zst");
+    let name = target.file_name()?.to_string_lossy();
+    let tmp = target.with_file_name(format!(".{name}.recompress"));
+
+    // An `.xz` whose `.zst` twin is already cached has nowhere to go.
+    if target != path && target.exists() {
+        return None;
+    }
+
+    let result = compress(path, &tmp, level).and_then(|_| {
+        let after = tmp.metadata().ok()?.len();
+        (after < before).then(|| before - after)
+    });
+
+    match result {
+        None => {
+            let _ = std::fs::remove_file(&tmp);
+            None
+        }
+        Some(saved) => {
+            std::fs::rename(&tmp, &target).ok()?;
+            if target != path {
+                let _ = std::fs::remove_file(path);
+            }
+            Some(saved)
+        }
+    }
+}
+
+/// Decompress one file into `zstd`, writing to another.
+fn compress(path: &Path, out: &Path, level: u8) -> Option<()> {
+    let mut decomp = Command::new(decompressor(path)?)
+        .arg("-dc")
+        .arg(path)
+        .stdout(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    let stdin = decomp.stdout.take()?;
+    let status = Command::new("zstd")
+        .arg("-q")
+        .arg("-f")
+        .arg("-T0")
+        // Levels above 19 are refused without this.
+        .args((level > 19).then(|| "--ultra"))
+        .arg(format!("-{level}"))
+        .arg("-o")
+        .arg(out)
+        .stdin(stdin)
+        .status()
+        .ok()?;
+
+    let decomp = decomp.wait().ok()?;
+    (status.success() && decomp.success()).then(|| ())
+}
@@ -0,0 +1,187 @@
+//! Staged system upgrades.
+//!
+//! With `-Su --canary <N>`, a few of the lowest-risk upgrades go first: those
+//! of leaf packages, which nothing else depends on, preferring those with the
+//! fewest dependencies of their own. Once they're in, the user checks that the
+//! system still works before the rest of the upgrade goes ahead.
+//!
+//! The canaries are upgraded by a `-Su` that ignores everything else, so they
+//! are never installed against libraries older than they need. Until the rest
+//! goes ahead, though, the system is partly upgraded, so declining to continue
+//! is reported as an error that says as much.
+
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::{aura, yellow};
+use alpm::Alpm;
+use aura::caps::Mutating;
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use std::ops::Not;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Env(crate::env::Error),
+    Pacman(crate::pacman::Error),
+    Cancelled,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Env(e) => e.nested(),
+            Error::Pacman(e) => e.nested(),
+            Error::Cancelled => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Env(e) => e.localise(fll),
+            Error::Pacman(e) => e.localise(fll),
+            Error::Cancelled => fl!(fll, "canary-stopped"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Env(e) => e.code(),
+            Error::Pacman(e) => e.code(),
+            Error::Cancelled => "canary-cancelled",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Env(e) => e.context(),
+            Error::Pacman(e) => e.context(),
+            Error::Cancelled => Vec::new(),
+        }
+    }
+}
+
+/// An upgradable package, as far as choosing canaries goes.
+struct Candidate {
+    name: String,
+    /// How many dependencies it has of its own.
+    depends: usize,
+    /// Does anything installed depend on it, even optionally?
+    needed: bool,
+    /// Is it ignored by `pacman.conf`?
+    ignored: bool,
+}
+
+/// Upgrade up to `count` leaf packages, then ask whether to continue. The sync
+/// databases of the given handle should be fresh, and `held` are packages that
+/// mustn't be upgraded at all. `args` are those of the whole `-Su`.
+pub(crate) fn canary(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    alpm: &Alpm,
+    count: usize,
+    held: &[String],
+    args: Vec<String>,
+) -> Result<(), Error> {
+    let candidates: Vec<Candidate> = crate::command::pin::upgrades(alpm)
+        .into_iter()
+        .map(|(p, _, _)| {
+            let groups: Vec<&str> = p.groups().iter().collect();
+
+            Candidate {
+                name: p.name().to_string(),
+                depends: p.depends().len(),
+                needed: p.required_by().is_empty().not() || p.optional_for().is_empty().not(),
+                ignored: env.is_ignored(p.name(), &groups),
+            }
+        })
+        .collect();
+
+    let canaries = choose(&candidates, held, count);
+    debug!("Canaries: {:?}", canaries);
+
+    if canaries.is_empty() {
+        yellow!(fll, "canary-none");
+        return Ok(());
+    }
+
+    aura!(fll, "canary-first", count = canaries.len());
+    for c in canaries.iter() {
+        println!(" {}", c.cyan());
+    }
+
+    // The canaries go in as a `-Su` of their own, with everything else ignored,
+    // so that `pacman` still resolves them against the rest of the system and
+    // declines any that would need the upgrade of something held back.
+    let rest: Vec<String> = candidates
+        .into_iter()
+        .map(|c| c.name)
+        .filter(|n| canaries.contains(n).not())
+        .chain(held.iter().cloned())
+        .collect();
+    crate::pacman::sudo_pacman_batch(m, crate::command::pin::ignoring(args, &rest))?;
+
+    aura!(fll, "canary-check");
+    crate::prompts::ask(fll, "canary-continue", fl!(fll, "canary-continue")).ok_or(Error::Cancelled)
+}
+
+/// Up to `count` leaf packages to upgrade first, those with the fewest
+/// dependencies of their own preferred.
+fn choose(candidates: &[Candidate], held: &[String], count: usize) -> Vec<String> {
+    let mut leaves: Vec<(usize, &str)> = candidates
+        .iter()
+        .filter(|c| c.needed.not() && c.ignored.not())
+        .filter(|c| held.iter().all(|h| h != &c.name))
+        .map(|c| (c.depends, c.name.as_str()))
+        .collect();
+    leaves.sort();
+
+    leaves
+        .into_iter()
+        .take(count)
+        .map(|(_, n)| n.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn candidate(name: &str, depends: usize, needed: bool, ignored: bool) -> Candidate {
+        Candidate {
+            name: name.to_string(),
+            depends,
+            needed,
+            ignored,
+        }
+    }
+
+    #[test]
+    fn leaves() {
+        let candidates = [
+            candidate("glibc", 3, true, false),
+            candidate("firefox", 40, false, false),
+            candidate("htop", 2, false, false),
+            candidate("neofetch", 1, false, true),
+            candidate("tree", 1, false, false),
+            candidate("ripgrep", 2, false, false),
+        ];
+        let held = ["ripgrep".to_string()];
+
+        assert_eq!(vec!["tree", "htop"], choose(&candidates, &held, 2));
+        assert_eq!(
+            vec!["tree", "htop", "firefox"],
+            choose(&candidates, &held, 10)
+        );
+        assert!(choose(&candidates, &held, 0).is_empty());
+    }
+}
@@ -3,9 +3,12 @@
 use crate::env::{Aur, Env};
 use crate::error::Nested;
 use crate::localization::Localised;
+use crate::pacman::DbLock;
 use crate::utils::PathStr;
-use crate::{aura, executable, green};
+use crate::{aura, green, proceed, yellow};
 use alpm::Alpm;
+use aura::caps::Mutating;
+use aura_core::Coded;
 use colored::*;
 use from_variants::FromVariants;
 use i18n_embed::fluent::FluentLanguageLoader;
@@ -13,27 +16,86 @@ use i18n_embed_fl::fl;
 use r2d2::Pool;
 use r2d2_alpm::AlpmManager;
 use rayon::prelude::*;
-use std::collections::HashSet;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::ops::Not;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+
+mod boot;
+mod files;
+mod fix;
+pub(crate) mod pending;
+mod remedy;
+pub(crate) mod syncdb;
+
+pub(crate) use boot::boot;
+pub(crate) use files::files;
+pub(crate) use fix::fix;
+pub(crate) use remedy::suggest_only;
+use remedy::Findings;
 
 pub(crate) const GOOD: &str = "✓";
 pub(crate) const WARN: &str = "!";
 pub(crate) const BAD: &str = "✕";
 pub(crate) const CANCEL: &str = "⊘";
 
-const SECS_IN_DAY: u64 = 60 * 60 * 24;
+/// How long after the last full upgrade a database sync may happen and still
+/// count as part of it.
+const SYNC_GRACE: i64 = 60;
+
+/// The names under which Aura itself is packaged on the AUR.
+const AURA_PKGS: &[&str] = &["aura", "aura-bin", "aura-git"];
+
+const RELEASES_URL: &str = "https://api.github.com/repos/fosskers/aura/releases";
+
+/// A published release of Aura, as described by the GitHub API.
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+impl Release {
+    fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    /// Do the release notes call out an important fix?
+    fn is_critical(&self) -> bool {
+        self.body
+            .as_deref()
+            .map(|b| b.to_lowercase())
+            .map(|b| b.contains("critical") || b.contains("security"))
+            .unwrap_or(false)
+    }
+}
 
 #[derive(FromVariants)]
 pub(crate) enum Error {
     Env(crate::env::Error),
+    Fetch(crate::fetch::Error),
+    Aur(crate::command::aur::Error),
+    Pacman(crate::pacman::Error),
+    #[from_variants(skip)]
+    Fix(String),
+    #[from_variants(skip)]
+    NotInstalled(String),
 }
 
 impl Nested for Error {
     fn nested(&self) {
         match self {
             Error::Env(e) => e.nested(),
+            Error::Fetch(e) => e.nested(),
+            Error::Aur(e) => e.nested(),
+            Error::Pacman(e) => e.nested(),
+            Error::Fix(_) => {}
+            Error::NotInstalled(_) => {}
         }
     }
 }
@@ -42,88 +104,527 @@ impl Localised for Error {
     fn localise(&self, fll: &FluentLanguageLoader) -> String {
         match self {
             Error::Env(e) => e.localise(fll),
+            Error::Fetch(e) => e.localise(fll),
+            Error::Aur(e) => e.localise(fll),
+            Error::Pacman(e) => e.localise(fll),
+            Error::Fix(c) => fl!(fll, "check-fix-failed", command = c.as_str()),
+            Error::NotInstalled(p) => fl!(fll, "verify-not-installed", pkg = p.as_str()),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Env(e) => e.code(),
+            Error::Fetch(e) => e.code(),
+            Error::Aur(e) => e.code(),
+            Error::Pacman(e) => e.code(),
+            Error::Fix(_) => "check-fix",
+            Error::NotInstalled(_) => "check-not-installed",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Env(e) => e.context(),
+            Error::Fetch(e) => e.context(),
+            Error::Aur(e) => e.context(),
+            Error::Pacman(e) => e.context(),
+            Error::Fix(c) => vec![("command", c.clone())],
+            Error::NotInstalled(p) => vec![("package", p.clone())],
+        }
+    }
+}
+
+/// Compare the running Aura with its latest release. If Aura was installed
+/// from the AUR, offer to rebuild it. Every build finishes before anything is
+/// installed, so a failed build leaves the current Aura in place.
+pub(crate) fn self_check(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    mut env: Env,
+) -> Result<(), Error> {
+    let current = env!("CARGO_PKG_VERSION");
+
+    // GitHub knows about critical fixes, but limits how often it can be asked.
+    // The AUR at least knows the latest version.
+    let releases: Vec<Release> = env.network.json(RELEASES_URL).unwrap_or_else(|e| {
+        log::warn!("Couldn't fetch Aura's releases: {}", e.code());
+        Vec::new()
+    });
+    let latest = match releases
+        .iter()
+        .map(|r| r.version().to_string())
+        .max_by(|a, b| aura_core::version::cmp(a, b))
+    {
+        Some(v) => v,
+        None => aura_core::faur::info(["aura"], &|u: &str| env.network.json(u))?
+            .into_iter()
+            .next()
+            .map(|p| aura_core::version::parse_evr(&p.version).1.to_string())
+            .unwrap_or_else(|| current.to_string()),
+    };
+
+    if aura_core::version::cmp(&latest, current) != Ordering::Greater {
+        green!(fll, "check-self-latest", version = current);
+        return Ok(());
+    }
+
+    yellow!(
+        fll,
+        "check-self-outdated",
+        current = current,
+        latest = latest
+    );
+
+    let critical: Vec<&str> = releases
+        .iter()
+        .filter(|r| aura_core::version::cmp(r.version(), current) == Ordering::Greater)
+        .filter(|r| r.is_critical())
+        .map(|r| r.version())
+        .collect();
+
+    if critical.is_empty().not() {
+        yellow!(fll, "check-self-critical", versions = critical.join(", "));
+    }
+
+    let from_aur = alpm_utils::alpm::foreigns(&env.alpm()?)
+        .map(|p| p.name().to_string())
+        .find(|n| AURA_PKGS.contains(&n.as_str()));
+
+    match from_aur {
+        None => aura!(fll, "check-self-manual"),
+        Some(pkg) => {
+            aura!(fll, "check-self-aur", package = pkg.as_str());
+
+            if proceed!(fll, "check-self-rebuild").is_some() {
+                env.aur.stage = true;
+                crate::command::aur::install(m, fll, &env, [pkg.as_str()])?;
+            }
         }
     }
+
+    Ok(())
+}
+
+/// Check the health of pacman's keyring, and offer to repair it.
+pub(crate) fn keys(m: &Mutating, fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let db = alpm.localdb();
+    let syncs = alpm.syncdbs();
+
+    aura!(fll, "check-keys");
+
+    // --- Is the keyring package up to date? --- //
+    let keyrings: Vec<alpm::Package> = db
+        .pkgs()
+        .iter()
+        .filter(|p| p.name().ends_with("-keyring"))
+        .filter(|p| syncs.pkg(p.name()).is_ok())
+        .collect();
+    let stale: Vec<&str> = keyrings
+        .iter()
+        .filter(|p| p.sync_new_version(syncs).is_some())
+        .map(|p| p.name())
+        .collect();
+    let good = keyrings.is_empty().not() && stale.is_empty();
+    let symbol = if good { GOOD.green() } else { BAD.red() };
+    println!("  [{}] {}", symbol, fl!(fll, "check-keys-keyring"));
+    if keyrings.is_empty() {
+        println!("      └─ {}", fl!(fll, "check-keys-keyring-missing"));
+    } else if stale.is_empty().not() {
+        let msg = fl!(fll, "check-keys-keyring-fix", pkgs = stale.join(", "));
+        println!("      └─ {}", msg);
+    }
+
+    // --- Have any keys expired? --- //
+    let expired = expired_keys(&env.pacman.gpg_dir);
+    let symbol = match expired {
+        Some(0) => GOOD.green(),
+        Some(_) => WARN.yellow(),
+        None => BAD.red(),
+    };
+    println!("  [{}] {}", symbol, fl!(fll, "check-keys-expired"));
+    match expired {
+        Some(0) => {}
+        Some(n) => println!("      └─ {}", fl!(fll, "check-keys-expired-fix", count = n)),
+        None => println!("      └─ {}", fl!(fll, "check-keys-unreadable")),
+    }
+
+    // --- Were there signature errors recently? --- //
+    let errors = signature_errors(env.alpm_log());
+    let symbol = if errors.is_empty() {
+        GOOD.green()
+    } else {
+        BAD.red()
+    };
+    println!("  [{}] {}", symbol, fl!(fll, "check-keys-errors"));
+    for e in errors.iter() {
+        println!("      └─ {}", e);
+    }
+
+    if good && expired == Some(0) && errors.is_empty() {
+        green!(fll, "common-done");
+        return Ok(());
+    }
+
+    // --- The standard repairs, one at a time --- //
+    let names: Vec<&str> = if keyrings.is_empty() {
+        vec!["archlinux-keyring"]
+    } else {
+        keyrings.iter().map(|p| p.name()).collect()
+    };
+
+    if proceed!(fll, "check-keys-repair-populate").is_some() {
+        crate::pacman::sudo_pacman_key(m, ["--populate"])?;
+    }
+
+    if proceed!(fll, "check-keys-repair-refresh").is_some() {
+        crate::pacman::sudo_pacman_key(m, ["--refresh-keys"])?;
+    }
+
+    // Syncing the keyring alone would leave the system partially upgraded, so
+    // newer keyring packages only come with a full upgrade.
+    if proceed!(fll, "check-keys-repair-upgrade").is_some() {
+        crate::pacman::sudo_pacman(m, "-Syu", ["--needed"], &names)?;
+    }
+
+    green!(fll, "common-done");
+    Ok(())
+}
+
+/// The number of expired keys in pacman's keyring that still matter, if it can
+/// be read.
+fn expired_keys(gpg_dir: &str) -> Option<usize> {
+    // Without root, `gpg` can't lock or update a keyring owned by root, and
+    // fails even though the keys were listed.
+    let out = Command::new("gpg")
+        .arg("--homedir")
+        .arg(gpg_dir)
+        .arg("--no-permission-warning")
+        .arg("--lock-never")
+        .arg("--no-auto-check-trustdb")
+        .arg("--with-colons")
+        .arg("--list-keys")
+        .output()
+        .ok()?;
+
+    count_expired(&String::from_utf8_lossy(&out.stdout))
+}
+
+/// The number of expired keys in a `gpg --with-colons` listing that are still
+/// trusted to certify others, like the master keys. `None` if it lists no keys
+/// at all, as a real keyring never would.
+///
+/// The keys of former packagers expire as a matter of course, and the keyring
+/// package always ships some, so those alone are no cause for alarm.
+fn count_expired(listing: &str) -> Option<usize> {
+    let keys: Vec<Vec<&str>> = listing
+        .lines()
+        .filter(|l| l.starts_with("pub:"))
+        .map(|l| l.split(':').collect())
+        .collect();
+
+    keys.is_empty().not().then(|| {
+        keys.iter()
+            .filter(|k| k.get(1) == Some(&"e"))
+            .filter(|k| matches!(k.get(8), Some(&"m" | &"f" | &"u")))
+            .count()
+    })
+}
+
+/// Signature failures that pacman logged since its last successful
+/// transaction.
+fn signature_errors(log: &Path) -> Vec<String> {
+    let raw = std::fs::read_to_string(log).unwrap_or_default();
+    signature_failures(&raw)
+        .into_iter()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// The lines of a `pacman` log that report signature failures, since its last
+/// successful transaction.
+fn signature_failures(log: &str) -> Vec<&str> {
+    let lines: Vec<&str> = log.lines().collect();
+    let since = lines
+        .iter()
+        .rposition(|l| l.contains("[ALPM] transaction completed"))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+
+    lines
+        .into_iter()
+        .skip(since)
+        .filter(|l| {
+            let l = l.to_lowercase();
+            l.contains("signature") || l.contains("public key") || l.contains("keyring")
+        })
+        .filter(|l| l.contains("error") || l.contains("unknown") || l.contains("invalid"))
+        .collect()
 }
 
 /// Validate the system.
 pub(crate) fn check(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
+    validate(fll, env, false).map(|_| ())
+}
+
+/// Run every check, showing what's found unless `quiet`, and yield the
+/// commands suggested along the way.
+fn validate(fll: &FluentLanguageLoader, env: &Env, quiet: bool) -> Result<Vec<Vec<String>>, Error> {
     let caches = env.caches();
     let alpm = env.alpm()?;
     let pool = env.alpm_pool()?;
+    let mut findings = Findings::new(quiet);
+    let out = &mut findings;
 
-    aura!(fll, "check-start");
-    environment(fll);
-    aura_config(fll);
-    pacman_config(fll, &env.pacman, &env.aur);
-    makepkg_config(fll);
-    snapshots(fll, &env.backups.snapshots, &caches);
-    cache(fll, &alpm, pool, &caches);
-    green!(fll, "common-done");
+    out.heading(fl!(fll, "check-start"));
+    environment(out, fll);
+    aura_config(out, fll);
+    pacman_config(out, fll, env, &alpm);
+    makepkg_config(out, fll);
+    snapshots(out, fll, &env.backups.snapshots, &caches);
+    cache(out, fll, &alpm, pool, &caches);
+    packages(out, fll, &alpm);
+    restarts(out, fll);
+    out.heading(fl!(fll, "common-done").green());
+
+    Ok(findings.suggested())
+}
+
+/// Count the pending updates from every known source. The repo count is as of
+/// the last database sync, unless `--refresh` gives a fresher one from Aura's
+/// own copies of the databases.
+///
+/// With `--cached`, the counts last recorded are given instead, if there are
+/// any. Otherwise, the fresh counts are recorded for next time.
+pub(crate) fn updates(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    flags: &aura::flags::Check,
+) -> Result<(), Error> {
+    let latest = flags.cached.then(pending::load).flatten();
+    let was_cached = latest.is_some();
+    let pending = match latest {
+        Some(p) => p,
+        None => {
+            let mut p = count_updates(env, flags.refresh)?;
+            pending::save(&mut p);
+            p
+        }
+    };
+
+    if flags.refresh_state {
+        return Ok(());
+    }
+
+    let (repo, aur) = (pending.repo, pending.aur);
+    let others: Vec<(&str, Option<usize>)> = pending
+        .others
+        .iter()
+        .map(|(label, n)| (label.as_str(), *n))
+        .collect();
+    let total: usize = repo + aur + others.iter().filter_map(|(_, n)| *n).sum::<usize>();
+
+    if flags.bar {
+        println!("{}", total);
+    } else {
+        let repo_label = fl!(fll, "check-updates-repo");
+        let aur_label = fl!(fll, "check-updates-aur");
+        let rows: Vec<(&str, Option<usize>)> = [(repo_label.as_str(), Some(repo))]
+            .into_iter()
+            .chain([(aur_label.as_str(), Some(aur))])
+            .chain(others.iter().copied())
+            .collect();
+        let longest = rows
+            .iter()
+            .map(|(l, _)| l.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        aura!(fll, "check-updates");
+        for (label, count) in rows.iter() {
+            match count {
+                Some(0) => println!(" {:w$} {}", label, "0".green(), w = longest),
+                Some(n) => println!(" {:w$} {}", label, n.to_string().cyan(), w = longest),
+                None => println!(" {:w$} {}", label, "?".red(), w = longest),
+            }
+        }
+
+        for (label, _) in rows.iter().filter(|(_, n)| n.is_none()) {
+            yellow!(fll, "check-updates-failed", source = *label);
+        }
+
+        if total == 0 {
+            green!(fll, "check-updates-none");
+        } else {
+            aura!(fll, "check-updates-total", total = total);
+        }
+
+        if was_cached {
+            let time = OffsetDateTime::from_unix_timestamp(pending.time)
+                .map(|t| t.to_offset(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC)))
+                .ok()
+                .and_then(|t| {
+                    t.format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+                        .ok()
+                })
+                .unwrap_or_default();
+            println!("{}", fl!(fll, "check-updates-cached", time = time));
+        }
+    }
 
     Ok(())
 }
 
-fn environment(fll: &FluentLanguageLoader) {
-    aura!(fll, "check-env");
-    editor(fll);
-    executable!(fll, "git", "check-env-installed", exec = "git");
-    executable!(fll, "fd", "check-env-installed", exec = "fd");
-    executable!(fll, "rg", "check-env-installed", exec = "rg");
+/// Count the pending updates from every known source, asking the network.
+fn count_updates(env: &Env, refresh: bool) -> Result<pending::Pending, Error> {
+    let alpm = if refresh {
+        syncdb::refresh(env)?
+    } else {
+        env.alpm()?
+    };
+    let repo = pending_repo(env, &alpm);
+    let aur = pending_aur(env, &alpm)?;
+
+    // User-supplied sources are left to report their own pending updates.
+    let others = env
+        .updates
+        .par_iter()
+        .map(|(label, cmd)| (label.clone(), external_updates(cmd)))
+        .collect();
+
+    Ok(pending::Pending {
+        repo,
+        aur,
+        others,
+        time: 0,
+    })
+}
+
+/// The number of repository packages with a newer version available, as of
+/// the last database sync.
+pub(crate) fn pending_repo(env: &Env, alpm: &Alpm) -> usize {
+    let syncs = alpm.syncdbs();
+
+    alpm.localdb()
+        .pkgs()
+        .iter()
+        .filter(|p| is_held(env, p).not())
+        .filter(|p| p.sync_new_version(syncs).is_some())
+        .count()
+}
+
+/// The number of AUR packages with a newer version available.
+pub(crate) fn pending_aur(env: &Env, alpm: &Alpm) -> Result<usize, crate::fetch::Error> {
+    let db = alpm.localdb();
+    let foreigns: Vec<alpm::Package> = alpm_utils::alpm::foreigns(alpm)
+        .filter(|p| is_held(env, p).not())
+        .collect();
+    let count = aura_core::faur::info(foreigns.iter().map(|p| p.name()), &|u: &str| {
+        env.network.json(u)
+    })?
+    .into_iter()
+    .filter_map(|new| db.pkg(new.name.as_str()).ok().map(|old| (old, new)))
+    .map(|(old, new)| (aura_core::Package::from(old), aura_core::Package::from(new)))
+    .filter(|(old, new)| old < new)
+    .count();
+
+    Ok(count)
+}
+
+fn is_held(env: &Env, p: &alpm::Package) -> bool {
+    let groups: Vec<&str> = p.groups().iter().collect();
+    env.is_ignored(p.name(), &groups)
+}
+
+/// The number of non-empty lines output by a successful shell command.
+fn external_updates(cmd: &str) -> Option<usize> {
+    Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .filter(|l| l.trim().is_empty().not())
+                .count()
+        })
+}
+
+fn environment(out: &mut Findings, fll: &FluentLanguageLoader) {
+    out.heading(fl!(fll, "check-env"));
+    editor(out, fll);
+    for exec in ["git", "fd", "rg"] {
+        out.executable(fll, exec, fl!(fll, "check-env-installed", exec = exec));
+    }
 }
 
-fn editor(fll: &FluentLanguageLoader) {
+fn editor(out: &mut Findings, fll: &FluentLanguageLoader) {
     let edit = std::env::var("EDITOR");
     let good = edit.is_ok();
     let symb = if good { GOOD.green() } else { WARN.yellow() };
-    println!("  [{}] {}", symb, fl!(fll, "check-env-editor"));
+    out.line(format!("  [{}] {}", symb, fl!(fll, "check-env-editor")));
 
     if let Ok(e) = edit.as_deref() {
-        executable!(fll, e, "check-env-editor-exec", exec = e);
+        out.executable(fll, e, fl!(fll, "check-env-editor-exec", exec = e));
     } else {
-        executable!(fll, "vi", "check-env-editor-vi");
+        out.executable(fll, "vi", fl!(fll, "check-env-editor-vi"));
     }
 }
 
-fn pacman_config(fll: &FluentLanguageLoader, c: &pacmanconf::Config, a: &Aur) {
-    aura!(fll, "check-pconf");
-    parallel_downloads(fll, c);
-    duplicate_ignores(fll, c, a);
-    pacnews(fll);
+fn pacman_config(out: &mut Findings, fll: &FluentLanguageLoader, env: &Env, alpm: &Alpm) {
+    let c = &env.pacman;
+
+    out.heading(fl!(fll, "check-pconf"));
+    parallel_downloads(out, fll, c);
+    database_lock(out, fll, c);
+    duplicate_ignores(out, fll, c, &env.aur);
+    partial_upgrade_check(out, fll, env, alpm);
+    pacnews(out, fll);
 }
 
-fn makepkg_config(fll: &FluentLanguageLoader) {
-    aura!(fll, "check-mconf");
-    packager_set(fll);
+fn makepkg_config(out: &mut Findings, fll: &FluentLanguageLoader) {
+    out.heading(fl!(fll, "check-mconf"));
+    packager_set(out, fll);
 }
 
-fn aura_config(fll: &FluentLanguageLoader) {
-    aura!(fll, "check-aconf");
-    parsable_aura_toml(fll);
+fn aura_config(out: &mut Findings, fll: &FluentLanguageLoader) {
+    out.heading(fl!(fll, "check-aconf"));
+    parsable_aura_toml(out, fll);
 }
 
-fn parsable_aura_toml(fll: &FluentLanguageLoader) {
+fn parsable_aura_toml(out: &mut Findings, fll: &FluentLanguageLoader) {
     let exists = crate::dirs::aura_config()
         .map(|file| file.is_file())
         .unwrap_or(false);
     let symbol = if exists { GOOD.green() } else { WARN.yellow() };
-    println!("  [{}] {}", symbol, fl!(fll, "check-aconf-aura-exists"));
+    out.line(format!(
+        "  [{}] {}",
+        symbol,
+        fl!(fll, "check-aconf-aura-exists")
+    ));
 
     if exists {
         let parsable = crate::env::parsable_env();
         let symbol = if parsable { GOOD.green() } else { BAD.red() };
-        println!("  [{}] {}", symbol, fl!(fll, "check-aconf-aura-parse"));
+        out.line(format!(
+            "  [{}] {}",
+            symbol,
+            fl!(fll, "check-aconf-aura-parse")
+        ));
     } else {
-        let cmd = "aura conf --gen > ~/.config/aura.toml"
-            .bold()
-            .cyan()
-            .to_string();
+        let cmd = out.command(&["aura", "conf", "--gen", ">", "~/.config/aura.toml"]);
         let msg = fl!(fll, "check-aconf-aura-exists-fix", cmd = cmd);
-        println!("      └─ {}", msg);
+        out.line(format!("      └─ {}", msg));
     }
 }
 
-fn packager_set(fll: &FluentLanguageLoader) {
+fn packager_set(out: &mut Findings, fll: &FluentLanguageLoader) {
     let (cmd, args) = crate::command::misc::searcher();
     let good = Command::new(cmd)
         .args(args)
@@ -136,19 +637,23 @@ fn packager_set(fll: &FluentLanguageLoader) {
         .map(|s| s.trim().lines().any(|line| line.starts_with("PACKAGER=")))
         .unwrap_or(false);
     let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!("  [{}] {}", symbol, fl!(fll, "check-mconf-packager"));
+    out.line(format!(
+        "  [{}] {}",
+        symbol,
+        fl!(fll, "check-mconf-packager")
+    ));
 
     if !good {
         let cmd = "PACKAGER=\"You <you@foo.com>\"".cyan().to_string();
         let msg = fl!(fll, "check-mconf-packager-fix", cmd = cmd);
-        println!("      └─ {}", msg);
+        out.line(format!("      └─ {}", msg));
     }
 }
 
-fn parallel_downloads(fll: &FluentLanguageLoader, c: &pacmanconf::Config) {
+fn parallel_downloads(out: &mut Findings, fll: &FluentLanguageLoader, c: &pacmanconf::Config) {
     let good = c.parallel_downloads > 1;
     let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!("  [{}] {}", symbol, fl!(fll, "check-pconf-par"));
+    out.line(format!("  [{}] {}", symbol, fl!(fll, "check-pconf-par")));
 
     if !good {
         let cmd = "ParallelDownloads".bold().cyan().to_string();
@@ -157,83 +662,192 @@ fn parallel_downloads(fll: &FluentLanguageLoader, c: &pacmanconf::Config) {
             .cyan()
             .to_string();
         let msg = fl!(fll, "check-pconf-par-fix", setting = cmd, set = fix);
-        println!("      └─ {}", msg);
+        out.line(format!("      └─ {}", msg));
+    }
+}
+
+fn database_lock(out: &mut Findings, fll: &FluentLanguageLoader, c: &pacmanconf::Config) {
+    let lock = crate::pacman::db_lock(&c.db_path);
+    let symbol = match lock {
+        DbLock::Free => GOOD.green(),
+        DbLock::Held(_, _) => WARN.yellow(),
+        DbLock::Stale(_) => BAD.red(),
+    };
+    out.line(format!("  [{}] {}", symbol, fl!(fll, "check-pconf-lock")));
+
+    match lock {
+        DbLock::Free => {}
+        DbLock::Held(pid, name) => {
+            let msg = fl!(fll, "check-pconf-lock-held", pid = pid, name = name);
+            out.line(format!("      └─ {}", msg));
+        }
+        DbLock::Stale(path) => {
+            let cmd = out.command(&["sudo", "rm", &path.utf8()]);
+            let msg = fl!(fll, "check-pconf-lock-fix", command = cmd);
+            out.line(format!("      └─ {}", msg));
+        }
+    }
+}
+
+fn partial_upgrade_check(out: &mut Findings, fll: &FluentLanguageLoader, env: &Env, alpm: &Alpm) {
+    let partial = partial_upgrade(env, alpm);
+    let symbol = if partial.is_none() {
+        GOOD.green()
+    } else {
+        BAD.red()
+    };
+    out.line(format!(
+        "  [{}] {}",
+        symbol,
+        fl!(fll, "check-pconf-partial")
+    ));
+
+    if let Some((count, days)) = partial {
+        let cmd = out.command(&["sudo", "aura", "-Syu"]);
+        let msg = fl!(
+            fll,
+            "check-pconf-partial-fix",
+            count = count,
+            days = days,
+            command = cmd
+        );
+        out.line(format!("      └─ {}", msg));
+    }
+}
+
+/// Would installing single repo packages right now risk a partial upgrade?
+/// That's the case when the databases were synced after the last full
+/// upgrade, and some packages are out of date as a result. Yields how many,
+/// and how many days the databases are ahead.
+pub(crate) fn partial_upgrade(env: &Env, alpm: &Alpm) -> Option<(usize, u64)> {
+    let upgraded = crate::command::log::last_upgrade(env.alpm_log())?.unix_timestamp();
+    let synced = std::fs::read_dir(Path::new(&env.pacman.db_path).join("sync"))
+        .ok()?
+        .filter_map(|de| de.ok())
+        .filter(|de| de.path().extension().map(|e| e == "db").unwrap_or(false))
+        .filter_map(|de| de.metadata().and_then(|m| m.modified()).ok())
+        .filter_map(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .max()?;
+
+    if synced <= upgraded + SYNC_GRACE {
+        return None;
+    }
+
+    let pending = pending_repo(env, alpm);
+    let days = (synced - upgraded) as u64 / SECS_IN_DAY;
+
+    (pending > 0).then(|| (pending, days))
+}
+
+/// Warn before installing single repo packages if that risks a partial
+/// upgrade, or if the databases are being refreshed without one (`-Sy`).
+pub(crate) fn partial_upgrade_warning(fll: &FluentLanguageLoader, env: &Env, refreshing: bool) {
+    if refreshing {
+        yellow!(fll, "check-partial-sy");
+    } else if let Some((count, days)) = env.alpm().ok().and_then(|a| partial_upgrade(env, &a)) {
+        yellow!(fll, "check-partial-warn", count = count, days = days);
     }
 }
 
-fn duplicate_ignores(fll: &FluentLanguageLoader, c: &pacmanconf::Config, a: &Aur) {
+fn duplicate_ignores(
+    out: &mut Findings,
+    fll: &FluentLanguageLoader,
+    c: &pacmanconf::Config,
+    a: &Aur,
+) {
     let pi: HashSet<_> = c.ignore_pkg.iter().map(|s| s.as_str()).collect();
     let ai: HashSet<_> = a.ignores.iter().map(|s| s.as_str()).collect();
     let mut ix = pi.intersection(&ai).copied().collect::<Vec<_>>();
 
     let good = ix.is_empty();
     let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!("  [{}] {}", symbol, fl!(fll, "check-pconf-ignores"));
+    out.line(format!(
+        "  [{}] {}",
+        symbol,
+        fl!(fll, "check-pconf-ignores")
+    ));
 
     if !good {
         ix.sort_unstable();
         let ps = ix.join(", ");
         let msg = fl!(fll, "check-pconf-ignores-fix", pkgs = ps);
-        println!("      └─ {}", msg);
+        out.line(format!("      └─ {}", msg));
     }
 }
 
-fn snapshots(fll: &FluentLanguageLoader, s_path: &Path, t_path: &[&Path]) {
-    aura!(fll, "check-snapshots");
-    usable_snapshots(fll, s_path, t_path);
+fn snapshots(out: &mut Findings, fll: &FluentLanguageLoader, s_path: &Path, t_path: &[&Path]) {
+    out.heading(fl!(fll, "check-snapshots"));
+    usable_snapshots(out, fll, s_path, t_path);
 }
 
-fn usable_snapshots(fll: &FluentLanguageLoader, s_path: &Path, t_path: &[&Path]) {
+fn usable_snapshots(
+    out: &mut Findings,
+    fll: &FluentLanguageLoader,
+    s_path: &Path,
+    t_path: &[&Path],
+) {
     let ss = aura_core::snapshot::snapshots(s_path);
     let vs = aura_core::cache::all_versions(t_path);
     let (goods, bads): (Vec<_>, Vec<_>) = ss.partition(|s| s.usable(&vs));
     let good = bads.is_empty();
 
     let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!(
+    out.line(format!(
         "  [{}] {} ({}/{})",
         symbol,
         fl!(fll, "check-snapshot-usable"),
         goods.len(),
         goods.len() + bads.len()
-    );
+    ));
 
     if !good {
-        let cmd = "aura -Bc".bold().cyan().to_string();
+        let cmd = out.command(&["aura", "-Bc"]);
         let msg = fl!(fll, "check-snapshot-usable-fix", command = cmd);
-        println!("      └─ {}", msg);
+        out.line(format!("      └─ {}", msg));
     }
 }
 
-fn cache(fll: &FluentLanguageLoader, alpm: &Alpm, pool: Pool<AlpmManager>, caches: &[&Path]) {
-    aura!(fll, "check-cache");
-    caches_exist(fll, caches);
-    official_packages_have_tarballs(fll, alpm, caches);
-    foreign_packages_have_tarballs(fll, alpm, caches);
-    valid_tarballs(fll, pool, caches);
+fn cache(
+    out: &mut Findings,
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    pool: Pool<AlpmManager>,
+    caches: &[&Path],
+) {
+    out.heading(fl!(fll, "check-cache"));
+    caches_exist(out, fll, caches);
+    official_packages_have_tarballs(out, fll, alpm, caches);
+    foreign_packages_have_tarballs(out, fll, alpm, caches);
+    valid_tarballs(out, fll, pool, caches);
 }
 
-fn caches_exist(fll: &FluentLanguageLoader, caches: &[&Path]) {
+fn caches_exist(out: &mut Findings, fll: &FluentLanguageLoader, caches: &[&Path]) {
     let (goods, bads): (Vec<&Path>, _) = caches.iter().partition(|p| p.is_dir());
     let good = bads.is_empty();
     let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!(
+    out.line(format!(
         "  [{}] {} ({}/{})",
         symbol,
         fl!(fll, "check-cache-exists"),
         goods.len(),
         goods.len() + bads.len()
-    );
+    ));
 
     if !good {
         for bad in bads {
-            println!("      └─ {}", bad.display());
+            out.line(format!("      └─ {}", bad.display()));
         }
     }
 }
 
 /// Is every tarball in the cache valid and loadable by ALPM?
-fn valid_tarballs(fll: &FluentLanguageLoader, pool: Pool<AlpmManager>, caches: &[&Path]) {
+fn valid_tarballs(
+    out: &mut Findings,
+    fll: &FluentLanguageLoader,
+    pool: Pool<AlpmManager>,
+    caches: &[&Path],
+) {
     let (goods, bads): (Vec<_>, Vec<_>) = aura_core::cache::package_paths(caches)
         .par_bridge()
         .partition(|pp| {
@@ -243,102 +857,356 @@ fn valid_tarballs(fll: &FluentLanguageLoader, pool: Pool<AlpmManager>, caches: &
         });
     let good = bads.is_empty();
     let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!(
+    out.line(format!(
         "  [{}] {} ({}/{})",
         symbol,
         fl!(fll, "check-cache-tarballs"),
         goods.len(),
         goods.len() + bads.len()
-    );
+    ));
 
     if !good {
-        let cmd = "aura -Ct".bold().cyan().to_string();
+        let cmd = out.command(&["aura", "-Ct"]);
         let msg = fl!(fll, "check-cache-tarballs-fix", command = cmd);
-        println!("      └─ {}", msg);
+        out.line(format!("      └─ {}", msg));
     }
 }
 
 /// Does every installed package have a tarball in the cache?
-fn official_packages_have_tarballs(fll: &FluentLanguageLoader, alpm: &Alpm, caches: &[&Path]) {
+fn official_packages_have_tarballs(
+    out: &mut Findings,
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    caches: &[&Path],
+) {
     let all_installed = alpm_utils::alpm::officials(alpm).count();
     let bads: Vec<_> = aura_core::cache::officials_missing_tarballs(alpm, caches).collect();
     let is_bad = bads.is_empty().not();
     let symbol = if is_bad { BAD.red() } else { GOOD.green() };
-    println!(
+    out.line(format!(
         "  [{}] {} ({}/{})",
         symbol,
         fl!(fll, "check-cache-missing"),
         all_installed - bads.len(),
         all_installed
-    );
+    ));
 
     if is_bad {
-        let cmd = "aura -Cy".bold().cyan().to_string();
+        let cmd = out.command(&["aura", "-Cy"]);
         let msg = fl!(fll, "check-cache-missing-fix", command = cmd);
-        println!("      └─ {}", msg);
+        out.line(format!("      └─ {}", msg));
     }
 }
 
 /// Does every installed foreign package have a tarball in the cache?
-fn foreign_packages_have_tarballs(fll: &FluentLanguageLoader, alpm: &Alpm, caches: &[&Path]) {
+fn foreign_packages_have_tarballs(
+    out: &mut Findings,
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    caches: &[&Path],
+) {
     let all_installed = alpm_utils::alpm::foreigns(alpm).count();
     let bads: Vec<_> = aura_core::cache::foreigns_missing_tarballs(alpm, caches).collect();
     let is_bad = bads.is_empty().not();
     let symbol = if is_bad { BAD.red() } else { GOOD.green() };
-    println!(
+    out.line(format!(
         "  [{}] {} ({}/{})",
         symbol,
         fl!(fll, "check-cache-missing-for"),
         all_installed - bads.len(),
         all_installed
-    );
+    ));
 
     if is_bad {
-        let cmd = "aura -Cm".bold().cyan().to_string();
+        let cmd = out.command(&["aura", "-Cm"]);
         let msg = fl!(fll, "check-cache-missing-for-fix", cmd = cmd);
-        println!("      └─ {}", msg);
+        out.line(format!("      └─ {}", msg));
     }
 }
 
-fn pacnews(fll: &FluentLanguageLoader) {
+fn packages(out: &mut Findings, fll: &FluentLanguageLoader, alpm: &Alpm) {
+    out.heading(fl!(fll, "check-pkgs"));
+    duplicate_variants(out, fll, alpm);
+    conflicting_packages(out, fll, alpm);
+}
+
+/// Having both `foo` and `foo-git` installed usually means a move from one to
+/// the other was never finished.
+fn duplicate_variants(out: &mut Findings, fll: &FluentLanguageLoader, alpm: &Alpm) {
+    let groups = variant_groups(alpm.localdb().pkgs().iter().map(|p| {
+        let date = p
+            .install_date()
+            .and_then(|d| u64::try_from(d).ok())
+            .unwrap_or(0);
+        (p.name(), date)
+    }));
+
+    let good = groups.is_empty();
+    let symbol = if good { GOOD.green() } else { WARN.yellow() };
+    out.line(format!(
+        "  [{}] {}",
+        symbol,
+        fl!(fll, "check-pkgs-variants")
+    ));
+
+    for (keep, others) in groups {
+        for other in others {
+            let cmd = out.command(&["aura", "switch", other, keep]);
+            let pkgs = format!("{other}, {keep}");
+            let msg = fl!(fll, "check-pkgs-variants-fix", pkgs = pkgs, command = cmd);
+            out.line(format!("      └─ {}", msg));
+        }
+    }
+}
+
+/// Installed packages, with their install dates, grouped as variants of one
+/// another. Of each group, the one installed last is kept, since that's likely
+/// where an unfinished move was headed, and the rest are switched from.
+fn variant_groups<'a, I>(installed: I) -> Vec<(&'a str, Vec<&'a str>)>
+where
+    I: Iterator<Item = (&'a str, u64)>,
+{
+    let mut by_stem: HashMap<&str, Vec<(&str, u64)>> = HashMap::new();
+    for (name, date) in installed {
+        by_stem
+            .entry(crate::command::aur::variants::stem(name))
+            .or_default()
+            .push((name, date));
+    }
+
+    let mut groups: Vec<(&str, Vec<&str>)> = by_stem
+        .into_values()
+        .filter(|g| g.len() > 1)
+        .map(|mut g| {
+            g.sort_unstable_by(|(a, ad), (b, bd)| ad.cmp(bd).then(a.cmp(b)));
+            let (keep, _) = g.pop().unwrap_or_default();
+            let mut others: Vec<&str> = g.into_iter().map(|(n, _)| n).collect();
+            others.sort_unstable();
+            (keep, others)
+        })
+        .collect();
+    groups.sort_unstable();
+    groups
+}
+
+/// Packages that declare a conflict with one another can still end up
+/// installed together, say through `-Udd`, or a conflict added in a later
+/// release of one of them.
+fn conflicting_packages(out: &mut Findings, fll: &FluentLanguageLoader, alpm: &Alpm) {
+    let pkgs = alpm.localdb().pkgs();
+    let pairs = conflict_pairs(pkgs.iter().flat_map(|p| {
+        p.conflicts()
+            .iter()
+            .filter_map(|c| pkgs.find_satisfier(c.to_string()))
+            .map(|q| (p.name(), q.name()))
+            .collect::<Vec<_>>()
+    }));
+
+    let good = pairs.is_empty();
+    let symbol = if good { GOOD.green() } else { BAD.red() };
+    out.line(format!(
+        "  [{}] {}",
+        symbol,
+        fl!(fll, "check-pkgs-conflicts")
+    ));
+
+    for (a, b) in pairs {
+        let msg = fl!(fll, "check-pkgs-conflicts-fix", a = a, b = b);
+        out.line(format!("      └─ {}", msg));
+    }
+}
+
+/// Pairs of a package and something installed that satisfies one of its
+/// conflicts, each given once and in order. A package that provides what it
+/// conflicts with, as many do, isn't in conflict with itself.
+fn conflict_pairs<'a, I>(found: I) -> BTreeSet<(&'a str, &'a str)>
+where
+    I: Iterator<Item = (&'a str, &'a str)>,
+{
+    found
+        .filter(|(a, b)| a != b)
+        .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+        .collect()
+}
+
+fn restarts(out: &mut Findings, fll: &FluentLanguageLoader) {
+    out.heading(fl!(fll, "check-restart"));
+    running_kernel(out, fll);
+    outdated_libraries(out, fll);
+}
+
+fn running_kernel(out: &mut Findings, fll: &FluentLanguageLoader) {
+    let stale = stale_kernel();
+    let good = stale.is_none();
+    let symbol = if good { GOOD.green() } else { BAD.red() };
+    out.line(format!(
+        "  [{}] {}",
+        symbol,
+        fl!(fll, "check-restart-kernel")
+    ));
+
+    if let Some((running, installed)) = stale {
+        let msg = fl!(
+            fll,
+            "check-restart-kernel-fix",
+            running = running,
+            installed = installed.join(", ")
+        );
+        out.line(format!("      └─ {}", msg));
+    }
+}
+
+fn outdated_libraries(out: &mut Findings, fll: &FluentLanguageLoader) {
+    let procs = stale_processes();
+    let good = procs.is_empty();
+    let symbol = if good { GOOD.green() } else { WARN.yellow() };
+    out.line(format!("  [{}] {}", symbol, fl!(fll, "check-restart-libs")));
+
+    if !good {
+        let names = procs.into_iter().collect::<Vec<_>>().join(", ");
+        let msg = fl!(fll, "check-restart-libs-fix", procs = names);
+        out.line(format!("      └─ {}", msg));
+    }
+}
+
+/// Warn if the last upgrade left the system in need of a reboot, or left some
+/// programs running with libraries that no longer exist on disk.
+pub(crate) fn restart_advice(fll: &FluentLanguageLoader) {
+    if let Some((running, installed)) = stale_kernel() {
+        yellow!(
+            fll,
+            "check-restart-reboot",
+            running = running,
+            installed = installed.join(", ")
+        );
+    } else {
+        let procs = stale_processes();
+
+        if procs.is_empty().not() {
+            let names = procs.into_iter().collect::<Vec<_>>().join(", ");
+            yellow!(fll, "check-restart-services", procs = names);
+        }
+    }
+}
+
+/// If the modules of the running kernel are no longer installed, the running
+/// kernel's version and those of the installed ones. A kernel upgrade removes
+/// the old version's module directory, which is also what prevents new modules
+/// from being loaded until a reboot.
+///
+/// Containers and custom kernels have no modules under `/usr/lib/modules`,
+/// and there's nothing to reboot into without an installed kernel, so both
+/// count as fine.
+fn stale_kernel() -> Option<(String, Vec<String>)> {
+    let modules = Path::new("/usr/lib/modules");
+    let running = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok()?;
+    let running = running.trim();
+
+    if modules.join(running).is_dir() {
+        None
+    } else {
+        let mut installed: Vec<String> = modules
+            .read_dir()
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.join("vmlinuz").is_file())
+            .filter_map(|path| path.file_name()?.to_str().map(|f| f.to_string()))
+            .collect();
+        installed.sort();
+
+        installed
+            .is_empty()
+            .not()
+            .then(|| (running.to_string(), installed))
+    }
+}
+
+/// The names of running programs (or the systemd services they belong to)
+/// that still have a deleted shared library mapped into memory. Only the
+/// processes the current user is allowed to inspect are considered.
+fn stale_processes() -> BTreeSet<String> {
+    let procs = match Path::new("/proc").read_dir() {
+        Ok(rd) => rd,
+        Err(_) => return BTreeSet::new(),
+    };
+
+    procs
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|f| f.to_str())
+                .map(|f| f.chars().all(|c| c.is_ascii_digit()))
+                .unwrap_or(false)
+        })
+        .filter(|path| {
+            std::fs::read_to_string(path.join("maps"))
+                .map(|maps| {
+                    maps.lines()
+                        .any(|l| l.ends_with(" (deleted)") && l.contains(".so"))
+                })
+                .unwrap_or(false)
+        })
+        .filter_map(|path| service(&path).or_else(|| command_name(&path)))
+        .collect()
+}
+
+/// The systemd service that a process belongs to, if any.
+fn service(proc: &Path) -> Option<String> {
+    std::fs::read_to_string(proc.join("cgroup"))
+        .ok()?
+        .lines()
+        .filter_map(|l| l.rsplit('/').next())
+        .find(|unit| unit.ends_with(".service"))
+        .map(|unit| unit.to_string())
+}
+
+fn command_name(proc: &Path) -> Option<String> {
+    std::fs::read_to_string(proc.join("comm"))
+        .ok()
+        .map(|c| c.trim().to_string())
+}
+
+fn pacnews(out: &mut Findings, fll: &FluentLanguageLoader) {
     match which::which("fd") {
         Err(_) => {
-            println!(
+            out.line(format!(
                 "  [{}] {}",
                 CANCEL.truecolor(128, 128, 128),
                 fl!(fll, "check-pconf-pacnew")
-            );
-            println!(
+            ));
+            out.line(format!(
                 "      └─ {}",
                 fl!(fll, "check-missing-exec", exec = "fd".cyan().to_string())
-            );
+            ));
         }
         Ok(_) => match pacnew_work() {
             None => {
-                println!(
+                out.line(format!(
                     "  [{}] {}",
                     CANCEL.truecolor(128, 128, 128),
                     fl!(fll, "check-pconf-pacnew")
-                );
-                println!(
+                ));
+                out.line(format!(
                     "      └─ {}",
                     fl!(
                         fll,
                         "check-pconf-pacnew-broken",
                         fd = "fd".cyan().to_string()
                     )
-                );
+                ));
             }
             Some(bads) => {
                 let good = bads.is_empty();
                 let sym = if good { GOOD.green() } else { BAD.red() };
-                println!("  [{}] {}", sym, fl!(fll, "check-pconf-pacnew"));
+                out.line(format!("  [{}] {}", sym, fl!(fll, "check-pconf-pacnew")));
 
                 let len = bads.len();
                 for (i, (path, days)) in bads.into_iter().enumerate() {
                     let arrow = if i + 1 == len { "└─" } else { "├─" };
 
-                    println!(
+                    out.line(format!(
                         "      {} {}",
                         arrow,
                         fl!(
@@ -347,7 +1215,7 @@ fn pacnews(fll: &FluentLanguageLoader) {
                             path = path.utf8().cyan().to_string(),
                             days = days.to_string().red().to_string(),
                         ),
-                    );
+                    ));
                 }
             }
         },
@@ -386,3 +1254,94 @@ fn pacnew_work() -> Option<Vec<(PathBuf, u64)>> {
 
     Some(bads)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn variants() {
+        let installed = [
+            ("foo", 10),
+            ("foo-git", 20),
+            ("bar-bin", 5),
+            ("bar", 5),
+            ("baz", 1),
+            ("qux-bin", 3),
+            ("qux-git", 1),
+            ("qux", 2),
+        ];
+        let groups = variant_groups(installed.into_iter());
+
+        assert_eq!(
+            vec![
+                ("bar-bin", vec!["bar"]),
+                ("foo-git", vec!["foo"]),
+                ("qux-bin", vec!["qux", "qux-git"]),
+            ],
+            groups
+        );
+    }
+
+    #[test]
+    fn no_variants() {
+        let installed = [("foo", 1), ("bar-git", 2), ("baz-bin", 3)];
+        assert!(variant_groups(installed.into_iter()).is_empty());
+    }
+
+    #[test]
+    fn conflicts() {
+        let found = [
+            ("pipewire-pulse", "pulseaudio"),
+            ("pulseaudio", "pipewire-pulse"),
+            ("iptables-nft", "iptables-nft"),
+            ("vim", "gvim"),
+        ];
+        let pairs: Vec<_> = conflict_pairs(found.into_iter()).into_iter().collect();
+
+        assert_eq!(
+            vec![("gvim", "vim"), ("pipewire-pulse", "pulseaudio")],
+            pairs
+        );
+    }
+
+    #[test]
+    fn expired() {
+        let listing = "\
+tru::1:1700000000:0:3:1:5
+pub:f:4096:1:AAAAAAAAAAAAAAAA:1400000000:::-:::scSC::::::23::0:
+fpr:::::::::AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA:
+pub:e:4096:1:BBBBBBBBBBBBBBBB:1300000000:1600000000::f:::sc::::::23::0:
+sub:e:4096:1:CCCCCCCCCCCCCCCC:1300000000:1600000000:::::e::::::23:
+pub:e:4096:1:EEEEEEEEEEEEEEEE:1300000000:1600000000::-:::sc::::::23::0:
+pub:-:255:22:DDDDDDDDDDDDDDDD:1650000000:::-:::scSC::::::23::0:
+";
+
+        // A former packager's key has expired, but only the trusted one counts.
+        assert_eq!(Some(1), count_expired(listing));
+        assert_eq!(Some(0), count_expired(&listing.replace("::f:::", "::-:::")));
+        assert_eq!(None, count_expired(""));
+        assert_eq!(
+            None,
+            count_expired("gpg: keyblock resource: Permission denied\n")
+        );
+    }
+
+    #[test]
+    fn signature_failures_since_last_transaction() {
+        let log = "\
+[2026-10-01T10:00:00+0000] [ALPM] error: linux: signature from \"Someone\" is unknown trust
+[2026-10-01T10:00:05+0000] [ALPM] transaction completed
+[2026-10-02T10:00:00+0000] [PACMAN] Running 'pacman -Syu'
+[2026-10-02T10:00:01+0000] [ALPM] error: mesa: signature from \"Someone Else\" is invalid
+[2026-10-02T10:00:02+0000] [ALPM] error: failed to commit transaction (invalid or corrupted package (PGP signature))
+[2026-10-02T10:00:03+0000] [ALPM] upgraded gtk4 (4.14.0-1 -> 4.14.1-1)
+";
+
+        let found = signature_failures(log);
+        assert_eq!(2, found.len());
+        assert!(found[0].contains("mesa"));
+        assert!(found[1].contains("PGP signature"));
+        assert!(signature_failures("").is_empty());
+    }
+}
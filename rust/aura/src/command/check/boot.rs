@@ -0,0 +1,405 @@
+//! Confirming that every installed kernel can actually be booted.
+//!
+//! A kernel package installs its image to `/usr/lib/modules`, and a `pacman`
+//! hook copies it to `/boot` and builds its initramfs there. If `/boot` wasn't
+//! mounted at the time, all of that lands on the root filesystem instead, under
+//! the mount point, and the bootloader keeps loading the old kernel, whose
+//! modules are gone. So each kernel's image in `/boot` is compared to the one
+//! its package installed, and its initramfs and bootloader entry are looked
+//! for.
+//!
+//! Not every system follows that layout. `kernel-install` instead places the
+//! image and initramfs under `<esp>/<entry-token>/<release>`, and a unified
+//! kernel image (UKI) carries both within itself, so there's nothing separate
+//! to compare.
+
+use super::{BAD, GOOD, WARN};
+use crate::aura;
+use crate::kernel::MODULES;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+
+const BOOT: &str = "/boot";
+
+/// Where an EFI system partition may be mounted.
+const ESPS: &[&str] = &["/boot", "/efi", "/boot/efi"];
+
+/// The initramfs images that the various generators produce for a kernel in
+/// `/boot`, by its package name or release.
+const INITRAMFS: &[&str] = &[
+    "initramfs-{base}.img",
+    "booster-{base}.img",
+    "initramfs-{release}.img",
+];
+
+/// Where a kernel's image and initramfs are found, which depends on what
+/// installed them.
+enum Layout {
+    /// Both are within a unified kernel image.
+    Uki,
+    /// As placed by `kernel-install`, in the given directory.
+    Installed(PathBuf),
+    /// In `/boot`, named for the kernel's package.
+    Boot,
+}
+
+/// An installed kernel.
+struct Kernel {
+    /// The name of its package, like `linux-lts`.
+    base: String,
+    /// Its release, like `6.1.82-1-lts`.
+    release: String,
+}
+
+/// Check that every kernel has a current image, an initramfs, and a bootloader
+/// entry, and that `/boot` is mounted if it should be.
+pub(crate) fn boot(fll: &FluentLanguageLoader) {
+    aura!(fll, "check-boot");
+
+    let mounted = boot_mounted();
+    let symbol = if mounted { GOOD.green() } else { BAD.red() };
+    println!("  [{}] {}", symbol, fl!(fll, "check-boot-mounted"));
+    if mounted.not() {
+        println!("      └─ {}", fl!(fll, "check-boot-mounted-fix"));
+    }
+
+    let loaders = Bootloaders::find();
+    if loaders.is_none() {
+        println!("  [{}] {}", WARN.yellow(), fl!(fll, "check-boot-no-loader"));
+    }
+
+    for k in kernels() {
+        let mut problems = Vec::new();
+        let (image, initramfs) = match layout(&loaders, &k) {
+            Layout::Uki => (None, None),
+            Layout::Installed(dir) => (Some(dir.join("linux")), Some(initrd(&dir))),
+            Layout::Boot => (
+                Some(Path::new(BOOT).join(format!("vmlinuz-{}", k.base))),
+                Some(initramfs(&k)),
+            ),
+        };
+
+        match image {
+            Some(image) if image.is_file().not() => problems.push(fl!(
+                fll,
+                "check-boot-no-image",
+                path = image.display().to_string()
+            )),
+            Some(image) if same_image(&image, &k).not() => problems.push(fl!(
+                fll,
+                "check-boot-stale-image",
+                path = image.display().to_string()
+            )),
+            _ => {}
+        }
+
+        if let Some(None) = initramfs {
+            problems.push(fl!(fll, "check-boot-no-initramfs"));
+        }
+
+        if loaders.is_some() && loaders.has_entry(&k).not() {
+            problems.push(fl!(fll, "check-boot-no-entry"));
+        }
+
+        let symbol = if problems.is_empty() {
+            GOOD.green()
+        } else {
+            BAD.red()
+        };
+        println!("  [{}] {} {}", symbol, k.base.bold(), k.release);
+        for p in problems {
+            println!("      └─ {}", p);
+        }
+    }
+}
+
+/// The installed kernels, by the `pkgbase` files of their module directories.
+fn kernels() -> Vec<Kernel> {
+    let mut kernels: Vec<Kernel> = Path::new(MODULES)
+        .read_dir()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let base = std::fs::read_to_string(e.path().join("pkgbase")).ok()?;
+            let release = e.file_name().to_str()?.to_string();
+
+            Some(Kernel {
+                base: base.trim().to_string(),
+                release,
+            })
+        })
+        .collect();
+    kernels.sort_by(|a, b| a.base.cmp(&b.base));
+    kernels
+}
+
+/// Is the kernel image in `/boot` the one that its package installed?
+fn same_image(image: &Path, kernel: &Kernel) -> bool {
+    let installed: PathBuf = [MODULES, &kernel.release, "vmlinuz"].iter().collect();
+    let size = |p: &Path| p.metadata().map(|m| m.len()).ok();
+
+    if size(image) != size(&installed) {
+        return false;
+    }
+
+    let hashes = crate::utils::sha256s(&[image, installed.as_path()]);
+    match (hashes.get(image), hashes.get(&installed)) {
+        (Some(a), Some(b)) => a == b,
+        // Unreadable, but the same size is the best that can be told.
+        _ => true,
+    }
+}
+
+/// How the given kernel is laid out for booting.
+fn layout(loaders: &Bootloaders, kernel: &Kernel) -> Layout {
+    if loaders.ukis.iter().any(|n| is_uki(n, kernel)) {
+        return Layout::Uki;
+    }
+
+    ESPS.iter()
+        .filter_map(|esp| Path::new(esp).read_dir().ok())
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().join(&kernel.release))
+        .find(|dir| dir.join("linux").is_file())
+        .map(Layout::Installed)
+        .unwrap_or(Layout::Boot)
+}
+
+/// The initramfs of a kernel in `/boot`, if one was built.
+fn initramfs(kernel: &Kernel) -> Option<PathBuf> {
+    INITRAMFS
+        .iter()
+        .map(|form| {
+            let name = form
+                .replace("{base}", &kernel.base)
+                .replace("{release}", &kernel.release);
+            Path::new(BOOT).join(name)
+        })
+        .find(|p| p.is_file())
+}
+
+/// The initramfs that `kernel-install` placed beside a kernel, if any.
+fn initrd(dir: &Path) -> Option<PathBuf> {
+    dir.read_dir()
+        .ok()?
+        .filter_map(|e| e.ok())
+        .find(|e| e.file_name().to_string_lossy().starts_with("initrd"))
+        .map(|e| e.path())
+}
+
+/// If `/boot` is listed in `fstab`, is it mounted now?
+fn boot_mounted() -> bool {
+    let points = |file: &str| -> Vec<String> {
+        std::fs::read_to_string(file)
+            .unwrap_or_default()
+            .lines()
+            .filter(|l| l.trim_start().starts_with('#').not())
+            .filter_map(|l| l.split_whitespace().nth(1).map(|p| p.to_string()))
+            .collect()
+    };
+
+    let separate = points("/etc/fstab").iter().any(|p| p == BOOT);
+    separate.not() || points("/proc/mounts").iter().any(|p| p == BOOT)
+}
+
+/// The configurations of the bootloaders found.
+struct Bootloaders {
+    /// The `linux` lines of every systemd-boot entry.
+    systemd: Vec<String>,
+    /// The names of any unified kernel images.
+    ukis: Vec<String>,
+    /// The contents of `grub.cfg`.
+    grub: Option<String>,
+}
+
+impl Bootloaders {
+    fn find() -> Bootloaders {
+        let entries = |esp: &str| -> Vec<PathBuf> {
+            Path::new(esp)
+                .join("loader/entries")
+                .read_dir()
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .collect()
+        };
+
+        let systemd = ESPS
+            .iter()
+            .flat_map(|esp| entries(esp))
+            .filter_map(|p| std::fs::read_to_string(p).ok())
+            .flat_map(|conf| {
+                conf.lines()
+                    .filter_map(|l| l.trim().strip_prefix("linux"))
+                    .map(|l| l.trim().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let ukis = ESPS
+            .iter()
+            .filter_map(|esp| Path::new(esp).join("EFI/Linux").read_dir().ok())
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+            .filter(|n| n.ends_with(".efi"))
+            .collect();
+
+        let grub = std::fs::read_to_string("/boot/grub/grub.cfg").ok();
+
+        Bootloaders {
+            systemd,
+            ukis,
+            grub,
+        }
+    }
+
+    fn is_none(&self) -> bool {
+        self.systemd.is_empty() && self.ukis.is_empty() && self.grub.is_none()
+    }
+
+    fn is_some(&self) -> bool {
+        self.is_none().not()
+    }
+
+    /// Does any bootloader know of the given kernel?
+    fn has_entry(&self, kernel: &Kernel) -> bool {
+        let image = format!("vmlinuz-{}", kernel.base);
+        let installed = format!("/{}/linux", kernel.release);
+
+        self.systemd
+            .iter()
+            .any(|l| names_image(l, &image) || l.trim().ends_with(&installed))
+            || self.ukis.iter().any(|n| is_uki(n, kernel))
+            || self
+                .grub
+                .as_deref()
+                .map(|g| {
+                    g.lines()
+                        .filter_map(|l| l.trim().strip_prefix("linux"))
+                        .any(|l| l.split_whitespace().any(|w| names_image(w, &image)))
+                })
+                .unwrap_or(false)
+    }
+}
+
+/// Is the last component of a path exactly the given kernel image? Otherwise
+/// `vmlinuz-linux` would be found in `vmlinuz-linux-lts`.
+fn names_image(path: &str, image: &str) -> bool {
+    path.trim().rsplit('/').next() == Some(image)
+}
+
+/// Is a unified kernel image built from the given kernel, either by its
+/// package name or, as `kernel-install` names them, by its release?
+fn is_uki(name: &str, kernel: &Kernel) -> bool {
+    is_uki_of(name, &kernel.base) || is_uki_for(name, &kernel.release)
+}
+
+/// Is a unified kernel image, like `arch-linux-zen.efi`, built from the given
+/// kernel? The kernel must be the whole name, or follow a `-`, and may be
+/// followed only by `-fallback`.
+fn is_uki_of(name: &str, base: &str) -> bool {
+    let stem = match name.strip_suffix(".efi") {
+        Some(s) => s.strip_suffix("-fallback").unwrap_or(s),
+        None => return false,
+    };
+
+    stem == base
+        || stem
+            .strip_suffix(base)
+            .map(|s| s.ends_with('-'))
+            .unwrap_or(false)
+}
+
+/// Is a unified kernel image, like `<entry-token>-6.1.82-1-lts+3-0.efi`, of
+/// the given release? Any boot counter after a `+` is ignored.
+fn is_uki_for(name: &str, release: &str) -> bool {
+    let stem = match name.strip_suffix(".efi") {
+        Some(s) => s.split('+').next().unwrap_or(s),
+        None => return false,
+    };
+
+    stem == release
+        || stem
+            .strip_suffix(release)
+            .map(|s| s.ends_with('-'))
+            .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kernel(base: &str, release: &str) -> Kernel {
+        Kernel {
+            base: base.to_string(),
+            release: release.to_string(),
+        }
+    }
+
+    fn loaders() -> Bootloaders {
+        Bootloaders {
+            systemd: vec![
+                "/vmlinuz-linux-lts".to_string(),
+                "/0123abcd/6.9.1-arch1-1/linux".to_string(),
+            ],
+            ukis: vec![
+                "arch-linux-zen.efi".to_string(),
+                "arch-linux-zen-fallback.efi".to_string(),
+                "0123abcd-6.8.2-hardened1-1+3-0.efi".to_string(),
+            ],
+            grub: Some(
+                "menuentry 'Arch Linux' {\n\tlinux /boot/vmlinuz-linux-hardened root=UUID=1234 rw\n\tinitrd /boot/initramfs-linux.img\n}"
+                    .to_string(),
+            ),
+        }
+    }
+
+    #[test]
+    fn entries() {
+        let b = loaders();
+        assert!(b.has_entry(&kernel("linux-lts", "6.1.82-1-lts")));
+        assert!(b.has_entry(&kernel("linux-zen", "6.9.1-zen1-1-zen")));
+        assert!(b.has_entry(&kernel("linux-hardened", "6.8.1-hardened1-1")));
+    }
+
+    #[test]
+    fn installed_entries() {
+        let b = loaders();
+        assert!(b.has_entry(&kernel("linux", "6.9.1-arch1-1")));
+        assert!(b.has_entry(&kernel("linux-rt", "6.8.2-hardened1-1")));
+        assert!(b.has_entry(&kernel("linux", "6.9.1-arch1-2")).not());
+    }
+
+    #[test]
+    fn neighbours() {
+        let b = loaders();
+        assert!(b.has_entry(&kernel("linux", "6.9.2-arch1-1")).not());
+        assert!(b.has_entry(&kernel("inux-zen", "6.9.1-zen1-1-zen")).not());
+        assert!(b.has_entry(&kernel("linux-lts-git", "6.1.82-1-lts")).not());
+    }
+
+    #[test]
+    fn ukis() {
+        assert!(is_uki_of("linux.efi", "linux"));
+        assert!(is_uki_of("arch-linux.efi", "linux"));
+        assert!(is_uki_of("arch-linux-fallback.efi", "linux"));
+        assert!(is_uki_of("arch-linux-zen.efi", "linux").not());
+        assert!(is_uki_of("archlinux.efi", "linux").not());
+        assert!(is_uki_of("arch-linux.conf", "linux").not());
+
+        assert!(is_uki_for("0123abcd-6.9.1-arch1-1.efi", "6.9.1-arch1-1"));
+        assert!(is_uki_for(
+            "0123abcd-6.9.1-arch1-1+3-0.efi",
+            "6.9.1-arch1-1"
+        ));
+        assert!(is_uki_for("0123abcd-16.9.1-arch1-1.efi", "6.9.1-arch1-1").not());
+        assert!(is_uki_for("0123abcd-6.9.1-arch1-1", "6.9.1-arch1-1").not());
+    }
+}
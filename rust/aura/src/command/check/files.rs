@@ -0,0 +1,377 @@
+//! Verifying installed files against what their packages installed.
+//!
+//! Every package's entry in the local database keeps an `mtree` of its files,
+//! recording the mode, owner, size, and SHA-256 of each. Files that no longer
+//! match are reported, with those that `pacman` itself expects to change, like
+//! the `backup` files of a package, set apart as config changes.
+
+use super::{Error, BAD, GOOD, WARN};
+use crate::env::Env;
+use crate::{aura, green};
+use alpm::Alpm;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use std::collections::{HashMap, HashSet};
+use std::ops::Not;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A file as its package installed it.
+#[derive(Debug, PartialEq, Eq)]
+struct Entry {
+    path: String,
+    mode: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    size: Option<u64>,
+    sha256: Option<String>,
+}
+
+/// How an installed file differs from its package's record of it.
+enum Change {
+    Missing,
+    Contents,
+    Mode(u32, u32),
+    Owner,
+}
+
+/// Verify the files of the given packages, or of every package if none are
+/// given.
+pub(crate) fn files(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    packages: &[String],
+) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let db = alpm.localdb();
+    let pkgs: Vec<alpm::Package> = if packages.is_empty() {
+        db.pkgs().iter().collect()
+    } else {
+        packages
+            .iter()
+            .map(|p| {
+                db.pkg(p.as_str())
+                    .map_err(|_| Error::NotInstalled(p.clone()))
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    aura!(fll, "check-files", count = pkgs.len());
+
+    let ignores = &env.general.files_ignore;
+    let mut unreadable = 0;
+    let mut clean = true;
+
+    for pkg in pkgs {
+        let entries = match mtree(&alpm, &pkg) {
+            Some(es) => es,
+            None => {
+                let name = pkg.name().cyan().to_string();
+                println!(
+                    "  [{}] {}",
+                    WARN.yellow(),
+                    fl!(fll, "check-files-no-mtree", pkg = name)
+                );
+                continue;
+            }
+        };
+
+        let configs: HashSet<String> = pkg
+            .backup()
+            .iter()
+            .map(|b| format!("/{}", b.name()))
+            .collect();
+
+        let entries = entries.into_iter().filter(|e| {
+            let path = Path::new(&e.path);
+            env.is_noextract(path).not()
+                && is_noupgrade(env, path).not()
+                && ignores
+                    .iter()
+                    .any(|i| aura_core::glob::matches(i, &e.path))
+                    .not()
+        });
+
+        let (changes, u) = changes(Path::new(&env.pacman.root_dir), entries);
+        unreadable += u;
+
+        if changes.is_empty() {
+            continue;
+        }
+
+        clean = false;
+        let (conf, other): (Vec<_>, Vec<_>) =
+            changes.into_iter().partition(|(p, _)| configs.contains(p));
+        let symbol = if other.is_empty() {
+            WARN.yellow()
+        } else {
+            BAD.red()
+        };
+
+        println!("  [{}] {}", symbol, pkg.name().bold());
+        for (path, change) in other {
+            println!("      └─ {}", describe(fll, &path, &change));
+        }
+        for (path, _) in conf {
+            let path = path.as_str();
+            println!("      └─ {}", fl!(fll, "check-files-config", path = path));
+        }
+    }
+
+    if unreadable > 0 {
+        println!(
+            "  [{}] {}",
+            WARN.yellow(),
+            fl!(fll, "check-files-unreadable", count = unreadable)
+        );
+    }
+
+    if clean {
+        println!("  [{}] {}", GOOD.green(), fl!(fll, "check-files-ok"));
+    }
+
+    green!(fll, "common-done");
+    Ok(())
+}
+
+fn describe(fll: &FluentLanguageLoader, path: &str, change: &Change) -> String {
+    match change {
+        Change::Missing => fl!(fll, "check-files-missing", path = path),
+        Change::Contents => fl!(fll, "check-files-contents", path = path),
+        Change::Owner => fl!(fll, "check-files-owner", path = path),
+        Change::Mode(expected, actual) => fl!(
+            fll,
+            "check-files-mode",
+            path = path,
+            expected = format!("{expected:o}"),
+            actual = format!("{actual:o}")
+        ),
+    }
+}
+
+/// Would `pacman` avoid overwriting this file during upgrades, per the
+/// `NoUpgrade` of `pacman.conf`?
+fn is_noupgrade(env: &Env, path: &Path) -> bool {
+    let relative = path.strip_prefix("/").unwrap_or(path).to_string_lossy();
+
+    env.pacman
+        .no_upgrade
+        .iter()
+        .any(|p| aura_core::glob::matches(p, &relative))
+}
+
+/// Compare files under the given root to their records, yielding what changed
+/// and how many files couldn't be read to tell. Changes are reported by the
+/// paths the packages gave, not where they were found.
+fn changes<I>(root: &Path, entries: I) -> (Vec<(String, Change)>, usize)
+where
+    I: Iterator<Item = Entry>,
+{
+    let mut changes = Vec::new();
+    let mut to_hash: HashMap<PathBuf, (String, String)> = HashMap::new();
+
+    for e in entries {
+        let on_disk = root.join(e.path.trim_start_matches('/'));
+        let meta = match std::fs::symlink_metadata(&on_disk) {
+            Ok(m) => m,
+            Err(_) => {
+                changes.push((e.path, Change::Missing));
+                continue;
+            }
+        };
+
+        let mode = meta.mode() & 0o7777;
+        if let Some(expected) = e.mode.filter(|m| *m != mode) {
+            changes.push((e.path.clone(), Change::Mode(expected, mode)));
+        }
+
+        let owned = e.uid.map(|u| u == meta.uid()).unwrap_or(true)
+            && e.gid.map(|g| g == meta.gid()).unwrap_or(true);
+        if owned.not() {
+            changes.push((e.path.clone(), Change::Owner));
+        }
+
+        match (e.size, e.sha256) {
+            (Some(s), _) if s != meta.len() => changes.push((e.path, Change::Contents)),
+            (_, Some(h)) => {
+                to_hash.insert(on_disk, (e.path, h));
+            }
+            _ => {}
+        }
+    }
+
+    let paths: Vec<&PathBuf> = to_hash.keys().collect();
+    let hashes = crate::utils::sha256s(&paths);
+    let unreadable = paths.len() - hashes.len();
+
+    for (path, hash) in hashes {
+        if let Some((p, expected)) = to_hash.get(&path) {
+            if *expected != hash {
+                changes.push((p.clone(), Change::Contents));
+            }
+        }
+    }
+
+    changes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    (changes, unreadable)
+}
+
+/// The file entries of an installed package's `mtree`.
+fn mtree(alpm: &Alpm, pkg: &alpm::Package) -> Option<Vec<Entry>> {
+    let path = Path::new(alpm.dbpath())
+        .join("local")
+        .join(format!("{}-{}", pkg.name(), pkg.version().as_str()))
+        .join("mtree");
+
+    let out = Command::new("gzip").arg("-dc").arg(path).output().ok()?;
+    out.status.success().then(|| ())?;
+
+    Some(parse(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// Parse the regular files from an `mtree`. Later `/set` lines change the
+/// defaults of the entries that follow them.
+fn parse(raw: &str) -> Vec<Entry> {
+    let mut defaults: HashMap<String, String> = HashMap::new();
+    let mut entries = Vec::new();
+
+    for line in raw.lines() {
+        let mut fields = line.split_whitespace();
+
+        match fields.next() {
+            Some("/set") => {
+                defaults.extend(fields.filter_map(|f| {
+                    let (k, v) = f.split_once('=')?;
+                    Some((k.to_string(), v.to_string()))
+                }));
+            }
+            // Package metadata like `.PKGINFO` isn't installed as such.
+            Some(p) if p.starts_with("./") && p.starts_with("./.").not() => {
+                let mut keys = defaults.clone();
+                keys.extend(fields.filter_map(|f| {
+                    let (k, v) = f.split_once('=')?;
+                    Some((k.to_string(), v.to_string()))
+                }));
+
+                if keys.get("type").map(|t| t.as_str()) != Some("file") {
+                    continue;
+                }
+
+                entries.push(Entry {
+                    path: unescape(&p[1..]),
+                    mode: keys
+                        .get("mode")
+                        .and_then(|m| u32::from_str_radix(m, 8).ok()),
+                    uid: keys.get("uid").and_then(|u| u.parse().ok()),
+                    gid: keys.get("gid").and_then(|g| g.parse().ok()),
+                    size: keys.get("size").and_then(|s| s.parse().ok()),
+                    sha256: keys.get("sha256digest").cloned(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+/// `mtree` paths escape unusual characters as octal, like `\040` for a space.
+fn unescape(raw: &str) -> String {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut rest = raw.as_bytes();
+
+    while let Some((b, tail)) = rest.split_first() {
+        let octal = tail
+            .get(..3)
+            .and_then(|o| std::str::from_utf8(o).ok())
+            .and_then(|o| u8::from_str_radix(o, 8).ok());
+
+        match octal {
+            Some(o) if *b == b'\\' => {
+                bytes.push(o);
+                rest = &tail[3..];
+            }
+            _ => {
+                bytes.push(*b);
+                rest = tail;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mtree_entries() {
+        let raw = "#mtree
+/set type=file uid=0 gid=0 mode=644
+./.BUILDINFO time=1.0 size=100 sha256digest=aaa
+./usr time=1.0 mode=755 type=dir
+./usr/bin/foo time=1.0 mode=755 size=5 sha256digest=bbb
+./usr/bin/foo.so time=1.0 type=link link=foo.so.1
+./usr/share/my\\040file time=1.0 size=3 sha256digest=ccc
+";
+
+        let expected = vec![
+            Entry {
+                path: "/usr/bin/foo".to_string(),
+                mode: Some(0o755),
+                uid: Some(0),
+                gid: Some(0),
+                size: Some(5),
+                sha256: Some("bbb".to_string()),
+            },
+            Entry {
+                path: "/usr/share/my file".to_string(),
+                mode: Some(0o644),
+                uid: Some(0),
+                gid: Some(0),
+                size: Some(3),
+                sha256: Some("ccc".to_string()),
+            },
+        ];
+
+        assert_eq!(expected, parse(raw));
+    }
+
+    #[test]
+    fn changes_under_root() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("aura-check-files-{}", std::process::id()));
+        let bin = root.join("usr/bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("foo"), "hello").unwrap();
+        std::fs::set_permissions(bin.join("foo"), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let entry = |path: &str, mode: u32| Entry {
+            path: path.to_string(),
+            mode: Some(mode),
+            uid: None,
+            gid: None,
+            size: Some(5),
+            sha256: Some(
+                "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".to_string(),
+            ),
+        };
+
+        let (clean, unreadable) = changes(&root, vec![entry("/usr/bin/foo", 0o755)].into_iter());
+        assert!(clean.is_empty());
+        assert_eq!(0, unreadable);
+
+        let entries = vec![entry("/usr/bin/foo", 0o644), entry("/usr/bin/bar", 0o755)];
+        let (found, _) = changes(&root, entries.into_iter());
+        let paths: Vec<&str> = found.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(vec!["/usr/bin/bar", "/usr/bin/foo"], paths);
+        assert!(matches!(found[0].1, Change::Missing));
+        assert!(matches!(found[1].1, Change::Mode(0o644, 0o755)));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+//! Automatic remedies for some of what `check` finds.
+//!
+//! Every fix is offered separately, and every elevated command it runs is
+//! recorded in the audit log.
+
+use super::Error;
+use crate::env::{Env, OrphanPolicy};
+use crate::pacman::DbLock;
+use crate::{aura, green};
+use alpm::Alpm;
+use aura::caps::Mutating;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::info;
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+const MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+const REFLECTOR_CONF: &str = "/etc/xdg/reflector/reflector.conf";
+
+/// How old a mirrorlist may grow before it's worth refreshing.
+const MIRRORLIST_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 90);
+
+/// Offer to fix whatever can be fixed automatically.
+pub(crate) fn fix(m: &Mutating, fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+
+    aura!(fll, "check-fix");
+    let mut fixed = 0;
+
+    fixed += stale_lock(m, fll, &env.pacman.db_path)?;
+    fixed += mirrorlist(m, fll)?;
+    fixed += orphans(m, fll, env.general.orphans, &alpm)?;
+    fixed += pacnews(m, fll)?;
+
+    green!(fll, "check-fix-done", count = fixed);
+    Ok(())
+}
+
+/// Ask about a fix, mentioning the command it will run.
+fn offer(fll: &FluentLanguageLoader, id: &str, msg: String, cmd: &[&str]) -> bool {
+    let cmd = format!("sudo {}", cmd.join(" ")).bold().cyan().to_string();
+    println!("  {}", msg);
+    crate::prompts::ask(fll, id, fl!(fll, "check-fix-run", command = cmd)).is_some()
+}
+
+/// Run a command as root.
+fn elevated(_: &Mutating, cmd: &[&str]) -> Result<(), Error> {
+    info!("Fixing with: sudo {}", cmd.join(" "));
+
+    crate::audit::status(Command::new("sudo").args(cmd))
+        .ok()
+        .filter(|s| s.success())
+        .map(|_| ())
+        .ok_or_else(|| Error::Fix(cmd.join(" ")))
+}
+
+fn stale_lock(m: &Mutating, fll: &FluentLanguageLoader, db_path: &str) -> Result<usize, Error> {
+    match crate::pacman::db_lock(db_path) {
+        DbLock::Stale(path) => {
+            let path = path.display().to_string();
+            let cmd = ["rm", path.as_str()];
+            let msg = fl!(fll, "check-fix-lock", path = path.as_str());
+
+            if offer(fll, "check-fix-lock", msg, &cmd) {
+                elevated(m, &cmd)?;
+                return Ok(1);
+            }
+
+            Ok(0)
+        }
+        _ => Ok(0),
+    }
+}
+
+fn mirrorlist(m: &Mutating, fll: &FluentLanguageLoader) -> Result<usize, Error> {
+    let stale = std::fs::metadata(MIRRORLIST)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .filter(|age| *age > MIRRORLIST_AGE);
+
+    match stale {
+        Some(age) if which::which("reflector").is_ok() => {
+            let conf = format!("@{REFLECTOR_CONF}");
+            let cmd = ["reflector", conf.as_str()];
+            let days = age.as_secs() / crate::utils::SECS_IN_DAY;
+            let msg = fl!(fll, "check-fix-mirrors", days = days);
+
+            if offer(fll, "check-fix-mirrors", msg, &cmd) {
+                elevated(m, &cmd)?;
+                return Ok(1);
+            }
+
+            Ok(0)
+        }
+        _ => Ok(0),
+    }
+}
+
+fn orphans(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    policy: OrphanPolicy,
+    alpm: &Alpm,
+) -> Result<usize, Error> {
+    let names: Vec<String> = alpm_utils::alpm::orphans(alpm)
+        .map(|p| p.name().to_string())
+        .collect();
+
+    if names.is_empty() {
+        return Ok(0);
+    }
+
+    let pkgs = names.join(", ");
+    let (id, msg, flags): (&str, String, &[&str]) = match policy {
+        OrphanPolicy::Ignore => return Ok(0),
+        OrphanPolicy::Adopt => (
+            "check-fix-orphans-adopt",
+            fl!(fll, "check-fix-orphans-adopt", pkgs = pkgs),
+            &["pacman", "-D", "--asexplicit"],
+        ),
+        OrphanPolicy::Remove => (
+            "check-fix-orphans-remove",
+            fl!(fll, "check-fix-orphans-remove", pkgs = pkgs),
+            &["pacman", "-Rns"],
+        ),
+    };
+
+    let cmd: Vec<&str> = flags
+        .iter()
+        .copied()
+        .chain(names.iter().map(|n| n.as_str()))
+        .collect();
+
+    if offer(fll, id, msg, &cmd) {
+        elevated(m, &cmd)?;
+        return Ok(1);
+    }
+
+    Ok(0)
+}
+
+fn pacnews(m: &Mutating, fll: &FluentLanguageLoader) -> Result<usize, Error> {
+    let trivial: Vec<(PathBuf, PathBuf)> = super::pacnew_work()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(orig, _)| {
+            let mut new = orig.clone().into_os_string();
+            new.push(".pacnew");
+            (orig, PathBuf::from(new))
+        })
+        .filter(|(orig, new)| is_trivial(orig, new))
+        .collect();
+
+    let mut fixed = 0;
+
+    for (orig, new) in trivial {
+        let (o, n) = (orig.display().to_string(), new.display().to_string());
+        let cmd = ["mv", n.as_str(), o.as_str()];
+        let msg = fl!(fll, "check-fix-pacnew", path = o.as_str());
+
+        if offer(fll, "check-fix-pacnew", msg, &cmd) {
+            elevated(m, &cmd)?;
+            fixed += 1;
+        }
+    }
+
+    Ok(fixed)
+}
+
+/// Does a `.pacnew` only differ from the file in use by comments and blank
+/// lines? Then it can replace it without changing any settings.
+fn is_trivial(orig: &Path, new: &Path) -> bool {
+    let settings = |p: &Path| {
+        std::fs::read_to_string(p).ok().map(|s| {
+            s.lines()
+                .map(|l| l.trim())
+                .filter(|l| l.is_empty().not() && l.starts_with('#').not())
+                .map(|l| l.to_string())
+                .collect::<Vec<_>>()
+        })
+    };
+
+    match (settings(orig), settings(new)) {
+        (Some(o), Some(n)) => o == n,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn trivial_pacnews() {
+        let dir = std::env::temp_dir().join(format!("aura-fix-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let orig = dir.join("foo.conf");
+        let new = dir.join("foo.conf.pacnew");
+        let other = dir.join("bar.conf.pacnew");
+
+        std::fs::write(&orig, "# Old comment\nA = 1\n\nB = 2\n").unwrap();
+        std::fs::write(&new, "# New comment\nA = 1\nB = 2\n# C = 3\n").unwrap();
+        std::fs::write(&other, "A = 1\nB = 3\n").unwrap();
+
+        assert!(is_trivial(&orig, &new));
+        assert!(is_trivial(&orig, &other).not());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+//! The last known count of pending updates, for answering instantly.
+//!
+//! Whenever Aura learns how many updates are pending, it writes them down: when
+//! counting them with `check --updates`, after syncing the repo databases, and
+//! after upgrading AUR packages. `check --updates --cached` then reads the
+//! record back without touching the network, which suits shell prompts and
+//! status bars that ask often. A timer can keep the record fresh by running
+//! `check --updates --refresh-state`.
+
+use crate::env::Env;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Pending updates from each source.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Pending {
+    pub(crate) repo: usize,
+    pub(crate) aur: usize,
+    /// From the commands of `[updates]` in `aura.toml`, if they could be run.
+    pub(crate) others: Vec<(String, Option<usize>)>,
+    /// When this was last written, in seconds since the epoch.
+    pub(crate) time: i64,
+}
+
+/// The last recorded counts, if there are any.
+pub(crate) fn load() -> Option<Pending> {
+    crate::dirs::pending_updates()
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// Record the given counts as the latest.
+pub(crate) fn save(pending: &mut Pending) {
+    pending.time = OffsetDateTime::now_utc().unix_timestamp();
+
+    if let Ok(path) = crate::dirs::pending_updates() {
+        let json = serde_json::to_string(pending).unwrap_or_default();
+
+        if let Err(e) = std::fs::write(&path, json) {
+            debug!(
+                "Failed to record pending updates to {}: {e}",
+                path.display()
+            );
+        }
+    }
+}
+
+/// Change part of the record, leaving the rest as last known.
+pub(crate) fn update<F>(f: F)
+where
+    F: FnOnce(&mut Pending),
+{
+    let mut pending = load().unwrap_or_default();
+    f(&mut pending);
+    save(&mut pending);
+}
+
+/// Recount the repo updates after a database sync, which needs no network.
+pub(crate) fn refresh_repo(env: &Env) {
+    if let Ok(alpm) = env.alpm() {
+        let repo = super::pending_repo(env, &alpm);
+        update(|p| p.repo = repo);
+    }
+}
@@ -0,0 +1,201 @@
+//! The commands that checks suggest to fix what they find.
+//!
+//! Each suggestion is recorded as it's shown, so that `--suggest-only` can
+//! print just the commands, exactly as they'd be typed and never localised,
+//! for scripts to consume. At a terminal, those that need root can then be
+//! run right away.
+
+use super::{Error, BAD, GOOD};
+use crate::env::Env;
+use aura::caps::Mutating;
+use aura_core::explain::quote;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::info;
+use std::ops::Not;
+use std::process::Command;
+
+/// The shell operators that suggestions may use as they are.
+const REDIRECTS: &[&str] = &[">", ">>", "|"];
+
+/// What the checks find, shown as it's found unless `quiet`, and the commands
+/// suggested along the way, each as the words it's made of.
+pub(super) struct Findings {
+    quiet: bool,
+    suggested: Vec<Vec<String>>,
+}
+
+impl Findings {
+    pub(super) fn new(quiet: bool) -> Findings {
+        Findings {
+            quiet,
+            suggested: Vec::new(),
+        }
+    }
+
+    /// Show a heading, in the usual Aura style.
+    pub(super) fn heading<S: Colorize>(&self, msg: S) {
+        if self.quiet.not() {
+            crate::aln!(msg);
+        }
+    }
+
+    /// Show one line of what was found.
+    pub(super) fn line(&self, line: String) {
+        if self.quiet.not() {
+            println!("{line}");
+        }
+    }
+
+    /// Show whether a program is installed.
+    pub(super) fn executable(&self, fll: &FluentLanguageLoader, exec: &str, msg: String) {
+        let good = which::which(exec).is_ok();
+        let symb = if good { GOOD.green() } else { BAD.red() };
+        self.line(format!("  [{}] {}", symb, msg));
+
+        if good.not() {
+            let msg = fl!(
+                fll,
+                "check-missing-exec",
+                exec = exec.cyan().bold().to_string()
+            );
+            self.line(format!("      └─ {}", msg));
+        }
+    }
+
+    /// Record a suggested command, yielding it as it should be shown.
+    pub(super) fn command(&mut self, words: &[&str]) -> String {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        let shown = shown(&words);
+
+        if self.suggested.contains(&words).not() {
+            self.suggested.push(words);
+        }
+
+        shown.bold().cyan().to_string()
+    }
+
+    /// The commands suggested, in the order they were first suggested.
+    pub(super) fn suggested(self) -> Vec<Vec<String>> {
+        self.suggested
+    }
+}
+
+/// A command as it would be typed, quoting the words that need it. Unlike the
+/// commands Aura runs, suggestions may redirect their output, or name a path
+/// within the home directory.
+fn shown(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|w| match w.strip_prefix("~/") {
+            _ if REDIRECTS.contains(&w.as_str()) => w.clone(),
+            Some(rest) => format!("~/{}", quote(rest)),
+            None => quote(w),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Does a suggested command raise privileges?
+fn is_elevated(words: &[String]) -> bool {
+    words.first().map(|w| w == "sudo").unwrap_or(false)
+}
+
+/// Run every check, but print only the commands suggested along the way, one
+/// per line.
+pub(crate) fn suggest_only(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &Env,
+) -> Result<(), Error> {
+    let suggested = super::validate(fll, env, true)?;
+    for words in suggested.iter() {
+        println!("{}", shown(words));
+    }
+
+    // Scripts only want the commands.
+    if unsafe { libc::isatty(libc::STDOUT_FILENO) } != 1 {
+        return Ok(());
+    }
+
+    for words in suggested.iter().filter(|w| is_elevated(w)) {
+        let shown = shown(words).bold().cyan().to_string();
+        let msg = fl!(fll, "check-fix-run", command = shown);
+
+        if crate::prompts::ask(fll, "check-suggest-run", msg).is_some() {
+            run(m, words)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn run(_: &Mutating, words: &[String]) -> Result<(), Error> {
+    let line = shown(words);
+    info!("Running a suggestion: {line}");
+    let (program, args) = words
+        .split_first()
+        .ok_or_else(|| Error::Fix(line.clone()))?;
+
+    crate::audit::status(Command::new(program).args(args))
+        .ok()
+        .filter(|s| s.success())
+        .map(|_| ())
+        .ok_or(Error::Fix(line))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn commands_recorded_once() {
+        let mut out = Findings::new(true);
+        out.command(&["aura", "-Ct"]);
+        out.command(&["sudo", "rm", "/var/lib/pacman/db.lck"]);
+        out.command(&["aura", "-Ct"]);
+
+        let suggested = out.suggested();
+        assert_eq!(2, suggested.len());
+        assert_eq!(vec!["aura", "-Ct"], suggested[0]);
+        assert_eq!(vec!["sudo", "rm", "/var/lib/pacman/db.lck"], suggested[1]);
+    }
+
+    #[test]
+    fn words_quoted() {
+        let words = |ws: &[&str]| ws.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+
+        assert_eq!("sudo aura -Syu", shown(&words(&["sudo", "aura", "-Syu"])));
+        assert_eq!(
+            "sudo rm '/mnt/my disk/db.lck'",
+            shown(&words(&["sudo", "rm", "/mnt/my disk/db.lck"]))
+        );
+        assert_eq!("echo 'it'\\''s'", shown(&words(&["echo", "it's"])));
+        assert_eq!(
+            "aura conf --gen > ~/.config/aura.toml",
+            shown(&words(&[
+                "aura",
+                "conf",
+                "--gen",
+                ">",
+                "~/.config/aura.toml"
+            ]))
+        );
+    }
+
+    #[test]
+    fn only_sudo_runs() {
+        let mut out = Findings::new(true);
+        out.command(&["aura", "-Bc"]);
+        out.command(&["sudo", "aura", "-Syu"]);
+        out.command(&["sudoku"]);
+
+        let elevated: Vec<_> = out
+            .suggested()
+            .into_iter()
+            .filter(|w| is_elevated(w))
+            .collect();
+        assert_eq!(vec![vec!["sudo", "aura", "-Syu"]], elevated);
+    }
+}
@@ -0,0 +1,123 @@
+//! Aura's own copies of the sync databases, for counting repo updates.
+//!
+//! Syncing `pacman`'s own databases without then upgrading invites a partial
+//! upgrade, so `check --updates --refresh` downloads into a copy of them
+//! instead, kept in Aura's cache. The copy's `local` is a link to the real
+//! local database, so that the installed packages are those of the system.
+//!
+//! Each database is only downloaded if its mirror reports it has changed since
+//! the copy was made, so frequent checks cost little more than a request per
+//! repository. A copy takes on the mirror's `Last-Modified` time, so that the
+//! comparison is made by the mirror's clock alone.
+
+use super::Error;
+use crate::env::Env;
+use alpm::Alpm;
+use log::{debug, warn};
+use std::io::Write;
+use std::ops::Not;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Bring each copied database up to date with its mirrors, and open them
+/// alongside the real local database.
+///
+/// A database that no mirror could provide is left as it last was.
+pub(crate) fn refresh(env: &Env) -> Result<Alpm, Error> {
+    let dir = crate::dirs::sync_copies().map_err(crate::env::Error::Dirs)?;
+    let local = dir.join("local");
+    let real = Path::new(&env.pacman.db_path).join("local");
+
+    // The real database may have moved, as with `--dbpath`.
+    if std::fs::read_link(&local).ok().as_deref() != Some(real.as_path()) {
+        let _ = std::fs::remove_file(&local);
+        if let Err(e) = std::os::unix::fs::symlink(&real, &local) {
+            warn!(
+                "Failed to link {} to {}: {e}",
+                local.display(),
+                real.display()
+            );
+        }
+    }
+
+    for repo in env.pacman.repos.iter() {
+        let target = dir.join("sync").join(format!("{}.db", repo.name));
+
+        if download(env, &repo.servers, &repo.name, &target).not() {
+            warn!(
+                "No mirror could refresh {}, keeping the last copy",
+                repo.name
+            );
+        }
+    }
+
+    let mut conf = env.pacman.clone();
+    conf.db_path = dir.to_string_lossy().into_owned();
+
+    alpm_utils::alpm_with_conf(&conf)
+        .map_err(crate::env::Error::Alpm)
+        .map_err(Error::Env)
+}
+
+/// Try each mirror until one of them answers, writing the database only if it
+/// changed. Is the copy now current?
+fn download(env: &Env, servers: &[String], name: &str, target: &Path) -> bool {
+    let since = std::fs::metadata(target).and_then(|m| m.modified()).ok();
+
+    for server in servers {
+        let url = format!("{}/{name}.db", server.trim_end_matches('/'));
+
+        match env.network.bytes_if_newer(&url, since) {
+            Err(crate::fetch::Error::Curl(_, _, e)) => debug!("{url} failed: {e}"),
+            Err(_) => {}
+            Ok(None) => return true,
+            Ok(Some((bytes, modified))) => {
+                // Written aside first, so that an interrupted write never
+                // leaves a truncated database in place.
+                let part = target.with_extension("db.part");
+
+                match write(&part, &bytes, modified).and_then(|_| std::fs::rename(&part, target)) {
+                    Ok(()) => return true,
+                    Err(e) => {
+                        warn!("Failed to write {}: {e}", target.display());
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Write a file, and give it the given modification time. Without one, the
+/// time of writing has to do.
+fn write(path: &Path, bytes: &[u8], modified: Option<SystemTime>) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(bytes)?;
+
+    if let Some(secs) = modified
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .and_then(|d| libc::time_t::try_from(d.as_secs()).ok())
+    {
+        let times = [
+            libc::timespec {
+                tv_sec: 0,
+                tv_nsec: libc::UTIME_OMIT,
+            },
+            libc::timespec {
+                tv_sec: secs,
+                tv_nsec: 0,
+            },
+        ];
+
+        // SAFETY: The descriptor is open for as long as `file` lives, and
+        // `times` holds the two entries that `futimens` reads.
+        if unsafe { libc::futimens(file.as_raw_fd(), times.as_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
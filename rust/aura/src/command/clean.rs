@@ -0,0 +1,127 @@
+//! Remove leftover build artifacts.
+
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::{dir_size, PathStr};
+use crate::{aura, green, proceed};
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use ubyte::ToByteUnit;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    #[from_variants(skip)]
+    ReadDir(PathBuf, std::io::Error),
+    #[from_variants(skip)]
+    DeleteDir(PathBuf, std::io::Error),
+    Cancelled,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::ReadDir(_, e) => error!("{e}"),
+            Error::DeleteDir(_, e) => error!("{e}"),
+            Error::Cancelled => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::ReadDir(p, _) => fl!(fll, "err-read-dir", dir = p.utf8()),
+            Error::DeleteDir(p, _) => fl!(fll, "clean-del", dir = p.utf8()),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::ReadDir(_, _) => "clean-read-dir",
+            Error::DeleteDir(_, _) => "clean-delete-dir",
+            Error::Cancelled => "clean-cancelled",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::ReadDir(p, _) => vec![("path", p.display().to_string())],
+            Error::DeleteDir(p, _) => vec![("path", p.display().to_string())],
+            Error::Cancelled => Vec::new(),
+        }
+    }
+}
+
+/// Report the size of each AUR build directory, and if `remove`, offer to
+/// remove them.
+pub(crate) fn builds(
+    fll: &FluentLanguageLoader,
+    build_d: &Path,
+    keep: &[String],
+    remove: bool,
+) -> Result<(), Error> {
+    let mut dirs: Vec<(String, PathBuf, u64)> = build_d
+        .read_dir()
+        .map_err(|e| Error::ReadDir(build_d.to_path_buf(), e))?
+        .filter_map(|de| de.ok())
+        .map(|de| de.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            keep.contains(&name).not().then(|| {
+                let size = dir_size(&path);
+                (name, path, size)
+            })
+        })
+        .collect();
+
+    if dirs.is_empty() {
+        green!(fll, "clean-builds-none");
+    } else {
+        dirs.sort_by(|(_, _, a), (_, _, b)| b.cmp(a));
+        let longest = dirs
+            .iter()
+            .map(|(n, _, _)| n.chars().count())
+            .max()
+            .unwrap_or(0);
+        let total: u64 = dirs.iter().map(|(_, _, s)| s).sum();
+
+        aura!(fll, "clean-builds");
+        for (name, _, size) in dirs.iter() {
+            println!(
+                " {:w$} {:>10}",
+                name.cyan(),
+                size.bytes().to_string(),
+                w = longest
+            );
+        }
+        println!(
+            " {:w$} {:>10}",
+            fl!(fll, "common-total").bold(),
+            total.bytes().to_string(),
+            w = longest
+        );
+
+        if remove.not() {
+            return Ok(());
+        }
+
+        // Proceed with the removal if the user accepts.
+        proceed!(fll, "clean-builds-remove").ok_or(Error::Cancelled)?;
+        dirs.into_iter().try_for_each(|(_, path, _)| {
+            std::fs::remove_dir_all(&path).map_err(|e| Error::DeleteDir(path, e))
+        })?;
+        green!(fll, "common-done");
+    }
+
+    Ok(())
+}
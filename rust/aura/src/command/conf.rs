@@ -6,6 +6,7 @@ use crate::error::Nested;
 use crate::localization::Localised;
 use crate::utils::{PathStr, ResultVoid};
 use aura::flags::Conf;
+use aura_core::Coded;
 use from_variants::FromVariants;
 use i18n_embed_fl::fl;
 use log::error;
@@ -47,6 +48,24 @@ impl Localised for Error {
     }
 }
 
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::PathToAuraConfig(e) => e.code(),
+            Error::SerializeEnv(_) => "conf-serialize-env",
+            Error::CouldntOpen(_, _) => "conf-couldnt-open",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::PathToAuraConfig(e) => e.context(),
+            Error::CouldntOpen(p, _) => vec![("path", p.to_string())],
+            Error::SerializeEnv(_) => Vec::new(),
+        }
+    }
+}
+
 /// The raw contents of a runtime `Env`.
 pub(crate) fn general(env: &Env) {
     println!("{:#?}", env);
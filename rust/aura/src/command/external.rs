@@ -0,0 +1,127 @@
+//! Running third-party `aura-<name>` executables as if they were subcommands.
+
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use aura_core::Coded;
+use from_variants::FromVariants;
+use i18n_embed::{fluent::FluentLanguageLoader, LanguageLoader};
+use i18n_embed_fl::fl;
+use log::{debug, error};
+use simplelog::LevelFilter;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Command;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    #[from_variants(skip)]
+    NotFound(String),
+    #[from_variants(skip)]
+    ExternalCmd(String, std::io::Error),
+    #[from_variants(skip)]
+    Failed(String, i32),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Dirs(e) => e.nested(),
+            Error::NotFound(_) => {}
+            Error::ExternalCmd(_, e) => error!("{e}"),
+            Error::Failed(_, _) => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Dirs(e) => e.localise(fll),
+            Error::NotFound(c) => fl!(fll, "external-not-found", cmd = c.as_str()),
+            Error::ExternalCmd(c, _) => fl!(fll, "external-failed", cmd = c.as_str()),
+            Error::Failed(c, _) => fl!(fll, "external-failed", cmd = c.as_str()),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::NotFound(_) => "external-not-found",
+            Error::ExternalCmd(_, _) => "external-spawn",
+            Error::Failed(_, _) => "external-failed",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::NotFound(cmd) => vec![("command", cmd.to_string())],
+            Error::ExternalCmd(cmd, _) => vec![("command", cmd.to_string())],
+            Error::Failed(cmd, code) => {
+                vec![("command", cmd.to_string()), ("status", code.to_string())]
+            }
+        }
+    }
+}
+
+/// Hand off to an `aura-<name>` executable found on the `PATH`, like `git` and
+/// `cargo` do. The remaining arguments are passed along untouched.
+///
+/// Aura's own context is passed through the environment:
+///
+/// - `AURA_CONFIG`: the path to `aura.toml`, whether it exists or not.
+/// - `AURA_LANG`: the language Aura is speaking, like `ja-JP`.
+/// - `AURA_LOG_LEVEL`: the `--log-level`, if one was given.
+/// - `AURA_VERSION`: the version of the calling Aura.
+///
+/// Aura exits with the same status as the subcommand.
+pub(crate) fn run(
+    fll: &FluentLanguageLoader,
+    log_level: Option<LevelFilter>,
+    args: &[String],
+) -> Result<(), Error> {
+    let (name, rest) = match args.split_first() {
+        Some(pair) => pair,
+        None => return Ok(()),
+    };
+
+    let cmd = format!("aura-{}", name);
+    let path = which::which(&cmd).map_err(|_| Error::NotFound(name.clone()))?;
+    debug!("Running external subcommand: {}", path.utf8());
+
+    let mut command = Command::new(&path);
+    command
+        .args(rest)
+        .env("AURA_CONFIG", crate::dirs::aura_config()?)
+        .env("AURA_LANG", fll.current_language().to_string())
+        .env("AURA_VERSION", env!("CARGO_PKG_VERSION"));
+
+    if let Some(l) = log_level {
+        command.env("AURA_LOG_LEVEL", l.to_string().to_lowercase());
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| Error::ExternalCmd(cmd.clone(), e))?;
+
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(Error::Failed(cmd, code)),
+        // Killed by a signal, reported the way a shell would.
+        None => Err(Error::Failed(cmd, 128 + status.signal().unwrap_or(0))),
+    }
+}
+
+impl Error {
+    /// The status Aura should exit with, to be as transparent as the
+    /// subcommand itself.
+    pub(crate) fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::Failed(_, code) => u8::try_from(*code).ok(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! A single view of package information, wherever the package comes from.
+
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::yellow;
+use alpm::PackageReason;
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::{fluent::FluentLanguageLoader, LanguageLoader};
+use i18n_embed_fl::fl;
+use std::io::{BufWriter, Write};
+use time::OffsetDateTime;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Env(crate::env::Error),
+    NoneExist,
+    Stdout,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Env(e) => e.nested(),
+            Error::NoneExist => {}
+            Error::Stdout => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Env(e) => e.localise(fll),
+            Error::NoneExist => fl!(fll, "err-none-exist"),
+            Error::Stdout => fl!(fll, "err-write"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Env(e) => e.code(),
+            Error::NoneExist => "info-none-exist",
+            Error::Stdout => "info-stdout",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Env(e) => e.context(),
+            Error::NoneExist | Error::Stdout => Vec::new(),
+        }
+    }
+}
+
+/// Display everything known about some packages, merging what the local
+/// database, the sync databases, and the AUR have to say about each.
+pub(crate) fn info(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    packages: &[String],
+) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let db = alpm.localdb();
+    let syncs = alpm.syncdbs();
+
+    // Only packages not in the official repos are looked up on the AUR, and
+    // all together. Should the AUR be out of reach, what's known locally is
+    // still worth showing.
+    let foreign: Vec<&str> = packages
+        .iter()
+        .map(|p| p.as_str())
+        .filter(|p| syncs.pkg(*p).is_err())
+        .collect();
+    let aur: Vec<aura_core::faur::Package> = if foreign.is_empty() {
+        Vec::new()
+    } else {
+        aura_core::faur::info(foreign, &|u: &str| env.network.json(u)).unwrap_or_else(|e| {
+            e.nested();
+            yellow!(fll, "info-aur-failed");
+            Vec::new()
+        })
+    };
+    crate::command::aur::trend::record(aur.iter());
+
+    let mut w = BufWriter::new(std::io::stdout());
+
+    let repo = fl!(fll, "A-i-repo");
+    let name = fl!(fll, "common-name");
+    let ver = fl!(fll, "A-i-version");
+    let inst = fl!(fll, "info-installed");
+    let reason = fl!(fll, "info-reason");
+    let date = fl!(fll, "info-install-date");
+    let stat = fl!(fll, "A-i-status");
+    let fresh = fl!(fll, "A-i-up-to-date");
+    let stale = fl!(fll, "A-i-out-of-date");
+    let main = fl!(fll, "A-i-maintainer");
+    let proj = fl!(fll, "A-i-proj-url");
+    let lic = fl!(fll, "A-i-license");
+    let deps = fl!(fll, "A-i-depends");
+    let votes = fl!(fll, "A-i-votes");
+    let pop = fl!(fll, "A-i-pop");
+    let desc = fl!(fll, "A-i-desc");
+    let none = fl!(fll, "common-none");
+    let no = fl!(fll, "common-no");
+
+    let mut found = false;
+
+    for p in packages {
+        let local = db.pkg(p.as_str()).ok();
+        let sync = syncs.pkg(p.as_str()).ok();
+        let remote = aur.iter().find(|a| &a.name == p);
+
+        if local.is_none() && sync.is_none() && remote.is_none() {
+            yellow!(fll, "info-unknown", pkg = p.as_str());
+            continue;
+        }
+
+        found = true;
+
+        // What the package is "supposed" to be, according to its source.
+        let source = sync.or(local);
+        let latest: String = match (sync, remote, local) {
+            (Some(s), _, _) => s.version().to_string(),
+            (None, Some(a), _) => a.version.clone(),
+            (None, None, Some(l)) => l.version().to_string(),
+            (None, None, None) => String::new(),
+        };
+        let origin = match (sync.and_then(|s| s.db()), remote) {
+            (Some(d), _) => d.name().magenta(),
+            (None, Some(_)) => "aur".magenta(),
+            (None, None) => "local".truecolor(128, 128, 128),
+        };
+
+        let mut pairs: Vec<(&str, ColoredString)> = vec![
+            (&repo, origin),
+            (&name, p.bold()),
+            (&ver, latest.normal()),
+            (
+                &inst,
+                match local {
+                    None => no.red(),
+                    Some(l) if l.version().as_str() == latest => l.version().as_str().green(),
+                    Some(l) => l.version().as_str().yellow(),
+                },
+            ),
+        ];
+
+        if let Some(l) = local {
+            let r = match l.reason() {
+                PackageReason::Explicit => fl!(fll, "info-explicit"),
+                PackageReason::Depend => fl!(fll, "info-dependency"),
+            };
+            let d = l
+                .install_date()
+                .and_then(|d| OffsetDateTime::from_unix_timestamp(d).ok())
+                .map(|d| d.date().to_string())
+                .unwrap_or_default();
+
+            pairs.push((&reason, r.normal()));
+            pairs.push((&date, d.normal()));
+        }
+
+        if let Some(a) = remote {
+            pairs.push((
+                &stat,
+                match a.out_of_date {
+                    None => fresh.green(),
+                    Some(_) => stale.red(),
+                },
+            ));
+            pairs.push((
+                &main,
+                a.maintainer
+                    .as_deref()
+                    .map(|m| m.normal())
+                    .unwrap_or_else(|| none.red()),
+            ));
+            pairs.push((&votes, format!("{}", a.num_votes).yellow()));
+            pairs.push((&pop, format!("{:.2}", a.popularity).yellow()));
+        }
+
+        let (url, licenses, depends, description) = match (source, remote) {
+            (Some(s), _) => (
+                s.url().map(|u| u.to_string()),
+                s.licenses().iter().collect::<Vec<_>>().join(" "),
+                s.depends()
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                s.desc().map(|d| d.to_string()),
+            ),
+            (None, Some(a)) => (
+                a.url.clone(),
+                a.license.join(" "),
+                a.depends.join(" "),
+                a.description.clone(),
+            ),
+            (None, None) => (None, String::new(), String::new(), None),
+        };
+
+        pairs.push((&proj, url.map(|u| u.cyan()).unwrap_or_else(|| none.red())));
+        pairs.push((&lic, licenses.normal()));
+        pairs.push((&deps, depends.normal()));
+        pairs.push((
+            &desc,
+            description
+                .map(|d| d.normal())
+                .unwrap_or_else(|| none.red()),
+        ));
+
+        crate::utils::info(&mut w, fll.current_language(), &pairs).map_err(|_| Error::Stdout)?;
+        writeln!(w).map_err(|_| Error::Stdout)?;
+    }
+
+    if found {
+        Ok(())
+    } else {
+        Err(Error::NoneExist)
+    }
+}
@@ -0,0 +1,149 @@
+//! The languages Aura speaks, and how much of each has been translated.
+
+use crate::error::Nested;
+use crate::localization::{self, Localised};
+use crate::{aura, green};
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use std::collections::{BTreeSet, HashMap};
+use unic_langid::{langid, LanguageIdentifier};
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Load(i18n_embed::I18nEmbedError),
+    #[from_variants(skip)]
+    Unknown(String),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Load(e) => error!("{e}"),
+            Error::Unknown(_) => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Load(_) => fl!(fll, "lang-load"),
+            Error::Unknown(l) => fl!(fll, "lang-unknown", lang = l.as_str()),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Load(_) => "lang-load",
+            Error::Unknown(_) => "lang-unknown",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Load(_) => Vec::new(),
+            Error::Unknown(l) => vec![("lang", l.clone())],
+        }
+    }
+}
+
+/// Every available language, by how many of its messages have been translated.
+pub(crate) fn list() -> Result<(), Error> {
+    let stats: HashMap<LanguageIdentifier, (String, usize)> = localization::load_all()?
+        .into_iter()
+        .map(|(lang, fll)| {
+            let count = fll.with_message_iter(&lang, |iter| iter.count());
+            let name = fl!(fll, "language-name");
+            (lang, (name, count))
+        })
+        .collect();
+
+    let max = stats.get(&langid!("en-US")).map(|(_, c)| *c).unwrap_or(1);
+    let mut sorted: Vec<_> = stats.into_iter().collect();
+    sorted.sort_by_key(|(_, (_, count))| *count);
+    sorted.reverse();
+    let long = sorted
+        .iter()
+        .map(|(l, (n, _))| visual_len(l, n))
+        .max()
+        .unwrap_or(0);
+
+    // TODO Make this a proper table with generalized table code.
+    for (lang, (n, c)) in sorted {
+        let perc = 100.0 * c as f64 / max as f64;
+        let l = if perc < 50.0 {
+            format!("{}", lang).red()
+        } else if perc < 100.0 {
+            format!("{}", lang).yellow()
+        } else {
+            format!("{}", lang).green()
+        };
+        let pad = long - visual_len(&lang, &n);
+        println!(
+            "{} [{}]{:w$} {:03}/{} ({:.2}%)",
+            l,
+            n,
+            "",
+            c,
+            max,
+            perc,
+            w = pad
+        );
+    }
+
+    Ok(())
+}
+
+/// The messages of English that some language doesn't have yet, by name.
+pub(crate) fn missing(fll: &FluentLanguageLoader, raw: &str) -> Result<(), Error> {
+    let lang = raw
+        .parse::<LanguageIdentifier>()
+        .ok()
+        .and_then(|l| localization::resolve(&l))
+        .ok_or_else(|| Error::Unknown(raw.to_string()))?;
+
+    let all = localization::load_all()?;
+    let names = |l: &LanguageIdentifier| -> BTreeSet<String> {
+        all.get(l)
+            .map(|loader| {
+                loader.with_message_iter(l, |msgs| msgs.map(|m| m.id.name.to_string()).collect())
+            })
+            .unwrap_or_default()
+    };
+
+    let english = names(&langid!("en-US"));
+    let translated = names(&lang);
+    let missing: Vec<_> = english.difference(&translated).collect();
+    let shown = lang.to_string();
+
+    if missing.is_empty() {
+        green!(fll, "lang-complete", lang = shown);
+    } else {
+        aura!(
+            fll,
+            "lang-missing",
+            lang = shown,
+            count = missing.len(),
+            total = english.len()
+        );
+        for name in missing {
+            println!(" {}", name);
+        }
+    }
+
+    Ok(())
+}
+
+fn visual_len(lang: &LanguageIdentifier, msg: &str) -> usize {
+    let raw = msg.chars().count();
+    match lang.language.as_str() {
+        "ja" => raw * 2,
+        _ => raw,
+    }
+}
@@ -2,12 +2,15 @@
 
 #![allow(clippy::many_single_char_names)]
 
+use crate::aura;
 use crate::command::misc;
 use crate::error::Nested;
 use crate::localization::Localised;
 use crate::utils::ResultVoid;
 use aura::Date;
+use aura_core::Coded;
 use colored::*;
+use from_variants::FromVariants;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed::LanguageLoader;
 use i18n_embed_fl::fl;
@@ -17,19 +20,32 @@ use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::ops::Deref;
 use std::path::Path;
 use std::process::Command;
+use time::macros::format_description;
+use time::OffsetDateTime;
 
+#[derive(FromVariants)]
 pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    #[from_variants(skip)]
     Search(&'static str, std::io::Error),
+    #[from_variants(skip)]
     View(std::io::Error),
+    #[from_variants(skip)]
     Info(std::io::Error),
+    #[from_variants(skip)]
+    Audit(std::io::Error),
+    Cancelled,
 }
 
 impl Nested for Error {
     fn nested(&self) {
         match self {
+            Error::Dirs(e) => e.nested(),
             Error::Search(_, e) => error!("{e}"),
             Error::View(e) => error!("{e}"),
             Error::Info(e) => error!("{e}"),
+            Error::Audit(e) => error!("{e}"),
+            Error::Cancelled => {}
         }
     }
 }
@@ -37,9 +53,33 @@ impl Nested for Error {
 impl Localised for Error {
     fn localise(&self, fll: &FluentLanguageLoader) -> String {
         match self {
+            Error::Dirs(e) => e.localise(fll),
             Error::Search(s, _) => fl!(fll, "L-search-err", cmd = s.deref()),
             Error::View(_) => fl!(fll, "L-view-err"),
             Error::Info(_) => fl!(fll, "err-write"),
+            Error::Audit(_) => fl!(fll, "L-audit-err"),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::Search(_, _) => "log-search",
+            Error::View(_) => "log-view",
+            Error::Info(_) => "log-info",
+            Error::Audit(_) => "log-audit",
+            Error::Cancelled => "log-cancelled",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::Search(cmd, _) => vec![("command", cmd.to_string())],
+            Error::View(_) | Error::Info(_) | Error::Audit(_) | Error::Cancelled => Vec::new(),
         }
     }
 }
@@ -58,6 +98,8 @@ pub(crate) fn search(path: &Path, term: String) -> Result<(), Error> {
 
 /// Display install/upgrade history for the given packages.
 pub(crate) fn info(fll: &FluentLanguageLoader, path: &Path, pks: Vec<String>) -> Result<(), Error> {
+    let pks =
+        crate::utils::expand(fll, pks, aura_core::log::packages(path)).ok_or(Error::Cancelled)?;
     info_work(fll, path, pks).map_err(Error::Info)
 }
 
@@ -128,3 +170,65 @@ fn view_work(path: &Path, before: Option<Date>, after: Option<Date>) -> Result<(
 
     Ok(())
 }
+
+/// Output every elevated command Aura has run, oldest first.
+pub(crate) fn audit(fll: &FluentLanguageLoader) -> Result<(), Error> {
+    let path = crate::dirs::audit_log()?;
+
+    match File::open(&path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            aura!(fll, "L-audit-empty");
+            Ok(())
+        }
+        Err(e) => Err(Error::Audit(e)),
+        Ok(file) => audit_work(BufReader::new(file)).map_err(Error::Audit),
+    }
+}
+
+fn audit_work<R: BufRead>(file: R) -> Result<(), std::io::Error> {
+    let mut out = BufWriter::new(std::io::stdout());
+
+    for rline in file.lines() {
+        let line = rline?;
+
+        match serde_json::from_str::<crate::audit::Entry>(&line) {
+            Ok(e) => {
+                let status = match e.status {
+                    Some(0) => "0".green(),
+                    Some(c) => c.to_string().red(),
+                    None => "-".yellow(),
+                };
+                writeln!(out, "[{}] [{}] {}", e.time, status, e.argv.join(" "))?;
+            }
+            // Lines are never dropped, even if they can't be understood.
+            Err(_) => writeln!(out, "{}", line)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// When the last full system upgrade recorded in the ALPM log completed.
+pub(crate) fn last_upgrade(log: &Path) -> Option<OffsetDateTime> {
+    let file = BufReader::new(File::open(log).ok()?);
+    let format = format_description!(
+        "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour sign:mandatory][offset_minute]"
+    );
+
+    let mut upgrading = false;
+    let mut last = None;
+
+    for line in file.lines().filter_map(|l| l.ok()) {
+        if line.contains("[PACMAN] starting full system upgrade") {
+            upgrading = true;
+        } else if upgrading && line.contains("[ALPM] transaction completed") {
+            upgrading = false;
+            last = line
+                .get(1..25)
+                .and_then(|t| OffsetDateTime::parse(t, format).ok())
+                .or(last);
+        }
+    }
+
+    last
+}
@@ -0,0 +1,279 @@
+//! Carrying over state from other AUR helpers.
+
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::{aura, green, proceed, yellow};
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::{debug, error};
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use toml_edit::{Document, Item};
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    Env(crate::env::Error),
+    Provenance(crate::command::aur::provenance::Error),
+    #[from_variants(skip)]
+    FileOpen(PathBuf, std::io::Error),
+    #[from_variants(skip)]
+    FileWrite(PathBuf, std::io::Error),
+    #[from_variants(skip)]
+    ConfigParse(PathBuf),
+    #[from_variants(skip)]
+    NotFound(String),
+    Cancelled,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Dirs(e) => e.nested(),
+            Error::Env(e) => e.nested(),
+            Error::Provenance(e) => e.nested(),
+            Error::FileOpen(_, e) => error!("{e}"),
+            Error::FileWrite(_, e) => error!("{e}"),
+            Error::ConfigParse(_) => {}
+            Error::NotFound(_) => {}
+            Error::Cancelled => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Dirs(e) => e.localise(fll),
+            Error::Env(e) => e.localise(fll),
+            Error::Provenance(e) => e.localise(fll),
+            Error::FileOpen(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
+            Error::FileWrite(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+            Error::ConfigParse(p) => fl!(fll, "migrate-bad-config", file = p.utf8()),
+            Error::NotFound(h) => fl!(fll, "migrate-not-found", helper = h.as_str()),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::Env(e) => e.code(),
+            Error::Provenance(e) => e.code(),
+            Error::FileOpen(_, _) => "migrate-file-open",
+            Error::FileWrite(_, _) => "migrate-file-write",
+            Error::ConfigParse(_) => "migrate-config-parse",
+            Error::NotFound(_) => "migrate-not-found",
+            Error::Cancelled => "migrate-cancelled",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::Env(e) => e.context(),
+            Error::Provenance(e) => e.context(),
+            Error::FileOpen(p, _) => vec![("path", p.display().to_string())],
+            Error::FileWrite(p, _) => vec![("path", p.display().to_string())],
+            Error::ConfigParse(p) => vec![("path", p.display().to_string())],
+            Error::NotFound(helper) => vec![("helper", helper.to_string())],
+            Error::Cancelled => Vec::new(),
+        }
+    }
+}
+
+/// The settings of another helper that have an equivalent in Aura.
+#[derive(Debug, Default)]
+struct Imported {
+    /// Where the helper keeps its clones of AUR repos.
+    clones: Option<PathBuf>,
+    /// Does the helper always check VCS packages for updates?
+    devel: bool,
+    /// Does the helper remove make dependencies after building?
+    removemake: bool,
+}
+
+/// Import the settings and build history of `yay` or `paru`.
+pub(crate) fn migrate(fll: &FluentLanguageLoader, env: &Env, from: &str) -> Result<(), Error> {
+    let imported = match from {
+        "paru" => paru()?,
+        _ => yay()?,
+    };
+    debug!("Imported from {}: {:?}", from, imported);
+
+    // --- Settings --- //
+    // Edited in place, so that the user's comments and layout survive.
+    let path = crate::dirs::aura_config()?;
+    let mut doc: Document = std::fs::read_to_string(&path)
+        .unwrap_or_default()
+        .parse()
+        .map_err(|_| Error::ConfigParse(path.clone()))?;
+    let mut changes = 0;
+
+    if let Some(clones) = imported.clones.as_deref().filter(|c| c.is_dir()) {
+        changes += set(&mut doc, "clones", toml_edit::value(clones.utf8()));
+    }
+    if imported.devel {
+        changes += set(&mut doc, "git", toml_edit::value(true));
+    }
+    if imported.removemake {
+        changes += set(&mut doc, "delmakedeps", toml_edit::value(true));
+    }
+
+    if changes == 0 {
+        aura!(fll, "migrate-no-settings");
+    } else {
+        aura!(fll, "migrate-settings", file = path.utf8());
+        if let Some(aur) = doc.get("aur").and_then(|a| a.as_table_like()) {
+            for (k, v) in aur.iter() {
+                println!(" {} = {}", k.cyan(), v.to_string().trim());
+            }
+        }
+        proceed!(fll, "proceed", "migrate").ok_or(Error::Cancelled)?;
+
+        std::fs::write(&path, doc.to_string()).map_err(|e| Error::FileWrite(path.clone(), e))?;
+    }
+
+    // --- Build History --- //
+    let clones = match imported.clones {
+        Some(c) => c,
+        None => default_clones(from)?,
+    };
+    let alpm = env.alpm()?;
+    let built: Vec<(alpm::Package<'_>, PathBuf)> = alpm_utils::alpm::foreigns(&alpm)
+        .filter_map(|p| {
+            let clone = clones.join(p.base().unwrap_or_else(|| p.name()));
+            clone.join(".git").is_dir().then(|| (p, clone))
+        })
+        .collect();
+
+    if built.is_empty() {
+        yellow!(fll, "migrate-no-builds", dir = clones.utf8());
+    } else {
+        for (_, clone) in built.iter() {
+            seed_hash(&env.aur.hashes, clone);
+        }
+
+        let added = crate::command::aur::provenance::adopt(&built)?;
+        green!(fll, "migrate-builds", total = built.len(), added = added);
+    }
+
+    Ok(())
+}
+
+/// Set a value in the `[aur]` table, unless the user already chose one. Yields
+/// `1` if something was set, for counting.
+fn set(doc: &mut Document, key: &str, value: Item) -> usize {
+    let aur = doc.entry("aur").or_insert_with(toml_edit::table);
+
+    match aur.as_table_like_mut() {
+        Some(aur) if aur.contains_key(key).not() => {
+            aur.insert(key, value);
+            1
+        }
+        _ => 0,
+    }
+}
+
+/// Remember the commit each clone is at, so that the next `-A --diff` only
+/// shows what changed since the other helper's build.
+fn seed_hash(hashes: &Path, clone: &Path) {
+    if let Some((hash, base)) = aura_core::git::hash(clone).ok().zip(clone.file_name()) {
+        let path = hashes.join(base);
+
+        if path.exists().not() {
+            let _ = std::fs::write(path, hash);
+        }
+    }
+}
+
+fn default_clones(from: &str) -> Result<PathBuf, Error> {
+    let cache = crate::dirs::xdg_cache()?;
+
+    match from {
+        "paru" => Ok(cache.join("paru").join("clone")),
+        _ => Ok(cache.join("yay")),
+    }
+}
+
+/// The relevant contents of `yay`'s `config.json`.
+fn yay() -> Result<Imported, Error> {
+    let path = crate::dirs::xdg_config()?.join("yay").join("config.json");
+    let file = std::fs::File::open(&path).map_err(|_| Error::NotFound("yay".to_string()))?;
+    let json: serde_json::Value =
+        serde_json::from_reader(file).map_err(|_| Error::ConfigParse(path.clone()))?;
+
+    let imported = Imported {
+        clones: json["buildDir"].as_str().map(expand_home),
+        devel: json["devel"].as_bool().unwrap_or(false),
+        removemake: json["removemake"].as_str() == Some("yes"),
+    };
+
+    Ok(imported)
+}
+
+/// The relevant contents of the `[options]` section of `paru.conf`.
+fn paru() -> Result<Imported, Error> {
+    let path = crate::dirs::xdg_config()?.join("paru").join("paru.conf");
+    let conf = std::fs::read_to_string(&path).map_err(|_| Error::NotFound("paru".to_string()))?;
+    let mut imported = Imported::default();
+    let mut in_options = false;
+
+    for line in conf.lines().map(|l| l.trim()) {
+        if line.starts_with('[') {
+            in_options = line == "[options]";
+        } else if in_options {
+            match line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("CloneDir", v)) => imported.clones = Some(expand_home(v)),
+                None if line == "Devel" => imported.devel = true,
+                None if line == "RemoveMake" => imported.removemake = true,
+                _ => {}
+            }
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Both helpers allow a leading `~` in their paths.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn comments_kept() {
+        let raw = "# My settings.\n[aur]\n# Always.\ngit = false\n";
+        let mut doc: Document = raw.parse().unwrap();
+
+        assert_eq!(0, set(&mut doc, "git", toml_edit::value(true)));
+        assert_eq!(1, set(&mut doc, "delmakedeps", toml_edit::value(true)));
+
+        let written = doc.to_string();
+        assert!(written.starts_with(raw));
+        assert!(written.contains("delmakedeps = true"));
+    }
+
+    #[test]
+    fn new_table() {
+        let mut doc: Document = "# Nothing yet.\n".parse().unwrap();
+        assert_eq!(1, set(&mut doc, "git", toml_edit::value(true)));
+
+        let written = doc.to_string();
+        assert!(written.contains("# Nothing yet."));
+        assert!(written.contains("[aur]\ngit = true"));
+    }
+}
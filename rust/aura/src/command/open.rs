@@ -2,6 +2,7 @@
 
 use crate::error::Nested;
 use crate::localization::Localised;
+use aura_core::Coded;
 use i18n_embed_fl::fl;
 use log::error;
 use std::borrow::Cow;
@@ -32,6 +33,20 @@ impl Localised for Error {
     }
 }
 
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::CouldntOpen(_, _) => "open-couldnt-open",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::CouldntOpen(url, _) => vec![("url", url.to_string())],
+        }
+    }
+}
+
 /// Open the Aura Book.
 pub(crate) fn book() -> Result<(), Error> {
     open(BOOK_URL)
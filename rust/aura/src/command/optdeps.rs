@@ -0,0 +1,137 @@
+//! Inspect and manage optional dependencies.
+
+use crate::command::check::{BAD, GOOD};
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::NOTHING;
+use crate::{aura, green, proceed, yellow};
+use alpm::{Alpm, PackageReason};
+use aura::caps::Mutating;
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use std::ops::Not;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Pacman(crate::pacman::Error),
+    Cancelled,
+    NoneExist,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Pacman(e) => e.nested(),
+            Error::Cancelled => {}
+            Error::NoneExist => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Pacman(e) => e.localise(fll),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+            Error::NoneExist => fl!(fll, "err-none-exist"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Pacman(e) => e.code(),
+            Error::Cancelled => "optdeps-cancelled",
+            Error::NoneExist => "optdeps-none-exist",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Pacman(e) => e.context(),
+            Error::Cancelled | Error::NoneExist => Vec::new(),
+        }
+    }
+}
+
+/// Display the optional dependencies of the given packages, and whether or not
+/// they're currently installed.
+pub(crate) fn list(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    packages: Vec<String>,
+) -> Result<(), Error> {
+    let db = alpm.localdb();
+    let reals: Vec<_> = packages
+        .into_iter()
+        .filter_map(|p| db.pkg(p).ok())
+        .collect();
+
+    // Exit early if no real packages were given.
+    if reals.is_empty() {
+        return Err(Error::NoneExist);
+    }
+
+    for p in reals {
+        let opts = p.optdepends();
+
+        if opts.is_empty() {
+            yellow!(fll, "optdeps-none", pkg = p.name());
+            continue;
+        }
+
+        aura!(fll, "optdeps-of", pkg = p.name().cyan().bold().to_string());
+        let longest = opts
+            .iter()
+            .map(|d| d.name().chars().count())
+            .max()
+            .unwrap_or(0);
+
+        for d in opts.iter() {
+            let name = d.name();
+            let installed = db.pkg(name).is_ok() || db.pkgs().find_satisfier(name).is_some();
+            let symb = if installed { GOOD.green() } else { BAD.red() };
+            println!("  [{}] {:w$} : {}", symb, name, d.desc(), w = longest);
+        }
+    }
+
+    Ok(())
+}
+
+/// Display dependencies that no installed package strictly requires, and which
+/// are only being kept around as the optional dependency of something else.
+/// Offer to remove them.
+pub(crate) fn unused(m: &Mutating, fll: &FluentLanguageLoader, alpm: &Alpm) -> Result<(), Error> {
+    let mut unused: Vec<_> = alpm
+        .localdb()
+        .pkgs()
+        .into_iter()
+        .filter(|p| p.reason() == PackageReason::Depend)
+        .filter(|p| p.required_by().is_empty())
+        .filter(|p| p.optional_for().is_empty().not())
+        .collect();
+
+    if unused.is_empty() {
+        green!(fll, "optdeps-no-unused");
+    } else {
+        unused.sort_by(|a, b| a.name().cmp(b.name()));
+        let longest = unused.iter().map(|p| p.name().len()).max().unwrap_or(0);
+
+        aura!(fll, "optdeps-unused");
+        for p in unused.iter() {
+            let wanted_by = p.optional_for().into_iter().collect::<Vec<_>>().join(", ");
+            println!(" {:w$} ({})", p.name().cyan(), wanted_by, w = longest);
+        }
+
+        // Proceed with the removal if the user accepts.
+        proceed!(fll, "optdeps-unused-remove").ok_or(Error::Cancelled)?;
+        crate::pacman::sudo_pacman(m, "-R", NOTHING, unused.iter().map(|p| p.name()))?;
+        green!(fll, "common-done");
+    }
+
+    Ok(())
+}
@@ -5,12 +5,16 @@ use crate::localization::Localised;
 use crate::{green, proceed, yellow};
 use alpm::{Alpm, PackageReason, TransFlag};
 use alpm_utils::alpm as arch;
+use aura::caps::Mutating;
+use aura_core::listing::Listing;
+use aura_core::Coded;
 use colored::*;
 use from_variants::FromVariants;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
 use log::error;
 use std::collections::HashSet;
+use std::path::Path;
 use ubyte::ToByteUnit;
 
 #[derive(FromVariants)]
@@ -21,6 +25,7 @@ pub(crate) enum Error {
     Sudo(crate::utils::SudoError),
     #[from_variants(skip)]
     AlpmTx(alpm::Error),
+    Listing(aura_core::listing::Error),
     Cancelled,
     NoneExist,
 }
@@ -32,6 +37,7 @@ impl Nested for Error {
             Error::Readline(e) => error!("{e}"),
             Error::Sudo(e) => e.nested(),
             Error::AlpmTx(e) => error!("{e}"),
+            Error::Listing(e) => error!("{e}"),
             Error::Cancelled => {}
             Error::NoneExist => {}
         }
@@ -47,22 +53,110 @@ impl Localised for Error {
             Error::NoneExist => fl!(fll, "err-none-exist"),
             Error::SetExplicit(p, _) => fl!(fll, "O-explicit-err", pkg = p.as_str()),
             Error::AlpmTx(_) => fl!(fll, "alpm-tx"),
+            Error::Listing(e) => fl!(fll, "listing-parse", err = e.to_string()),
         }
     }
 }
 
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::SetExplicit(_, _) => "orphans-set-explicit",
+            Error::Readline(_) => "orphans-readline",
+            Error::Sudo(_) => "orphans-sudo",
+            Error::AlpmTx(_) => "orphans-alpm-tx",
+            Error::Listing(e) => e.code(),
+            Error::Cancelled => "orphans-cancelled",
+            Error::NoneExist => "orphans-none-exist",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::SetExplicit(pkg, _) => vec![("package", pkg.to_string())],
+            Error::Listing(e) => e.context(),
+            Error::Readline(_)
+            | Error::Sudo(_)
+            | Error::AlpmTx(_)
+            | Error::Cancelled
+            | Error::NoneExist => Vec::new(),
+        }
+    }
+}
+
+/// The fields of orphans that listings can sort and filter by.
+const FIELDS: &[&str] = &["name", "version", "size", "installed"];
+
 /// Print the name of each orphaned package.
-pub(crate) fn list(alpm: &Alpm) {
-    arch::orphans(alpm).for_each(|o| println!("{} {}", o.name(), o.version()))
+pub(crate) fn list(alpm: &Alpm, listing: &aura::flags::Listing) -> Result<(), Error> {
+    let listing = Listing::new(listing.sort.as_deref(), listing.filter.as_deref(), FIELDS)?;
+    let orphans: Vec<_> = arch::orphans(alpm).collect();
+
+    let rows = listing.apply(orphans, |o, f| match f {
+        "name" => o.name().to_string(),
+        "version" => o.version().as_str().to_string(),
+        "size" => o.isize().to_string(),
+        "installed" => o.install_date().unwrap_or(0).to_string(),
+        _ => String::new(),
+    });
+
+    rows.iter()
+        .for_each(|o| println!("{} {}", o.name(), o.version()));
+    Ok(())
+}
+
+/// Explain how each orphan came to be one, from the recorded reasons it was
+/// installed and the removals in the ALPM log.
+pub(crate) fn why(fll: &FluentLanguageLoader, alpm: &Alpm, log: &Path) {
+    let reasons = crate::reasons::load();
+    let removals = crate::reasons::removals(log);
+    let db = alpm.localdb();
+
+    for o in arch::orphans(alpm) {
+        let pkg = o.name().cyan().to_string();
+
+        match reasons.get(o.name()) {
+            None => println!("{}", fl!(fll, "O-why-unknown", pkg = pkg.as_str())),
+            Some(r) => {
+                let removed: Vec<&str> = r
+                    .parents
+                    .iter()
+                    .map(|p| p.as_str())
+                    .filter(|p| db.pkg(*p).is_err())
+                    .collect();
+
+                if removed.is_empty() {
+                    let parents = r.parents.join(", ");
+                    println!(
+                        "{}",
+                        fl!(fll, "O-why-dropped", pkg = pkg.as_str(), parents = parents)
+                    );
+                }
+
+                for p in removed {
+                    let date = removals.get(p).map(|d| d.as_str()).unwrap_or("?");
+                    let msg = fl!(
+                        fll,
+                        "O-why-removed",
+                        pkg = pkg.as_str(),
+                        parent = p,
+                        date = date
+                    );
+                    println!("{}", msg);
+                }
+            }
+        }
+    }
 }
 
 /// Sets a package's install reason to "as explicit". An alias for `-D --asexplicit`.
 pub(crate) fn adopt(
+    m: &Mutating,
     alpm: &Alpm,
     fll: &FluentLanguageLoader,
     packages: Vec<String>,
 ) -> Result<(), Error> {
-    crate::utils::sudo()?;
+    crate::utils::sudo(m)?;
 
     let db = alpm.localdb();
     let reals: Vec<_> = packages
@@ -88,8 +182,12 @@ pub(crate) fn adopt(
 ///
 /// Will fail if the process does not have permission to create the lockfile,
 /// which usually lives in a root-owned directory.
-pub(crate) fn remove(alpm: &mut Alpm, fll: &FluentLanguageLoader) -> Result<(), Error> {
-    crate::utils::sudo()?;
+pub(crate) fn remove(
+    m: &Mutating,
+    alpm: &mut Alpm,
+    fll: &FluentLanguageLoader,
+) -> Result<(), Error> {
+    crate::utils::sudo(m)?;
 
     // Check for orphans.
     let orphans: Vec<_> = arch::orphans(alpm).collect();
@@ -129,7 +227,7 @@ pub(crate) fn remove(alpm: &mut Alpm, fll: &FluentLanguageLoader) -> Result<(),
         println!("  {:w$} {:>9}\n", "Total", size, w = longest);
 
         // Proceed with the removal if the user accepts.
-        proceed!(fll, "proceed").ok_or(Error::Cancelled)?;
+        proceed!(fll, "proceed", "O-abandon", removal.len()).ok_or(Error::Cancelled)?;
 
         alpm.trans_commit().map_err(|(_, e)| Error::AlpmTx(e))?;
         alpm.trans_release().map_err(Error::AlpmTx)?;
@@ -0,0 +1,296 @@
+//! Holding packages back from upgrades.
+//!
+//! A package can be pinned at some version, past which it isn't upgraded, and
+//! a whole repo can be held, such that none of its packages are upgraded. Pins
+//! live in the `[pins]` table of `aura.toml`, so they can be edited by hand as
+//! well, and the rest of the file is left as it was written. `-Su` passes the
+//! packages they hold to `pacman` as `--ignore`, and `-Au` leaves them out of
+//! its plan.
+//!
+//! With `-Syu`, pins are judged against Aura's own freshly synced copies of the
+//! databases, so that `pacman`'s are only ever synced by the upgrade itself.
+
+use crate::env::{Env, Pins};
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::{aura, green, yellow};
+use alpm::Alpm;
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::{debug, error};
+use std::cmp::Ordering;
+use std::ops::Not;
+use std::path::PathBuf;
+use toml::value::Table;
+use toml_edit::{Array, Document, Item};
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    Env(crate::env::Error),
+    #[from_variants(skip)]
+    FileWrite(PathBuf, std::io::Error),
+    #[from_variants(skip)]
+    ConfigParse(PathBuf),
+    #[from_variants(skip)]
+    NotInstalled(String),
+    #[from_variants(skip)]
+    NotPinned(String),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Dirs(e) => e.nested(),
+            Error::Env(e) => e.nested(),
+            Error::FileWrite(_, e) => error!("{e}"),
+            Error::ConfigParse(_) => {}
+            Error::NotInstalled(_) => {}
+            Error::NotPinned(_) => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Dirs(e) => e.localise(fll),
+            Error::Env(e) => e.localise(fll),
+            Error::FileWrite(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+            Error::ConfigParse(p) => fl!(fll, "pin-bad-config", file = p.utf8()),
+            Error::NotInstalled(p) => fl!(fll, "pin-not-installed", pkg = p.as_str()),
+            Error::NotPinned(p) => fl!(fll, "pin-not-pinned", name = p.as_str()),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::Env(e) => e.code(),
+            Error::FileWrite(_, _) => "pin-file-write",
+            Error::ConfigParse(_) => "pin-config-parse",
+            Error::NotInstalled(_) => "pin-not-installed",
+            Error::NotPinned(_) => "pin-not-pinned",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::Env(e) => e.context(),
+            Error::FileWrite(p, _) => vec![("path", p.display().to_string())],
+            Error::ConfigParse(p) => vec![("path", p.display().to_string())],
+            Error::NotInstalled(pkg) => vec![("package", pkg.to_string())],
+            Error::NotPinned(name) => vec![("name", name.to_string())],
+        }
+    }
+}
+
+/// Display every pin.
+pub(crate) fn list(fll: &FluentLanguageLoader, env: &Env) {
+    if env.pins.is_empty() {
+        aura!(fll, "pin-none");
+        return;
+    }
+
+    let longest = env
+        .pins
+        .packages
+        .keys()
+        .chain(env.pins.repos.iter())
+        .map(|n| n.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for (name, max) in env.pins.packages.iter() {
+        println!(" {:w$} <= {}", name.cyan(), max, w = longest);
+    }
+
+    let held = fl!(fll, "pin-held-repo");
+    for repo in env.pins.repos.iter() {
+        println!(" {:w$} {}", repo.magenta(), held, w = longest);
+    }
+}
+
+/// Pin packages, each given as `name=version` or as only a name, which pins
+/// it at the version installed. Repos are held entirely.
+pub(crate) fn add(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    packages: &[String],
+    repos: &[String],
+) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let db = alpm.localdb();
+    let (path, mut doc, mut pins) = config()?;
+
+    for p in packages {
+        let (name, ver) = match p.split_once('=') {
+            Some((name, ver)) => (name.to_string(), ver.to_string()),
+            None => {
+                let installed = db
+                    .pkg(p.as_str())
+                    .map_err(|_| Error::NotInstalled(p.clone()))?;
+                (p.clone(), installed.version().to_string())
+            }
+        };
+
+        green!(
+            fll,
+            "pin-added",
+            pkg = name.as_str(),
+            version = ver.as_str()
+        );
+        pins.packages.insert(name, ver);
+    }
+
+    for r in repos {
+        green!(fll, "pin-added-repo", repo = r.as_str());
+        pins.repos.insert(r.clone());
+    }
+
+    write(path, &mut doc, &pins)
+}
+
+/// Remove the pins of the given packages or repos.
+pub(crate) fn remove(fll: &FluentLanguageLoader, names: &[String]) -> Result<(), Error> {
+    let (path, mut doc, mut pins) = config()?;
+
+    for name in names {
+        let pkg = pins.packages.remove(name).is_some();
+        let repo = pins.repos.remove(name);
+
+        if pkg.not() && repo.not() {
+            return Err(Error::NotPinned(name.clone()));
+        }
+
+        green!(fll, "pin-removed", name = name.as_str());
+    }
+
+    write(path, &mut doc, &pins)
+}
+
+/// The upgradable repo packages that pins hold back. The comparison is against
+/// the sync databases of the given handle, so they should be fresh.
+pub(crate) fn held(fll: &FluentLanguageLoader, env: &Env, alpm: &Alpm) -> Vec<String> {
+    if env.pins.is_empty() {
+        return Vec::new();
+    }
+
+    let held: Vec<String> = upgrades(alpm)
+        .into_iter()
+        .filter(|(p, repo, new)| env.is_pinned(p.name(), repo, new))
+        .map(|(p, _, _)| p.name().to_string())
+        .collect();
+    debug!("Held back by pins: {:?}", held);
+
+    if held.is_empty().not() {
+        yellow!(fll, "pin-holding", pkgs = held.join(", "));
+    }
+
+    held
+}
+
+/// Installed packages with a newer version in the sync databases, along with
+/// the repo and version on offer.
+pub(crate) fn upgrades(alpm: &Alpm) -> Vec<(alpm::Package<'_>, &str, String)> {
+    alpm.localdb()
+        .pkgs()
+        .iter()
+        .filter_map(|local| {
+            let (repo, new) = alpm.syncdbs().iter().find_map(|db| {
+                db.pkg(local.name())
+                    .ok()
+                    .map(|p| (db.name(), p.version().to_string()))
+            })?;
+            let newer =
+                aura_core::version::cmp(&new, local.version().as_str()) == Ordering::Greater;
+
+            newer.then(|| (local, repo, new))
+        })
+        .collect()
+}
+
+/// The given `pacman` arguments, told to ignore the given packages.
+pub(crate) fn ignoring(args: Vec<String>, held: &[String]) -> Vec<String> {
+    match held.is_empty() {
+        true => args,
+        false => args
+            .into_iter()
+            .chain(["--ignore".to_string(), held.join(",")])
+            .collect(),
+    }
+}
+
+/// The path to `aura.toml`, its contents, and the pins within them.
+fn config() -> Result<(PathBuf, Document, Pins), Error> {
+    let path = crate::dirs::aura_config()?;
+    let raw = std::fs::read_to_string(&path).unwrap_or_default();
+    let bad = || Error::ConfigParse(path.clone());
+    let doc: Document = raw.parse().map_err(|_| bad())?;
+    let table: Table = toml::from_str(&raw).map_err(|_| bad())?;
+    let pins = match table.get("pins").cloned() {
+        None => Pins::default(),
+        Some(v) => v.try_into().map_err(|_| bad())?,
+    };
+
+    Ok((path, doc, pins))
+}
+
+/// Write the given pins into `aura.toml`, touching only the entries that
+/// changed, such that the user's comments and layout survive.
+fn write(path: PathBuf, doc: &mut Document, pins: &Pins) -> Result<(), Error> {
+    let bad = || Error::ConfigParse(path.clone());
+    let table = doc
+        .entry("pins")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .ok_or_else(bad)?;
+
+    if pins.packages.is_empty().not() || table.contains_key("packages") {
+        let packages = table
+            .entry("packages")
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or_else(bad)?;
+        let stale: Vec<String> = packages
+            .iter()
+            .map(|(k, _)| k.to_string())
+            .filter(|k| pins.packages.contains_key(k).not())
+            .collect();
+
+        for k in stale {
+            packages.remove(&k);
+        }
+
+        for (name, max) in pins.packages.iter() {
+            if packages.get(name).and_then(|i| i.as_str()) != Some(max.as_str()) {
+                packages.insert(name, toml_edit::value(max.as_str()));
+            }
+        }
+    }
+
+    let repos = |i: &Item| -> Option<Vec<String>> {
+        i.as_array()
+            .map(|a| a.iter().filter_map(|v| v.as_str()).map(|s| s.to_string()))
+            .map(|r| r.collect())
+    };
+    let now: Vec<&String> = pins.repos.iter().collect();
+    let before = table.get("repos").and_then(repos);
+    let changed = before
+        .map(|b| b.iter().ne(now.iter().copied()))
+        .unwrap_or(true);
+
+    if changed && (pins.repos.is_empty().not() || table.contains_key("repos")) {
+        let array: Array = pins.repos.iter().map(|r| r.as_str()).collect();
+        table.insert("repos", toml_edit::value(array));
+    }
+
+    std::fs::write(&path, doc.to_string()).map_err(|e| Error::FileWrite(path, e))
+}
@@ -0,0 +1,144 @@
+//! Find which AUR packages provide a given file.
+
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::{aura, green, yellow};
+use alpm::Alpm;
+use aura_core::cache::PkgPath;
+use aura_core::files::FileIndex;
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    Env(crate::env::Error),
+    #[from_variants(skip)]
+    JsonWrite(PathBuf, serde_json::Error),
+    #[from_variants(skip)]
+    OpenFile(PathBuf, std::io::Error),
+    NoIndex,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Dirs(e) => e.nested(),
+            Error::Env(e) => e.nested(),
+            Error::JsonWrite(_, e) => error!("{e}"),
+            Error::OpenFile(_, e) => error!("{e}"),
+            Error::NoIndex => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Dirs(e) => e.localise(fll),
+            Error::Env(e) => e.localise(fll),
+            Error::JsonWrite(p, _) => fl!(fll, "err-json-write", file = p.utf8()),
+            Error::OpenFile(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
+            Error::NoIndex => fl!(fll, "provides-no-index"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::Env(e) => e.code(),
+            Error::JsonWrite(_, _) => "provides-json-write",
+            Error::OpenFile(_, _) => "provides-open-file",
+            Error::NoIndex => "provides-no-index",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::Env(e) => e.context(),
+            Error::JsonWrite(p, _) => vec![("path", p.display().to_string())],
+            Error::OpenFile(p, _) => vec![("path", p.display().to_string())],
+            Error::NoIndex => Vec::new(),
+        }
+    }
+}
+
+/// Display every indexed AUR package that provides the given file.
+pub(crate) fn search(fll: &FluentLanguageLoader, file: &str) -> Result<(), Error> {
+    let path = crate::dirs::file_index()?;
+    let index = FileIndex::from_path(&path).ok_or(Error::NoIndex)?;
+    let mut matches: Vec<_> = index.search(file).collect();
+
+    if matches.is_empty() {
+        yellow!(fll, "provides-none", file = file);
+    } else {
+        matches.sort_by(|(a, _), (b, _)| a.package.cmp(&b.package));
+
+        for (entry, path) in matches {
+            let repo = "aur/".magenta();
+            let pkg = entry.package.bold();
+            let ver = entry.version.green();
+            println!("{repo}{pkg} {ver} :: /{path}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Rebuild the file index from the newest version of each package in Aura's
+/// tarball cache. Tarballs that were already indexed aren't read again.
+pub(crate) fn refresh(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
+    aura!(fll, "provides-refresh");
+
+    let alpm = env.alpm()?;
+    let path = crate::dirs::file_index()?;
+    let mut index = FileIndex::from_path(&path).unwrap_or_default();
+    let tarballs = aura_core::cache::newest_package_paths(&[&env.aur.cache]);
+
+    // Forget about tarballs that have since been removed from the cache.
+    index
+        .tarballs
+        .retain(|file, _| env.aur.cache.join(file).is_file());
+
+    for pp in tarballs.iter().filter(|pp| !index.contains(pp)) {
+        if !index.insert(&alpm, pp) {
+            yellow!(fll, "provides-unreadable", file = pp.as_path().utf8());
+        }
+    }
+
+    write(&path, &index)?;
+    green!(fll, "provides-indexed", count = index.tarballs.len());
+
+    Ok(())
+}
+
+/// Add freshly built tarballs to an existing file index.
+pub(crate) fn update(alpm: &Alpm, tarballs: &[PathBuf]) -> Result<(), Error> {
+    let path = crate::dirs::file_index()?;
+    let mut index = FileIndex::from_path(&path).unwrap_or_default();
+
+    for pp in tarballs.iter().filter_map(|t| PkgPath::new(t.clone())) {
+        index.insert(alpm, &pp);
+    }
+
+    write(&path, &index)
+}
+
+fn write(path: &Path, index: &FileIndex) -> Result<(), Error> {
+    let file = File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| Error::OpenFile(path.to_path_buf(), e))?;
+    serde_json::to_writer(file, index).map_err(|e| Error::JsonWrite(path.to_path_buf(), e))
+}
@@ -0,0 +1,378 @@
+//! Compact reports about a machine, and an overview of many of them.
+//!
+//! `aura report --emit` prints a single line of JSON about the current machine,
+//! optionally clear-signed with `gpg`. Collected into one directory (by `scp`,
+//! a shared mount, or otherwise), `aura report --aggregate` merges them into a
+//! table. A signature is only good if it was made by one of the `--signer`
+//! keys, or, if none are given, by a key that `gpg` trusts.
+
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::{aura, yellow};
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::io::Write;
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Arch's security tracker: every advisory group still marked vulnerable.
+const ADVISORIES_URL: &str = "https://security.archlinux.org/issues/vulnerable/json";
+
+/// The version of the report format.
+const FORMAT: u32 = 1;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Env(crate::env::Error),
+    #[from_variants(skip)]
+    ReadDir(PathBuf, std::io::Error),
+    #[from_variants(skip)]
+    Sign(std::io::Error),
+    Gpg,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Env(e) => e.nested(),
+            Error::ReadDir(_, e) => error!("{e}"),
+            Error::Sign(e) => error!("{e}"),
+            Error::Gpg => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Env(e) => e.localise(fll),
+            Error::ReadDir(p, _) => fl!(fll, "err-read-dir", dir = p.utf8()),
+            Error::Sign(_) | Error::Gpg => fl!(fll, "report-sign"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Env(e) => e.code(),
+            Error::ReadDir(_, _) => "report-read-dir",
+            Error::Sign(_) => "report-sign",
+            Error::Gpg => "report-gpg",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Env(e) => e.context(),
+            Error::ReadDir(p, _) => vec![("path", p.display().to_string())],
+            Error::Sign(_) | Error::Gpg => Vec::new(),
+        }
+    }
+}
+
+/// A summary of one machine.
+#[derive(Serialize, Deserialize)]
+struct Report {
+    format: u32,
+    host: String,
+    time: String,
+    packages: usize,
+    foreign: usize,
+    orphans: usize,
+    repo_updates: usize,
+    /// Absent if the AUR couldn't be reached.
+    aur_updates: Option<usize>,
+    /// Installed packages with open advisories, like `openssl AVG-2781`.
+    /// Absent if the security tracker couldn't be reached.
+    advisories: Option<Vec<String>>,
+}
+
+/// An advisory group from the security tracker.
+#[derive(Deserialize)]
+struct Advisory {
+    name: String,
+    packages: Vec<String>,
+    fixed: Option<String>,
+}
+
+/// Print a report about this machine, signed if asked.
+pub(crate) fn emit(env: &Env, sign: bool) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let db = alpm.localdb();
+
+    let advisories = env
+        .network
+        .json::<Vec<Advisory>>(ADVISORIES_URL)
+        .map(|avs| {
+            avs.into_iter()
+                .flat_map(|av| {
+                    av.packages
+                        .iter()
+                        .filter_map(|p| db.pkg(p.as_str()).ok())
+                        .filter(|p| match av.fixed.as_deref() {
+                            None => true,
+                            Some(f) => {
+                                aura_core::version::cmp(p.version().as_str(), f) == Ordering::Less
+                            }
+                        })
+                        .map(|p| format!("{} {}", p.name(), av.name))
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        })
+        .map_err(|_| debug!("Couldn't reach {ADVISORIES_URL}"))
+        .ok();
+
+    let report = Report {
+        format: FORMAT,
+        host: crate::utils::hostname(),
+        time: OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_default(),
+        packages: db.pkgs().len(),
+        foreign: alpm_utils::alpm::foreigns(&alpm).count(),
+        orphans: alpm_utils::alpm::orphans(&alpm).count(),
+        repo_updates: crate::command::check::pending_repo(env, &alpm),
+        aur_updates: crate::command::check::pending_aur(env, &alpm).ok(),
+        advisories,
+    };
+
+    let json = serde_json::to_string(&report).unwrap_or_default();
+
+    if sign {
+        let mut child = Command::new("gpg")
+            .arg("--clearsign")
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(Error::Sign)?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(json.as_bytes()).map_err(Error::Sign)?;
+        }
+
+        child
+            .wait()
+            .map_err(Error::Sign)?
+            .success()
+            .then(|| ())
+            .ok_or(Error::Gpg)
+    } else {
+        println!("{json}");
+        Ok(())
+    }
+}
+
+/// Merge every report in a directory into one table.
+pub(crate) fn aggregate(
+    fll: &FluentLanguageLoader,
+    dir: &Path,
+    signers: &[String],
+) -> Result<(), Error> {
+    let mut reports: Vec<(Report, Option<bool>)> = std::fs::read_dir(dir)
+        .map_err(|e| Error::ReadDir(dir.to_path_buf(), e))?
+        .filter_map(|de| de.ok())
+        .map(|de| de.path())
+        .filter(|p| p.is_file())
+        .filter_map(|p| match read(&p, signers) {
+            Some(r) => Some(r),
+            None => {
+                yellow!(fll, "report-bad-file", file = p.utf8());
+                None
+            }
+        })
+        .collect();
+
+    if reports.is_empty() {
+        aura!(fll, "report-none");
+        return Ok(());
+    }
+
+    reports.sort_by(|(a, _), (b, _)| a.host.cmp(&b.host));
+
+    let unknown = || "?".to_string();
+    let header = [
+        fl!(fll, "report-host"),
+        fl!(fll, "report-packages"),
+        fl!(fll, "report-foreign"),
+        fl!(fll, "report-orphans"),
+        fl!(fll, "report-repo"),
+        fl!(fll, "report-aur"),
+        fl!(fll, "report-advisories"),
+        fl!(fll, "report-signed"),
+        fl!(fll, "report-time"),
+    ];
+    let rows: Vec<[String; 9]> = reports
+        .iter()
+        .map(|(r, signed)| {
+            [
+                r.host.clone(),
+                r.packages.to_string(),
+                r.foreign.to_string(),
+                r.orphans.to_string(),
+                r.repo_updates.to_string(),
+                r.aur_updates.map(|n| n.to_string()).unwrap_or_else(unknown),
+                r.advisories
+                    .as_ref()
+                    .map(|a| a.len().to_string())
+                    .unwrap_or_else(unknown),
+                match signed {
+                    Some(true) => crate::command::check::GOOD.to_string(),
+                    Some(false) => crate::command::check::BAD.to_string(),
+                    None => "-".to_string(),
+                },
+                r.time.clone(),
+            ]
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            std::iter::once(&header[i])
+                .chain(rows.iter().map(|r| &r[i]))
+                .map(|s| s.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(c, w)| format!("{:w$}", c, w = w))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    println!("{}", line(&header).bold());
+    for ((r, signed), row) in reports.iter().zip(rows.iter()) {
+        let text = line(row);
+        let bad = signed == &Some(false)
+            || r.advisories
+                .as_ref()
+                .map(|a| a.is_empty().not())
+                .unwrap_or(false);
+
+        if bad {
+            println!("{}", text.red());
+        } else {
+            println!("{}", text);
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a report, and whether its signature is good. `None` for the signature
+/// means it wasn't signed at all.
+fn read(path: &Path, signers: &[String]) -> Option<(Report, Option<bool>)> {
+    let raw = std::fs::read_to_string(path).ok()?;
+
+    if raw.starts_with("-----BEGIN PGP SIGNED MESSAGE-----") {
+        debug!("Verifying {}", path.display());
+        let out = Command::new("gpg")
+            .arg("--status-fd")
+            .arg("2")
+            .arg("--decrypt")
+            .arg(path)
+            .output()
+            .ok()?;
+        let report = serde_json::from_slice(&out.stdout).ok()?;
+        let status = String::from_utf8_lossy(&out.stderr);
+        let good = out.status.success() && trusted(&status, signers);
+
+        Some((report, Some(good)))
+    } else {
+        serde_json::from_str(raw.trim()).ok().map(|r| (r, None))
+    }
+}
+
+/// Was a signature, as `gpg --status-fd` described it, made by one of the given
+/// keys? Without any, the key must be one that `gpg` trusts instead.
+fn trusted(status: &str, signers: &[String]) -> bool {
+    let lines: Vec<&str> = status
+        .lines()
+        .filter_map(|l| l.strip_prefix("[GNUPG:] "))
+        .collect();
+
+    // Both the signing key's fingerprint and that of its primary key.
+    let fingerprints: Vec<&str> = lines
+        .iter()
+        .filter_map(|l| l.strip_prefix("VALIDSIG "))
+        .flat_map(|rest| {
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            [fields.first().copied(), fields.last().copied()]
+        })
+        .flatten()
+        .collect();
+
+    if fingerprints.is_empty() {
+        false
+    } else if signers.is_empty() {
+        lines
+            .iter()
+            .any(|l| l.starts_with("TRUST_FULLY") || l.starts_with("TRUST_ULTIMATE"))
+    } else {
+        signers.iter().any(|s| {
+            let s: String = s.split_whitespace().collect();
+            fingerprints.iter().any(|f| f.eq_ignore_ascii_case(&s))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FPR: &str = "ABCDEF0123456789ABCDEF0123456789ABCDEF01";
+    const PRIMARY: &str = "0123456789ABCDEF0123456789ABCDEF01234567";
+
+    fn status(trust: &str) -> String {
+        format!(
+            "gpg: Good signature\n\
+             [GNUPG:] GOODSIG 89ABCDEF01 host <root@host>\n\
+             [GNUPG:] VALIDSIG {FPR} 2026-10-14 1791936000 0 4 0 22 10 01 {PRIMARY}\n\
+             [GNUPG:] {trust} 0 pgp\n"
+        )
+    }
+
+    #[test]
+    fn named_signers() {
+        let s = status("TRUST_UNDEFINED");
+        assert!(trusted(&s, &[FPR.to_string()]));
+        assert!(trusted(&s, &[PRIMARY.to_lowercase()]));
+        assert!(trusted(
+            &s,
+            &["0123 4567 89AB CDEF 0123 4567 89AB CDEF 0123 4567".to_string()]
+        ));
+        assert!(trusted(
+            &s,
+            &["FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF".to_string()]
+        )
+        .not());
+    }
+
+    #[test]
+    fn trusted_keys() {
+        assert!(trusted(&status("TRUST_FULLY"), &[]));
+        assert!(trusted(&status("TRUST_ULTIMATE"), &[]));
+        assert!(trusted(&status("TRUST_UNDEFINED"), &[]).not());
+        assert!(trusted(
+            "[GNUPG:] BADSIG 89ABCDEF01 host\n[GNUPG:] TRUST_FULLY 0 pgp\n",
+            &[]
+        )
+        .not());
+    }
+}
@@ -0,0 +1,270 @@
+//! A single search across the official repos and the AUR.
+
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::yellow;
+use alpm::Alpm;
+use aura_core::template::Template;
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::{debug, error};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::ops::Not;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Env(crate::env::Error),
+    Fetch(crate::fetch::Error),
+    Template(aura_core::template::Error),
+    NoMatches,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Env(e) => e.nested(),
+            Error::Fetch(e) => e.nested(),
+            Error::Template(e) => error!("{e}"),
+            Error::NoMatches => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Env(e) => e.localise(fll),
+            Error::Fetch(e) => e.localise(fll),
+            Error::Template(e) => fl!(fll, "template-parse", err = e.to_string()),
+            Error::NoMatches => fl!(fll, "search-none"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Env(e) => e.code(),
+            Error::Fetch(e) => e.code(),
+            Error::Template(e) => e.code(),
+            Error::NoMatches => "search-no-matches",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Env(e) => e.context(),
+            Error::Fetch(e) => e.context(),
+            Error::Template(e) => e.context(),
+            Error::NoMatches => Vec::new(),
+        }
+    }
+}
+
+/// The fields of a match available to `--format`.
+const FIELDS: &[&str] = &[
+    "repo",
+    "name",
+    "version",
+    "description",
+    "votes",
+    "installed",
+];
+
+/// A package that matched, from either source.
+#[derive(Debug)]
+struct Hit {
+    repo: String,
+    name: String,
+    version: String,
+    description: String,
+    /// How closely the name matched, lower being closer.
+    score: u8,
+    votes: u64,
+    out_of_date: bool,
+}
+
+/// Search the sync databases and the AUR at the same time. A package in both
+/// is only shown from its repo.
+pub(crate) fn search(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    mut terms: Vec<String>,
+    limit: Option<usize>,
+    quiet: bool,
+    format: Option<&str>,
+) -> Result<(), Error> {
+    let format = format.map(|f| Template::parse(f, FIELDS)).transpose()?;
+    terms.sort_unstable_by_key(|t| t.len());
+    for t in terms.iter_mut() {
+        t.make_ascii_lowercase();
+    }
+
+    let (repos, aur) = rayon::join(
+        || env.alpm().map(|alpm| repo_hits(&alpm, &terms)),
+        || {
+            aura_core::faur::search(terms.iter().map(|t| t.as_str()), &|u: &str| {
+                env.network.json(u)
+            })
+        },
+    );
+    let repos = repos?;
+
+    // The repo results are still worth showing if the AUR can't be reached,
+    // but without any, the failure itself is the answer.
+    let aur = match aur {
+        Ok(aur) => aur,
+        Err(e) if repos.is_empty() => return Err(Error::Fetch(e)),
+        Err(e) => {
+            e.nested();
+            yellow!(fll, "search-aur-failed");
+            Vec::new()
+        }
+    };
+    debug!("Repo matches: {}, AUR matches: {}", repos.len(), aur.len());
+    crate::command::aur::trend::record(aur.iter());
+
+    let names: HashSet<&str> = repos.iter().map(|h| h.name.as_str()).collect();
+    let aur: Vec<Hit> = aur
+        .into_iter()
+        .filter(|p| names.contains(p.name.as_str()).not())
+        .map(|p| Hit {
+            repo: "aur".to_string(),
+            score: score(&p.name, &terms),
+            description: p.description.unwrap_or_default(),
+            name: p.name,
+            version: p.version,
+            votes: p.num_votes,
+            out_of_date: p.out_of_date.is_some(),
+        })
+        .collect();
+
+    let mut hits: Vec<Hit> = repos.into_iter().chain(aur).collect();
+    if hits.is_empty() {
+        return Err(Error::NoMatches);
+    }
+
+    // Repo packages are preferred over AUR ones that match as well, and
+    // popular AUR packages over obscure ones.
+    hits.sort_by(|a, b| {
+        (a.score, a.repo == "aur", Reverse(a.votes), &a.name).cmp(&(
+            b.score,
+            b.repo == "aur",
+            Reverse(b.votes),
+            &b.name,
+        ))
+    });
+
+    let alpm = env.alpm()?;
+    let db = alpm.localdb();
+    let installed = fl!(fll, "search-installed");
+
+    for h in hits.into_iter().take(limit.unwrap_or(usize::MAX)) {
+        if let Some(t) = format.as_ref() {
+            let installed = db.pkg(h.name.as_str()).is_ok();
+            println!("{}", t.render(|f| field(&h, installed, f)));
+            continue;
+        }
+
+        if quiet {
+            println!("{}", h.name);
+            continue;
+        }
+
+        let ver = if h.out_of_date {
+            h.version.red()
+        } else {
+            h.version.green()
+        };
+        let votes = if h.repo == "aur" {
+            format!(" ({})", h.votes).yellow()
+        } else {
+            "".normal()
+        };
+        let ins = match db.pkg(h.name.as_str()) {
+            Ok(_) => format!(" [{}]", installed).bold(),
+            Err(_) => "".normal(),
+        };
+
+        let label = format!("[{}]", h.repo).magenta();
+        println!("{} {} {}{}{}", label, h.name.bold(), ver, votes, ins);
+        println!("    {}", h.description);
+    }
+
+    Ok(())
+}
+
+/// The value of one of the [`FIELDS`] of a match.
+fn field(h: &Hit, installed: bool, field: &str) -> String {
+    match field {
+        "repo" => h.repo.clone(),
+        "name" => h.name.clone(),
+        "version" => h.version.clone(),
+        "description" => h.description.clone(),
+        "votes" => h.votes.to_string(),
+        "installed" => installed.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Packages in the sync databases whose name or description has every term.
+fn repo_hits(alpm: &Alpm, terms: &[String]) -> Vec<Hit> {
+    alpm.syncdbs()
+        .iter()
+        .flat_map(|db| {
+            db.pkgs()
+                .iter()
+                .filter(|p| {
+                    let name = p.name().to_lowercase();
+                    let desc = p.desc().unwrap_or_default().to_lowercase();
+                    terms.iter().all(|t| name.contains(t) || desc.contains(t))
+                })
+                .map(|p| Hit {
+                    repo: db.name().to_string(),
+                    name: p.name().to_string(),
+                    version: p.version().to_string(),
+                    description: p.desc().unwrap_or_default().to_string(),
+                    score: score(p.name(), terms),
+                    votes: 0,
+                    out_of_date: false,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// How well a package's name matches the terms: exactly, at its start,
+/// somewhere within it, or not at all (only in its description).
+fn score(name: &str, terms: &[String]) -> u8 {
+    let name = name.to_lowercase();
+
+    if terms.iter().any(|t| *t == name) {
+        0
+    } else if terms.iter().any(|t| name.starts_with(t.as_str())) {
+        1
+    } else if terms.iter().all(|t| name.contains(t.as_str())) {
+        2
+    } else {
+        3
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn name_scores() {
+        let terms = vec!["aura".to_string()];
+
+        assert_eq!(0, score("aura", &terms));
+        assert_eq!(1, score("aura-bin", &terms));
+        assert_eq!(2, score("python-aura", &terms));
+        assert_eq!(3, score("paru", &terms));
+    }
+}
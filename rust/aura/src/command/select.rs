@@ -0,0 +1,168 @@
+//! Selecting sets of packages with a small query language.
+
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use alpm::PackageReason;
+use aura_core::query::{Expr, Facts};
+use aura_core::template::Template;
+use aura_core::Coded;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::{debug, error};
+use std::collections::HashSet;
+use std::io::{BufWriter, Write};
+use time::OffsetDateTime;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Env(crate::env::Error),
+    Parse(aura_core::query::Error),
+    Template(aura_core::template::Error),
+    Json(serde_json::Error),
+    Stdout,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Env(e) => e.nested(),
+            Error::Parse(e) => error!("{e}"),
+            Error::Template(e) => error!("{e}"),
+            Error::Json(e) => error!("{e}"),
+            Error::Stdout => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Env(e) => e.localise(fll),
+            Error::Parse(e) => fl!(fll, "query-parse", err = e.to_string()),
+            Error::Template(e) => fl!(fll, "template-parse", err = e.to_string()),
+            Error::Json(_) => fl!(fll, "err-write"),
+            Error::Stdout => fl!(fll, "err-write"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Env(e) => e.code(),
+            Error::Parse(e) => e.code(),
+            Error::Template(e) => e.code(),
+            Error::Json(_) => "select-json",
+            Error::Stdout => "select-stdout",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Env(e) => e.context(),
+            Error::Parse(e) => e.context(),
+            Error::Template(e) => e.context(),
+            Error::Json(_) | Error::Stdout => Vec::new(),
+        }
+    }
+}
+
+/// The fields of a package available to `--format`.
+const FIELDS: &[&str] = &["name", "version", "repo", "installed", "size"];
+
+/// Print the names of all packages (installed or available) that satisfy an
+/// expression, full JSON records of them, or a line of each per a template.
+pub(crate) fn select(env: &Env, expr: &str, json: bool, format: Option<&str>) -> Result<(), Error> {
+    let expr = Expr::parse(expr)?;
+    let format = format.map(|f| Template::parse(f, FIELDS)).transpose()?;
+    debug!("{:?}", expr);
+
+    let alpm = env.alpm()?;
+    let db = alpm.localdb();
+    let syncs = alpm.syncdbs();
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let orphans: HashSet<&str> = alpm_utils::alpm::orphans(&alpm).map(|p| p.name()).collect();
+
+    let installed = db.pkgs().into_iter().map(|p| {
+        let repo = syncs
+            .iter()
+            .find(|s| s.pkg(p.name()).is_ok())
+            .map(|s| s.name());
+        let facts = Facts {
+            name: p.name(),
+            repo,
+            installed: true,
+            foreign: repo.is_none(),
+            orphan: orphans.contains(p.name()),
+            explicit: p.reason() == PackageReason::Explicit,
+            size: p.isize(),
+            date: p.install_date().unwrap_or(0),
+        };
+        (p, facts)
+    });
+
+    let mut seen: HashSet<&str> = HashSet::new();
+    let available = syncs
+        .iter()
+        .flat_map(|s| s.pkgs().into_iter().map(move |p| (s.name(), p)))
+        .filter(|(_, p)| db.pkg(p.name()).is_err())
+        .filter(|(_, p)| seen.insert(p.name()))
+        .map(|(repo, p)| {
+            let facts = Facts {
+                name: p.name(),
+                repo: Some(repo),
+                installed: false,
+                foreign: false,
+                orphan: false,
+                explicit: false,
+                size: p.isize(),
+                date: p.build_date(),
+            };
+            (p, facts)
+        });
+
+    let mut matched: Vec<_> = installed
+        .chain(available)
+        .filter(|(_, facts)| expr.eval(facts, now))
+        .collect();
+    matched.sort_by(|(a, _), (b, _)| a.name().cmp(b.name()));
+
+    let mut w = BufWriter::new(std::io::stdout());
+
+    if json {
+        let records: Vec<serde_json::Value> = matched
+            .iter()
+            .map(|(p, facts)| {
+                serde_json::json!({
+                    "name": p.name(),
+                    "version": p.version().as_str(),
+                    "repo": facts.repo,
+                    "installed": facts.installed,
+                    "size": facts.size,
+                })
+            })
+            .collect();
+        serde_json::to_writer_pretty(&mut w, &records)?;
+        writeln!(w).map_err(|_| Error::Stdout)?;
+    } else if let Some(t) = format {
+        for (p, facts) in matched {
+            let line = t.render(|f| match f {
+                "name" => p.name().to_string(),
+                "version" => p.version().as_str().to_string(),
+                "repo" => facts.repo.unwrap_or_default().to_string(),
+                "installed" => facts.installed.to_string(),
+                "size" => facts.size.to_string(),
+                _ => String::new(),
+            });
+            writeln!(w, "{line}").map_err(|_| Error::Stdout)?;
+        }
+    } else {
+        for (p, _) in matched {
+            writeln!(w, "{}", p.name()).map_err(|_| Error::Stdout)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,314 @@
+//! An interactive first-run configuration of Aura.
+
+use crate::env::{Env, Keep};
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::{a, aura, green, proceed, yellow};
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use rustyline::Editor;
+use std::ops::Not;
+use std::path::PathBuf;
+use toml_edit::{Document, Item};
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    Readline(rustyline::error::ReadlineError),
+    #[from_variants(skip)]
+    FileWrite(PathBuf, std::io::Error),
+    #[from_variants(skip)]
+    ConfigParse(PathBuf),
+    Cancelled,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Dirs(e) => e.nested(),
+            Error::Readline(e) => error!("{e}"),
+            Error::FileWrite(_, e) => error!("{e}"),
+            Error::ConfigParse(_) => {}
+            Error::Cancelled => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Dirs(e) => e.localise(fll),
+            Error::Readline(_) => fl!(fll, "err-user-input"),
+            Error::FileWrite(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+            Error::ConfigParse(p) => fl!(fll, "setup-bad-config", file = p.utf8()),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::Readline(_) => "setup-readline",
+            Error::FileWrite(_, _) => "setup-file-write",
+            Error::ConfigParse(_) => "setup-config-parse",
+            Error::Cancelled => "setup-cancelled",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::FileWrite(p, _) => vec![("path", p.display().to_string())],
+            Error::ConfigParse(p) => vec![("path", p.display().to_string())],
+            Error::Readline(_) | Error::Cancelled => Vec::new(),
+        }
+    }
+}
+
+/// Ask the user about the most important settings, and write them to a fresh,
+/// commented `aura.toml`. The current settings are offered as defaults. An
+/// existing `aura.toml` is edited in place instead, so that its comments and
+/// other settings survive.
+pub(crate) fn setup(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
+    let path = crate::dirs::aura_config()?;
+
+    let existing = if path.exists() {
+        let doc: Document = std::fs::read_to_string(&path)
+            .unwrap_or_default()
+            .parse()
+            .map_err(|_| Error::ConfigParse(path.clone()))?;
+        yellow!(fll, "setup-exists", file = path.utf8());
+        proceed!(fll, "proceed", "setup-update").ok_or(Error::Cancelled)?;
+        Some(doc)
+    } else {
+        None
+    };
+
+    aura!(fll, "setup-start");
+    let mut rl = Editor::<()>::new()?;
+
+    // --- Language --- //
+    let langs = crate::localization::available_languages();
+    aura!(fll, "setup-language");
+    for (i, l) in langs.iter().enumerate() {
+        println!(" {:>2}) {}", i, l);
+    }
+    let language = langs
+        .get(crate::utils::select(">>> ", langs.len().saturating_sub(1))?)
+        .map(|l| l.to_string())
+        .unwrap_or_default();
+
+    // --- Editing and Building --- //
+    let editor = ask(&mut rl, fl!(fll, "setup-editor"), &env.general.editor)?;
+    let build = ask(&mut rl, fl!(fll, "setup-build"), &env.aur.build.utf8())?;
+    let current = match env.aur.keep {
+        Keep::All => "all",
+        Keep::Sources => "sources",
+        Keep::None => "none",
+    };
+    let keep = loop {
+        let k = ask(&mut rl, fl!(fll, "setup-keep"), current)?;
+        if ["all", "sources", "none"].contains(&k.as_str()) {
+            break k;
+        }
+    };
+    let diff = ask_bool(&mut rl, fl!(fll, "setup-diff"), env.aur.diff)?;
+    let delmakedeps = ask_bool(&mut rl, fl!(fll, "setup-delmakedeps"), env.aur.delmakedeps)?;
+    let namcap = which::which("namcap").is_ok()
+        && ask_bool(&mut rl, fl!(fll, "setup-namcap"), env.aur.namcap)?;
+
+    // --- Snapshots --- //
+    let snapshots = ask(
+        &mut rl,
+        fl!(fll, "setup-snapshots"),
+        &env.backups.snapshots.utf8(),
+    )?;
+
+    let toml = match existing {
+        Some(mut doc) => {
+            put(&mut doc, "general", "language", toml_edit::value(language));
+            put(&mut doc, "general", "editor", toml_edit::value(editor));
+            put(&mut doc, "aur", "build", toml_edit::value(build));
+            put(&mut doc, "aur", "keep", toml_edit::value(keep));
+            put(&mut doc, "aur", "diff", toml_edit::value(diff));
+            put(
+                &mut doc,
+                "aur",
+                "delmakedeps",
+                toml_edit::value(delmakedeps),
+            );
+            put(&mut doc, "aur", "namcap", toml_edit::value(namcap));
+            put(
+                &mut doc,
+                "backups",
+                "snapshots",
+                toml_edit::value(snapshots),
+            );
+            doc.to_string()
+        }
+        None => fresh(
+            &language,
+            &editor,
+            &build,
+            &keep,
+            diff,
+            delmakedeps,
+            namcap,
+            &snapshots,
+        ),
+    };
+
+    if let Some(parent) = path.parent().filter(|p| p.is_dir().not()) {
+        std::fs::create_dir_all(parent).map_err(|e| Error::FileWrite(path.clone(), e))?;
+    }
+    std::fs::write(&path, toml).map_err(|e| Error::FileWrite(path.clone(), e))?;
+    green!(fll, "setup-written", file = path.utf8());
+
+    Ok(())
+}
+
+/// Set a key of some table, keeping any comments around an older value.
+fn put(doc: &mut Document, table: &str, key: &str, mut value: Item) {
+    let table = doc.entry(table).or_insert_with(toml_edit::table);
+
+    if let Some(table) = table.as_table_like_mut() {
+        match table.get_mut(key) {
+            Some(old) => {
+                if let (Some(o), Some(v)) = (old.as_value(), value.as_value_mut()) {
+                    *v.decor_mut() = o.decor().clone();
+                }
+                *old = value;
+            }
+            None => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+/// A fresh, commented `aura.toml`.
+#[allow(clippy::too_many_arguments)]
+fn fresh(
+    language: &str,
+    editor: &str,
+    build: &str,
+    keep: &str,
+    diff: bool,
+    delmakedeps: bool,
+    namcap: bool,
+    snapshots: &str,
+) -> String {
+    format!(
+        "# Aura's configuration, as generated by `aura setup`.
+# Run `aura conf --gen` to see every available setting.
+
+[general]
+# The language of Aura's output, unless overridden by a flag like `--english`.
+language = {}
+# The editor used to view and edit PKGBUILDs.
+editor = {}
+
+[aur]
+# Where AUR packages are built.
+build = {}
+# What remains of a build directory after a successful build:
+# \"all\", \"sources\" (delete only src/ and pkg/), or \"none\".
+keep = {}
+# Show the diffs of PKGBUILDs (etc.) since the last build, before building.
+diff = {}
+# Remove make dependencies after building.
+delmakedeps = {}
+# Lint PKGBUILDs and built packages with namcap.
+namcap = {}
+
+[backups]
+# Where package snapshots (`aura -B`) are saved.
+snapshots = {}
+",
+        string(language),
+        string(editor),
+        string(build),
+        string(keep),
+        diff,
+        delmakedeps,
+        namcap,
+        string(snapshots),
+    )
+}
+
+/// Ask for some value, falling back to a default if none is given.
+fn ask(rl: &mut Editor<()>, msg: String, default: &str) -> Result<String, Error> {
+    let line = rl.readline(&a!(format!("{} [{}] ", msg, default)))?;
+    let line = line.trim();
+
+    if line.is_empty() {
+        Ok(default.to_string())
+    } else {
+        Ok(line.to_string())
+    }
+}
+
+/// Ask a yes-or-no question.
+fn ask_bool(rl: &mut Editor<()>, msg: String, default: bool) -> Result<bool, Error> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    let line = rl.readline(&a!(format!("{} [{}] ", msg, hint)))?;
+
+    match line.trim() {
+        "" => Ok(default),
+        l => Ok(l.starts_with(['y', 'Y'])),
+    }
+}
+
+/// A properly escaped TOML string.
+fn string(s: &str) -> String {
+    toml::Value::String(s.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn answers_kept_in_place() {
+        let mut doc: Document =
+            "# Mine\n[aur]\n# Where builds go.\nbuild = \"/tmp\" # Fast.\ngit = true\n"
+                .parse()
+                .unwrap();
+
+        put(&mut doc, "aur", "build", toml_edit::value("/var/tmp"));
+        put(&mut doc, "aur", "diff", toml_edit::value(false));
+        put(&mut doc, "general", "editor", toml_edit::value("vim"));
+
+        let text = doc.to_string();
+        assert!(text.starts_with(
+            "# Mine\n[aur]\n# Where builds go.\nbuild = \"/var/tmp\" # Fast.\ngit = true\n"
+        ));
+        assert!(text.contains("diff = false"));
+        assert!(text.contains("[general]\neditor = \"vim\""));
+    }
+
+    #[test]
+    fn fresh_parses() {
+        let raw = fresh(
+            "en-US",
+            "vi",
+            "/tmp",
+            "all",
+            true,
+            false,
+            false,
+            "/var/cache/aura",
+        );
+        let doc: Document = raw.parse().unwrap();
+
+        assert_eq!(Some("vi"), doc["general"]["editor"].as_str());
+        assert_eq!(Some(true), doc["aur"]["diff"].as_bool());
+    }
+}
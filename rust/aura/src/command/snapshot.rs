@@ -1,24 +1,37 @@
 //! All functionality involving the `-B` command.
 
+use crate::env::{Backups, Network};
 use crate::error::Nested;
 use crate::localization::Localised;
 use crate::utils::{PathStr, NOTHING};
-use crate::{aura, green, proceed};
+use crate::{aura, green, proceed, yellow};
 use alpm::Alpm;
-use aura_core::snapshot::Snapshot;
+use aura::caps::Mutating;
+use aura_core::listing::Listing;
+use aura_core::snapshot::{Database, Snapshot};
+use aura_core::Coded;
 use colored::*;
 use from_variants::FromVariants;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
 use log::error;
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
 use std::fs::File;
 use std::io::BufWriter;
 use std::ops::Not;
 use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 use time::macros::format_description;
 
+mod remote;
+
+pub(crate) use remote::{pull, push};
+
+/// The Arch Linux Archive, which keeps every version of every official package.
+const ALA_URL: &str = "https://archive.archlinux.org/packages";
+
 #[derive(FromVariants)]
 pub(crate) enum Error {
     Dirs(crate::dirs::Error),
@@ -34,6 +47,23 @@ pub(crate) enum Error {
     TimeFormat(time::error::Format),
     Cancelled,
     NoSnapshots,
+    #[from_variants(skip)]
+    BadName(String),
+    #[from_variants(skip)]
+    NameTaken(String),
+    #[from_variants(skip)]
+    NoSuchName(String),
+    #[from_variants(skip)]
+    Unconfigured(&'static str),
+    #[from_variants(skip)]
+    MissingTool(&'static str),
+    #[from_variants(skip)]
+    Remote(String),
+    #[from_variants(skip)]
+    Mkdir(PathBuf, std::io::Error),
+    #[from_variants(skip)]
+    CopyFile(PathBuf, std::io::Error),
+    Listing(aura_core::listing::Error),
 }
 
 impl Nested for Error {
@@ -47,8 +77,17 @@ impl Nested for Error {
             Error::OpenFile(_, e) => error!("{e}"),
             Error::Cancelled => {}
             Error::NoSnapshots => {}
+            Error::BadName(_) => {}
+            Error::NameTaken(_) => {}
+            Error::NoSuchName(_) => {}
+            Error::Unconfigured(_) => {}
+            Error::MissingTool(_) => {}
+            Error::Remote(_) => {}
+            Error::Mkdir(_, e) => error!("{e}"),
+            Error::CopyFile(_, e) => error!("{e}"),
             Error::TimeLocal(e) => error!("{e}"),
             Error::TimeFormat(e) => error!("{e}"),
+            Error::Listing(e) => error!("{e}"),
         }
     }
 }
@@ -62,10 +101,69 @@ impl Localised for Error {
             Error::JsonWrite(p, _) => fl!(fll, "err-json-write", file = p.utf8()),
             Error::Cancelled => fl!(fll, "common-cancelled"),
             Error::NoSnapshots => fl!(fll, "B-none"),
+            Error::BadName(n) => fl!(fll, "B-bad-name", name = n.as_str()),
+            Error::NameTaken(n) => fl!(fll, "B-name-taken", name = n.as_str()),
+            Error::NoSuchName(n) => fl!(fll, "B-no-such-name", name = n.as_str()),
+            Error::Unconfigured(s) => fl!(fll, "B-unconfigured", setting = *s),
+            Error::MissingTool(t) => fl!(fll, "B-missing-tool", tool = *t),
+            Error::Remote(c) => fl!(fll, "B-remote-failed", command = c.as_str()),
+            Error::Mkdir(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+            Error::CopyFile(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
             Error::DeleteFile(p, _) => fl!(fll, "err-file-del", file = p.utf8()),
             Error::OpenFile(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
             Error::TimeLocal(_) => fl!(fll, "err-time-local"),
             Error::TimeFormat(_) => fl!(fll, "err-time-format"),
+            Error::Listing(e) => fl!(fll, "listing-parse", err = e.to_string()),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::Pacman(e) => e.code(),
+            Error::Readline(_) => "snapshot-readline",
+            Error::JsonWrite(_, _) => "snapshot-json-write",
+            Error::DeleteFile(_, _) => "snapshot-delete-file",
+            Error::OpenFile(_, _) => "snapshot-open-file",
+            Error::TimeLocal(_) => "snapshot-time-local",
+            Error::TimeFormat(_) => "snapshot-time-format",
+            Error::Cancelled => "snapshot-cancelled",
+            Error::NoSnapshots => "snapshot-no-snapshots",
+            Error::BadName(_) => "snapshot-bad-name",
+            Error::NameTaken(_) => "snapshot-name-taken",
+            Error::NoSuchName(_) => "snapshot-no-such-name",
+            Error::Unconfigured(_) => "snapshot-unconfigured",
+            Error::MissingTool(_) => "snapshot-missing-tool",
+            Error::Remote(_) => "snapshot-remote",
+            Error::Mkdir(_, _) => "snapshot-mkdir",
+            Error::CopyFile(_, _) => "snapshot-copy-file",
+            Error::Listing(e) => e.code(),
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::Pacman(e) => e.context(),
+            Error::Listing(e) => e.context(),
+            Error::JsonWrite(p, _) => vec![("path", p.display().to_string())],
+            Error::DeleteFile(p, _) => vec![("path", p.display().to_string())],
+            Error::OpenFile(p, _) => vec![("path", p.display().to_string())],
+            Error::BadName(n) => vec![("name", n.clone())],
+            Error::NameTaken(n) => vec![("name", n.clone())],
+            Error::NoSuchName(n) => vec![("name", n.clone())],
+            Error::Unconfigured(s) => vec![("setting", s.to_string())],
+            Error::MissingTool(t) => vec![("tool", t.to_string())],
+            Error::Remote(c) => vec![("command", c.clone())],
+            Error::Mkdir(p, _) => vec![("path", p.display().to_string())],
+            Error::CopyFile(p, _) => vec![("path", p.display().to_string())],
+            Error::Readline(_)
+            | Error::TimeLocal(_)
+            | Error::TimeFormat(_)
+            | Error::Cancelled
+            | Error::NoSnapshots => Vec::new(),
         }
     }
 }
@@ -81,17 +179,53 @@ struct StateDiff<'a> {
     to_remove: HashSet<&'a str>,
 }
 
-pub(crate) fn save(fll: &FluentLanguageLoader, alpm: &Alpm, snapshots: &Path) -> Result<(), Error> {
-    let snap = Snapshot::from_alpm(alpm)?;
+/// How the installed version of a package changed since a snapshot was taken.
+#[derive(Debug, PartialEq, Eq)]
+struct Change<'a> {
+    name: &'a str,
+    then: Option<&'a str>,
+    now: Option<&'a str>,
+}
+
+pub(crate) fn save(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    backups: &Backups,
+    name: Option<&str>,
+) -> Result<(), Error> {
+    let snapshots = backups.snapshots.as_path();
+
+    if let Some(n) = name {
+        if is_valid_name(n).not() {
+            return Err(Error::BadName(n.to_string()));
+        }
+
+        if named(snapshots, n).is_some() {
+            return Err(Error::NameTaken(n.to_string()));
+        }
+    }
+
+    let mut snap = Snapshot::from_alpm(alpm)?;
+    snap.name = name.map(|n| n.to_string());
+    snap.databases = databases(alpm);
+
     let form =
         format_description!("[year].[month]([month repr:short]).[day].[hour].[minute].[second]");
-    let name = format!("{}.json", snap.time.format(form)?);
-    let path = snapshots.join(name);
+    let time = snap.time.format(form)?;
+    let file = match name {
+        None => format!("{time}.json"),
+        Some(n) => format!("{time}-{n}.json"),
+    };
+    let path = snapshots.join(file);
 
     let file = BufWriter::new(File::create(&path).map_err(|e| Error::OpenFile(path.clone(), e))?);
-    serde_json::to_writer(file, &snap).map_err(|e| Error::JsonWrite(path, e))?;
+    serde_json::to_writer(file, &snap).map_err(|e| Error::JsonWrite(path.clone(), e))?;
     green!(fll, "B-saved");
 
+    if backups.remote.is_some() {
+        remote::upload(backups, &path)?;
+    }
+
     Ok(())
 }
 
@@ -114,26 +248,104 @@ pub(crate) fn clean(
     Ok(())
 }
 
-/// Show all saved package snapshot filenames.
-pub(crate) fn list(snapshots: &Path) -> Result<(), Error> {
-    for (path, _) in aura_core::snapshot::snapshots_with_paths(snapshots) {
-        println!("{}", path.display());
+/// The fields of snapshots that listings can sort and filter by.
+const FIELDS: &[&str] = &["path", "name", "time", "packages", "pinned"];
+
+/// Show all saved package snapshot filenames, along with the names of those
+/// that have one. The two are separated by a tab, for the sake of scripts.
+pub(crate) fn list(snapshots: &Path, listing: &aura::flags::Listing) -> Result<(), Error> {
+    let listing = Listing::new(listing.sort.as_deref(), listing.filter.as_deref(), FIELDS)?;
+    let shots: Vec<_> = aura_core::snapshot::snapshots_with_paths(snapshots).collect();
+    let shots = listing.apply(shots, |(path, snap), f| match f {
+        "path" => path.display().to_string(),
+        "name" => snap.name.clone().unwrap_or_default(),
+        "time" => snap.time.unix_timestamp().to_string(),
+        "packages" => snap.packages.len().to_string(),
+        "pinned" => snap.pinned.to_string(),
+        _ => String::new(),
+    });
+
+    for (path, snap) in shots {
+        match snap.name {
+            None => println!("{}", path.display()),
+            Some(n) => println!("{}\t{}", path.display(), n),
+        }
     }
 
     Ok(())
 }
 
 pub(crate) fn restore(
+    m: &Mutating,
     fll: &FluentLanguageLoader,
     alpm: &Alpm,
+    net: &Network,
     caches: &[&Path],
     snapshots: &Path,
+    name: Option<&str>,
 ) -> Result<(), Error> {
     let vers = aura_core::cache::all_versions(caches);
+    // Snapshots that recorded their databases know where their missing
+    // tarballs can still be found.
+    let shots = aura_core::snapshot::snapshots(snapshots)
+        .filter(|ss| ss.usable(&vers) || ss.databases.is_empty().not());
+    let snapshot = choose(fll, shots, name)?;
+
+    restore_snapshot(m, fll, alpm, net, caches, snapshot)?;
 
-    let mut shots: Vec<_> = aura_core::snapshot::snapshots(snapshots)
-        .filter(|ss| ss.usable(&vers))
+    green!(fll, "common-done");
+    Ok(())
+}
+
+/// Show how the installed packages have changed since a snapshot was taken.
+pub(crate) fn diff(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    snapshots: &Path,
+    name: Option<&str>,
+) -> Result<(), Error> {
+    let snapshot = choose(fll, aura_core::snapshot::snapshots(snapshots), name)?;
+    let installed: HashMap<&str, &str> = alpm
+        .localdb()
+        .pkgs()
+        .iter()
+        .map(|p| (p.name(), p.version().as_str()))
         .collect();
+
+    let changes = changes_since(&snapshot, &installed);
+    if changes.is_empty() {
+        green!(fll, "B-diff-none");
+        return Ok(());
+    }
+
+    for c in changes {
+        match (c.then, c.now) {
+            (None, Some(now)) => println!("{} {} {}", "+".green(), c.name.bold(), now),
+            (Some(then), None) => println!("{} {} {}", "-".red(), c.name.bold(), then),
+            (Some(then), Some(now)) => {
+                println!("{} {} {} -> {}", "~".yellow(), c.name.bold(), then, now)
+            }
+            (None, None) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Pick a snapshot by name, or from a menu if no name was given.
+fn choose<I>(fll: &FluentLanguageLoader, shots: I, name: Option<&str>) -> Result<Snapshot, Error>
+where
+    I: Iterator<Item = Snapshot>,
+{
+    let mut shots: Vec<_> = shots.collect();
+
+    if let Some(n) = name {
+        return shots
+            .into_iter()
+            .find(|ss| ss.name.as_deref() == Some(n))
+            .ok_or_else(|| Error::NoSuchName(n.to_string()));
+    }
+
     shots.sort_by_key(|ss| ss.time);
     let digits = 1 + (shots.len() / 10);
 
@@ -145,18 +357,163 @@ pub(crate) fn restore(
     for (i, ss) in shots.iter().enumerate() {
         let form = format_description!("[year]-[month]-[day] [hour]-[minute]-[second]");
         let time = ss.time.format(form)?;
+        let name = ss.name.as_deref().unwrap_or_default().bold();
         let pinned = ss.pinned.then(|| "[pinned]".cyan()).unwrap_or_default();
-        println!(" {:w$}) {} {}", i, time, pinned, w = digits);
+        println!(" {:w$}) {} {} {}", i, time, name, pinned, w = digits);
     }
 
     let index = crate::utils::select(">>> ", shots.len() - 1)?;
-    restore_snapshot(alpm, caches, shots.remove(index))?;
+    Ok(shots.remove(index))
+}
 
-    green!(fll, "common-done");
-    Ok(())
+/// The current state of every sync database.
+fn databases(alpm: &Alpm) -> BTreeMap<String, Database> {
+    let sync = Path::new(alpm.dbpath()).join("sync");
+    let paths: Vec<PathBuf> = alpm
+        .syncdbs()
+        .iter()
+        .map(|db| sync.join(format!("{}.db", db.name())))
+        .collect();
+    let hashes = crate::utils::sha256s(&paths);
+
+    alpm.syncdbs()
+        .iter()
+        .zip(paths)
+        .map(|(db, path)| {
+            let synced = path
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64);
+
+            let data = Database {
+                synced,
+                sha256: hashes.get(&path).cloned(),
+                servers: db.servers().iter().map(|s| s.to_string()).collect(),
+            };
+
+            (db.name().to_string(), data)
+        })
+        .collect()
+}
+
+/// The repos whose mirrors have changed since the given databases were
+/// recorded.
+fn moved_mirrors<'a>(alpm: &Alpm, then: &'a BTreeMap<String, Database>) -> Vec<&'a str> {
+    let now: HashMap<&str, Vec<&str>> = alpm
+        .syncdbs()
+        .iter()
+        .map(|db| (db.name(), db.servers().iter().collect()))
+        .collect();
+
+    then.iter()
+        .filter(|(repo, db)| {
+            now.get(repo.as_str())
+                .map(|ss| ss.iter().ne(db.servers.iter()))
+                .unwrap_or(true)
+        })
+        .map(|(repo, _)| repo.as_str())
+        .collect()
+}
+
+/// Where the current mirrors offer the tarball of some exact version of a
+/// package, if they still do.
+fn mirror_url(alpm: &Alpm, name: &str, ver: &str) -> Option<String> {
+    let pkg = alpm
+        .syncdbs()
+        .pkg(name)
+        .ok()
+        .filter(|p| p.version().as_str() == ver)?;
+    let server = pkg.db()?.servers().iter().next()?.to_string();
+
+    Some(format!("{}/{}", server, pkg.filename()))
+}
+
+/// Where the archive keeps the tarball of some version of a package, if it
+/// does. The architecture is assumed not to have changed since. Only packages
+/// of the official repos are archived, and older ones were compressed with
+/// `xz` rather than `zstd`.
+fn archive_url(net: &Network, alpm: &Alpm, name: &str, ver: &str) -> Option<String> {
+    alpm.syncdbs().pkg(name).ok()?;
+
+    let arch = alpm
+        .localdb()
+        .pkg(name)
+        .ok()
+        .or_else(|| alpm.syncdbs().pkg(name).ok())
+        .and_then(|p| p.arch().map(|a| a.to_string()))
+        .unwrap_or_else(|| std::env::consts::ARCH.to_string());
+    let first = name.chars().next().unwrap_or_default();
+
+    ["zst", "xz"]
+        .iter()
+        .map(|ext| format!("{ALA_URL}/{first}/{name}/{name}-{ver}-{arch}.pkg.tar.{ext}"))
+        .find(|url| net.exists(url))
+}
+
+/// The snapshot of the given name, if there is one.
+fn named(snapshots: &Path, name: &str) -> Option<Snapshot> {
+    aura_core::snapshot::snapshots(snapshots).find(|ss| ss.name.as_deref() == Some(name))
 }
 
-fn restore_snapshot(alpm: &Alpm, caches: &[&Path], snapshot: Snapshot) -> Result<(), Error> {
+/// Names become part of a filename, and are given on the command line, so only
+/// plain ones are allowed.
+fn is_valid_name(name: &str) -> bool {
+    name.is_empty().not()
+        && name.starts_with('-').not()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Every package whose version is different now than when the snapshot was
+/// taken, sorted by name.
+fn changes_since<'a>(
+    snapshot: &'a Snapshot,
+    installed: &'a HashMap<&'a str, &'a str>,
+) -> Vec<Change<'a>> {
+    let mut changes: Vec<Change<'a>> = snapshot
+        .packages
+        .iter()
+        .filter(|(name, _)| installed.contains_key(name.as_str()).not())
+        .map(|(name, ver)| Change {
+            name: name.as_str(),
+            then: Some(ver.as_str()),
+            now: None,
+        })
+        .collect();
+
+    for (name, ver) in installed.iter() {
+        match snapshot.packages.get(*name) {
+            None => changes.push(Change {
+                name: *name,
+                then: None,
+                now: Some(*ver),
+            }),
+            Some(v) if aura_core::version::cmp(v.as_str(), ver) != Ordering::Equal => {
+                changes.push(Change {
+                    name: *name,
+                    then: Some(v.as_str()),
+                    now: Some(*ver),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    changes.sort_by_key(|c| c.name);
+    changes
+}
+
+fn restore_snapshot(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    net: &Network,
+    caches: &[&Path],
+    snapshot: Snapshot,
+) -> Result<(), Error> {
     let installed: HashMap<&str, &str> = alpm
         .localdb()
         .pkgs()
@@ -165,25 +522,88 @@ fn restore_snapshot(alpm: &Alpm, caches: &[&Path], snapshot: Snapshot) -> Result
         .collect();
     let diff = package_diff(&snapshot, &installed);
 
+    let cached: Vec<_> = aura_core::cache::package_paths(caches)
+        .filter(|pp| {
+            let p = pp.as_package();
+            match diff.to_add_or_alter.get(p.name.as_ref()) {
+                Some(v) if p.same_version(v) => true,
+                Some(_) | None => false,
+            }
+        })
+        .collect();
+    let found: HashSet<String> = cached
+        .iter()
+        .map(|pp| pp.as_package().name.to_string())
+        .collect();
+
+    // Whatever isn't cached is downloaded, from the mirrors if they still carry
+    // the needed version, else from the archive. Anything else, like an AUR
+    // package, is left as it is.
+    let mut urls = Vec::new();
+    let mut archived = Vec::new();
+    let mut unavailable = Vec::new();
+    for (name, ver) in diff
+        .to_add_or_alter
+        .iter()
+        .filter(|(n, _)| found.contains(**n).not())
+    {
+        let full = format!("{name}-{ver}");
+
+        match mirror_url(alpm, name, ver) {
+            Some(url) => urls.push(url),
+            None => match archive_url(net, alpm, name, ver) {
+                Some(url) => {
+                    archived.push(full);
+                    urls.push(url);
+                }
+                None => unavailable.push(full),
+            },
+        }
+    }
+
+    let moved = moved_mirrors(alpm, &snapshot.databases);
+    if moved.is_empty().not() {
+        yellow!(fll, "B-restore-mirrors", repos = moved.join(", "));
+    }
+
+    if archived.is_empty().not() {
+        archived.sort_unstable();
+        yellow!(fll, "B-restore-archive", pkgs = archived.join(", "));
+    }
+
+    if unavailable.is_empty().not() {
+        unavailable.sort_unstable();
+        yellow!(fll, "B-restore-unavailable", pkgs = unavailable.join(", "));
+    }
+
+    let mut changing: Vec<&str> = diff
+        .to_add_or_alter
+        .iter()
+        .filter(|(n, v)| unavailable.contains(&format!("{n}-{v}")).not())
+        .map(|(n, _)| *n)
+        .chain(diff.to_remove.iter().copied())
+        .collect();
+    changing.sort_unstable();
+    aura!(fll, "B-restore-changes");
+    for p in changing.iter() {
+        println!(" {}", p.cyan());
+    }
+    proceed!(fll, "proceed", "B-restore", changing.len()).ok_or(Error::Cancelled)?;
+
     // Alter packages first to avoid potential breakage from the later removal
     // step.
-    if diff.to_add_or_alter.is_empty().not() {
-        let tarballs = aura_core::cache::package_paths(caches)
-            .filter(|pp| {
-                let p = pp.as_package();
-                match diff.to_add_or_alter.get(p.name.as_ref()) {
-                    Some(v) if p.same_version(v) => true,
-                    Some(_) | None => false,
-                }
-            })
-            .map(|pp| pp.into_pathbuf().into_os_string());
+    if cached.is_empty().not() || urls.is_empty().not() {
+        let tarballs = cached
+            .into_iter()
+            .map(|pp| pp.into_pathbuf().into_os_string())
+            .chain(urls.into_iter().map(OsString::from));
 
-        crate::pacman::sudo_pacman("-U", NOTHING, tarballs)?;
+        crate::pacman::sudo_pacman(m, "-U", NOTHING, tarballs)?;
     }
 
     // Remove packages that weren't installed within the chosen snapshot.
     if diff.to_remove.is_empty().not() {
-        crate::pacman::sudo_pacman("-R", NOTHING, diff.to_remove)?;
+        crate::pacman::sudo_pacman(m, "-R", NOTHING, diff.to_remove)?;
     }
 
     Ok(())
@@ -212,7 +632,7 @@ fn package_diff<'a>(
             None => {
                 to_remove.insert(name);
             }
-            Some(v) => match alpm::vercmp(v.as_str(), ver) {
+            Some(v) => match aura_core::version::cmp(v.as_str(), ver) {
                 // The installed version is the same as the snapshot; no action
                 // necessary.
                 Ordering::Equal => {}
@@ -229,3 +649,55 @@ fn package_diff<'a>(
         to_remove,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert!(is_valid_name("before-nvidia-550"));
+        assert!(is_valid_name("v1.2_ok"));
+        assert!(is_valid_name("").not());
+        assert!(is_valid_name("-x").not());
+        assert!(is_valid_name("a/b").not());
+        assert!(is_valid_name("a b").not());
+    }
+
+    #[test]
+    fn changes() {
+        let snapshot = Snapshot {
+            time: time::OffsetDateTime::UNIX_EPOCH,
+            pinned: false,
+            name: None,
+            databases: BTreeMap::new(),
+            packages: [("a", "1.0-1"), ("b", "2.0-1"), ("c", "3.0-1")]
+                .into_iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+        };
+        let installed: HashMap<&str, &str> = [("a", "1.0-1"), ("b", "2.1-1"), ("d", "4.0-1")]
+            .into_iter()
+            .collect();
+
+        let expected = vec![
+            Change {
+                name: "b",
+                then: Some("2.0-1"),
+                now: Some("2.1-1"),
+            },
+            Change {
+                name: "c",
+                then: Some("3.0-1"),
+                now: None,
+            },
+            Change {
+                name: "d",
+                then: None,
+                now: Some("4.0-1"),
+            },
+        ];
+
+        assert_eq!(expected, changes_since(&snapshot, &installed));
+    }
+}
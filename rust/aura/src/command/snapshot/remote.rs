@@ -0,0 +1,210 @@
+//! Keeping copies of snapshots off this machine.
+//!
+//! Snapshots are always written locally first, since that's where they're read
+//! from. With a `remote` set in the `[backups]` section of `aura.toml`, each is
+//! also copied there by `rclone`, which speaks to S3 and most other storage
+//! services. With `encryption` set as well, the copies are first encrypted to
+//! the `recipient`, by `age` or `gpg`, and decrypted again when pulled back.
+
+use super::Error;
+use crate::env::{Backups, Cipher};
+use crate::{aura, green};
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Copy every local snapshot to the remote.
+pub(crate) fn push(fll: &FluentLanguageLoader, backups: &Backups) -> Result<(), Error> {
+    let remote = backups
+        .remote
+        .as_deref()
+        .ok_or(Error::Unconfigured("remote"))?;
+    aura!(fll, "B-pushing", remote = remote);
+
+    let mut count = 0;
+    for (path, _) in aura_core::snapshot::snapshots_with_paths(&backups.snapshots) {
+        upload(backups, &path)?;
+        count += 1;
+    }
+
+    green!(fll, "B-pushed", count = count);
+    Ok(())
+}
+
+/// Fetch the snapshots on the remote that aren't kept locally, say after a
+/// reinstall.
+pub(crate) fn pull(fll: &FluentLanguageLoader, backups: &Backups) -> Result<(), Error> {
+    let remote = backups
+        .remote
+        .as_deref()
+        .ok_or(Error::Unconfigured("remote"))?;
+    aura!(fll, "B-pulling", remote = remote);
+
+    // Named for this process, so that two pulls never share their downloads.
+    let tmp = crate::dirs::scratch()?.join(format!("pull-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir(&tmp).map_err(|e| Error::Mkdir(tmp.clone(), e))?;
+    std::fs::create_dir_all(&backups.snapshots)
+        .map_err(|e| Error::Mkdir(backups.snapshots.clone(), e))?;
+
+    let result = rclone(&[OsStr::new("copy"), OsStr::new(remote), tmp.as_os_str()])
+        .and_then(|_| restore_all(backups, &tmp));
+    let _ = std::fs::remove_dir_all(&tmp);
+
+    let count = result?;
+    green!(fll, "B-pulled", count = count);
+    Ok(())
+}
+
+/// Move the fetched snapshots into place, yielding how many were new.
+fn restore_all(backups: &Backups, fetched: &Path) -> Result<usize, Error> {
+    let files: Vec<PathBuf> = fetched
+        .read_dir()
+        .map_err(|e| Error::OpenFile(fetched.to_path_buf(), e))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+
+    let mut count = 0;
+
+    for path in files {
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(n) => n,
+            None => continue,
+        };
+        let plain = match backups.encryption {
+            Some(c) => name.strip_suffix(extension(c)),
+            None => Some(name),
+        };
+        let target = match plain.filter(|p| p.ends_with(".json")) {
+            Some(p) => backups.snapshots.join(p),
+            None => continue,
+        };
+
+        if target.exists() {
+            continue;
+        }
+
+        debug!("Restoring {} from the remote", target.display());
+        match backups.encryption {
+            Some(c) => decrypt(backups, c, &path, &target)?,
+            None => {
+                std::fs::copy(&path, &target).map_err(|e| Error::CopyFile(target.clone(), e))?;
+            }
+        }
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Copy one snapshot to the remote, encrypting it first if need be.
+pub(super) fn upload(backups: &Backups, path: &Path) -> Result<(), Error> {
+    let remote = backups
+        .remote
+        .as_deref()
+        .ok_or(Error::Unconfigured("remote"))?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    match backups.encryption {
+        None => {
+            let dest = format!("{remote}/{name}");
+            rclone(&[OsStr::new("copyto"), path.as_os_str(), OsStr::new(&dest)])
+        }
+        Some(c) => {
+            let sealed = format!("{name}{}", extension(c));
+            let tmp = crate::dirs::scratch()?.join(&sealed);
+            let dest = format!("{remote}/{sealed}");
+
+            let result = encrypt(backups, c, path, &tmp)
+                .and_then(|_| rclone(&[OsStr::new("copyto"), tmp.as_os_str(), OsStr::new(&dest)]));
+            let _ = std::fs::remove_file(&tmp);
+            result
+        }
+    }
+}
+
+fn extension(cipher: Cipher) -> &'static str {
+    match cipher {
+        Cipher::Age => ".age",
+        Cipher::Gpg => ".gpg",
+    }
+}
+
+fn encrypt(backups: &Backups, cipher: Cipher, from: &Path, to: &Path) -> Result<(), Error> {
+    let recipient = backups
+        .recipient
+        .as_deref()
+        .ok_or(Error::Unconfigured("recipient"))?;
+
+    let mut cmd = match cipher {
+        Cipher::Age => tool("age")?,
+        Cipher::Gpg => {
+            let mut c = tool("gpg")?;
+            c.args(["--batch", "--yes", "--encrypt"]);
+            c
+        }
+    };
+
+    cmd.arg("--recipient")
+        .arg(recipient)
+        .arg("--output")
+        .arg(to)
+        .arg(from);
+    run(cmd)
+}
+
+fn decrypt(backups: &Backups, cipher: Cipher, from: &Path, to: &Path) -> Result<(), Error> {
+    let mut cmd = match cipher {
+        Cipher::Age => {
+            let identity = backups
+                .identity
+                .as_deref()
+                .ok_or(Error::Unconfigured("identity"))?;
+            let mut c = tool("age")?;
+            c.arg("--decrypt").arg("--identity").arg(identity);
+            c
+        }
+        Cipher::Gpg => {
+            let mut c = tool("gpg")?;
+            c.args(["--batch", "--yes", "--decrypt"]);
+            c
+        }
+    };
+
+    cmd.arg("--output").arg(to).arg(from);
+    run(cmd)
+}
+
+fn rclone(args: &[&OsStr]) -> Result<(), Error> {
+    let mut cmd = tool("rclone")?;
+    cmd.args(args);
+    run(cmd)
+}
+
+/// A command for some external program, if it's installed.
+fn tool(name: &'static str) -> Result<Command, Error> {
+    which::which(name)
+        .map(|_| Command::new(name))
+        .map_err(|_| Error::MissingTool(name))
+}
+
+fn run(mut cmd: Command) -> Result<(), Error> {
+    let line = format!(
+        "{} {}",
+        cmd.get_program().to_string_lossy(),
+        cmd.get_args()
+            .map(|a| a.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    debug!("Running: {line}");
+
+    match cmd.status() {
+        Ok(s) if s.success() => Ok(()),
+        _ => Err(Error::Remote(line)),
+    }
+}
@@ -1,26 +1,33 @@
 //! Statistics about the user's machine or about Aura itself.
 
+use crate::env::Env;
 use crate::error::Nested;
-use crate::localization::{self, Localised};
+use crate::localization::Localised;
+use crate::utils::PathStr;
 use alpm::Alpm;
+use aura_core::Coded;
 use colored::*;
 use from_variants::FromVariants;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
-use log::error;
-use std::collections::{HashMap, HashSet};
+use log::{debug, error};
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
 use ubyte::ToByteUnit;
-use unic_langid::{langid, LanguageIdentifier};
 
 #[derive(FromVariants)]
 pub(crate) enum Error {
-    LangLoad(i18n_embed::I18nEmbedError),
+    Env(crate::env::Error),
+    #[from_variants(skip)]
+    FileWrite(PathBuf, std::io::Error),
 }
 
 impl Nested for Error {
     fn nested(&self) {
         match self {
-            Error::LangLoad(e) => error!("{e}"),
+            Error::Env(e) => e.nested(),
+            Error::FileWrite(_, e) => error!("{e}"),
         }
     }
 }
@@ -28,63 +35,25 @@ impl Nested for Error {
 impl Localised for Error {
     fn localise(&self, fll: &FluentLanguageLoader) -> String {
         match self {
-            Error::LangLoad(_) => fl!(fll, "stats-local"),
+            Error::Env(e) => e.localise(fll),
+            Error::FileWrite(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
         }
     }
 }
 
-/// Raw contents of loaded localizations.
-pub(crate) fn localization() -> Result<(), Error> {
-    let stats: HashMap<LanguageIdentifier, (String, usize)> = localization::load_all()?
-        .into_iter()
-        .map(|(lang, fll)| {
-            let count = fll.with_message_iter(&lang, |iter| iter.count());
-            let name = fl!(fll, "language-name");
-            (lang, (name, count))
-        })
-        .collect();
-
-    let (_, max) = *stats.get(&langid!("en-US")).unwrap();
-    let mut sorted: Vec<_> = stats.into_iter().collect();
-    sorted.sort_by_key(|(_, (_, count))| *count);
-    sorted.reverse();
-    let long = sorted
-        .iter()
-        .map(|(_, (n, _))| n.chars().count())
-        .max()
-        .unwrap_or(0);
-
-    // TODO Make this a proper table with generalized table code.
-    for (lang, (n, c)) in sorted {
-        let perc = 100.0 * c as f64 / max as f64;
-        let l = if perc < 50.0 {
-            format!("{}", lang).red()
-        } else if perc < 100.0 {
-            format!("{}", lang).yellow()
-        } else {
-            format!("{}", lang).green()
-        };
-        let pad = long - visual_len(&lang, &n);
-        println!(
-            "{} [{}]{:w$} {:03}/{} ({:.2}%)",
-            l,
-            n,
-            "",
-            c,
-            max,
-            perc,
-            w = pad
-        );
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Env(e) => e.code(),
+            Error::FileWrite(_, _) => "stats-file-write",
+        }
     }
 
-    Ok(())
-}
-
-fn visual_len(lang: &LanguageIdentifier, msg: &str) -> usize {
-    let raw = msg.chars().count();
-    match lang.language.as_str() {
-        "ja" => raw * 2,
-        _ => raw,
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Env(e) => e.context(),
+            Error::FileWrite(p, _) => vec![("path", p.display().to_string())],
+        }
     }
 }
 
@@ -124,3 +93,73 @@ pub(crate) fn groups(alpm: &Alpm) {
         println!("{}", p);
     }
 }
+
+/// Metrics about the machine in Prometheus' text format, for monitoring many
+/// machines at once.
+///
+/// With a path, they're written there instead of to `stdout`, as expected by
+/// `node_exporter`'s textfile collector.
+pub(crate) fn prometheus(env: &Env, path: Option<&Path>) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let mut out = String::new();
+
+    let mut metric = |name: &str, help: &str, value: String| {
+        let _ = writeln!(out, "# HELP aura_{name} {help}");
+        let _ = writeln!(out, "# TYPE aura_{name} gauge");
+        let _ = writeln!(out, "aura_{name} {value}");
+    };
+
+    metric(
+        "pending_repo_updates",
+        "Repository packages with a newer version available.",
+        crate::command::check::pending_repo(env, &alpm).to_string(),
+    );
+
+    // Better no metric than a wrong one, if the AUR can't be reached.
+    match crate::command::check::pending_aur(env, &alpm) {
+        Ok(n) => {
+            crate::command::check::pending::update(|p| p.aur = n);
+            metric(
+                "pending_aur_updates",
+                "AUR packages with a newer version available.",
+                n.to_string(),
+            )
+        }
+        Err(_) => debug!("Couldn't count pending AUR updates."),
+    }
+
+    metric(
+        "orphans",
+        "Installed dependencies that nothing requires.",
+        alpm_utils::alpm::orphans(&alpm).count().to_string(),
+    );
+
+    if let Some(t) = crate::command::log::last_upgrade(env.alpm_log()) {
+        metric(
+            "last_upgrade_timestamp_seconds",
+            "When the last full system upgrade completed.",
+            t.unix_timestamp().to_string(),
+        );
+    }
+
+    let cache: u64 = env.caches().into_iter().map(crate::utils::dir_size).sum();
+    metric(
+        "cache_bytes",
+        "The total size of the package caches.",
+        cache.to_string(),
+    );
+
+    match path {
+        None => print!("{out}"),
+        Some(p) => {
+            // Written whole and then moved, so that a collector never reads a
+            // half-written file.
+            let tmp = p.with_extension("prom.tmp");
+            std::fs::write(&tmp, out)
+                .and_then(|_| std::fs::rename(&tmp, p))
+                .map_err(|e| Error::FileWrite(p.to_path_buf(), e))?;
+        }
+    }
+
+    Ok(())
+}
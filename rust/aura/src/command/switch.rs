@@ -0,0 +1,235 @@
+//! Moving from one variant of a package to another.
+//!
+//! Variants like `foo`, `foo-bin`, and `foo-git` usually conflict with and
+//! provide one another, so installing the new one has `pacman` offer to remove
+//! the old in the same transaction. Aura then makes sure the old one is gone,
+//! gives the new one the old one's install reason, and keeps the optional
+//! dependencies that only the old one wanted from being orphaned.
+//!
+//! `pacman` can't install one package and remove another in a single
+//! transaction, so when the variants don't conflict, the old one is removed
+//! in a second. Should that fail, the new one is removed again, so that a
+//! switch never leaves both variants, or neither, behind.
+
+use crate::command::aur::variants;
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::NOTHING;
+use crate::{aura, green, proceed, yellow};
+use alpm::PackageReason;
+use aura::caps::Mutating;
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use std::collections::HashSet;
+use std::ops::Not;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Env(crate::env::Error),
+    Pacman(crate::pacman::Error),
+    Aur(crate::command::aur::Error),
+    #[from_variants(skip)]
+    NotInstalled(String),
+    #[from_variants(skip)]
+    Same(String),
+    Cancelled,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Env(e) => e.nested(),
+            Error::Pacman(e) => e.nested(),
+            Error::Aur(e) => e.nested(),
+            Error::NotInstalled(_) => {}
+            Error::Same(_) => {}
+            Error::Cancelled => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Env(e) => e.localise(fll),
+            Error::Pacman(e) => e.localise(fll),
+            Error::Aur(e) => e.localise(fll),
+            Error::NotInstalled(p) => fl!(fll, "switch-not-installed", pkg = p.as_str()),
+            Error::Same(p) => fl!(fll, "switch-same", pkg = p.as_str()),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Env(e) => e.code(),
+            Error::Pacman(e) => e.code(),
+            Error::Aur(e) => e.code(),
+            Error::NotInstalled(_) => "switch-not-installed",
+            Error::Same(_) => "switch-same",
+            Error::Cancelled => "switch-cancelled",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Env(e) => e.context(),
+            Error::Pacman(e) => e.context(),
+            Error::Aur(e) => e.context(),
+            Error::NotInstalled(pkg) => vec![("package", pkg.to_string())],
+            Error::Same(pkg) => vec![("package", pkg.to_string())],
+            Error::Cancelled => Vec::new(),
+        }
+    }
+}
+
+/// The package to switch to. A variant may be named in full, or by its
+/// suffix alone, like `bin` or `git`. `source` names the original.
+fn target(from: &str, variant: &str) -> String {
+    let stem = variants::stem(from);
+    let suffix = format!("-{variant}");
+
+    if variant == "source" {
+        stem.to_string()
+    } else if variants::SUFFIXES.contains(&suffix.as_str()) {
+        format!("{stem}{suffix}")
+    } else {
+        variant.to_string()
+    }
+}
+
+/// Replace an installed package with another variant of it.
+pub(crate) fn switch(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    from: &str,
+    variant: &str,
+) -> Result<(), Error> {
+    let to = target(from, variant);
+    if to == from {
+        return Err(Error::Same(to));
+    }
+
+    // --- What to preserve --- //
+    let (reason, optdeps, in_repos) = {
+        let alpm = env.alpm()?;
+        let old = alpm
+            .localdb()
+            .pkg(from)
+            .map_err(|_| Error::NotInstalled(from.to_string()))?;
+        let optdeps: Vec<String> = old
+            .optdepends()
+            .iter()
+            .map(|d| d.name().to_string())
+            .collect();
+        let in_repos = alpm.syncdbs().pkg(to.as_str()).is_ok();
+
+        (old.reason(), optdeps, in_repos)
+    };
+    debug!("Switching {from} to {to}, from the repos: {in_repos}");
+
+    let source = if in_repos {
+        "repo".normal()
+    } else {
+        "aur".magenta()
+    };
+    aura!(
+        fll,
+        "switch-plan",
+        from = from.cyan().to_string(),
+        to = to.cyan().to_string()
+    );
+    println!("  {} {}", to.bold(), source);
+    proceed!(fll, "proceed", "switch").ok_or(Error::Cancelled)?;
+
+    // --- Install the new variant --- //
+    if in_repos {
+        crate::pacman::sudo_pacman(m, "-S", NOTHING, [&to])?;
+    } else {
+        crate::command::aur::install(m, fll, env, [to.as_str()])?;
+    }
+
+    // A fresh handle sees the result of the transaction. An AUR build that
+    // failed leaves the old variant where it is.
+    if env.alpm()?.localdb().pkg(to.as_str()).is_err() {
+        return Err(Error::NotInstalled(to));
+    }
+
+    // --- Remove the old one, if its conflicts didn't already --- //
+    if env.alpm()?.localdb().pkg(from).is_ok() {
+        yellow!(fll, "switch-remove", pkg = from);
+
+        if let Err(e) = crate::pacman::sudo_pacman(m, "-R", NOTHING, [from]) {
+            yellow!(fll, "switch-rollback", from = from, to = to.as_str());
+            if crate::pacman::sudo_pacman(m, "-R", NOTHING, [&to]).is_err() {
+                debug!("Removing {to} again failed too.");
+            }
+            return Err(e.into());
+        }
+    }
+
+    // And another, the result of the removal, which may have freed more.
+    let alpm = env.alpm()?;
+    let db = alpm.localdb();
+    let new = db
+        .pkg(to.as_str())
+        .map_err(|_| Error::NotInstalled(to.clone()))?;
+
+    // --- Restore the install reason --- //
+    if new.reason() != reason {
+        let flag = match reason {
+            PackageReason::Explicit => "--asexplicit",
+            PackageReason::Depend => "--asdeps",
+        };
+        crate::pacman::sudo_pacman(m, "-D", [flag], [&to])?;
+    }
+
+    // --- Keep what only the old variant wanted --- //
+    let wanted: HashSet<String> = new
+        .optdepends()
+        .iter()
+        .map(|d| d.name().to_string())
+        .collect();
+    let stranded: Vec<String> = optdeps
+        .into_iter()
+        .filter(|d| wanted.contains(d).not())
+        .filter(|d| {
+            db.pkg(d.as_str())
+                .map(|p| {
+                    p.reason() == PackageReason::Depend
+                        && p.required_by().is_empty()
+                        && p.optional_for().is_empty()
+                })
+                .unwrap_or(false)
+        })
+        .collect();
+
+    if stranded.is_empty().not() {
+        yellow!(fll, "switch-optdeps", pkgs = stranded.join(", "));
+        crate::pacman::sudo_pacman(m, "-D", ["--asexplicit"], &stranded)?;
+    }
+
+    green!(fll, "switch-done", from = from, to = to.as_str());
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn targets() {
+        assert_eq!("foo-bin", target("foo", "bin"));
+        assert_eq!("foo-git", target("foo-bin", "git"));
+        assert_eq!("foo", target("foo-git", "source"));
+        assert_eq!("bar", target("foo", "bar"));
+    }
+}
@@ -0,0 +1,431 @@
+//! Extra care around `-U`, which installs tarballs from outside the repos.
+//!
+//! Before `pacman` is called, local tarballs are checked against any checksum
+//! file beside them, like `foo.pkg.tar.zst.sha256` or a `SHA256SUMS`, and
+//! unsigned ones are pointed out. Signatures themselves are left to `pacman`.
+//! Afterwards, each install is recorded in Aura's provenance database, and
+//! tarballs from outside the cache can be copied into it, so that later
+//! downgrades to them are possible.
+//!
+//! If the upgrade fails because it would overwrite files already on the
+//! filesystem, the conflicts are listed, and the user may choose to overwrite
+//! them. They're found from the tarballs themselves, rather than from what
+//! `pacman` printed, so that this works in any language.
+
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::{aura, green, yellow};
+use aura::caps::Mutating;
+use aura_core::cache::PkgPath;
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::{debug, error};
+use std::collections::{HashMap, HashSet};
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Provenance(crate::command::aur::provenance::Error),
+    Pacman(crate::pacman::Error),
+    Readline(rustyline::error::ReadlineError),
+    Cancelled,
+    #[from_variants(skip)]
+    Checksum(PathBuf),
+    #[from_variants(skip)]
+    Copy(PathBuf, std::io::Error),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Provenance(e) => e.nested(),
+            Error::Pacman(e) => e.nested(),
+            Error::Readline(e) => error!("{e}"),
+            Error::Cancelled => {}
+            Error::Checksum(_) => {}
+            Error::Copy(_, e) => error!("{e}"),
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Provenance(e) => e.localise(fll),
+            Error::Pacman(e) => e.localise(fll),
+            Error::Readline(_) => fl!(fll, "err-user-input"),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+            Error::Checksum(p) => fl!(fll, "U-checksum", file = p.utf8()),
+            Error::Copy(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Provenance(e) => e.code(),
+            Error::Pacman(e) => e.code(),
+            Error::Readline(_) => "upgrade-readline",
+            Error::Cancelled => "upgrade-cancelled",
+            Error::Checksum(_) => "upgrade-checksum",
+            Error::Copy(_, _) => "upgrade-copy",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Provenance(e) => e.context(),
+            Error::Pacman(e) => e.context(),
+            Error::Readline(_) | Error::Cancelled => Vec::new(),
+            Error::Checksum(p) => vec![("path", p.display().to_string())],
+            Error::Copy(p, _) => vec![("path", p.display().to_string())],
+        }
+    }
+}
+
+/// A file that a transaction would have written over.
+struct Conflict {
+    /// The package that contains the file.
+    pkg: String,
+    file: PathBuf,
+    /// The installed package that already owns the file, if any.
+    owner: Option<String>,
+}
+
+/// Something given to `-U`.
+pub(crate) enum Target {
+    /// A tarball on this machine.
+    Local(PkgPath<'static>),
+    /// A tarball to be downloaded by `pacman`, which caches it itself.
+    Remote(String, PkgPath<'static>),
+}
+
+/// Check the given `-U` targets before they're installed.
+pub(crate) fn prepare(
+    fll: &FluentLanguageLoader,
+    targets: &[String],
+) -> Result<Vec<Target>, Error> {
+    let mut checked = Vec::new();
+
+    for t in targets {
+        if ["https://", "http://", "ftp://"]
+            .iter()
+            .any(|s| t.starts_with(s))
+        {
+            let file = t.rsplit('/').next().unwrap_or(t);
+            if let Some(pp) = PkgPath::new(PathBuf::from(file)) {
+                checked.push(Target::Remote(t.clone(), pp));
+            }
+        } else if let Some(pp) = PkgPath::new(PathBuf::from(t)) {
+            let path = pp.as_path();
+
+            match expected_sha256(path) {
+                None => debug!("No checksum found for {}", path.display()),
+                Some(expected) => {
+                    if sha256(path).as_deref() != Some(expected.as_str()) {
+                        return Err(Error::Checksum(path.to_path_buf()));
+                    }
+                    green!(fll, "U-checksum-ok", file = path.utf8());
+                }
+            }
+
+            if pp.sig_file().is_file().not() {
+                yellow!(fll, "U-unsigned", file = path.utf8());
+            }
+
+            checked.push(Target::Local(pp));
+        }
+    }
+
+    Ok(checked)
+}
+
+/// Run `pacman -U` with the given arguments. Should the upgrade conflict with
+/// files already present, the user is asked how to proceed.
+pub(crate) fn install(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    mut args: Vec<String>,
+) -> Result<(), Error> {
+    let mut overwritten: HashSet<PathBuf> = HashSet::new();
+
+    loop {
+        crate::pacman::ensure_unlocked(m, fll, &env.pacman.db_path)?;
+
+        let e = match crate::pacman::pacman_upgrade(m, fll, env.alpm_log(), &args) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        // Only conflicts not already overwritten could explain the failure.
+        let cs: Vec<Conflict> = conflicts(env, &args)
+            .into_iter()
+            .filter(|c| overwritten.contains(&c.file).not())
+            .collect();
+        if cs.is_empty() {
+            return Err(Error::Pacman(e));
+        }
+
+        resolve(fll, &cs)?;
+
+        // Patterns are matched as globs, so the paths are escaped.
+        for c in cs {
+            args.push("--overwrite".to_string());
+            args.push(escape(&c.file.to_string_lossy()));
+            overwritten.insert(c.file);
+        }
+    }
+}
+
+/// Files that the local tarballs among these arguments would write over, and
+/// who owns them now.
+fn conflicts(env: &Env, args: &[String]) -> Vec<Conflict> {
+    let alpm = match env.alpm() {
+        Ok(a) => a,
+        Err(_) => return Vec::new(),
+    };
+
+    let found: Vec<(String, PathBuf)> = args
+        .iter()
+        .filter_map(|a| PkgPath::new(PathBuf::from(a)))
+        .filter(|pp| pp.as_path().is_file())
+        .flat_map(|pp| {
+            let pkg = pp.as_package().name.to_string();
+            aura_core::files::conflicts(&alpm, pp.as_path())
+                .into_iter()
+                .map(move |f| (pkg.clone(), f))
+        })
+        .collect();
+
+    if found.is_empty() {
+        return Vec::new();
+    }
+
+    let wanted: HashSet<String> = found
+        .iter()
+        .filter_map(|(_, f)| f.strip_prefix("/").ok()?.to_str().map(|s| s.to_string()))
+        .collect();
+    let mut owners: HashMap<String, String> = HashMap::new();
+    for p in alpm.localdb().pkgs() {
+        for f in p.files().files() {
+            if wanted.contains(f.name()) {
+                owners.insert(f.name().to_string(), p.name().to_string());
+            }
+        }
+    }
+
+    found
+        .into_iter()
+        .map(|(pkg, file)| {
+            let owner = file
+                .strip_prefix("/")
+                .ok()
+                .and_then(|f| f.to_str())
+                .and_then(|f| owners.get(f).cloned());
+            Conflict { pkg, file, owner }
+        })
+        .collect()
+}
+
+/// Show which files conflict, and loop until the user chooses to overwrite
+/// them or to abort.
+fn resolve(fll: &FluentLanguageLoader, conflicts: &[Conflict]) -> Result<(), Error> {
+    yellow!(fll, "U-conflicts", count = conflicts.len());
+    for c in conflicts {
+        let owner = match c.owner.as_deref() {
+            Some(o) => fl!(fll, "U-conflict-owned", pkg = o),
+            None => fl!(fll, "U-conflict-unowned"),
+        };
+        println!(" {} {} {}", c.pkg.cyan(), c.file.display(), owner.dimmed());
+    }
+
+    loop {
+        aura!(fll, "U-conflict-what");
+        println!(" 0) {}", fl!(fll, "U-conflict-overwrite"));
+        println!(" 1) {}", fl!(fll, "U-conflict-inspect"));
+        println!(" 2) {}", fl!(fll, "U-conflict-abort"));
+
+        match crate::utils::select(">>> ", 2)? {
+            0 => return Ok(()),
+            1 => conflicts.iter().for_each(|c| inspect(fll, c)),
+            _ => return Err(Error::Cancelled),
+        }
+    }
+}
+
+/// Describe a conflicting file as it stands.
+fn inspect(fll: &FluentLanguageLoader, conflict: &Conflict) {
+    println!(" {}", conflict.file.display().to_string().bold());
+
+    match conflict.file.symlink_metadata() {
+        Err(_) => println!("    {}", fl!(fll, "U-conflict-gone")),
+        Ok(meta) => {
+            let kind = if meta.file_type().is_symlink() {
+                fl!(fll, "U-conflict-symlink")
+            } else if meta.is_dir() {
+                fl!(fll, "U-conflict-dir")
+            } else {
+                fl!(fll, "U-conflict-file", size = meta.len())
+            };
+            println!("    {kind}");
+
+            let when = meta
+                .modified()
+                .ok()
+                .map(time::OffsetDateTime::from)
+                .and_then(|t| {
+                    t.format(&time::format_description::well_known::Rfc3339)
+                        .ok()
+                });
+            if let Some(t) = when {
+                println!("    {}", fl!(fll, "U-conflict-modified", time = t));
+            }
+        }
+    }
+
+    let owner = conflict.owner.as_deref().unwrap_or("-");
+    println!(
+        "    {}",
+        fl!(
+            fll,
+            "U-conflict-from",
+            new = conflict.pkg.as_str(),
+            old = owner
+        )
+    );
+}
+
+/// Escape the characters that `--overwrite` would treat as a glob.
+fn escape(path: &str) -> String {
+    path.chars()
+        .flat_map(|c| match c {
+            '*' | '?' | '[' | ']' | '\\' => vec!['\\', c],
+            _ => vec![c],
+        })
+        .collect()
+}
+
+/// Record what was installed, and offer to keep local tarballs in the cache.
+pub(crate) fn record(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    targets: &[Target],
+) -> Result<(), Error> {
+    // Reinstalling a tarball that Aura built itself, say from the cache, keeps
+    // the record of how it was built.
+    let prov = crate::command::aur::provenance::load();
+    let built_here = |p: &aura_core::Package| {
+        prov.get(p.name.as_ref())
+            .map(|r| r.source.is_none() && r.version == p.version.as_ref())
+            .unwrap_or(false)
+    };
+
+    // Tarballs given by relative path are recorded by their full one, which
+    // still means something when read from elsewhere.
+    let sources: Vec<(&aura_core::Package, String)> = targets
+        .iter()
+        .filter(|t| match t {
+            Target::Local(pp) => built_here(pp.as_package()).not(),
+            Target::Remote(_, _) => true,
+        })
+        .map(|t| match t {
+            Target::Local(pp) => {
+                let path = std::fs::canonicalize(pp.as_path())
+                    .unwrap_or_else(|_| pp.as_path().to_path_buf());
+                (pp.as_package(), path.to_string_lossy().into_owned())
+            }
+            Target::Remote(url, pp) => (pp.as_package(), url.clone()),
+        })
+        .collect();
+
+    if sources.is_empty().not() {
+        let installed: Vec<(&aura_core::Package, &str)> =
+            sources.iter().map(|(p, s)| (*p, s.as_str())).collect();
+        crate::command::aur::provenance::record_installed(&installed)?;
+    }
+
+    let caches = env.caches();
+    let uncached: Vec<&PkgPath> = targets
+        .iter()
+        .filter_map(|t| match t {
+            Target::Local(pp) => Some(pp),
+            Target::Remote(_, _) => None,
+        })
+        .filter(|pp| in_cache(&caches, pp.as_path()).not())
+        .collect();
+
+    if uncached.is_empty().not() {
+        let cache = &env.aur.cache;
+
+        let msg = fl!(fll, "U-copy", dir = cache.utf8());
+
+        if crate::prompts::ask(fll, "U-copy", msg).is_some() {
+            for pp in uncached {
+                copy(pp.as_path(), cache)?;
+
+                let sig = pp.sig_file();
+                if sig.is_file() {
+                    copy(&sig, cache)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn copy(file: &Path, dir: &Path) -> Result<(), Error> {
+    let target = dir.join(file.file_name().unwrap_or_default());
+    debug!("Copying {} to {}", file.display(), target.display());
+
+    std::fs::copy(file, &target)
+        .map(|_| ())
+        .map_err(|e| Error::Copy(target, e))
+}
+
+/// Is the given tarball already within one of the caches?
+fn in_cache(caches: &[&Path], path: &Path) -> bool {
+    let full = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    caches.iter().any(|c| {
+        c.canonicalize()
+            .map(|c| full.starts_with(c))
+            .unwrap_or(false)
+    })
+}
+
+/// The checksum published beside a tarball, if there is one.
+fn expected_sha256(path: &Path) -> Option<String> {
+    let file = path.file_name()?.to_str()?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    ["sha256", "sha256sum"]
+        .iter()
+        .map(|ext| dir.join(format!("{file}.{ext}")))
+        .chain(std::iter::once(dir.join("SHA256SUMS")))
+        .filter_map(|p| std::fs::read_to_string(p).ok())
+        .find_map(|s| {
+            s.lines().find_map(|line| {
+                let mut words = line.split_whitespace();
+                let sum = words.next()?;
+                let name = words.next().unwrap_or(file).trim_start_matches('*');
+                (name == file).then(|| sum.to_lowercase())
+            })
+        })
+}
+
+fn sha256(path: &Path) -> Option<String> {
+    let out = Command::new("sha256sum").arg(path).output().ok()?;
+    let stdout = String::from_utf8(out.stdout).ok()?;
+
+    stdout.split_whitespace().next().map(|s| s.to_string())
+}
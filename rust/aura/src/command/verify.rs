@@ -0,0 +1,269 @@
+//! Check whether installed AUR packages can be built reproducibly.
+
+use crate::command::check::{BAD, GOOD, WARN};
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::{aura, yellow};
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::{debug, error, warn};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Env(crate::env::Error),
+    Git(aura_core::git::Error),
+    Aur(aura_core::aur::Error),
+    Build(crate::command::aur::Error),
+    #[from_variants(skip)]
+    NotInstalled(String),
+    #[from_variants(skip)]
+    NoTarball(String),
+    #[from_variants(skip)]
+    DeleteDir(PathBuf, std::io::Error),
+    #[from_variants(skip)]
+    ReadFile(PathBuf, std::io::Error),
+    Makepkg,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Env(e) => e.nested(),
+            Error::Git(e) => e.nested(),
+            Error::Aur(e) => e.nested(),
+            Error::Build(e) => e.nested(),
+            Error::NotInstalled(_) => {}
+            Error::NoTarball(_) => {}
+            Error::DeleteDir(_, e) => error!("{e}"),
+            Error::ReadFile(_, e) => error!("{e}"),
+            Error::Makepkg => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Env(e) => e.localise(fll),
+            Error::Git(e) => e.localise(fll),
+            Error::Aur(e) => e.localise(fll),
+            Error::Build(e) => e.localise(fll),
+            Error::NotInstalled(p) => fl!(fll, "verify-not-installed", pkg = p.as_str()),
+            Error::NoTarball(p) => fl!(fll, "verify-no-tarball", pkg = p.as_str()),
+            Error::DeleteDir(p, _) => fl!(fll, "clean-del", dir = p.utf8()),
+            Error::ReadFile(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
+            Error::Makepkg => fl!(fll, "A-build-e-makepkg"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Env(e) => e.code(),
+            Error::Git(e) => e.code(),
+            Error::Aur(e) => e.code(),
+            Error::Build(e) => e.code(),
+            Error::NotInstalled(_) => "verify-not-installed",
+            Error::NoTarball(_) => "verify-no-tarball",
+            Error::DeleteDir(_, _) => "verify-delete-dir",
+            Error::ReadFile(_, _) => "verify-read-file",
+            Error::Makepkg => "verify-makepkg",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Env(e) => e.context(),
+            Error::Git(e) => e.context(),
+            Error::Aur(e) => e.context(),
+            Error::Build(e) => e.context(),
+            Error::NotInstalled(pkg) => vec![("package", pkg.to_string())],
+            Error::NoTarball(pkg) => vec![("package", pkg.to_string())],
+            Error::DeleteDir(p, _) => vec![("path", p.display().to_string())],
+            Error::ReadFile(p, _) => vec![("path", p.display().to_string())],
+            Error::Makepkg => Vec::new(),
+        }
+    }
+}
+
+/// Rebuild an installed AUR package from a fresh copy of the exact commit it
+/// was installed from, and compare the result to the original tarball.
+pub(crate) fn verify(fll: &FluentLanguageLoader, env: &Env, package: &str) -> Result<(), Error> {
+    let alpm = env.alpm()?;
+    let pkg = alpm
+        .localdb()
+        .pkg(package)
+        .map_err(|_| Error::NotInstalled(package.to_string()))?;
+    let version = pkg.version().as_str();
+
+    // --- Find the tarball that was actually installed --- //
+    let caches = env.caches();
+    let original = aura_core::cache::package_paths(&caches)
+        .find(|pp| pp.as_package().name == package && pp.as_package().version == version)
+        .ok_or_else(|| Error::NoTarball(package.to_string()))?
+        .into_pathbuf();
+    debug!("Original tarball: {}", original.display());
+
+    // --- Prepare a clean copy of the AUR repo --- //
+    let clone = aura_core::aur::clone_path_of_pkgbase(&env.aur.clones, package, &|u: &str| {
+        env.network.json(u)
+    })?;
+    let base = clone
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or(package)
+        .to_string();
+    let dir = env.aur.build.join(format!("{base}-verify"));
+
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).map_err(|e| Error::DeleteDir(dir.clone(), e))?;
+    }
+
+    aura!(
+        fll,
+        "verify-building",
+        pkg = package.cyan().bold().to_string()
+    );
+    aura_core::git::local_clone(&clone, &dir)?;
+
+    // Build from the same commit that was last installed, if it's known.
+    let commit = crate::command::aur::provenance::load()
+        .get(package)
+        .map(|r| r.commit.clone())
+        .or_else(|| std::fs::read_to_string(env.aur.hashes.join(&base)).ok());
+
+    match commit {
+        Some(hash) => aura_core::git::checkout(&dir, hash.trim())?,
+        None => {
+            yellow!(fll, "verify-no-hash");
+        }
+    }
+
+    // --- Rebuild, mimicking the original as closely as possible --- //
+    let rebuilt = rebuild(env, &base, &dir, pkg.build_date(), pkg.packager())?
+        .into_iter()
+        .find(|tb| {
+            aura_core::cache::PkgPath::new(tb.clone())
+                .map(|pp| pp.as_package().name == package)
+                .unwrap_or(false)
+        })
+        .ok_or(Error::Makepkg)?;
+    debug!("Rebuilt tarball: {}", rebuilt.display());
+
+    // --- Compare --- //
+    let a = std::fs::read(&original).map_err(|e| Error::ReadFile(original.clone(), e))?;
+    let b = std::fs::read(&rebuilt).map_err(|e| Error::ReadFile(rebuilt.clone(), e))?;
+
+    if a == b {
+        println!("  [{}] {}", GOOD.green(), fl!(fll, "verify-identical"));
+    } else {
+        let differing = match (mtree(&original), mtree(&rebuilt)) {
+            (Some(x), Some(y)) => differences(&x, &y),
+            _ => vec![fl!(fll, "verify-mtree")],
+        };
+
+        if differing.is_empty() {
+            println!("  [{}] {}", WARN.yellow(), fl!(fll, "verify-files-match"));
+        } else {
+            println!("  [{}] {}", BAD.red(), fl!(fll, "verify-differs"));
+            differing.iter().for_each(|d| println!("      └─ {}", d));
+        }
+
+        if which::which("diffoscope").is_ok() {
+            let _ = Command::new("diffoscope")
+                .arg(&original)
+                .arg(&rebuilt)
+                .status();
+        }
+    }
+
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        warn!("Failed to remove {}: {}", dir.display(), e);
+    }
+
+    Ok(())
+}
+
+/// Build a package as `-A` would, as the build user and under the active
+/// profile, but reusing the original's timestamp and packager so that they
+/// don't needlessly differ. Yields the paths of the built tarballs.
+fn rebuild(
+    env: &Env,
+    base: &str,
+    dir: &Path,
+    build_date: i64,
+    packager: Option<&str>,
+) -> Result<Vec<PathBuf>, Error> {
+    let date = build_date.to_string();
+    let vars: Vec<(&str, &str)> = std::iter::once(("SOURCE_DATE_EPOCH", date.as_str()))
+        .chain(packager.map(|p| ("PACKAGER", p)))
+        .collect();
+
+    crate::command::aur::build::rebuild(env, base, dir, &vars).map_err(|e| Error::Build(e.into()))
+}
+
+/// The `.MTREE` of a package tarball, which records the size and digest of
+/// every file it would install. Entries are keyed by path, and timestamps are
+/// dropped.
+fn mtree(tarball: &Path) -> Option<BTreeMap<String, String>> {
+    let gz = Command::new("bsdtar")
+        .arg("-xOf")
+        .arg(tarball)
+        .arg(".MTREE")
+        .output()
+        .ok()?
+        .stdout;
+
+    let mut child = Command::new("gzip")
+        .arg("-dc")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    // Written from another thread so that a full `stdout` pipe can't
+    // deadlock us.
+    let mut stdin = child.stdin.take()?;
+    let writer = std::thread::spawn(move || stdin.write_all(&gz));
+    let out = child.wait_with_output().ok()?;
+    writer.join().ok()?.ok()?;
+
+    let entries = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter(|l| l.starts_with("./"))
+        // Package metadata like `.PKGINFO` always differs between builds.
+        .filter(|l| l.starts_with("./.").not())
+        .filter_map(|l| {
+            let mut fields = l.split_whitespace();
+            let path = fields.next()?.to_string();
+            let rest: Vec<_> = fields.filter(|f| f.starts_with("time=").not()).collect();
+            Some((path, rest.join(" ")))
+        })
+        .collect();
+
+    Some(entries)
+}
+
+/// Paths whose entries differ between two `.MTREE` files.
+fn differences(a: &BTreeMap<String, String>, b: &BTreeMap<String, String>) -> Vec<String> {
+    let mut diffs: Vec<String> = a
+        .iter()
+        .filter(|(path, entry)| b.get(*path) != Some(entry))
+        .map(|(path, _)| path.clone())
+        .chain(b.keys().filter(|path| a.contains_key(*path).not()).cloned())
+        .collect();
+
+    diffs.sort();
+    diffs
+}
@@ -3,16 +3,21 @@
 use crate::error::Nested;
 use crate::localization::Localised;
 use crate::utils::PathStr;
+use aura_core::Coded;
 use i18n_embed_fl::fl;
-use log::error;
+use log::{error, warn};
 use std::ops::Not;
 use std::path::PathBuf;
 
+/// Where the locks held by running Aura processes are kept.
+const LOCKS: &str = "/run/aura";
+
 #[derive(Debug)]
 pub enum Error {
     Mkdir(PathBuf, std::io::Error),
     XdgHome(std::env::VarError),
     XdgCache(std::env::VarError),
+    XdgState(std::env::VarError),
 }
 
 impl Nested for Error {
@@ -21,6 +26,7 @@ impl Nested for Error {
             Error::Mkdir(_, e) => error!("{e}"),
             Error::XdgHome(e) => error!("{e}"),
             Error::XdgCache(e) => error!("{e}"),
+            Error::XdgState(e) => error!("{e}"),
         }
     }
 }
@@ -31,12 +37,31 @@ impl Localised for Error {
             Error::Mkdir(p, _) => fl!(fll, "dir-mkdir", dir = p.utf8()),
             Error::XdgHome(_) => fl!(fll, "dir-home"),
             Error::XdgCache(_) => fl!(fll, "dir-cache"),
+            Error::XdgState(_) => fl!(fll, "dir-state"),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Mkdir(_, _) => "dirs-mkdir",
+            Error::XdgHome(_) => "dirs-xdg-home",
+            Error::XdgCache(_) => "dirs-xdg-cache",
+            Error::XdgState(_) => "dirs-xdg-state",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Mkdir(p, _) => vec![("path", p.display().to_string())],
+            Error::XdgHome(_) | Error::XdgCache(_) | Error::XdgState(_) => Vec::new(),
         }
     }
 }
 
 /// Like [`xdg_cache`], but for `XDG_CONFIG_HOME`.
-fn xdg_config() -> Result<PathBuf, Error> {
+pub(crate) fn xdg_config() -> Result<PathBuf, Error> {
     std::env::var("XDG_CONFIG_HOME")
         .map(PathBuf::from)
         .or_else(|_| std::env::var("HOME").map(|h| [&h, ".config"].iter().collect()))
@@ -54,13 +79,22 @@ pub(crate) fn aura_config() -> Result<PathBuf, Error> {
 /// > `$XDG_CACHE_HOME` defines the base directory relative to which user specific
 /// > non-essential data files should be stored. If `$XDG_CACHE_HOME` is either not
 /// > set or empty, a default equal to `$HOME/.cache` should be used.
-fn xdg_cache() -> Result<PathBuf, Error> {
+pub(crate) fn xdg_cache() -> Result<PathBuf, Error> {
     std::env::var("XDG_CACHE_HOME")
         .map(PathBuf::from)
         .or_else(|_| std::env::var("HOME").map(|h| [&h, ".cache"].iter().collect()))
         .map_err(Error::XdgCache)
 }
 
+/// Like [`xdg_cache`], but for `XDG_STATE_HOME`, whose default is
+/// `$HOME/.local/state`.
+pub(crate) fn xdg_state() -> Result<PathBuf, Error> {
+    std::env::var("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|h| [&h, ".local", "state"].iter().collect()))
+        .map_err(Error::XdgState)
+}
+
 /// The full path to the Aura cache.
 fn aura_xdg_cache() -> Result<PathBuf, Error> {
     let cache = xdg_cache()?.join("aura");
@@ -134,3 +168,294 @@ pub(crate) fn hashes() -> Result<PathBuf, Error> {
 
     Ok(path)
 }
+
+/// The full path to the index of files provided by built AUR packages.
+///
+/// Creates the parent directory if it doesn't exist.
+pub(crate) fn file_index() -> Result<PathBuf, Error> {
+    let cache = aura_xdg_cache()?;
+
+    if cache.is_dir().not() {
+        std::fs::create_dir_all(&cache).map_err(|e| Error::Mkdir(cache.clone(), e))?;
+    }
+
+    Ok(cache.join("files.json"))
+}
+
+/// The full path to the file that remembers where the last `--log-file` was.
+///
+/// Creates the parent directory if it doesn't exist.
+pub(crate) fn last_log() -> Result<PathBuf, Error> {
+    let cache = aura_xdg_cache()?;
+
+    if cache.is_dir().not() {
+        std::fs::create_dir_all(&cache).map_err(|e| Error::Mkdir(cache.clone(), e))?;
+    }
+
+    Ok(cache.join("last-log"))
+}
+
+/// The full path to the directory of cached responses from remote endpoints.
+///
+/// Creates the directory if it doesn't exist.
+pub(crate) fn http_cache() -> Result<PathBuf, Error> {
+    let path = aura_xdg_cache()?.join("http");
+
+    if path.is_dir().not() {
+        std::fs::create_dir_all(&path).map_err(|e| Error::Mkdir(path.clone(), e))?;
+    }
+
+    Ok(path)
+}
+
+/// The full path to the directory of past dependency resolutions.
+///
+/// Creates the directory if it doesn't exist.
+pub(crate) fn resolutions() -> Result<PathBuf, Error> {
+    let path = aura_xdg_cache()?.join("resolutions");
+
+    if path.is_dir().not() {
+        std::fs::create_dir_all(&path).map_err(|e| Error::Mkdir(path.clone(), e))?;
+    }
+
+    Ok(path)
+}
+
+/// The full path to Aura's own copies of the sync databases, which sit beside
+/// a link to the real local database, as `pacman`'s `DBPath` would.
+///
+/// Creates the directory if it doesn't exist.
+pub(crate) fn sync_copies() -> Result<PathBuf, Error> {
+    let path = aura_xdg_cache()?.join("syncdb");
+    let sync = path.join("sync");
+
+    if sync.is_dir().not() {
+        std::fs::create_dir_all(&sync).map_err(|e| Error::Mkdir(sync.clone(), e))?;
+    }
+
+    Ok(path)
+}
+
+/// The full path to the list of every package name in the AUR.
+///
+/// Creates the parent directory if it doesn't exist.
+pub(crate) fn aur_names() -> Result<PathBuf, Error> {
+    let cache = aura_xdg_cache()?;
+
+    if cache.is_dir().not() {
+        std::fs::create_dir_all(&cache).map_err(|e| Error::Mkdir(cache.clone(), e))?;
+    }
+
+    Ok(cache.join("aur-names"))
+}
+
+/// The full path to the record of how long each package's tests last took.
+///
+/// Creates the parent directory if it doesn't exist.
+pub(crate) fn check_times() -> Result<PathBuf, Error> {
+    let cache = aura_xdg_cache()?;
+
+    if cache.is_dir().not() {
+        std::fs::create_dir_all(&cache).map_err(|e| Error::Mkdir(cache.clone(), e))?;
+    }
+
+    Ok(cache.join("check-times.json"))
+}
+
+/// The full path to the samples of AUR package votes and popularity.
+///
+/// Creates the parent directory if it doesn't exist.
+pub(crate) fn trends() -> Result<PathBuf, Error> {
+    let cache = aura_xdg_cache()?;
+
+    if cache.is_dir().not() {
+        std::fs::create_dir_all(&cache).map_err(|e| Error::Mkdir(cache.clone(), e))?;
+    }
+
+    Ok(cache.join("aur-trends.json"))
+}
+
+/// The full path to the rules of which packages keep versioned user data.
+pub(crate) fn state_rules() -> Result<PathBuf, Error> {
+    xdg_config().map(|p| p.join("aura").join("rules.toml"))
+}
+
+/// The full path to the latest copy of the upgrade rules that Aura ships.
+///
+/// Creates the parent directory if it doesn't exist.
+pub(crate) fn upgrade_rules() -> Result<PathBuf, Error> {
+    let cache = aura_xdg_cache()?;
+
+    if cache.is_dir().not() {
+        std::fs::create_dir_all(&cache).map_err(|e| Error::Mkdir(cache.clone(), e))?;
+    }
+
+    Ok(cache.join("upgrade-rules.toml"))
+}
+
+/// The full path to the copies of version markers kept before upgrades. Like the
+/// audit log, it lives in `XDG_STATE_HOME`.
+///
+/// Creates the directory if it doesn't exist.
+pub(crate) fn markers() -> Result<PathBuf, Error> {
+    let path = xdg_state()?.join("aura").join("markers");
+
+    if path.is_dir().not() {
+        std::fs::create_dir_all(&path).map_err(|e| Error::Mkdir(path.clone(), e))?;
+    }
+
+    Ok(path)
+}
+
+/// The full path to the directory of logs left by quiet builds.
+///
+/// Creates the directory if it doesn't exist.
+pub(crate) fn build_logs() -> Result<PathBuf, Error> {
+    let logs = aura_xdg_cache()?.join("logs");
+
+    if logs.is_dir().not() {
+        std::fs::create_dir_all(&logs).map_err(|e| Error::Mkdir(logs.clone(), e))?;
+    }
+
+    Ok(logs)
+}
+
+/// The full path to the directory of locks held by running Aura processes.
+/// It's shared by every user, so that a run by one user sees the locks of
+/// another's, or of a timer's running as root. Being under `/run`, no lock
+/// outlives a reboot.
+///
+/// Creates the directory if it doesn't exist, writable by all but sticky, so
+/// that nobody can remove another user's lock file. Only root may write to
+/// `/run`, so this may call `sudo`.
+pub(crate) fn locks(_: &aura::caps::Mutating) -> Result<PathBuf, Error> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let path = PathBuf::from(LOCKS);
+
+    if path.is_dir().not() {
+        let made = std::fs::DirBuilder::new()
+            .mode(0o1777)
+            .create(&path)
+            // The mode given above is subject to the umask.
+            .and_then(|_| std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o1777)));
+
+        match made {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                let made = crate::audit::status(
+                    std::process::Command::new("sudo")
+                        .args(["install", "-d", "-m", "1777"])
+                        .arg(&path),
+                )
+                .map_err(|e| Error::Mkdir(path.clone(), e))?;
+
+                if made.success().not() {
+                    return Err(Error::Mkdir(path.clone(), e));
+                }
+            }
+            Err(e) => return Err(Error::Mkdir(path.clone(), e)),
+        }
+    }
+
+    Ok(path)
+}
+
+/// The full path to the record of how each AUR package was built. Unlike a
+/// cache, it can't be rebuilt once lost, so like the audit log, it lives in
+/// `XDG_STATE_HOME`.
+///
+/// Creates the parent directory if it doesn't exist, and moves over a record
+/// left in the cache by earlier versions.
+pub(crate) fn provenance() -> Result<PathBuf, Error> {
+    let state = xdg_state()?.join("aura");
+
+    if state.is_dir().not() {
+        std::fs::create_dir_all(&state).map_err(|e| Error::Mkdir(state.clone(), e))?;
+    }
+
+    let path = state.join("provenance.json");
+    let old = aura_xdg_cache()?.join("provenance.json");
+
+    if path.exists().not() && old.is_file() {
+        if let Err(e) = std::fs::rename(&old, &path)
+            .or_else(|_| std::fs::copy(&old, &path).and_then(|_| std::fs::remove_file(&old)))
+        {
+            warn!(
+                "Failed to move {} to {}: {e}",
+                old.display(),
+                path.display()
+            );
+        }
+    }
+
+    Ok(path)
+}
+
+/// The full path to the record of why each dependency was installed. Like the
+/// audit log, it lives in `XDG_STATE_HOME`.
+///
+/// Creates the parent directory if it doesn't exist.
+pub(crate) fn reasons() -> Result<PathBuf, Error> {
+    let state = xdg_state()?.join("aura");
+
+    if state.is_dir().not() {
+        std::fs::create_dir_all(&state).map_err(|e| Error::Mkdir(state.clone(), e))?;
+    }
+
+    Ok(state.join("reasons.log"))
+}
+
+/// The full path to the last known count of pending updates. Like the audit
+/// log, it lives in `XDG_STATE_HOME`.
+///
+/// Creates the parent directory if it doesn't exist.
+pub(crate) fn pending_updates() -> Result<PathBuf, Error> {
+    let state = xdg_state()?.join("aura");
+
+    if state.is_dir().not() {
+        std::fs::create_dir_all(&state).map_err(|e| Error::Mkdir(state.clone(), e))?;
+    }
+
+    Ok(state.join("pending-updates.json"))
+}
+
+/// The full path to the audit log of elevated commands. Unlike the rest of
+/// Aura's files, it lives in `XDG_STATE_HOME`, since clearing a cache shouldn't
+/// erase it.
+///
+/// Creates the parent directory if it doesn't exist.
+pub(crate) fn audit_log() -> Result<PathBuf, Error> {
+    let state = xdg_state()?.join("aura");
+
+    if state.is_dir().not() {
+        std::fs::create_dir_all(&state).map_err(|e| Error::Mkdir(state.clone(), e))?;
+    }
+
+    Ok(state.join("audit.log"))
+}
+
+/// The full path to a private directory for short-lived files, such as
+/// snapshots on their way to or from a remote. Only the user can enter it,
+/// since what passes through may be sensitive.
+///
+/// Creates the directory if it doesn't exist.
+pub(crate) fn scratch() -> Result<PathBuf, Error> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let path = aura_xdg_cache()?.join("scratch");
+
+    if path.is_dir().not() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&path)
+            .map_err(|e| Error::Mkdir(path.clone(), e))?;
+    }
+
+    // In case it was made by something less careful.
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o700))
+        .map_err(|e| Error::Mkdir(path.clone(), e))?;
+
+    Ok(path)
+}
@@ -4,16 +4,26 @@ use crate::dirs;
 use crate::error::Nested;
 use crate::localization::Localised;
 use alpm::Alpm;
+use aura_core::glob;
+use aura_core::Coded;
 use from_variants::FromVariants;
 use i18n_embed_fl::fl;
 use log::error;
 use r2d2::Pool;
 use r2d2_alpm::AlpmManager;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ops::Not;
 use std::path::{Path, PathBuf};
+use unic_langid::LanguageIdentifier;
 
 const DEFAULT_EDITOR: &str = "vi";
+const DEFAULT_CONNECT_TIMEOUT: u64 = 30;
+const DEFAULT_READ_TIMEOUT: u64 = 30;
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_METERED_THRESHOLD: u64 = 50;
+const DEFAULT_MAX_TEMP: u32 = 85;
 
 #[derive(FromVariants)]
 pub(crate) enum Error {
@@ -22,6 +32,14 @@ pub(crate) enum Error {
     Alpm(alpm::Error),
     R2d2(r2d2::Error),
     MissingEditor,
+    MissingAria2,
+    MissingNamcap,
+    MissingDevtools,
+    MissingSystemdRun,
+    #[from_variants(skip)]
+    UnknownProfile(String),
+    #[from_variants(skip)]
+    ChrootProfile(String),
 }
 
 impl Nested for Error {
@@ -30,6 +48,12 @@ impl Nested for Error {
             Error::Dirs(e) => e.nested(),
             Error::PConf(e) => error!("{e}"),
             Error::MissingEditor => {}
+            Error::MissingAria2 => {}
+            Error::MissingNamcap => {}
+            Error::MissingDevtools => {}
+            Error::MissingSystemdRun => {}
+            Error::UnknownProfile(_) => {}
+            Error::ChrootProfile(_) => {}
             Error::Alpm(e) => error!("{e}"),
             Error::R2d2(e) => error!("{e}"),
         }
@@ -42,17 +66,63 @@ impl Localised for Error {
             Error::Dirs(e) => e.localise(fll),
             Error::PConf(_) => fl!(fll, "env-pconf"),
             Error::MissingEditor => fl!(fll, "env-missing-editor"),
+            Error::MissingAria2 => fl!(fll, "env-missing-aria2"),
+            Error::MissingNamcap => fl!(fll, "env-missing-namcap"),
+            Error::MissingDevtools => fl!(fll, "env-missing-devtools"),
+            Error::MissingSystemdRun => fl!(fll, "env-missing-systemd-run"),
+            Error::UnknownProfile(p) => fl!(fll, "env-unknown-profile", profile = p.as_str()),
+            Error::ChrootProfile(p) => fl!(fll, "env-chroot-profile", profile = p.as_str()),
             Error::Alpm(_) => fl!(fll, "err-alpm"),
             Error::R2d2(_) => fl!(fll, "err-pool-create"),
         }
     }
 }
 
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::PConf(_) => "env-pacman-conf",
+            Error::Alpm(_) => "env-alpm",
+            Error::R2d2(_) => "env-r2d2",
+            Error::MissingEditor => "env-missing-editor",
+            Error::MissingAria2 => "env-missing-aria2",
+            Error::MissingNamcap => "env-missing-namcap",
+            Error::MissingDevtools => "env-missing-devtools",
+            Error::MissingSystemdRun => "env-missing-systemd-run",
+            Error::UnknownProfile(_) => "env-unknown-profile",
+            Error::ChrootProfile(_) => "env-chroot-profile",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::UnknownProfile(p) => vec![("profile", p.clone())],
+            Error::ChrootProfile(p) => vec![("profile", p.clone())],
+            Error::PConf(_)
+            | Error::Alpm(_)
+            | Error::R2d2(_)
+            | Error::MissingEditor
+            | Error::MissingAria2
+            | Error::MissingNamcap
+            | Error::MissingDevtools
+            | Error::MissingSystemdRun => Vec::new(),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct RawEnv {
     general: Option<RawGeneral>,
     aur: Option<RawAur>,
     backups: Option<RawBackups>,
+    network: Option<RawNetwork>,
+    aliases: Option<BTreeMap<String, String>>,
+    updates: Option<BTreeMap<String, String>>,
+    prompts: Option<RawPrompts>,
+    notify: Option<RawNotify>,
+    pins: Option<Pins>,
 }
 
 impl RawEnv {
@@ -65,6 +135,23 @@ impl RawEnv {
     }
 }
 
+/// The user's command aliases, if any are defined. These are needed before
+/// the rest of the environment, as they affect how the CLI is parsed.
+pub(crate) fn aliases() -> BTreeMap<String, String> {
+    RawEnv::try_new()
+        .and_then(|re| re.aliases)
+        .unwrap_or_default()
+}
+
+/// The preferred language set in the config file, if any. Like aliases, this
+/// is needed before the rest of the environment.
+pub(crate) fn language() -> Option<LanguageIdentifier> {
+    RawEnv::try_new()
+        .and_then(|re| re.general)
+        .and_then(|rg| rg.language)
+        .and_then(|l| l.parse().ok())
+}
+
 /// Can the `aura.toml` be parsed?
 pub(crate) fn parsable_env() -> bool {
     RawEnv::try_new().is_some()
@@ -81,6 +168,19 @@ pub(crate) struct Env {
     pub(crate) aur: Aur,
     /// Saving and restoring package states.
     pub(crate) backups: Backups,
+    /// Behaviour of all outgoing HTTP requests.
+    pub(crate) network: Network,
+    /// Short names for longer Aura invocations.
+    pub(crate) aliases: BTreeMap<String, String>,
+    /// Extra commands that list pending updates (one per line) from sources
+    /// other than pacman and the AUR, like Flatpak.
+    pub(crate) updates: BTreeMap<String, String>,
+    /// How yes/no questions are answered.
+    pub(crate) prompts: Prompts,
+    /// Where to report the outcome of `-Au` runs.
+    pub(crate) notify: Notify,
+    /// Packages and repos held back from upgrades.
+    pub(crate) pins: Pins,
     /// Settings from a `pacman.conf`.
     #[serde(skip_serializing)]
     pub(crate) pacman: pacmanconf::Config,
@@ -91,19 +191,31 @@ impl Env {
         // Read the config file, if it's there. We don't actually mind if it isn't,
         // because sensible defaults can (probably) be set anyway.
         let raw: Option<RawEnv> = RawEnv::try_new();
-        let (general, aur, backups) = match raw {
+        let (general, aur, backups, network, aliases, updates, prompts, notify, pins) = match raw {
             Some(re) => (
                 re.general.map(|rg| rg.into()),
                 re.aur.map(|ra| ra.try_into()),
                 re.backups.map(|rb| rb.try_into()),
+                re.network.map(|rn| rn.into()),
+                re.aliases,
+                re.updates,
+                re.prompts.map(|rp| rp.into()),
+                re.notify.map(|rn| rn.into()),
+                re.pins,
             ),
-            None => (None, None, None),
+            None => (None, None, None, None, None, None, None, None, None),
         };
 
         let e = Env {
             general: general.unwrap_or_default(),
             aur: aur.unwrap_or_else(Aur::try_default)?,
             backups: backups.unwrap_or_else(Backups::try_default)?,
+            network: network.unwrap_or_default(),
+            aliases: aliases.unwrap_or_default(),
+            updates: updates.unwrap_or_default(),
+            prompts: prompts.unwrap_or_default(),
+            notify: notify.unwrap_or_default(),
+            pins: pins.unwrap_or_default(),
             pacman: pacmanconf::Config::new().map_err(Error::PConf)?,
         };
 
@@ -127,6 +239,23 @@ impl Env {
         alpm_utils::alpm_with_conf(&self.pacman).map_err(Error::Alpm)
     }
 
+    /// The architecture that packages are resolved and built for: the one
+    /// set in `aura.toml`, else that of `pacman.conf`, else this machine's.
+    pub(crate) fn carch(&self) -> &str {
+        self.aur
+            .active_profile()
+            .and_then(|p| p.carch.as_deref())
+            .or(self.aur.carch.as_deref())
+            .or_else(|| {
+                self.pacman
+                    .architecture
+                    .iter()
+                    .map(|a| a.as_str())
+                    .find(|a| *a != "auto")
+            })
+            .unwrap_or(std::env::consts::ARCH)
+    }
+
     /// All tarball caches across the various config sources.
     pub(crate) fn caches(&self) -> Vec<&Path> {
         self.pacman
@@ -142,11 +271,82 @@ impl Env {
         Path::new(&self.pacman.log_file)
     }
 
+    /// Should a package be left alone during upgrades? Considers Aura's own
+    /// ignores, as well as the `IgnorePkg` and `IgnoreGroup` of `pacman.conf`,
+    /// both of which may contain glob patterns.
+    pub(crate) fn is_ignored(&self, name: &str, groups: &[&str]) -> bool {
+        self.aur.ignores.contains(name)
+            || self
+                .pacman
+                .ignore_pkg
+                .iter()
+                .any(|p| glob::matches(p, name))
+            || self
+                .pacman
+                .ignore_group
+                .iter()
+                .any(|p| groups.iter().any(|g| glob::matches(p, g)))
+    }
+
+    /// Would upgrading a package from the given repo to the given version break
+    /// one of its pins? AUR packages come from the repo `aur`.
+    pub(crate) fn is_pinned(&self, name: &str, repo: &str, version: &str) -> bool {
+        self.pins.repos.contains(repo)
+            || self
+                .pins
+                .packages
+                .get(name)
+                .map(|max| aura_core::version::cmp(version, max) == Ordering::Greater)
+                .unwrap_or(false)
+    }
+
+    /// Will `pacman` decline to write the given file to the filesystem, per the
+    /// `NoExtract` of `pacman.conf`? Patterns prefixed by a `!` reinstate files
+    /// matched by an earlier pattern.
+    pub(crate) fn is_noextract(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix("/").unwrap_or(path).to_string_lossy();
+
+        self.pacman
+            .no_extract
+            .iter()
+            .fold(false, |skip, p| match p.strip_prefix('!') {
+                Some(neg) if glob::matches(neg, &relative) => false,
+                None if glob::matches(p, &relative) => true,
+                _ => skip,
+            })
+    }
+
     /// Allow CLI flags to override settings from `aura.toml`.
-    pub(crate) fn reconcile_cli(&mut self, flags: &aura::flags::SubCmd) {
-        if let aura::flags::SubCmd::Aur(a) = flags {
+    pub(crate) fn reconcile_cli(&mut self, args: &aura::flags::Args) {
+        if let Some(rate) = args.limit_rate {
+            self.network.limit_rate = Some(rate);
+        }
+
+        if let aura::flags::SubCmd::Aur(a) = &args.subcmd {
             self.aur.reconcile(a)
         }
+
+        // So that whatever Aura judges of the databases, it judges of the same
+        // ones that `pacman` is about to use.
+        if let aura::flags::SubCmd::Sync(s) = &args.subcmd {
+            let (config, dbpath, root) = s.alternates();
+
+            if config.is_some() || root.is_some() {
+                match pacmanconf::Config::with_opts(None, config, root) {
+                    Ok(c) => self.pacman = c,
+                    Err(e) => error!("{e}"),
+                }
+            }
+
+            if let Some(db) = dbpath {
+                self.pacman.db_path = db.to_string();
+            }
+        }
+    }
+
+    /// How the work of installing and upgrading AUR packages is presented.
+    pub(crate) fn reporter(&self) -> crate::reporter::Reporter {
+        crate::reporter::Reporter::new(self.aur.verbosity)
     }
 
     /// Before continuing, confirm that the settled `Env` is valid to use.
@@ -155,6 +355,35 @@ impl Env {
             which::which(&self.general.editor).map_err(|_| Error::MissingEditor)?;
         }
 
+        if self.aur.downloader == Downloader::Aria2 {
+            which::which("aria2c").map_err(|_| Error::MissingAria2)?;
+        }
+
+        if self.aur.namcap {
+            which::which("namcap").map_err(|_| Error::MissingNamcap)?;
+        }
+
+        if let Some(name) = self.aur.profile.as_deref() {
+            let profile = self
+                .aur
+                .profiles
+                .get(name)
+                .ok_or_else(|| Error::UnknownProfile(name.to_string()))?;
+
+            if profile.chroot.is_some() {
+                // Builds in a chroot follow the chroot's own `makepkg.conf`.
+                if profile.env.is_empty().not() || profile.makepkg_conf.is_some() {
+                    return Err(Error::ChrootProfile(name.to_string()));
+                }
+
+                which::which("makechrootpkg").map_err(|_| Error::MissingDevtools)?;
+            }
+        }
+
+        if self.aur.limits.is_scoped() {
+            which::which("systemd-run").map_err(|_| Error::MissingSystemdRun)?;
+        }
+
         Ok(())
     }
 }
@@ -163,12 +392,49 @@ impl Env {
 struct RawGeneral {
     cpus: Option<u32>,
     editor: Option<String>,
+    language: Option<String>,
+    #[serde(default)]
+    orphans: OrphanPolicy,
+    #[serde(default)]
+    files_ignore: Vec<String>,
+    #[serde(default)]
+    etc_diff: bool,
+    #[serde(default)]
+    state_hooks: bool,
+    refresh_rules: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
 pub(crate) struct General {
     pub(crate) cpus: u32,
     pub(crate) editor: String,
+    /// The preferred language of Aura's output, like `ja-JP`.
+    pub(crate) language: Option<String>,
+    /// What `check --fix` should do with orphans.
+    pub(crate) orphans: OrphanPolicy,
+    /// Files that `check --files` should expect to have been changed locally,
+    /// as patterns like `/etc/*`.
+    pub(crate) files_ignore: Vec<String>,
+    /// Report the files in `/etc` that each transaction changed.
+    pub(crate) etc_diff: bool,
+    /// Keep the version markers of package data before upgrades, per the
+    /// rules in `~/.config/aura/rules.toml`.
+    pub(crate) state_hooks: bool,
+    /// Renew the shipped upgrade rules from upstream when they're stale.
+    pub(crate) refresh_rules: bool,
+}
+
+/// What to do with orphan packages when fixing the system.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OrphanPolicy {
+    /// Leave them be.
+    #[default]
+    Ignore,
+    /// Mark them as explicitly installed.
+    Adopt,
+    /// Uninstall them, along with their own unneeded dependencies.
+    Remove,
 }
 
 impl Default for General {
@@ -176,6 +442,12 @@ impl Default for General {
         Self {
             cpus: num_cpus::get() as u32,
             editor: editor(),
+            language: None,
+            orphans: OrphanPolicy::default(),
+            files_ignore: Vec::new(),
+            etc_diff: false,
+            state_hooks: false,
+            refresh_rules: true,
         }
     }
 }
@@ -185,6 +457,12 @@ impl From<RawGeneral> for General {
         General {
             cpus: raw.cpus.unwrap_or_else(|| num_cpus::get() as u32),
             editor: raw.editor.unwrap_or_else(editor),
+            language: raw.language,
+            orphans: raw.orphans,
+            files_ignore: raw.files_ignore,
+            etc_diff: raw.etc_diff,
+            state_hooks: raw.state_hooks,
+            refresh_rules: raw.refresh_rules.unwrap_or(true),
         }
     }
 }
@@ -210,6 +488,41 @@ struct RawAur {
     diff: bool,
     #[serde(default)]
     delmakedeps: bool,
+    #[serde(default)]
+    fileindex: bool,
+    #[serde(default)]
+    downloader: Downloader,
+    #[serde(default)]
+    keep: Keep,
+    builduser: Option<String>,
+    #[serde(default)]
+    namcap: bool,
+    #[serde(default)]
+    namcap_strict: HashSet<String>,
+    #[serde(default)]
+    sources: bool,
+    #[serde(default)]
+    stage: bool,
+    #[serde(default)]
+    keep_going: bool,
+    #[serde(default)]
+    changes: bool,
+    carch: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    power: Power,
+    #[serde(default)]
+    limits: Limits,
+    timeout: Option<u64>,
+    #[serde(default)]
+    timeouts: BTreeMap<String, u64>,
+    #[serde(default)]
+    nocheck: Nocheck,
+    #[serde(default)]
+    binaries: Binaries,
+    #[serde(default)]
+    verbosity: Verbosity,
 }
 
 #[derive(Debug, Serialize)]
@@ -227,6 +540,185 @@ pub(crate) struct Aur {
     pub(crate) diff: bool,
     /// Delete makedeps after building.
     pub(crate) delmakedeps: bool,
+    /// Record the file lists of newly built packages, for `aura provides`.
+    pub(crate) fileindex: bool,
+    /// How to fetch the remote sources of a PKGBUILD.
+    pub(crate) downloader: Downloader,
+    /// What to leave behind in a build directory after a successful build.
+    pub(crate) keep: Keep,
+    /// Packages whose build directories should always be left untouched.
+    pub(crate) keeps: HashSet<String>,
+    /// Interactively choose which upgrades to apply during `-Au`.
+    pub(crate) pick: bool,
+    /// The unprivileged user to build as, when Aura itself is run as root.
+    pub(crate) builduser: Option<String>,
+    /// Lint PKGBUILDs and built packages with `namcap`.
+    pub(crate) namcap: bool,
+    /// `namcap` tags (or whole severities, as `E`, `W`, or `I`) that should
+    /// fail a build.
+    pub(crate) namcap_strict: HashSet<String>,
+    /// Report the remote sources of each package before building.
+    pub(crate) sources: bool,
+    /// Build everything first, then install it all in a single transaction.
+    pub(crate) stage: bool,
+    /// Skip failed builds and their dependents, rather than stopping.
+    pub(crate) keep_going: bool,
+    /// Show the AUR commits behind each upgrade during `-Au`.
+    pub(crate) changes: bool,
+    /// The architecture to resolve and build for, if not this machine's own.
+    /// It's passed to `makepkg` as `CARCH`, which takes effect only if the
+    /// active `makepkg.conf` doesn't set it outright.
+    pub(crate) carch: Option<String>,
+    /// Named ways of building, chosen with `--profile`.
+    pub(crate) profiles: BTreeMap<String, Profile>,
+    /// The name of the profile to build with, if any.
+    pub(crate) profile: Option<String>,
+    /// When to hold back heavy builds on battery or when the CPU is hot.
+    pub(crate) power: Power,
+    /// How much of the machine `makepkg` may take from everything else.
+    pub(crate) limits: Limits,
+    /// Minutes after which any one build is stopped and counted as failed.
+    pub(crate) timeout: Option<u64>,
+    /// Per-package overrides of `timeout`, for those known to build slowly.
+    pub(crate) timeouts: BTreeMap<String, u64>,
+    /// When to skip the test suites of packages.
+    pub(crate) nocheck: Nocheck,
+    /// Third-party repos to install pre-built AUR packages from.
+    pub(crate) binaries: Binaries,
+    /// How much of the work of building and installing to show.
+    pub(crate) verbosity: Verbosity,
+}
+
+/// Pre-built AUR packages from third-party repos, like `chaotic-aur`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Binaries {
+    /// Repos from `pacman.conf` to look for pre-built packages in, by order of
+    /// preference.
+    pub(crate) repos: Vec<String>,
+    /// Packages to always install pre-built when possible, without asking.
+    pub(crate) always: HashSet<String>,
+    /// Packages to always build, without asking.
+    pub(crate) never: HashSet<String>,
+}
+
+/// When to pass `--nocheck` to `makepkg`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Nocheck {
+    /// Skip tests, unless a package is listed in `run`.
+    pub(crate) default: bool,
+    /// Packages whose tests are always run.
+    pub(crate) run: HashSet<String>,
+    /// Packages whose tests are always skipped.
+    pub(crate) skip: HashSet<String>,
+    /// Ask before running tests that last took longer than this many minutes.
+    pub(crate) ask_after: Option<u64>,
+}
+
+/// Limits on the resources of builds, so that they don't freeze interactive
+/// sessions.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Limits {
+    /// The niceness to build with, from -20 to 19.
+    pub(crate) nice: Option<i32>,
+    /// The `ionice` scheduling class: `idle`, `best-effort`, or `realtime`.
+    pub(crate) ionice: Option<String>,
+    /// The `MemoryMax=` of a transient systemd scope, like `4G`.
+    pub(crate) memory_max: Option<String>,
+    /// The `CPUQuota=` of a transient systemd scope, like `200%`.
+    pub(crate) cpu_quota: Option<String>,
+}
+
+impl Limits {
+    /// Must builds run within a systemd scope?
+    pub(crate) fn is_scoped(&self) -> bool {
+        self.memory_max.is_some() || self.cpu_quota.is_some()
+    }
+}
+
+/// Guarding laptops against heavy builds at bad times.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Power {
+    /// Check the battery and CPU temperature before building at all?
+    pub(crate) guard: bool,
+    /// The CPU temperature, in degrees Celsius, that counts as already hot.
+    pub(crate) max_temp: u32,
+    /// Packages (or globs of them) whose builds count as heavy. When empty,
+    /// every build does.
+    pub(crate) heavy: HashSet<String>,
+    /// Defer heavy builds instead of only warning about them.
+    pub(crate) defer: bool,
+}
+
+impl Default for Power {
+    fn default() -> Self {
+        Power {
+            guard: false,
+            max_temp: DEFAULT_MAX_TEMP,
+            heavy: HashSet::new(),
+            defer: false,
+        }
+    }
+}
+
+/// A named way of building packages, like for another architecture or with
+/// hardened flags.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct Profile {
+    /// The architecture to resolve and build for.
+    pub(crate) carch: Option<String>,
+    /// Extra environment variables for `makepkg`, like `CC` or `CFLAGS`.
+    #[serde(default)]
+    pub(crate) env: BTreeMap<String, String>,
+    /// A `makepkg.conf` to use instead of `/etc/makepkg.conf`.
+    pub(crate) makepkg_conf: Option<PathBuf>,
+    /// A chroot to build within via `makechrootpkg`, as made by `mkarchroot`.
+    /// Its own `makepkg.conf` applies, so `env` and `makepkg_conf` must be
+    /// left unset.
+    pub(crate) chroot: Option<PathBuf>,
+}
+
+/// What to retain of a build directory after a successful build.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Keep {
+    /// Remove the build directory entirely.
+    None,
+    /// Remove the extracted `src/` and `pkg/` trees, but keep downloaded
+    /// sources so that they needn't be fetched again.
+    Sources,
+    /// Leave everything as it is.
+    #[default]
+    All,
+}
+
+/// How the remote sources of a PKGBUILD are fetched before building.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Downloader {
+    /// Let `makepkg` fetch them itself.
+    #[default]
+    Makepkg,
+    /// Delegate to `aria2c`, for resumable downloads over multiple connections.
+    Aria2,
+    /// Use Aura's own parallel downloader.
+    Aura,
+}
+
+/// How much of the work of building and installing is shown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Verbosity {
+    /// One line per package.
+    Quiet,
+    /// Each stage, and all build output.
+    #[default]
+    Normal,
+    /// As well as dependency resolution and the commands that are run.
+    Verbose,
 }
 
 impl Aur {
@@ -242,6 +734,28 @@ impl Aur {
             hotedit: false,
             diff: false,
             delmakedeps: false,
+            fileindex: false,
+            downloader: Downloader::default(),
+            keep: Keep::default(),
+            keeps: HashSet::new(),
+            pick: false,
+            builduser: None,
+            namcap: false,
+            namcap_strict: HashSet::new(),
+            sources: false,
+            stage: false,
+            keep_going: false,
+            changes: false,
+            carch: None,
+            profiles: BTreeMap::new(),
+            profile: None,
+            power: Power::default(),
+            limits: Limits::default(),
+            timeout: None,
+            timeouts: BTreeMap::new(),
+            nocheck: Nocheck::default(),
+            binaries: Binaries::default(),
+            verbosity: Verbosity::default(),
         };
 
         Ok(a)
@@ -262,13 +776,102 @@ impl Aur {
             self.diff = true;
         }
 
+        if flags.changes {
+            self.changes = true;
+        }
+
+        if flags.pick {
+            self.pick = true;
+        }
+
         if flags.delmakedeps {
             self.delmakedeps = true;
         }
 
+        if flags.namcap {
+            self.namcap = true;
+        }
+
+        if flags.sources {
+            self.sources = true;
+        }
+
+        if flags.stage {
+            self.stage = true;
+        }
+
+        if flags.keep_going {
+            self.keep_going = true;
+        }
+
         // Harmless clone, as we don't expect many "ignores" to be passed on the
         // command line.
         self.ignores.extend(flags.ignore.clone());
+        self.keeps.extend(flags.keep.clone());
+
+        if let Some(b) = flags.builddir.as_ref() {
+            self.build = b.clone();
+        }
+
+        if let Some(c) = flags.clonedir.as_ref() {
+            self.clones = c.clone();
+        }
+
+        if let Some(p) = flags.profile.as_ref() {
+            self.profile = Some(p.clone());
+        }
+
+        if let Some(n) = flags.nice {
+            self.limits.nice = Some(n);
+        }
+
+        if let Some(c) = flags.ionice.as_ref() {
+            self.limits.ionice = Some(c.clone());
+        }
+
+        if let Some(m) = flags.memory_max.as_ref() {
+            self.limits.memory_max = Some(m.clone());
+        }
+
+        if let Some(q) = flags.cpu_quota.as_ref() {
+            self.limits.cpu_quota = Some(q.clone());
+        }
+
+        if flags.nocheck {
+            self.nocheck.default = true;
+        }
+
+        if let Some(t) = flags.timeout {
+            self.timeout = Some(t);
+        }
+
+        if flags.defer_on_battery {
+            self.power.guard = true;
+            self.power.defer = true;
+        }
+
+        if flags.quiet {
+            self.verbosity = Verbosity::Quiet;
+        }
+
+        if flags.verbose {
+            self.verbosity = Verbosity::Verbose;
+        }
+    }
+
+    /// The minutes after which a build of the given package base is stopped.
+    /// A limit of `0` means none at all.
+    pub(crate) fn timeout_for(&self, base: &str) -> Option<u64> {
+        self.timeouts
+            .get(base)
+            .copied()
+            .or(self.timeout)
+            .filter(|m| *m > 0)
+    }
+
+    /// The profile chosen with `--profile`, if it exists.
+    pub(crate) fn active_profile(&self) -> Option<&Profile> {
+        self.profile.as_deref().and_then(|p| self.profiles.get(p))
     }
 }
 
@@ -291,6 +894,28 @@ impl TryFrom<RawAur> for Aur {
             hotedit: raw.hotedit,
             diff: raw.diff,
             delmakedeps: raw.delmakedeps,
+            fileindex: raw.fileindex,
+            downloader: raw.downloader,
+            keep: raw.keep,
+            keeps: HashSet::new(),
+            pick: false,
+            builduser: raw.builduser,
+            namcap: raw.namcap,
+            namcap_strict: raw.namcap_strict,
+            sources: raw.sources,
+            stage: raw.stage,
+            keep_going: raw.keep_going,
+            changes: raw.changes,
+            carch: raw.carch,
+            profiles: raw.profiles,
+            profile: None,
+            power: raw.power,
+            limits: raw.limits,
+            timeout: raw.timeout,
+            timeouts: raw.timeouts,
+            nocheck: raw.nocheck,
+            binaries: raw.binaries,
+            verbosity: raw.verbosity,
         };
 
         Ok(a)
@@ -300,11 +925,32 @@ impl TryFrom<RawAur> for Aur {
 #[derive(Deserialize)]
 struct RawBackups {
     snapshots: Option<PathBuf>,
+    remote: Option<String>,
+    encryption: Option<Cipher>,
+    recipient: Option<String>,
+    identity: Option<PathBuf>,
 }
 
 #[derive(Debug, Serialize)]
 pub(crate) struct Backups {
     pub(crate) snapshots: PathBuf,
+    /// An `rclone` destination, like `s3:bucket/aura`, to copy snapshots to.
+    pub(crate) remote: Option<String>,
+    /// How to encrypt the remote copies, if at all.
+    pub(crate) encryption: Option<Cipher>,
+    /// Who the remote copies are encrypted to: an `age` public key, or a GPG
+    /// key ID.
+    pub(crate) recipient: Option<String>,
+    /// The `age` identity file that decrypts the remote copies.
+    pub(crate) identity: Option<PathBuf>,
+}
+
+/// A tool to encrypt snapshots with before they leave the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Cipher {
+    Age,
+    Gpg,
 }
 
 impl Backups {
@@ -312,6 +958,10 @@ impl Backups {
     fn try_default() -> Result<Self, dirs::Error> {
         let g = Backups {
             snapshots: dirs::snapshot()?,
+            remote: None,
+            encryption: None,
+            recipient: None,
+            identity: None,
         };
         Ok(g)
     }
@@ -322,12 +972,162 @@ impl TryFrom<RawBackups> for Backups {
 
     fn try_from(raw: RawBackups) -> Result<Self, Self::Error> {
         let snapshots = raw.snapshots.map(Ok).unwrap_or_else(dirs::snapshot)?;
-        let g = Backups { snapshots };
+        let g = Backups {
+            snapshots,
+            remote: raw.remote,
+            encryption: raw.encryption,
+            recipient: raw.recipient,
+            identity: raw.identity,
+        };
 
         Ok(g)
     }
 }
 
+#[derive(Deserialize)]
+struct RawNetwork {
+    connect_timeout: Option<u64>,
+    read_timeout: Option<u64>,
+    retries: Option<u32>,
+    proxy: Option<String>,
+    noproxy: Option<String>,
+    limit_rate: Option<u64>,
+    #[serde(default)]
+    metered: bool,
+    metered_threshold: Option<u64>,
+    #[serde(default)]
+    faur: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Network {
+    /// Seconds to wait for a connection to be established.
+    pub(crate) connect_timeout: u64,
+    /// Seconds a transfer may stall before it's abandoned.
+    pub(crate) read_timeout: u64,
+    /// How many times to retry a request that failed for a temporary reason.
+    pub(crate) retries: u32,
+    /// A proxy to use instead of one given by `http_proxy`/`https_proxy`.
+    pub(crate) proxy: Option<String>,
+    /// Hosts to reach directly, instead of those given by `no_proxy`.
+    pub(crate) noproxy: Option<String>,
+    /// The maximum download speed of any single transfer, in KB/s.
+    pub(crate) limit_rate: Option<u64>,
+    /// Is this a metered connection, where large downloads should be avoided?
+    pub(crate) metered: bool,
+    /// On a metered connection, the size in MiB past which to ask before
+    /// downloading anything.
+    pub(crate) metered_threshold: u64,
+    /// `faur` instances to ask for package metadata, in order of preference.
+    pub(crate) faur: Vec<String>,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            proxy: None,
+            noproxy: None,
+            limit_rate: None,
+            metered: false,
+            metered_threshold: DEFAULT_METERED_THRESHOLD,
+            faur: vec![aura_core::faur::FAUR_URL.to_string()],
+        }
+    }
+}
+
+impl From<RawNetwork> for Network {
+    fn from(raw: RawNetwork) -> Self {
+        Network {
+            connect_timeout: raw.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: raw.read_timeout.unwrap_or(DEFAULT_READ_TIMEOUT),
+            retries: raw.retries.unwrap_or(DEFAULT_RETRIES),
+            proxy: raw.proxy,
+            noproxy: raw.noproxy,
+            limit_rate: raw.limit_rate,
+            metered: raw.metered,
+            metered_threshold: raw.metered_threshold.unwrap_or(DEFAULT_METERED_THRESHOLD),
+            faur: if raw.faur.is_empty() {
+                vec![aura_core::faur::FAUR_URL.to_string()]
+            } else {
+                raw.faur
+                    .into_iter()
+                    .map(|u| u.trim_end_matches('/').to_string())
+                    .collect()
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawPrompts {
+    #[serde(default)]
+    defaults: BTreeMap<String, bool>,
+    #[serde(default)]
+    confirm_count: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub(crate) struct Prompts {
+    /// The answer that pressing Enter gives, by prompt ID. Unlisted prompts
+    /// default to "yes".
+    pub(crate) defaults: BTreeMap<String, bool>,
+    /// Must destructive operations be confirmed by typing the number of
+    /// packages they affect?
+    pub(crate) confirm_count: bool,
+}
+
+impl From<RawPrompts> for Prompts {
+    fn from(raw: RawPrompts) -> Self {
+        Prompts {
+            defaults: raw.defaults,
+            confirm_count: raw.confirm_count,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawNotify {
+    webhook: Option<String>,
+    command: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub(crate) struct Notify {
+    /// A URL to `POST` a JSON summary to.
+    pub(crate) webhook: Option<String>,
+    /// A shell command to pipe a JSON summary to, like `mail -s aura admin`.
+    pub(crate) command: Option<String>,
+}
+
+impl From<RawNotify> for Notify {
+    fn from(raw: RawNotify) -> Self {
+        Notify {
+            webhook: raw.webhook,
+            command: raw.command,
+        }
+    }
+}
+
+/// Limits on how far packages may be upgraded.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct Pins {
+    /// The newest version each package may be upgraded to.
+    #[serde(default)]
+    pub(crate) packages: BTreeMap<String, String>,
+    /// Repos whose packages aren't upgraded at all.
+    #[serde(default)]
+    pub(crate) repos: BTreeSet<String>,
+}
+
+impl Pins {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.packages.is_empty() && self.repos.is_empty()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -338,6 +1138,7 @@ mod test {
         assert!(e.general.is_none());
         assert!(e.aur.is_none());
         assert!(e.backups.is_none());
+        assert!(e.network.is_none());
     }
 
     #[test]
@@ -356,4 +1157,15 @@ mod test {
         let exp: HashSet<_> = ["foo".to_string(), "bar".to_string()].into();
         assert_eq!(exp, aur.ignores);
     }
+
+    #[test]
+    fn pins() {
+        let raw = "[pins]\nrepos = [\"testing\"]\n\n[pins.packages]\nlinux = \"6.1.12\"\n";
+        let pins = toml::from_str::<RawEnv>(raw).unwrap().pins.unwrap();
+        assert!(pins.repos.contains("testing"));
+        assert_eq!(
+            Some("6.1.12"),
+            pins.packages.get("linux").map(|v| v.as_str())
+        );
+    }
 }
@@ -2,9 +2,12 @@
 
 use crate::localization::Localised;
 use aura_core::aur::dependencies as deps;
+use aura_core::Coded;
 use from_variants::FromVariants;
 use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
 use log::error;
+use serde_json::json;
 
 /// Error type for all issues that can occur in the Aura library or executable.
 #[derive(FromVariants)]
@@ -12,7 +15,9 @@ pub(crate) enum Error {
     A(crate::command::aur::Error),
     B(crate::command::snapshot::Error),
     C(crate::command::cache::Error),
+    Canary(crate::command::canary::Error),
     L(crate::log::Error),
+    Lock(crate::lock::Error),
     O(crate::command::orphans::Error),
     Dirs(crate::dirs::Error),
     /// A non-zero exit code was returned from a call to Pacman.
@@ -20,26 +25,77 @@ pub(crate) enum Error {
     Env(crate::env::Error),
     Conf(crate::conf::Error),
     Check(crate::check::Error),
+    Clean(crate::clean::Error),
+    Debug(crate::debug::Error),
+    Info(crate::info::Error),
+    Lang(crate::lang::Error),
     Open(crate::open::Error),
+    Optdeps(crate::optdeps::Error),
+    Pin(crate::pin::Error),
+    External(crate::external::Error),
+    Migrate(crate::migrate::Error),
+    Provides(crate::provides::Error),
+    Select(crate::select::Error),
+    Setup(crate::setup::Error),
     Stats(crate::stats::Error),
+    Switch(crate::switch::Error),
+    Report(crate::report::Error),
+    Search(crate::search::Error),
+    Upgrade(crate::upgrade::Error),
+    Verify(crate::verify::Error),
+    /// A `-Su` wouldn't fit on disk.
+    Space(crate::space::Shortfall),
+    /// A read-only command tried to change the system.
+    ReadOnly,
+    /// Some packages weren't built, the user having chosen to skip past them.
+    #[from_variants(skip)]
+    Incomplete(usize),
 }
 
 impl Nested for Error {
     /// Log nested errors.
     fn nested(&self) {
+        let context = self
+            .context()
+            .into_iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>();
+        error!("[{}] {}", self.code(), context.join(" "));
+
         match self {
             Error::A(e) => e.nested(),
             Error::B(e) => e.nested(),
             Error::C(e) => e.nested(),
+            Error::Canary(e) => e.nested(),
             Error::L(e) => e.nested(),
+            Error::Lock(e) => e.nested(),
             Error::O(e) => e.nested(),
             Error::Dirs(e) => e.nested(),
             Error::Pacman(e) => e.nested(),
             Error::Env(e) => e.nested(),
             Error::Conf(e) => e.nested(),
             Error::Check(e) => e.nested(),
+            Error::Clean(e) => e.nested(),
+            Error::Debug(e) => e.nested(),
+            Error::Info(e) => e.nested(),
             Error::Open(e) => e.nested(),
+            Error::Optdeps(e) => e.nested(),
+            Error::Pin(e) => e.nested(),
+            Error::External(e) => e.nested(),
+            Error::Migrate(e) => e.nested(),
+            Error::Provides(e) => e.nested(),
+            Error::Select(e) => e.nested(),
+            Error::Setup(e) => e.nested(),
+            Error::Lang(e) => e.nested(),
             Error::Stats(e) => e.nested(),
+            Error::Switch(e) => e.nested(),
+            Error::Report(e) => e.nested(),
+            Error::Search(e) => e.nested(),
+            Error::Upgrade(e) => e.nested(),
+            Error::Verify(e) => e.nested(),
+            Error::Space(_) => {}
+            Error::ReadOnly => {}
+            Error::Incomplete(_) => {}
         }
     }
 }
@@ -50,17 +106,170 @@ impl Localised for Error {
             Error::A(e) => e.localise(fll),
             Error::B(e) => e.localise(fll),
             Error::C(e) => e.localise(fll),
+            Error::Canary(e) => e.localise(fll),
             Error::L(e) => e.localise(fll),
+            Error::Lock(e) => e.localise(fll),
             Error::O(e) => e.localise(fll),
             Error::Dirs(e) => e.localise(fll),
             Error::Pacman(e) => e.localise(fll),
             Error::Env(e) => e.localise(fll),
             Error::Conf(e) => e.localise(fll),
             Error::Check(e) => e.localise(fll),
+            Error::Clean(e) => e.localise(fll),
+            Error::Debug(e) => e.localise(fll),
+            Error::Info(e) => e.localise(fll),
             Error::Open(e) => e.localise(fll),
+            Error::Optdeps(e) => e.localise(fll),
+            Error::Pin(e) => e.localise(fll),
+            Error::External(e) => e.localise(fll),
+            Error::Migrate(e) => e.localise(fll),
+            Error::Provides(e) => e.localise(fll),
+            Error::Select(e) => e.localise(fll),
+            Error::Setup(e) => e.localise(fll),
+            Error::Lang(e) => e.localise(fll),
             Error::Stats(e) => e.localise(fll),
+            Error::Switch(e) => e.localise(fll),
+            Error::Report(e) => e.localise(fll),
+            Error::Search(e) => e.localise(fll),
+            Error::Upgrade(e) => e.localise(fll),
+            Error::Verify(e) => e.localise(fll),
+            Error::Space(s) => s.localise(fll),
+            Error::ReadOnly => fl!(fll, "err-read-only"),
+            Error::Incomplete(n) => fl!(fll, "A-incomplete", count = n),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::A(e) => e.code(),
+            Error::B(e) => e.code(),
+            Error::C(e) => e.code(),
+            Error::Canary(e) => e.code(),
+            Error::L(e) => e.code(),
+            Error::Lock(e) => e.code(),
+            Error::O(e) => e.code(),
+            Error::Dirs(e) => e.code(),
+            Error::Pacman(e) => e.code(),
+            Error::Env(e) => e.code(),
+            Error::Conf(e) => e.code(),
+            Error::Check(e) => e.code(),
+            Error::Clean(e) => e.code(),
+            Error::Debug(e) => e.code(),
+            Error::Info(e) => e.code(),
+            Error::Open(e) => e.code(),
+            Error::Optdeps(e) => e.code(),
+            Error::Pin(e) => e.code(),
+            Error::External(e) => e.code(),
+            Error::Migrate(e) => e.code(),
+            Error::Provides(e) => e.code(),
+            Error::Select(e) => e.code(),
+            Error::Setup(e) => e.code(),
+            Error::Lang(e) => e.code(),
+            Error::Stats(e) => e.code(),
+            Error::Switch(e) => e.code(),
+            Error::Report(e) => e.code(),
+            Error::Search(e) => e.code(),
+            Error::Upgrade(e) => e.code(),
+            Error::Verify(e) => e.code(),
+            Error::Space(s) => s.code(),
+            Error::ReadOnly => "read-only",
+            Error::Incomplete(_) => "aur-incomplete",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::A(e) => e.context(),
+            Error::B(e) => e.context(),
+            Error::C(e) => e.context(),
+            Error::Canary(e) => e.context(),
+            Error::L(e) => e.context(),
+            Error::Lock(e) => e.context(),
+            Error::O(e) => e.context(),
+            Error::Dirs(e) => e.context(),
+            Error::Pacman(e) => e.context(),
+            Error::Env(e) => e.context(),
+            Error::Conf(e) => e.context(),
+            Error::Check(e) => e.context(),
+            Error::Clean(e) => e.context(),
+            Error::Debug(e) => e.context(),
+            Error::Info(e) => e.context(),
+            Error::Open(e) => e.context(),
+            Error::Optdeps(e) => e.context(),
+            Error::Pin(e) => e.context(),
+            Error::External(e) => e.context(),
+            Error::Migrate(e) => e.context(),
+            Error::Provides(e) => e.context(),
+            Error::Select(e) => e.context(),
+            Error::Setup(e) => e.context(),
+            Error::Lang(e) => e.context(),
+            Error::Stats(e) => e.context(),
+            Error::Switch(e) => e.context(),
+            Error::Report(e) => e.context(),
+            Error::Search(e) => e.context(),
+            Error::Upgrade(e) => e.context(),
+            Error::Verify(e) => e.context(),
+            Error::Space(s) => s.context(),
+            Error::ReadOnly => Vec::new(),
+            Error::Incomplete(n) => vec![("count", n.to_string())],
+        }
+    }
+}
+
+impl Error {
+    /// The status to exit with, if something more specific than a plain
+    /// failure is known.
+    pub(crate) fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::External(e) => e.exit_code(),
+            _ => None,
         }
     }
+
+    /// The names of any packages that the error reports don't exist.
+    pub(crate) fn unknown_packages(&self) -> Vec<&str> {
+        match self {
+            Error::A(crate::command::aur::Error::Aur(e)) => unknown_aur(e),
+            Error::A(crate::command::aur::Error::Deps(e)) => unknown_deps(e),
+            Error::Verify(crate::verify::Error::Aur(e)) => unknown_aur(e),
+            _ => Vec::new(),
+        }
+    }
+
+    /// A machine-readable rendering of this error, for `--json` output.
+    pub(crate) fn json(&self, fll: &FluentLanguageLoader) -> serde_json::Value {
+        let context: serde_json::Map<_, _> = self
+            .context()
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), serde_json::Value::String(v)))
+            .collect();
+
+        json!({
+            "error": {
+                "code": self.code(),
+                "message": self.localise(fll),
+                "context": context,
+            }
+        })
+    }
+}
+
+fn unknown_aur(e: &aura_core::aur::Error) -> Vec<&str> {
+    match e {
+        aura_core::aur::Error::PackageDoesNotExist(p) => vec![p.as_str()],
+        _ => Vec::new(),
+    }
+}
+
+fn unknown_deps<E>(e: &deps::Error<E>) -> Vec<&str> {
+    match e {
+        deps::Error::DoesntExist(p) => vec![p.as_str()],
+        deps::Error::DoesntExistWithParent(_, p) => vec![p.as_str()],
+        deps::Error::Resolutions(es) => es.iter().flat_map(unknown_deps).collect(),
+        _ => Vec::new(),
+    }
 }
 
 /// Do something with the nested errors of this type.
@@ -79,6 +288,8 @@ impl Nested for aura_core::git::Error {
             aura_core::git::Error::Clone(_) => {}
             aura_core::git::Error::Pull(_) => {}
             aura_core::git::Error::Diff(_) => {}
+            aura_core::git::Error::Push(_) => {}
+            aura_core::git::Error::Checkout(_) => {}
             aura_core::git::Error::ReadHash(e) => error!("{e}"),
         }
     }
@@ -0,0 +1,280 @@
+//! Changes to `/etc` made by package transactions.
+//!
+//! With `etc_diff` enabled in the `[general]` table of `aura.toml`, the
+//! metadata and SHA-256 of every file in `/etc` is noted before Aura hands a
+//! transaction to `pacman`, and compared once it's done. Contents are never
+//! kept. Each file that changed is reported along with the package that owns
+//! it, and how that package changed, so that later breakage can be tied to a
+//! specific update.
+
+use crate::aura;
+use crate::env::Env;
+use alpm::Alpm;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Not;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+const ETC: &str = "/etc";
+
+/// What's known about a file, short of its contents.
+#[derive(Debug, Clone)]
+struct Meta {
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    size: u64,
+    mtime: i64,
+    ino: u64,
+    /// Absent for files that couldn't be read.
+    sha256: Option<String>,
+}
+
+impl Meta {
+    /// Could the file have been written since?
+    fn touched(&self, other: &Meta) -> bool {
+        self.size != other.size || self.mtime != other.mtime || self.ino != other.ino
+    }
+}
+
+/// How a file in `/etc` changed.
+#[derive(Debug, PartialEq, Eq)]
+enum Change {
+    Added,
+    Removed,
+    Contents,
+    /// Its mode or owner.
+    Metadata,
+}
+
+/// The state of `/etc` before some transaction began.
+pub(crate) struct Watch {
+    files: HashMap<PathBuf, Meta>,
+    versions: HashMap<String, String>,
+}
+
+impl Watch {
+    /// Note the state of `/etc` now, if that's been asked for.
+    pub(crate) fn start(env: &Env) -> Option<Watch> {
+        if env.general.etc_diff.not() {
+            return None;
+        }
+
+        let alpm = env.alpm().ok()?;
+        let mut files = scan(Path::new(ETC));
+        let paths: Vec<&PathBuf> = files.keys().collect();
+        let hashes = crate::utils::sha256s(&paths);
+
+        for (path, hash) in hashes {
+            if let Some(m) = files.get_mut(&path) {
+                m.sha256 = Some(hash);
+            }
+        }
+
+        debug!("Noted {} files in {ETC}", files.len());
+
+        Some(Watch {
+            files,
+            versions: versions(&alpm),
+        })
+    }
+
+    /// Report what changed in `/etc` since the watch began.
+    pub(crate) fn finish(self, fll: &FluentLanguageLoader, env: &Env) {
+        let mut after = scan(Path::new(ETC));
+
+        // Only files that might have been written need hashing again.
+        let touched: Vec<PathBuf> = after
+            .iter()
+            .filter(|(p, m)| self.files.get(*p).map(|b| b.touched(m)).unwrap_or(true))
+            .map(|(p, _)| p.clone())
+            .collect();
+        let mut hashes = crate::utils::sha256s(&touched);
+
+        for (path, m) in after.iter_mut() {
+            m.sha256 = match hashes.remove(path) {
+                Some(h) => Some(h),
+                None => self.files.get(path).and_then(|b| b.sha256.clone()),
+            };
+        }
+
+        let changes = diff(&self.files, &after);
+        if changes.is_empty() {
+            aura!(fll, "etc-unchanged");
+            return;
+        }
+
+        let alpm = match env.alpm() {
+            Ok(a) => a,
+            Err(_) => return,
+        };
+        let owners = owners(&alpm);
+        let now = versions(&alpm);
+
+        aura!(fll, "etc-changed", count = changes.len());
+        for (path, change) in changes {
+            let symbol = match change {
+                Change::Added => "+".green(),
+                Change::Removed => "-".red(),
+                Change::Contents => "M".yellow(),
+                Change::Metadata => "~".cyan(),
+            };
+
+            // A `.pacnew` belongs to whoever owns the file it would replace.
+            let owned = path.to_str().and_then(|p| p.strip_suffix(".pacnew"));
+            let owner = owners
+                .get(owned.map(Path::new).unwrap_or(&path))
+                .map(|o| {
+                    let old = self.versions.get(o);
+                    let new = now.get(o);
+                    match (old, new) {
+                        (Some(a), Some(b)) if a != b => format!("({} {a} -> {b})", o.cyan()),
+                        _ => format!("({})", o.cyan()),
+                    }
+                })
+                .unwrap_or_default();
+
+            println!("  {} {} {}", symbol, path.display(), owner);
+        }
+    }
+}
+
+/// Every file beneath a directory. Symlinks are noted, but not followed.
+fn scan(dir: &Path) -> HashMap<PathBuf, Meta> {
+    let mut files = HashMap::new();
+
+    if let Ok(rd) = dir.read_dir() {
+        for entry in rd.filter_map(|e| e.ok()) {
+            let path = entry.path();
+
+            match entry.metadata() {
+                Ok(m) if m.is_dir() => files.extend(scan(&path)),
+                Ok(m) => {
+                    let meta = Meta {
+                        mode: m.mode() & 0o7777,
+                        uid: m.uid(),
+                        gid: m.gid(),
+                        size: m.len(),
+                        mtime: m.mtime(),
+                        ino: m.ino(),
+                        sha256: None,
+                    };
+                    files.insert(path, meta);
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    files
+}
+
+/// How each file changed, ordered by path.
+fn diff(before: &HashMap<PathBuf, Meta>, after: &HashMap<PathBuf, Meta>) -> Vec<(PathBuf, Change)> {
+    let mut changes: BTreeMap<PathBuf, Change> = BTreeMap::new();
+
+    for (path, b) in before {
+        let change = match after.get(path) {
+            None => Some(Change::Removed),
+            Some(a) if a.sha256 != b.sha256 => Some(Change::Contents),
+            // Without a hash to tell, a rewritten file is assumed to differ.
+            Some(a) if a.sha256.is_none() && a.touched(b) => Some(Change::Contents),
+            Some(a) if (a.mode, a.uid, a.gid) != (b.mode, b.uid, b.gid) => Some(Change::Metadata),
+            Some(_) => None,
+        };
+
+        if let Some(c) = change {
+            changes.insert(path.clone(), c);
+        }
+    }
+
+    for path in after.keys().filter(|p| before.contains_key(*p).not()) {
+        changes.insert(path.clone(), Change::Added);
+    }
+
+    changes.into_iter().collect()
+}
+
+/// The installed version of each package.
+fn versions(alpm: &Alpm) -> HashMap<String, String> {
+    alpm.localdb()
+        .pkgs()
+        .iter()
+        .map(|p| (p.name().to_string(), p.version().to_string()))
+        .collect()
+}
+
+/// The package that owns each file in `/etc`.
+fn owners(alpm: &Alpm) -> HashMap<PathBuf, String> {
+    alpm.localdb()
+        .pkgs()
+        .iter()
+        .flat_map(|p| {
+            p.files()
+                .files()
+                .iter()
+                .filter(|f| f.name().starts_with("etc/"))
+                .map(|f| (Path::new("/").join(f.name()), p.name().to_string()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn meta(size: u64, sha256: Option<&str>) -> Meta {
+        Meta {
+            mode: 0o644,
+            uid: 0,
+            gid: 0,
+            size,
+            mtime: 0,
+            ino: 1,
+            sha256: sha256.map(|s| s.to_string()),
+        }
+    }
+
+    #[test]
+    fn changes() {
+        let before: HashMap<PathBuf, Meta> = [
+            ("/etc/same", meta(1, Some("a"))),
+            ("/etc/edited", meta(1, Some("b"))),
+            ("/etc/gone", meta(1, Some("c"))),
+            ("/etc/chmod", meta(1, Some("d"))),
+            ("/etc/secret", meta(1, None)),
+        ]
+        .into_iter()
+        .map(|(p, m)| (PathBuf::from(p), m))
+        .collect();
+
+        let mut chmod = meta(1, Some("d"));
+        chmod.mode = 0o600;
+
+        let after: HashMap<PathBuf, Meta> = [
+            ("/etc/same", meta(1, Some("a"))),
+            ("/etc/edited", meta(2, Some("e"))),
+            ("/etc/chmod", chmod),
+            ("/etc/secret", meta(5, None)),
+            ("/etc/new.pacnew", meta(1, Some("f"))),
+        ]
+        .into_iter()
+        .map(|(p, m)| (PathBuf::from(p), m))
+        .collect();
+
+        let expected = vec![
+            (PathBuf::from("/etc/chmod"), Change::Metadata),
+            (PathBuf::from("/etc/edited"), Change::Contents),
+            (PathBuf::from("/etc/gone"), Change::Removed),
+            (PathBuf::from("/etc/new.pacnew"), Change::Added),
+            (PathBuf::from("/etc/secret"), Change::Contents),
+        ];
+
+        assert_eq!(expected, diff(&before, &after));
+    }
+}
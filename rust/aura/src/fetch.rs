@@ -1,25 +1,49 @@
 //! Fetching data from remote endpoints.
 
+use crate::env::Network;
 use crate::error::Nested;
 use crate::localization::Localised;
-use curl::easy::Easy;
-use from_variants::FromVariants;
+use aura_core::faur::FAUR_URL;
+use aura_core::timings::Phase;
+use aura_core::Coded;
+use curl::easy::{Easy, IpResolve, List, TimeCondition};
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
-use log::error;
+use log::{debug, error, warn};
 use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Once;
+use std::time::Duration;
+use std::time::SystemTime;
+
+/// Requests answered from the local cache, since the server said nothing had
+/// changed.
+static HITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Requests that had to be downloaded in full.
+static MISSES: AtomicUsize = AtomicUsize::new(0);
+
+/// How long a cached response is kept after it was last downloaded in full.
+/// Older ones are dropped the next time anything is cached, so that responses
+/// for packages no longer asked about don't pile up.
+const CACHE_AGE: Duration = Duration::from_secs(30 * crate::utils::SECS_IN_DAY);
+
+/// Has the cache been pruned during this run?
+static PRUNED: Once = Once::new();
 
-#[derive(FromVariants)]
 pub enum Error {
-    Curl(curl::Error),
-    #[from_variants(skip)]
+    /// The endpoint, the final attempt number, and what went wrong.
+    Curl(String, u32, curl::Error),
     Json(String, serde_json::Error),
 }
 
 impl Nested for Error {
     fn nested(&self) {
         match self {
-            Error::Curl(e) => error!("{e}"),
+            Error::Curl(_, _, e) => error!("{e}"),
             Error::Json(_, e) => error!("{e}"),
         }
     }
@@ -28,23 +52,335 @@ impl Nested for Error {
 impl Localised for Error {
     fn localise(&self, fll: &FluentLanguageLoader) -> String {
         match self {
-            Error::Curl(_) => fl!(fll, "err-curl"),
+            Error::Curl(url, n, _) => fl!(fll, "err-curl", url = url.as_str(), attempt = n),
             Error::Json(url, _) => fl!(fll, "err-json-decode", url = url.as_str()),
         }
     }
 }
 
-/// Fetch JSON from some given endpoint.
-pub(crate) fn fetch_json<T>(url: &str) -> Result<T, Error>
-where
-    T: DeserializeOwned,
-{
-    let mut handle = Easy::new();
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Curl(_, _, _) => "fetch-curl",
+            Error::Json(_, _) => "fetch-json",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Curl(url, attempt, _) => {
+                vec![("url", url.to_string()), ("attempt", attempt.to_string())]
+            }
+            Error::Json(url, _) => vec![("url", url.to_string())],
+        }
+    }
+}
+
+impl Network {
+    /// Fetch JSON from some given endpoint.
+    ///
+    /// A request to the main `faur` instance is tried against each configured
+    /// instance in turn, until one of them can be reached.
+    pub(crate) fn json<T>(&self, url: &str) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let _timer = aura_core::timings::time(Phase::Metadata);
+        let mirrors = self.mirrors(url);
+
+        for (m, next) in mirrors.iter().zip(mirrors.iter().skip(1)) {
+            match self.json_from(m) {
+                Err(Error::Curl(_, _, e)) => warn!("{m} failed ({e}), trying {next}"),
+                res => return res,
+            }
+        }
+
+        self.json_from(mirrors.last().map(|m| m.as_str()).unwrap_or(url))
+    }
+
+    /// The same request against each configured `faur` instance.
+    fn mirrors(&self, url: &str) -> Vec<String> {
+        match url.strip_prefix(FAUR_URL) {
+            Some(rest) => self
+                .faur
+                .iter()
+                .map(|base| format!("{base}{rest}"))
+                .collect(),
+            None => vec![url.to_string()],
+        }
+    }
+
+    /// Fetch JSON from exactly the given endpoint.
+    ///
+    /// Responses that carry an `ETag` or `Last-Modified` header are cached, and
+    /// later requests for the same URL only download anything if the server
+    /// reports a change.
+    fn json_from<T>(&self, url: &str) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let cached = Cached::load(url);
+
+        let (code, data, headers) = self.retrying(url, |handle| {
+            let mut data = Vec::new();
+            let mut headers = Vec::new();
+
+            if let Some(c) = cached.as_ref() {
+                handle.http_headers(c.conditions()?)?;
+            }
+
+            // Blocked off to allow `data` to be returned down below.
+            {
+                let mut tx = handle.transfer();
+                tx.header_function(|h| {
+                    headers.push(String::from_utf8_lossy(h).trim().to_string());
+                    true
+                })?;
+                tx.write_function(|bytes| {
+                    data.extend_from_slice(bytes);
+                    Ok(bytes.len())
+                })?;
+                tx.perform()?;
+            }
+
+            Ok((handle.response_code()?, data, headers))
+        })?;
+
+        let data = match cached {
+            Some(c) if code == 304 => {
+                debug!("Cache hit: {url}");
+                HITS.fetch_add(1, Ordering::SeqCst);
+                c.body.into_bytes()
+            }
+            _ => {
+                debug!("Cache miss: {url}");
+                MISSES.fetch_add(1, Ordering::SeqCst);
+                Cached::save(url, &headers, &data);
+                data
+            }
+        };
+
+        let json = serde_json::from_slice(&data).map_err(|e| Error::Json(url.to_string(), e))?;
+        Ok(json)
+    }
+
+    /// Download the raw contents of some URL.
+    pub(crate) fn bytes(&self, url: &str) -> Result<Vec<u8>, Error> {
+        self.retrying(url, download)
+    }
+
+    /// Download the raw contents of some URL, giving up without retrying if
+    /// the whole transfer takes longer than `limit`. For requests that aren't
+    /// worth holding up the rest of a command for.
+    pub(crate) fn bytes_within(&self, url: &str, limit: Duration) -> Result<Vec<u8>, Error> {
+        self.retrying_when(url, never, |handle| {
+            handle.timeout(limit)?;
+            download(handle)
+        })
+    }
+
+    /// Download the raw contents of some URL, but only if they've changed since
+    /// the given time. Yields nothing if the server says they haven't, and
+    /// otherwise the contents alongside their `Last-Modified` time, if given.
+    pub(crate) fn bytes_if_newer(
+        &self,
+        url: &str,
+        since: Option<SystemTime>,
+    ) -> Result<Option<(Vec<u8>, Option<SystemTime>)>, Error> {
+        let since = since
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+
+        let (code, data, modified) = self.retrying(url, |handle| {
+            let mut data = Vec::new();
+
+            handle.fetch_filetime(true)?;
+            if let Some(t) = since {
+                handle.time_condition(TimeCondition::IfModifiedSince)?;
+                handle.time_value(t)?;
+            }
+
+            {
+                let mut tx = handle.transfer();
+                tx.write_function(|bytes| {
+                    data.extend_from_slice(bytes);
+                    Ok(bytes.len())
+                })?;
+                tx.perform()?;
+            }
+
+            Ok((handle.response_code()?, data, handle.filetime()?))
+        })?;
+
+        if code == 304 {
+            debug!("Unchanged: {url}");
+            HITS.fetch_add(1, Ordering::SeqCst);
+            Ok(None)
+        } else {
+            MISSES.fetch_add(1, Ordering::SeqCst);
+            let modified = modified
+                .and_then(|t| u64::try_from(t).ok())
+                .map(|t| SystemTime::UNIX_EPOCH + Duration::from_secs(t));
+            Ok(Some((data, modified)))
+        }
+    }
+
+    /// `POST` some JSON to a URL, ignoring whatever comes back. Unlike a `GET`,
+    /// it's only retried if the request never left, since the server may have
+    /// acted on one that failed afterward.
+    pub(crate) fn post_json(&self, url: &str, body: &serde_json::Value) -> Result<(), Error> {
+        let body = body.to_string();
+
+        self.retrying_when(url, unsent, |handle| {
+            let mut headers = List::new();
+            headers.append("Content-Type: application/json")?;
+            handle.http_headers(headers)?;
+            handle.post(true)?;
+            handle.post_fields_copy(body.as_bytes())?;
+
+            let mut tx = handle.transfer();
+            tx.write_function(|bytes| Ok(bytes.len()))?;
+            tx.perform()
+        })
+    }
+
+    /// The size in bytes of the file at some URL, if the server reports one.
+    /// Nothing is downloaded.
+    pub(crate) fn content_length(&self, url: &str) -> Option<u64> {
+        self.retrying(url, |handle| {
+            handle.nobody(true)?;
+            handle.perform()?;
+            handle.content_length_download()
+        })
+        .ok()
+        .filter(|len| *len >= 0.0)
+        .map(|len| len as u64)
+    }
+
+    /// Does the server offer a file at some URL? Nothing is downloaded.
+    pub(crate) fn exists(&self, url: &str) -> bool {
+        self.retrying(url, |handle| {
+            handle.nobody(true)?;
+            handle.perform()?;
+            handle.response_code()
+        })
+        .map(|code| code == 200)
+        .unwrap_or(false)
+    }
+
+    /// Perform some transfer against the given URL, retrying with exponential
+    /// backoff if it fails for reasons that might be temporary.
+    ///
+    /// If a host can't be connected to at all, it's tried once more over IPv4
+    /// alone, in case IPv6 is what's broken.
+    pub(crate) fn retrying<T, F>(&self, url: &str, f: F) -> Result<T, Error>
+    where
+        F: FnMut(&mut Easy) -> Result<T, curl::Error>,
+    {
+        self.retrying_when(url, transient, f)
+    }
+
+    /// Like [`Network::retrying`], but only retrying failures that `worth`
+    /// allows.
+    fn retrying_when<T, F>(
+        &self,
+        url: &str,
+        worth: fn(&mut Easy, &curl::Error) -> bool,
+        mut f: F,
+    ) -> Result<T, Error>
+    where
+        F: FnMut(&mut Easy) -> Result<T, curl::Error>,
+    {
+        let mut attempt = 1;
+        let mut ipv4 = false;
+
+        loop {
+            let mut handle = Easy::new();
+            let res = self
+                .configure(&mut handle, url)
+                .and_then(|_| {
+                    if ipv4 {
+                        handle.ip_resolve(IpResolve::V4)
+                    } else {
+                        Ok(())
+                    }
+                })
+                .and_then(|_| f(&mut handle));
+
+            match res {
+                Ok(t) => return Ok(t),
+                Err(e) if ipv4.not() && e.is_couldnt_connect() => {
+                    warn!("Couldn't connect to {url}, retrying over IPv4: {e}");
+                    ipv4 = true;
+                }
+                Err(e) if attempt <= self.retries && worth(&mut handle, &e) => {
+                    warn!("Attempt {attempt} on {url} failed: {e}");
+                    std::thread::sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1)));
+                    attempt += 1;
+                }
+                Err(e) => return Err(Error::Curl(url.to_string(), attempt, e)),
+            }
+        }
+    }
+
+    /// Apply timeouts and proxy settings to a fresh handle.
+    ///
+    /// Unless overridden in `aura.toml`, curl itself respects the `http_proxy`,
+    /// `https_proxy`, and `no_proxy` environment variables.
+    fn configure(&self, handle: &mut Easy, url: &str) -> Result<(), curl::Error> {
+        handle.url(url)?;
+        handle.useragent(concat!("aura/", env!("CARGO_PKG_VERSION")))?;
+        handle.fail_on_error(true)?;
+        handle.follow_location(true)?;
+        handle.connect_timeout(Duration::from_secs(self.connect_timeout))?;
+
+        // Abort transfers that have stalled completely for too long, without
+        // putting a cap on the total time a large download may take.
+        handle.low_speed_limit(1)?;
+        handle.low_speed_time(Duration::from_secs(self.read_timeout))?;
+
+        if let Some(rate) = self.limit_rate {
+            handle.max_recv_speed(rate * 1024)?;
+        }
+
+        if let Some(proxy) = self.proxy.as_deref() {
+            handle.proxy(proxy)?;
+        }
+
+        if let Some(noproxy) = self.noproxy.as_deref() {
+            handle.noproxy(noproxy)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Is a failure worth trying again? Server-side HTTP errors are, but a missing
+/// page or a malformed URL will never succeed.
+fn transient(handle: &mut Easy, e: &curl::Error) -> bool {
+    if e.is_http_returned_error() {
+        handle.response_code().map(|c| c >= 500).unwrap_or(false)
+    } else {
+        e.is_couldnt_connect()
+            || e.is_couldnt_resolve_host()
+            || e.is_couldnt_resolve_proxy()
+            || e.is_operation_timedout()
+            || e.is_recv_error()
+            || e.is_send_error()
+            || e.is_partial_file()
+            || e.is_got_nothing()
+    }
+}
+
+/// For requests that get only one chance.
+fn never(_: &mut Easy, _: &curl::Error) -> bool {
+    false
+}
+
+/// Read the whole body of a configured handle.
+fn download(handle: &mut Easy) -> Result<Vec<u8>, curl::Error> {
     let mut data = Vec::new();
-    handle.url(url)?;
-    handle.fail_on_error(true)?;
 
-    // Blocked off to allow `data` to be borrowed again down below.
     {
         let mut tx = handle.transfer();
         tx.write_function(|bytes| {
@@ -54,6 +390,130 @@ where
         tx.perform()?;
     }
 
-    let json = serde_json::from_slice(&data).map_err(|e| Error::Json(url.to_string(), e))?;
-    Ok(json)
+    Ok(data)
+}
+
+/// Did a failure happen before the request could be sent?
+fn unsent(_: &mut Easy, e: &curl::Error) -> bool {
+    e.is_couldnt_connect() || e.is_couldnt_resolve_host() || e.is_couldnt_resolve_proxy()
+}
+
+/// How many requests were answered from the cache, and how many weren't.
+pub(crate) fn cache_stats() -> (usize, usize) {
+    (HITS.load(Ordering::SeqCst), MISSES.load(Ordering::SeqCst))
+}
+
+/// A response body, and what the server said to identify its version.
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+impl Cached {
+    fn path(url: &str) -> Option<PathBuf> {
+        crate::dirs::http_cache()
+            .ok()
+            .map(|dir| dir.join(format!("{:016x}.json", fnv(url))))
+    }
+
+    /// The cached response for a URL, if there is one.
+    fn load(url: &str) -> Option<Cached> {
+        let raw = std::fs::read_to_string(Cached::path(url)?).ok()?;
+
+        // A hash collision is unlikely, but harmless.
+        serde_json::from_str::<Cached>(&raw)
+            .ok()
+            .filter(|c| c.url == url)
+    }
+
+    /// Remember a fresh response, if the server gave a way to ask later whether
+    /// it has changed.
+    fn save(url: &str, headers: &[String], body: &[u8]) {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .rev()
+                .filter_map(|h| h.split_once(':'))
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.trim().to_string())
+        };
+
+        let etag = header("etag");
+        let last_modified = header("last-modified");
+
+        if let (Some(path), Ok(body), true) = (
+            Cached::path(url),
+            std::str::from_utf8(body),
+            etag.is_some() || last_modified.is_some(),
+        ) {
+            PRUNED.call_once(|| {
+                if let Some(dir) = path.parent() {
+                    Cached::prune(dir, SystemTime::now());
+                }
+            });
+
+            let cached = Cached {
+                url: url.to_string(),
+                etag,
+                last_modified,
+                body: body.to_string(),
+            };
+
+            if let Ok(json) = serde_json::to_string(&cached) {
+                if let Err(e) = std::fs::write(&path, json) {
+                    warn!("Failed to cache {url}: {e}");
+                }
+            }
+        }
+    }
+
+    /// Remove every cached response older than [`CACHE_AGE`].
+    fn prune(dir: &Path, now: SystemTime) {
+        let old = dir
+            .read_dir()
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.metadata()
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|t| now.duration_since(t).ok())
+                    .map(|age| age > CACHE_AGE)
+                    .unwrap_or(false)
+            });
+
+        for e in old {
+            if let Err(err) = std::fs::remove_file(e.path()) {
+                debug!("Failed to prune {}: {err}", e.path().display());
+            }
+        }
+    }
+
+    /// Headers that ask the server to only respond in full if something has
+    /// changed.
+    fn conditions(&self) -> Result<List, curl::Error> {
+        let mut list = List::new();
+
+        if let Some(etag) = self.etag.as_deref() {
+            list.append(&format!("If-None-Match: {etag}"))?;
+        }
+
+        if let Some(date) = self.last_modified.as_deref() {
+            list.append(&format!("If-Modified-Since: {date}"))?;
+        }
+
+        Ok(list)
+    }
+}
+
+/// The 64-bit FNV-1a hash, which is stable across runs and Rust versions,
+/// unlike that of the standard library.
+pub(crate) fn fnv(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
 }
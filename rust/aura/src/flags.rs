@@ -1,15 +1,57 @@
 //! Types and utilities for parsing flags from the command line.
 
 use crate::Date;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use simplelog::LevelFilter;
+use std::collections::BTreeMap;
 use std::ops::Not;
 use std::path::PathBuf;
 use unic_langid::{langid, LanguageIdentifier};
 
 /// Global options only applicable to Aura that must be removed from the
 /// top-level args list before sending it to Pacman.
-pub const AURA_GLOBALS: &[&str] = &["--english", "--japanese", "--german"];
+pub const AURA_GLOBALS: &[&str] = &[
+    "--english",
+    "--japanese",
+    "--german",
+    "--polish",
+    "--spanish",
+    "--no-pager",
+    "--explain",
+    "--timings",
+];
+
+/// Expand a user-defined alias, if the first argument names one. Aliases may
+/// refer to other aliases, but never shadow Aura's own subcommands.
+///
+/// Yields the chain of names involved if some aliases form a cycle.
+pub fn expand_aliases(
+    mut args: Vec<String>,
+    aliases: &BTreeMap<String, String>,
+) -> Result<Vec<String>, Vec<String>> {
+    let mut seen: Vec<String> = Vec::new();
+
+    loop {
+        let first = match args.get(1) {
+            Some(f) if f.starts_with('-').not() => f.clone(),
+            _ => return Ok(args),
+        };
+
+        let expansion = match aliases.get(&first) {
+            Some(e) if Args::command().find_subcommand(&first).is_none() => e,
+            _ => return Ok(args),
+        };
+
+        let cyclic = seen.contains(&first);
+        seen.push(first);
+
+        if cyclic {
+            return Err(seen);
+        }
+
+        args.splice(1..2, expansion.split_whitespace().map(|s| s.to_string()));
+    }
+}
 
 /// Commandline arguments to the Aura executable.
 #[derive(Parser, Debug)]
@@ -45,11 +87,55 @@ pub struct Args {
         display_order = 10
     )]
     pub german: bool,
+    /// Output in Polish (alias: polski).
+    #[clap(
+        group = "language",
+        long,
+        global = true,
+        alias = "polski",
+        display_order = 10
+    )]
+    pub polish: bool,
+    /// Output in Spanish (alias: español).
+    #[clap(
+        group = "language",
+        long,
+        global = true,
+        alias = "español",
+        display_order = 10
+    )]
+    pub spanish: bool,
 
     // --- Other Aura Options --- //
     /// Minimum level of Aura log messages to display.
-    #[clap(long, value_name = "level", possible_values = &["debug", "info", "warn", "error"], global = true)]
+    #[clap(long, value_name = "level", possible_values = &["trace", "debug", "info", "warn", "error"], global = true)]
     pub log_level: Option<LevelFilter>,
+    /// Display Aura's log messages: -v for info, -vv for debug, -vvv for trace.
+    #[clap(short = 'v', parse(from_occurrences), display_order = 10)]
+    pub verbosity: u8,
+    /// Also write Aura's log messages to a file, with timestamps and modules.
+    #[clap(long, value_name = "path", global = true)]
+    pub log_file: Option<PathBuf>,
+    /// Cap the speed of Aura's own downloads, in KB/s.
+    #[clap(long, value_name = "KB/s", global = true)]
+    pub limit_rate: Option<u64>,
+    /// Never send long output through a pager.
+    #[clap(long, global = true)]
+    pub no_pager: bool,
+    /// Print each external command, like `pacman` or `git`, as it's run.
+    #[clap(long, global = true)]
+    pub explain: bool,
+    /// Print how long each phase of the work took, as text or as JSON.
+    #[clap(
+        long,
+        global = true,
+        value_name = "format",
+        possible_values = &["text", "json"],
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    pub timings: Option<String>,
     /// The Pacman/Aura subcommand to run.
     #[clap(subcommand)]
     pub subcmd: SubCmd,
@@ -63,9 +149,31 @@ impl Args {
             _ if self.english => Some(langid!("en-US")),
             _ if self.german => Some(langid!("de-DE")),
             _ if self.japanese => Some(langid!("ja-JP")),
+            _ if self.polish => Some(langid!("pl-PL")),
+            _ if self.spanish => Some(langid!("es-ES")),
             _ => None,
         }
     }
+
+    /// The requested level of logging, if any. An explicit `--log-level`
+    /// takes precedence over `-v`.
+    pub fn log_level(&self) -> Option<LevelFilter> {
+        self.log_level.or(match self.verbosity {
+            0 => None,
+            1 => Some(LevelFilter::Info),
+            2 => Some(LevelFilter::Debug),
+            _ => Some(LevelFilter::Trace),
+        })
+    }
+
+    /// Was JSON output requested? Errors are then reported as JSON too.
+    pub fn is_json(&self) -> bool {
+        match &self.subcmd {
+            SubCmd::Aur(a) => a.json.is_empty().not(),
+            SubCmd::Select(s) => s.json,
+            _ => false,
+        }
+    }
 }
 
 /// The Aura Package Manager.
@@ -111,14 +219,120 @@ pub enum SubCmd {
     Orphans(Orphans),
     /// Validate your system.
     Check(Check),
+    /// Remove leftover build artifacts.
+    Clean(Clean),
     /// View various configuration settings and files.
     Conf(Conf),
+    /// Gather logs and settings into a tarball for bug reports.
+    Debug(DebugInfo),
     /// Output a dependency graph in DOT format.
     Deps(Deps),
+    /// View package information, whether it's from the repos or the AUR.
+    Info(Info),
+    /// View the aliases defined in your aura.toml.
+    Alias(Alias),
+    /// View the languages Aura is available in, and how complete each is.
+    Lang(Lang),
     /// Open various webpages related to Aura.
     Open(Open),
+    /// View and manage optional dependencies.
+    Optdeps(Optdeps),
+    /// Hold packages or whole repos back from upgrades.
+    Pin(Pin),
+    /// Find the AUR packages that provide a given file.
+    Provides(Provides),
+    /// Summarize this machine, or many machines, for managing a fleet.
+    Report(Report),
+    /// Search the official repos and the AUR at once.
+    Search(Search),
+    /// Select packages by their attributes, like 'foreign and size > 100MB'.
+    Select(Select),
+    /// Interactively create an aura.toml.
+    Setup(Setup),
+    /// Import the settings and build history of another AUR helper.
+    Migrate(Migrate),
     /// View statistics about your machine or about Aura itself.
     Stats(Stats),
+    /// Replace a package with another variant of it, like foo-bin or foo-git.
+    Switch(Switch),
+    /// Check whether an installed AUR package builds reproducibly.
+    Verify(Verify),
+    /// Compare two package versions, as pacman does.
+    Vercmp(Vercmp),
+    /// Any other subcommand is run as an `aura-<name>` executable from the PATH.
+    #[clap(external_subcommand)]
+    External(Vec<String>),
+}
+
+impl SubCmd {
+    /// Is this command only a query? Read-only commands never raise
+    /// privileges, nor write outside of Aura's own caches.
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            SubCmd::Database(d) => d.needs_sudo().not(),
+            SubCmd::Files(f) => f.needs_sudo().not(),
+            SubCmd::Query(_) => true,
+            SubCmd::Remove(r) => r.needs_sudo().not(),
+            SubCmd::Sync(s) => s.needs_sudo().not(),
+            SubCmd::DepTest(_) => true,
+            SubCmd::Upgrade(u) => u.needs_sudo().not(),
+            SubCmd::Aur(a) => {
+                a.info.is_empty().not()
+                    || a.search.is_empty().not()
+                    || a.json.is_empty().not()
+                    || a.open.is_some()
+                    || a.pkgbuild.is_some()
+            }
+            SubCmd::Backup(b) => b.list || b.diff,
+            SubCmd::Cache(c) => {
+                c.info.is_empty().not()
+                    || c.search.is_some()
+                    || c.list
+                    || c.missing
+                    || c.owns.is_some()
+            }
+            SubCmd::Log(_) => true,
+            SubCmd::Orphans(o) => o.abandon.not() && o.adopt.is_empty(),
+            SubCmd::Check(c) => {
+                c.self_check.not() && c.keys.not() && c.fix.not() && c.suggest_only.not()
+            }
+            SubCmd::Clean(_) => false,
+            SubCmd::Conf(_) => true,
+            SubCmd::Debug(_) => false,
+            SubCmd::Deps(_) => true,
+            SubCmd::Info(_) => true,
+            SubCmd::Alias(_) => true,
+            SubCmd::Open(_) => true,
+            SubCmd::Optdeps(o) => o.unused.not(),
+            SubCmd::Pin(p) => p.add.is_empty() && p.repo.is_empty() && p.remove.is_empty(),
+            SubCmd::Provides(_) => true,
+            SubCmd::Report(_) => true,
+            SubCmd::Search(_) => true,
+            SubCmd::Select(_) => true,
+            SubCmd::Setup(_) => false,
+            SubCmd::Migrate(_) => false,
+            SubCmd::Stats(_) => true,
+            SubCmd::Lang(_) => true,
+            SubCmd::Switch(_) => false,
+            SubCmd::Verify(_) => false,
+            SubCmd::Vercmp(_) => true,
+            // Nothing is known about what these do.
+            SubCmd::External(_) => false,
+        }
+    }
+
+    /// Might this command print screens' worth of output? Then it's worth
+    /// paging, so long as it never asks anything along the way.
+    pub fn is_pageable(&self) -> bool {
+        match self {
+            SubCmd::Aur(a) => a.info.is_empty().not() || a.search.is_empty().not(),
+            SubCmd::Check(c) => self.is_read_only() && c.updates.not(),
+            SubCmd::Info(_) => true,
+            SubCmd::Lang(l) => l.missing.is_some(),
+            SubCmd::Search(_) => true,
+            _ => false,
+        }
+    }
 }
 
 /// Synchronize official packages.
@@ -266,11 +480,19 @@ pub struct Sync {
     /// Operate on a mounted guest system (root-only).
     #[clap(long)]
     sysroot: bool,
+    /// Upgrade this many leaf packages first, and ask before upgrading the rest.
+    #[clap(long, value_name = "N", requires = "sysupgrade")]
+    canary: Option<usize>,
     /// Packages to search/install.
     packages: Vec<String>,
 }
 
 impl Sync {
+    /// Is this a full system upgrade?
+    pub fn is_sysupgrade(&self) -> bool {
+        self.sysupgrade > 0
+    }
+
     /// Does this `-S` subflag need sudo?
     pub fn needs_sudo(&self) -> bool {
         (self.info.is_empty().not()
@@ -279,6 +501,31 @@ impl Sync {
             || self.print)
             .not()
     }
+
+    /// Does this install some packages without a full system upgrade?
+    pub fn is_targeted_install(&self) -> bool {
+        self.needs_sudo() && self.packages.is_empty().not() && self.is_sysupgrade().not()
+    }
+
+    /// Are the package databases refreshed first?
+    pub fn is_refresh(&self) -> bool {
+        self.refresh > 0
+    }
+
+    /// How many packages to upgrade before the rest, if the upgrade is staged.
+    pub fn canary(&self) -> Option<usize> {
+        self.canary
+    }
+
+    /// The alternate `pacman` config, database path, and installation root, if
+    /// any were given.
+    pub fn alternates(&self) -> (Option<&str>, Option<&str>, Option<&str>) {
+        (
+            self.config.as_deref(),
+            self.dbpath.as_deref(),
+            self.root.as_deref(),
+        )
+    }
 }
 
 // TODO Reconcile `pacman -Th` and the manpage entry for -T.
@@ -426,6 +673,11 @@ pub struct Upgrade {
 }
 
 impl Upgrade {
+    /// The tarballs or URLs to install.
+    pub fn targets(&self) -> &[String] {
+        &self.packages
+    }
+
     /// Does this `-U` subflag need sudo?
     pub fn needs_sudo(&self) -> bool {
         self.print.not()
@@ -790,6 +1042,12 @@ pub struct Orphans {
     /// Uninstall all orphan packages.
     #[clap(group = "orphans", long, short = 'j')]
     pub abandon: bool,
+    /// Explain how each orphan came to be one.
+    #[clap(group = "orphans", long)]
+    pub why: bool,
+
+    #[clap(flatten)]
+    pub listing: Listing,
 }
 
 /// View various configuration settings and files.
@@ -827,6 +1085,10 @@ pub struct Log {
     #[clap(group = "log", long, short, value_name = "term", display_order = 1)]
     pub search: Option<String>,
 
+    /// View every elevated command Aura has run, and how it ended.
+    #[clap(group = "log", long, display_order = 1)]
+    pub audit: bool,
+
     /// Only display log entries from before the given date.
     #[clap(long, short, value_name = "YYYY-MM-DD")]
     pub before: Option<Date>,
@@ -840,6 +1102,18 @@ pub struct Log {
     logfile: Option<PathBuf>,
 }
 
+/// View the languages Aura is available in, and how complete each is.
+#[derive(Parser, Debug)]
+pub struct Lang {
+    /// List every language, with how many of its messages are translated.
+    #[clap(group = "lang", long, short, display_order = 1)]
+    pub list: bool,
+
+    /// View the messages not yet translated into a language, like pl or de-DE.
+    #[clap(group = "lang", long, value_name = "lang", display_order = 1)]
+    pub missing: Option<String>,
+}
+
 /// View statistics about your machine or Aura itself.
 #[derive(Parser, Debug)]
 pub struct Stats {
@@ -854,6 +1128,14 @@ pub struct Stats {
     /// View the Top 10 heaviest installed packages.
     #[clap(group = "stats", long, display_order = 1)]
     pub heavy: bool,
+
+    /// Output metrics like pending updates and cache size for Prometheus.
+    #[clap(group = "stats", long, display_order = 1)]
+    pub prometheus: bool,
+
+    /// Write the metrics to a file instead, for a textfile collector.
+    #[clap(long, value_name = "path", requires = "prometheus", display_order = 2)]
+    pub output: Option<PathBuf>,
 }
 
 /// Synchronize AUR packages.
@@ -882,23 +1164,58 @@ pub struct Aur {
     )]
     pub search: Vec<String>,
 
+    /// Print the AUR's records of packages as JSON, exactly as fetched.
+    #[clap(
+        group = "aur",
+        long,
+        value_name = "packages",
+        multiple_values = true,
+        display_order = 1
+    )]
+    pub json: Vec<String>,
+
+    /// [--json] Only print these fields of each record, like Name,Version.
+    #[clap(long, value_name = "fields", value_delimiter = ',', display_order = 2)]
+    pub fields: Vec<String>,
+
     // TODO Avoid boolean blindness.
     /// [-s] Sort results alphabetically.
     #[clap(long, display_order = 2)]
     pub abc: bool,
 
+    /// [-i] Show how installed packages were built, from Aura's own records.
+    #[clap(long, display_order = 2)]
+    pub local: bool,
+
+    /// [-i] Show how votes and popularity have changed, as Aura has seen them
+    /// over time.
+    #[clap(long, conflicts_with = "local", display_order = 2)]
+    pub trend: bool,
+
     /// [-s] Limit the results to N results.
     #[clap(long, value_name = "N", display_order = 2)]
     pub limit: Option<usize>,
 
+    /// [-i/-s] Print one line per package, like '{name} {version} {votes}'.
+    #[clap(long, value_name = "template", display_order = 2)]
+    pub format: Option<String>,
+
+    #[clap(flatten)]
+    pub listing: Listing,
+
     /// [-s] Reverse the search results.
     #[clap(long, short, display_order = 2)]
     pub reverse: bool,
 
-    /// [-s] Only print matching package names.
+    /// [-s] Only print matching package names. When installing, show one
+    /// line per package.
     #[clap(long, short, display_order = 2)]
     pub quiet: bool,
 
+    /// When installing, also show dependency resolution and build commands.
+    #[clap(long, conflicts_with = "quiet", display_order = 4)]
+    pub verbose: bool,
+
     /// Open a given package's AUR package.
     #[clap(group = "aur", long, short, value_name = "package", display_order = 1)]
     pub open: Option<String>,
@@ -907,6 +1224,10 @@ pub struct Aur {
     #[clap(group = "aur", long, short, value_name = "package", display_order = 1)]
     pub pkgbuild: Option<String>,
 
+    /// [Maintainers] Validate a local AUR package repository, then push it.
+    #[clap(group = "aur", long, value_name = "dir", display_order = 1)]
+    pub push: Option<PathBuf>,
+
     /// View/edit PKGBUILDs and related build files before building.
     #[clap(long, display_order = 4)]
     pub hotedit: bool,
@@ -919,10 +1240,47 @@ pub struct Aur {
     #[clap(long, short = 'a', display_order = 4)]
     pub delmakedeps: bool,
 
+    /// Lint PKGBUILDs and built packages with namcap.
+    #[clap(long, display_order = 4)]
+    pub namcap: bool,
+
+    /// Report where each package will download its sources from before building.
+    #[clap(long, display_order = 4)]
+    pub sources: bool,
+
+    /// Build all packages before installing any of them, then install them together.
+    #[clap(long, display_order = 4)]
+    pub stage: bool,
+
+    /// Skip packages that fail to build (and their dependents) instead of stopping.
+    #[clap(long, display_order = 4)]
+    pub keep_going: bool,
+
+    /// If another Aura is already building or upgrading, wait for it to finish.
+    #[clap(long, display_order = 4)]
+    pub wait: bool,
+
+    /// When a name only partially matches several AUR packages, take the most
+    /// voted one instead of asking.
+    #[clap(long, conflicts_with = "exact", display_order = 4)]
+    pub pick_first: bool,
+
+    /// Only install packages whose names match exactly.
+    #[clap(long, display_order = 4)]
+    pub exact: bool,
+
     /// Upgrade all installed AUR packages.
     #[clap(group = "aur", long, short = 'u', display_order = 1)]
     pub sysupgrade: bool,
 
+    /// [-u] Choose which of the available upgrades to apply.
+    #[clap(long, display_order = 3)]
+    pub pick: bool,
+
+    /// [-u] Show the AUR commits behind each upgrade.
+    #[clap(long, display_order = 3)]
+    pub changes: bool,
+
     /// [-u] Rebuild all git/svn/hg/etc. packages as well.
     #[clap(long, display_order = 3)]
     pub git: bool,
@@ -936,6 +1294,62 @@ pub struct Aur {
     )]
     pub ignore: Vec<String>,
 
+    /// Leave a package's build directory untouched after building (can be used
+    /// more than once).
+    #[clap(
+        long,
+        value_name = "package",
+        multiple_occurrences = true,
+        display_order = 4
+    )]
+    pub keep: Vec<String>,
+
+    /// Build packages within a different directory. If it's a tmpfs that's too
+    /// small for a package, that package is built on disk instead.
+    #[clap(long, value_name = "path", display_order = 4)]
+    pub builddir: Option<PathBuf>,
+
+    /// Keep clones of AUR repositories in a different directory.
+    #[clap(long, value_name = "path", display_order = 4)]
+    pub clonedir: Option<PathBuf>,
+
+    /// Build with one of the [aur.profiles] of aura.toml.
+    #[clap(long, value_name = "name", display_order = 4)]
+    pub profile: Option<String>,
+
+    /// Don't start heavy builds while on battery or while the CPU is hot.
+    #[clap(long, display_order = 4)]
+    pub defer_on_battery: bool,
+
+    /// Skip the test suites of packages, unless configured to always run them.
+    #[clap(long, display_order = 4)]
+    pub nocheck: bool,
+
+    /// Stop any build that takes longer than this many minutes.
+    #[clap(long, value_name = "minutes", display_order = 4)]
+    pub timeout: Option<u64>,
+
+    /// Build with this niceness, from -20 to 19.
+    #[clap(long, value_name = "n", allow_hyphen_values = true, display_order = 4)]
+    pub nice: Option<i32>,
+
+    /// Build with this I/O scheduling class.
+    #[clap(
+        long,
+        value_name = "class",
+        possible_values = ["idle", "best-effort", "realtime"],
+        display_order = 4
+    )]
+    pub ionice: Option<String>,
+
+    /// Build within a systemd scope with this much memory at most, like 4G.
+    #[clap(long, value_name = "size", display_order = 4)]
+    pub memory_max: Option<String>,
+
+    /// Build within a systemd scope with this CPU quota, like 200%.
+    #[clap(long, value_name = "percent", display_order = 4)]
+    pub cpu_quota: Option<String>,
+
     /// Clone a package's AUR repository, but don't build anything.
     #[clap(
         group = "aur",
@@ -955,10 +1369,37 @@ pub struct Aur {
     #[clap(long, short = 'y', display_order = 1)]
     pub refresh: bool,
 
+    /// Build and install every package in a list, like the `pacman -Qqm` of
+    /// another machine.
+    #[clap(group = "aur", long, value_name = "pkglist", display_order = 1)]
+    pub rebuild_from: Option<PathBuf>,
+
+    /// Rebuild the AUR packages left behind by a new Python, Perl, or GHC.
+    #[clap(group = "aur", long, display_order = 1)]
+    pub stale: bool,
+
     /// Packages to install.
     pub packages: Vec<String>,
 }
 
+/// How to sort and filter a listing. Each listing has its own fields, which a
+/// mistaken field name lists.
+#[derive(clap::Args, Debug)]
+pub struct Listing {
+    /// Sort by some field, or in reverse if it starts with '-', like -votes.
+    #[clap(
+        long,
+        value_name = "field",
+        allow_hyphen_values = true,
+        display_order = 5
+    )]
+    pub sort: Option<String>,
+
+    /// Only list what matches, like "votes > 100 and name ~ 'python-*'".
+    #[clap(long, value_name = "expr", display_order = 5)]
+    pub filter: Option<String>,
+}
+
 /// Save and restore the global package state.
 #[derive(Parser, Debug)]
 #[clap(short_flag = 'B', long_flag = "backup")]
@@ -974,6 +1415,25 @@ pub struct Backup {
     /// Restore to a previous package snapshot.
     #[clap(group = "backup", long, short, display_order = 1)]
     pub restore: bool,
+
+    /// Show how the installed packages differ from a snapshot.
+    #[clap(group = "backup", long, short, display_order = 1)]
+    pub diff: bool,
+
+    /// Copy every local snapshot to the remote set in aura.toml.
+    #[clap(group = "backup", long, display_order = 1)]
+    pub push: bool,
+
+    /// Fetch the snapshots from the remote that aren't kept locally.
+    #[clap(group = "backup", long, display_order = 1)]
+    pub pull: bool,
+
+    /// Name the snapshot being saved, or pick one to restore or diff by name.
+    #[clap(long, short, value_name = "name", display_order = 1)]
+    pub name: Option<String>,
+
+    #[clap(flatten)]
+    pub listing: Listing,
 }
 
 /// Manage the package cache.
@@ -1018,7 +1478,29 @@ pub struct Cache {
     #[clap(group = "cache", long, short, display_order = 1)]
     pub missing: bool,
 
-    /// Packages to downgrade.
+    /// Find which cached (but not necessarily installed) tarballs own a file.
+    #[clap(group = "cache", long, short, value_name = "path", display_order = 1)]
+    pub owns: Option<PathBuf>,
+
+    /// Recompress old `.xz` and weakly compressed `.zst` tarballs to save space.
+    #[clap(group = "cache", long, display_order = 1)]
+    pub recompress: bool,
+
+    /// [--recompress] The zstd level to compress at, from 1 to 22.
+    #[clap(
+        long,
+        value_name = "N",
+        default_value = "19",
+        requires = "recompress",
+        value_parser = clap::value_parser!(u8).range(1..=22),
+        display_order = 2
+    )]
+    pub zstd_level: u8,
+
+    #[clap(flatten)]
+    pub listing: Listing,
+
+    /// Packages to downgrade (or with -c, the only packages to clean).
     pub packages: Vec<String>,
 }
 
@@ -1065,6 +1547,447 @@ pub struct Deps {
     pub packages: Vec<String>,
 }
 
+/// View package information, whether it's from the repos or the AUR.
+#[derive(Parser, Debug)]
+pub struct Info {
+    /// Packages to display information about.
+    #[clap(required = true)]
+    pub packages: Vec<String>,
+}
+
+/// View the aliases defined in your aura.toml.
+#[derive(Parser, Debug)]
+pub struct Alias {
+    /// List each alias and what it expands to (default).
+    #[clap(long, short, display_order = 1)]
+    pub list: bool,
+}
+
+/// View and manage optional dependencies.
+#[derive(Parser, Debug)]
+pub struct Optdeps {
+    /// List dependencies that are only held by other packages' optdepends.
+    #[clap(long, short, display_order = 1)]
+    pub unused: bool,
+
+    /// Packages whose optional dependencies to display.
+    pub packages: Vec<String>,
+}
+
+/// Hold packages or whole repos back from upgrades.
+#[derive(Parser, Debug)]
+pub struct Pin {
+    /// List all pins (default).
+    #[clap(long, short, display_order = 1)]
+    pub list: bool,
+
+    /// Pin packages, as NAME=VERSION to allow upgrades up to VERSION, or as
+    /// only NAME to hold them at the installed version.
+    #[clap(
+        long,
+        short,
+        value_name = "packages",
+        multiple_values = true,
+        conflicts_with_all = &["list", "remove"],
+        display_order = 1
+    )]
+    pub add: Vec<String>,
+
+    /// Hold every package of the given repos. The AUR is `aur`.
+    #[clap(
+        long,
+        value_name = "repos",
+        multiple_values = true,
+        conflicts_with_all = &["list", "remove"],
+        display_order = 1
+    )]
+    pub repo: Vec<String>,
+
+    /// Remove the pins of packages or repos.
+    #[clap(
+        long,
+        short,
+        value_name = "names",
+        multiple_values = true,
+        conflicts_with = "list",
+        display_order = 1
+    )]
+    pub remove: Vec<String>,
+}
+
+/// Find the AUR packages that provide a given file.
+#[derive(Parser, Debug)]
+pub struct Provides {
+    /// Rebuild the file index from the AUR packages in Aura's tarball cache,
+    /// before searching it if a file is also given.
+    #[clap(long, short = 'y', display_order = 1)]
+    pub refresh: bool,
+
+    /// A binary name (like `aura`) or a full path (like `/usr/bin/aura`).
+    #[clap(required_unless_present = "refresh")]
+    pub file: Option<String>,
+}
+
+/// Search the official repos and the AUR at once.
+#[derive(Parser, Debug)]
+pub struct Search {
+    /// Limit the results to N results.
+    #[clap(long, value_name = "N", display_order = 1)]
+    pub limit: Option<usize>,
+
+    /// Only print matching package names.
+    #[clap(long, short, display_order = 1)]
+    pub quiet: bool,
+
+    /// Print one line per package, like '{repo}/{name} {version}'.
+    #[clap(long, value_name = "template", display_order = 1)]
+    pub format: Option<String>,
+
+    /// Terms that must all appear in a package's name or description.
+    #[clap(required = true)]
+    pub terms: Vec<String>,
+}
+
+/// Select packages by their attributes.
+///
+/// Combine the following with 'and', 'or', 'not', and parentheses:
+///
+///   installed, foreign, orphan, explicit, dependency
+///   name == aura, name ~ 'python-*', repo != extra
+///   size > 100MB (also KB, GB, KiB, MiB, GiB)
+///   older-than 1y, newer-than 2w (also d, m)
+///
+/// Without 'installed', packages available from the repos are considered too.
+#[derive(Parser, Debug)]
+pub struct Select {
+    /// Output JSON records instead of package names.
+    #[clap(long, display_order = 1)]
+    pub json: bool,
+
+    /// Print one line per package, like '{name} {size}'.
+    #[clap(
+        long,
+        value_name = "template",
+        conflicts_with = "json",
+        display_order = 1
+    )]
+    pub format: Option<String>,
+
+    /// The expression to evaluate. Quoting it is optional.
+    #[clap(required = true, value_name = "expr")]
+    pub expr: Vec<String>,
+}
+
+/// Interactively create an aura.toml.
+#[derive(Parser, Debug)]
+pub struct Setup {}
+
+/// Import the settings and build history of another AUR helper.
+#[derive(Parser, Debug)]
+pub struct Migrate {
+    /// The helper to migrate from.
+    #[clap(long, value_name = "helper", possible_values = &["yay", "paru"])]
+    pub from: String,
+}
+
 /// Validate your system.
 #[derive(Parser, Debug)]
-pub struct Check {}
+pub struct Check {
+    /// Count pending updates from the repos, the AUR, and any commands in the
+    /// [updates] section of aura.toml.
+    #[clap(long, short, display_order = 1)]
+    pub updates: bool,
+
+    /// With --updates, print only the total (for status bars).
+    #[clap(long, requires = "updates", display_order = 2)]
+    pub bar: bool,
+
+    /// With --updates, give the last known counts without using the network.
+    #[clap(long, requires = "updates", display_order = 2)]
+    pub cached: bool,
+
+    /// With --updates, first refresh Aura's own copies of the repo databases,
+    /// leaving pacman's untouched.
+    #[clap(
+        long,
+        requires = "updates",
+        conflicts_with = "cached",
+        display_order = 2
+    )]
+    pub refresh: bool,
+
+    /// With --updates, record fresh counts for --cached and print nothing (for
+    /// timers).
+    #[clap(
+        long,
+        requires = "updates",
+        conflicts_with_all = &["cached", "bar"],
+        display_order = 2
+    )]
+    pub refresh_state: bool,
+
+    /// Compare this Aura with its latest release, and offer to rebuild it if
+    /// it came from the AUR.
+    #[clap(long = "self", conflicts_with = "updates", display_order = 1)]
+    pub self_check: bool,
+
+    /// Check the health of pacman's keyring, and offer to repair it.
+    #[clap(long, conflicts_with_all = &["updates", "self_check"], display_order = 1)]
+    pub keys: bool,
+
+    /// After checking, offer to fix what can be fixed automatically.
+    #[clap(
+        long,
+        conflicts_with_all = &["updates", "self_check", "keys"],
+        display_order = 1
+    )]
+    pub fix: bool,
+
+    /// Verify installed files against their packages, for the given packages
+    /// or for all of them.
+    #[clap(
+        long,
+        value_name = "packages",
+        multiple_values = true,
+        min_values = 0,
+        conflicts_with_all = &["updates", "self_check", "keys", "fix"],
+        display_order = 1
+    )]
+    pub files: Option<Vec<String>>,
+
+    /// Check that every installed kernel has a current image, an initramfs,
+    /// and a bootloader entry.
+    #[clap(
+        long,
+        conflicts_with_all = &["updates", "self_check", "keys", "fix", "files"],
+        display_order = 1
+    )]
+    pub boot: bool,
+
+    /// Print only the commands suggested to fix what was found, one per line.
+    /// At a terminal, offer to run those that need root.
+    #[clap(
+        long,
+        conflicts_with_all = &["updates", "self_check", "keys", "fix", "files", "boot"],
+        display_order = 1
+    )]
+    pub suggest_only: bool,
+}
+
+/// Gather logs and settings into a tarball for bug reports.
+#[derive(Parser, Debug)]
+pub struct DebugInfo {
+    /// Pack the last log, the config (with secrets removed), the last pacman
+    /// transaction, and system details into a tarball (default).
+    #[clap(long, short, display_order = 1)]
+    pub bundle: bool,
+
+    /// Also include the PKGBUILD and .SRCINFO of an AUR package (can be used
+    /// more than once).
+    #[clap(
+        long,
+        short,
+        value_name = "package",
+        multiple_occurrences = true,
+        display_order = 2
+    )]
+    pub package: Vec<String>,
+
+    /// The Aura log to include, if not the last one written by --log-file.
+    #[clap(long, value_name = "path", display_order = 2)]
+    pub log: Option<PathBuf>,
+
+    /// Time the dependency resolution of some packages over several runs, to
+    /// measure its performance.
+    #[clap(
+        long,
+        value_name = "package",
+        multiple_values = true,
+        conflicts_with = "bundle",
+        display_order = 1
+    )]
+    pub bench_resolve: Vec<String>,
+}
+
+/// Remove leftover build artifacts.
+#[derive(Parser, Debug)]
+pub struct Clean {
+    /// Remove the build directories of AUR packages, after reporting their
+    /// sizes. Otherwise they're only reported.
+    #[clap(long, short, display_order = 1)]
+    pub builds: bool,
+
+    /// Spare the build directory of a package (can be used more than once).
+    #[clap(
+        long,
+        short,
+        value_name = "package",
+        multiple_occurrences = true,
+        display_order = 2
+    )]
+    pub keep: Vec<String>,
+}
+
+/// Summarize this machine, or many machines, for managing a fleet.
+#[derive(Parser, Debug)]
+pub struct Report {
+    /// Print a one-line JSON report about this machine.
+    #[clap(group = "report", long, display_order = 1)]
+    pub emit: bool,
+
+    /// With --emit, clear-sign the report with gpg.
+    #[clap(long, requires = "emit", display_order = 2)]
+    pub sign: bool,
+
+    /// Merge the reports in a directory into one table.
+    #[clap(group = "report", long, value_name = "dir", display_order = 1)]
+    pub aggregate: Option<PathBuf>,
+
+    /// With --aggregate, the fingerprint of a key that reports may be signed
+    /// with. Without any, a signature is only good if its key is trusted.
+    #[clap(
+        long,
+        requires = "aggregate",
+        value_name = "fingerprint",
+        multiple_occurrences = true,
+        display_order = 2
+    )]
+    pub signer: Vec<String>,
+}
+
+/// Replace a package with another variant of it, like foo-bin or foo-git.
+#[derive(Parser, Debug)]
+pub struct Switch {
+    /// The installed package to switch away from.
+    pub package: String,
+
+    /// The variant to switch to, either named in full or by a suffix like
+    /// `bin` or `git`. Use `source` for the original package.
+    pub variant: String,
+}
+
+/// Check whether an installed AUR package builds reproducibly.
+#[derive(Parser, Debug)]
+pub struct Verify {
+    /// The installed AUR package to rebuild and compare.
+    #[clap(value_name = "package")]
+    pub package: String,
+}
+
+/// Compare two package versions, as pacman does.
+///
+/// Prints -1 if the first is older, 0 if they're equal, and 1 if it's newer.
+#[derive(Parser, Debug)]
+pub struct Vercmp {
+    /// The first version, like 1:2.3.4-5.
+    pub a: String,
+    /// The second version.
+    pub b: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn alias_expansion() {
+        let aliases: BTreeMap<String, String> = [
+            ("up", "-Au --stage"),
+            ("upp", "up --pick"),
+            ("info", "-Ai"),
+            ("loop", "pool"),
+            ("pool", "loop"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let exp = args("aura -Au --stage --pick -v");
+        assert_eq!(Ok(exp), expand_aliases(args("aura upp -v"), &aliases));
+        // Built-in subcommands are never shadowed.
+        let info = args("aura info aura");
+        assert_eq!(Ok(info.clone()), expand_aliases(info, &aliases));
+        assert!(expand_aliases(args("aura loop"), &aliases).is_err());
+    }
+
+    #[test]
+    fn json_output() {
+        let json = |s: &str| Args::parse_from(args(s)).is_json();
+
+        assert!(json("aura -A --json aura"));
+        assert!(json("aura select --json foreign"));
+        assert!(!json("aura select foreign"));
+        assert!(!json("aura -Ai aura"));
+    }
+
+    #[test]
+    fn read_only_commands() {
+        let read_only = |s: &str| Args::parse_from(args(s)).subcmd.is_read_only();
+
+        for query in [
+            "aura -Qi gcc",
+            "aura -Ss gcc",
+            "aura -Si gcc",
+            "aura -Ai aura",
+            "aura -As aura",
+            "aura -As aura --format {name}",
+            "aura -As aura --sort -votes --filter votes>10",
+            "aura -O --sort size",
+            "aura -Cl --filter name~gcc*",
+            "aura -Bl --sort -time",
+            "aura -A --json aura --fields Name,Version",
+            "aura -Ci gcc",
+            "aura -Bl",
+            "aura -Bd --name foo",
+            "aura -O",
+            "aura info gcc",
+            "aura optdeps gcc",
+            "aura pin",
+            "aura select foreign",
+            "aura search gcc",
+            "aura check",
+            "aura check --boot",
+            "aura vercmp 1 2",
+            "aura lang --list",
+            "aura lang --missing pl",
+        ] {
+            assert!(read_only(query), "{query}");
+            assert!(crate::caps::mutating(&Args::parse_from(args(query)).subcmd).is_none());
+        }
+
+        for change in [
+            "aura -Syu",
+            "aura -S gcc",
+            "aura -R gcc",
+            "aura -A aura",
+            "aura -Au",
+            "aura -C gcc",
+            "aura -Cc 2",
+            "aura -Br",
+            "aura -Oj",
+            "aura optdeps --unused",
+            "aura check --self",
+            "aura check --suggest-only",
+            "aura setup",
+            "aura foo",
+        ] {
+            assert!(read_only(change).not(), "{change}");
+        }
+    }
+
+    #[test]
+    fn pageable_commands() {
+        let pageable = |s: &str| Args::parse_from(args(s)).subcmd.is_pageable();
+
+        assert!(pageable("aura -Ai aura"));
+        assert!(pageable("aura search gcc"));
+        assert!(pageable("aura check"));
+        assert!(pageable("aura check --fix").not());
+        assert!(pageable("aura check --updates").not());
+        assert!(pageable("aura -A aura").not());
+    }
+}
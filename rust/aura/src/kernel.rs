@@ -0,0 +1,383 @@
+//! Keeping kernels and their out-of-tree modules in step.
+//!
+//! Every installed kernel has a directory in `/usr/lib/modules`, whose `pkgbase`
+//! file names the package it came from. Before an upgrade of one, the packages
+//! that build or ship modules for it are checked, since a kernel that outpaces
+//! them is the classic path to a machine that won't boot:
+//!
+//! - DKMS modules, like `nvidia-dkms`, need the kernel's headers to be built.
+//! - Prebuilt modules, like `zfs-linux`, depend on an exact kernel version.
+//!
+//! DKMS builds its modules from a `pacman` hook, whose failures are easy to
+//! miss among the rest of a transaction's output. So after any transaction that
+//! changed a kernel or a DKMS module, `dkms status` is checked for a built and
+//! installed module for every kernel.
+
+use crate::env::Env;
+use crate::{green, red, yellow};
+use alpm::Alpm;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub(crate) const MODULES: &str = "/usr/lib/modules";
+
+/// Where DKMS keeps the sources and build logs of its modules.
+const DKMS_TREE: &str = "/var/lib/dkms";
+
+/// The versions of the kernels and DKMS modules before some transaction.
+pub(crate) struct Watch {
+    versions: HashMap<String, String>,
+}
+
+impl Watch {
+    /// Note the versions of the kernels and DKMS modules now, if DKMS is in use.
+    pub(crate) fn start(env: &Env) -> Option<Watch> {
+        which::which("dkms").ok()?;
+        let alpm = env.alpm().ok()?;
+
+        Some(Watch {
+            versions: watched(&alpm),
+        })
+    }
+
+    /// Check that DKMS built everything, if the transaction changed anything
+    /// that it builds for.
+    pub(crate) fn finish(self, fll: &FluentLanguageLoader, env: &Env) {
+        let changed = env
+            .alpm()
+            .map(|alpm| watched(&alpm) != self.versions)
+            .unwrap_or(false);
+
+        if changed {
+            verify(fll);
+        }
+    }
+}
+
+/// The versions of the installed kernels and DKMS modules.
+fn watched(alpm: &Alpm) -> HashMap<String, String> {
+    let kernels = kernels();
+    let dkms = dkms_modules(alpm);
+
+    alpm.localdb()
+        .pkgs()
+        .iter()
+        .filter(|p| kernels.contains(p.name()) || dkms.contains(&p.name()))
+        .map(|p| (p.name().to_string(), p.version().to_string()))
+        .collect()
+}
+
+/// A line of `dkms status`.
+#[derive(Debug, PartialEq, Eq)]
+struct Status {
+    module: String,
+    version: String,
+    kernel: Option<String>,
+    state: String,
+}
+
+/// Report any DKMS module that wasn't built and installed for every kernel.
+fn verify(fll: &FluentLanguageLoader) {
+    let raw = match Command::new("dkms").arg("status").output() {
+        Ok(o) => String::from_utf8_lossy(&o.stdout).into_owned(),
+        Err(e) => {
+            debug!("Couldn't run dkms: {e}");
+            return;
+        }
+    };
+
+    let failed = failures(&statuses(&raw), &releases());
+
+    if failed.is_empty() {
+        green!(fll, "kernel-dkms-ok");
+        return;
+    }
+
+    for (module, version, kernel) in failed {
+        let m = format!("{module}/{version}").cyan().to_string();
+        red!(
+            fll,
+            "kernel-dkms-failed",
+            module = m,
+            kernel = kernel.as_str()
+        );
+
+        let log: PathBuf = [DKMS_TREE, &module, &version, "build", "make.log"]
+            .iter()
+            .collect();
+        if log.is_file() {
+            let path = log.display().to_string();
+            println!("      └─ {}", fl!(fll, "kernel-dkms-log", path = path));
+        }
+    }
+}
+
+/// Parse the output of `dkms status`, in the forms of both DKMS 2 and 3:
+///
+/// ```text
+/// nvidia/550.54.14, 6.7.9-arch1-1, x86_64: installed
+/// nvidia, 550.54.14, 6.7.9-arch1-1, x86_64: installed
+/// zfs/2.2.3: added
+/// ```
+fn statuses(raw: &str) -> Vec<Status> {
+    raw.lines()
+        .filter_map(|line| {
+            let (left, state) = line.rsplit_once(": ")?;
+            let mut fields = left.split(", ");
+            let first = fields.next()?;
+            let (module, version) = match first.split_once('/') {
+                Some((m, v)) => (m, v),
+                None => (first, fields.next()?),
+            };
+
+            Some(Status {
+                module: module.to_string(),
+                version: version.to_string(),
+                kernel: fields.next().map(|k| k.to_string()),
+                state: state.split_whitespace().next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The latest version of every module that isn't installed for some kernel,
+/// along with that kernel.
+fn failures(statuses: &[Status], kernels: &[String]) -> Vec<(String, String, String)> {
+    let mut latest: BTreeMap<&str, &str> = BTreeMap::new();
+    for s in statuses {
+        let newer = latest
+            .get(s.module.as_str())
+            .map(|v| aura_core::version::cmp(&s.version, v) == Ordering::Greater)
+            .unwrap_or(true);
+        if newer {
+            latest.insert(&s.module, &s.version);
+        }
+    }
+
+    latest
+        .into_iter()
+        .flat_map(|(module, version)| {
+            kernels
+                .iter()
+                .filter(move |k| {
+                    statuses
+                        .iter()
+                        .any(|s| {
+                            s.module == module
+                                && s.version == version
+                                && s.kernel.as_deref() == Some(k.as_str())
+                                && s.state == "installed"
+                        })
+                        .not()
+                })
+                .map(move |k| (module.to_string(), version.to_string(), k.clone()))
+        })
+        .collect()
+}
+
+/// The releases of the installed kernels, like `6.7.9-arch1-1`.
+fn releases() -> Vec<String> {
+    let mut releases: Vec<String> = Path::new(MODULES)
+        .read_dir()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join("pkgbase").is_file())
+        .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+        .collect();
+    releases.sort();
+    releases
+}
+
+/// Warn about kernel upgrades that the installed modules won't keep up with.
+/// Each upgrade is a package name, with its old and new versions.
+pub(crate) fn before(fll: &FluentLanguageLoader, alpm: &Alpm, upgrades: &[(&str, &str, &str)]) {
+    let kernels = kernels();
+    let db = alpm.localdb();
+    let upgrading: HashSet<&str> = upgrades.iter().map(|(n, _, _)| *n).collect();
+    let dkms = dkms_modules(alpm);
+
+    for (kernel, old, new) in upgrades.iter().filter(|(n, _, _)| kernels.contains(*n)) {
+        let headers = format!("{kernel}-headers");
+        let has_headers = db.pkg(headers.as_str()).is_ok();
+
+        if has_headers && upgrading.contains(headers.as_str()).not() {
+            yellow!(
+                fll,
+                "kernel-headers-behind",
+                kernel = *kernel,
+                headers = headers.as_str()
+            );
+        }
+
+        if dkms.is_empty().not() {
+            let mods = dkms.join(", ");
+
+            if has_headers.not() {
+                yellow!(
+                    fll,
+                    "kernel-headers-missing",
+                    kernel = *kernel,
+                    headers = headers.as_str(),
+                    mods = mods.as_str()
+                );
+            }
+
+            if series(old) != series(new) {
+                yellow!(
+                    fll,
+                    "kernel-dkms-series",
+                    kernel = *kernel,
+                    to = *new,
+                    mods = mods.as_str()
+                );
+            }
+        }
+
+        let mut behind: Vec<&str> = db
+            .pkgs()
+            .iter()
+            .filter(|p| upgrading.contains(p.name()).not())
+            .filter(|p| {
+                p.depends()
+                    .iter()
+                    .map(|d| d.to_string())
+                    .any(|d| is_pinned_to(&d, kernel) && satisfied(&d, kernel, new).not())
+            })
+            .map(|p| p.name())
+            .collect();
+
+        if behind.is_empty().not() {
+            behind.sort_unstable();
+            let pkgs = behind.join(", ");
+            yellow!(
+                fll,
+                "kernel-modules-behind",
+                kernel = *kernel,
+                to = *new,
+                pkgs = pkgs
+            );
+        }
+    }
+}
+
+/// The names of the installed kernel packages.
+pub(crate) fn kernels() -> HashSet<String> {
+    Path::new(MODULES)
+        .read_dir()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| std::fs::read_to_string(e.path().join("pkgbase")).ok())
+        .map(|base| base.trim().to_string())
+        .collect()
+}
+
+/// The installed packages that are built by DKMS.
+pub(crate) fn dkms_modules(alpm: &Alpm) -> Vec<&str> {
+    let mut mods: Vec<&str> = alpm
+        .localdb()
+        .pkgs()
+        .iter()
+        .filter(|p| p.depends().iter().any(|d| d.name() == "dkms"))
+        .map(|p| p.name())
+        .collect();
+    mods.sort_unstable();
+    mods
+}
+
+/// Is this dependency on some version of the given package, not just on the
+/// package itself?
+fn is_pinned_to(dep: &str, name: &str) -> bool {
+    dep.strip_prefix(name)
+        .map(|rest| rest.starts_with(&['<', '>', '='][..]))
+        .unwrap_or(false)
+}
+
+/// Would this version of a package satisfy a dependency on it, like
+/// `linux=6.5.1.arch1-1`?
+fn satisfied(dep: &str, name: &str, version: &str) -> bool {
+    let rest = match dep.strip_prefix(name) {
+        Some(r) => r,
+        None => return false,
+    };
+
+    let (op, wanted) = ["<=", ">=", "=", "<", ">"]
+        .iter()
+        .find_map(|op| rest.strip_prefix(op).map(|w| (*op, w)))
+        .unwrap_or(("", ""));
+    let ord = aura_core::version::cmp(version, wanted);
+
+    match op {
+        "" => rest.is_empty(),
+        "=" => ord.is_eq(),
+        "<=" => ord.is_le(),
+        ">=" => ord.is_ge(),
+        "<" => ord.is_lt(),
+        _ => ord.is_gt(),
+    }
+}
+
+/// The leading two components of a kernel version, like `6.5` of
+/// `6.5.1.arch1-1`. Out-of-tree modules tend to be ported once per series.
+fn series(version: &str) -> Vec<&str> {
+    let (_, v, _) = aura_core::version::parse_evr(version);
+    v.split('.').take(2).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dependencies() {
+        assert!(is_pinned_to("linux=6.5.1.arch1-1", "linux"));
+        assert!(is_pinned_to("linux", "linux").not());
+        assert!(is_pinned_to("linux-lts=6.1.55-1", "linux").not());
+
+        assert!(satisfied("linux=6.5.1.arch1-1", "linux", "6.5.1.arch1-1"));
+        assert!(satisfied("linux=6.5.1.arch1-1", "linux", "6.5.2.arch1-1").not());
+        assert!(satisfied("linux>=6.5", "linux", "6.6.1.arch1-1"));
+        assert!(satisfied("linux<6.6", "linux", "6.6.1.arch1-1").not());
+    }
+
+    #[test]
+    fn dkms_status() {
+        let raw = "nvidia/550.54.14, 6.7.9-arch1-1, x86_64: installed
+nvidia, 550.54.14, 6.1.82-1-lts, x86_64: installed (WARNING! Diff between built and installed module!)
+zfs/2.2.3: added
+";
+        let statuses = statuses(raw);
+        assert_eq!(3, statuses.len());
+        assert_eq!("installed", statuses[1].state);
+        assert_eq!(None, statuses[2].kernel);
+
+        let kernels = vec!["6.1.82-1-lts".to_string(), "6.7.9-arch1-1".to_string()];
+        let expected = vec![
+            (
+                "zfs".to_string(),
+                "2.2.3".to_string(),
+                "6.1.82-1-lts".to_string(),
+            ),
+            (
+                "zfs".to_string(),
+                "2.2.3".to_string(),
+                "6.7.9-arch1-1".to_string(),
+            ),
+        ];
+        assert_eq!(expected, failures(&statuses, &kernels));
+    }
+
+    #[test]
+    fn kernel_series() {
+        assert_eq!(series("6.5.1.arch1-1"), series("6.5.9.arch2-1"));
+        assert_ne!(series("6.5.9.arch2-1"), series("6.6.1.arch1-1"));
+    }
+}
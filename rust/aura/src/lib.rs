@@ -1,5 +1,6 @@
 //! Types and modules that need to be shared across components.
 
+pub mod caps;
 pub mod flags;
 
 use std::str::FromStr;
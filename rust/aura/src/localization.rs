@@ -1,11 +1,15 @@
 //! Utilities for localizing messages printed by the Aura executable.
 
+use crate::a;
 use crate::utils::PathStr;
 use aura_core::aur::dependencies as deps;
 use aura_core::Apply;
+use colored::*;
 use i18n_embed::fluent::{fluent_language_loader, FluentLanguageLoader};
 use i18n_embed::{I18nEmbedError, LanguageLoader};
 use i18n_embed_fl::fl;
+use log::debug;
+use once_cell::sync::OnceCell;
 use rust_embed::RustEmbed;
 use std::collections::HashMap;
 use unic_langid::LanguageIdentifier;
@@ -18,11 +22,8 @@ struct Translations;
 // libraries that do this, but they incur heavy dependencies.
 
 // TODO
-// pl-PL Polish
 // hr-HR Crotian
 // sv-SE Swedish
-// de-DE German
-// es-ES Spanish
 // pt-PT Portuguese
 // fr-FR French
 // ru-RU Russian
@@ -47,6 +48,8 @@ impl Localised for aura_core::git::Error {
             aura_core::git::Error::Clone(p) => fl!(fll, "git-clone", dir = p.utf8()),
             aura_core::git::Error::Pull(p) => fl!(fll, "git-pull", dir = p.utf8()),
             aura_core::git::Error::Diff(p) => fl!(fll, "git-diff", file = p.utf8()),
+            aura_core::git::Error::Push(p) => fl!(fll, "git-push", dir = p.utf8()),
+            aura_core::git::Error::Checkout(p) => fl!(fll, "git-checkout", dir = p.utf8()),
             aura_core::git::Error::ReadHash(_) => fl!(fll, "git-hash"),
         }
     }
@@ -113,6 +116,7 @@ where
 pub(crate) fn load(
     lang: Option<LanguageIdentifier>,
 ) -> Result<FluentLanguageLoader, I18nEmbedError> {
+    let lang = lang.map(|l| resolve(&l).unwrap_or(l));
     let loader = fluent_language_loader!();
     loader.load_languages(
         &Translations,
@@ -122,6 +126,46 @@ pub(crate) fn load(
     Ok(loader)
 }
 
+/// The localizations of one language, loaded only once the first message is
+/// looked up.
+///
+/// Dereferences to a [`FluentLanguageLoader`], so it can be passed anywhere
+/// one is expected. Should the chosen language fail to load, English is used
+/// instead.
+pub(crate) struct Lazy {
+    /// Chosen on the command line, else taken from `aura.toml` when loading.
+    lang: Option<LanguageIdentifier>,
+    loader: OnceCell<FluentLanguageLoader>,
+}
+
+impl Lazy {
+    /// Prepare to localise in some language, or in that of `aura.toml`.
+    pub(crate) fn new(lang: Option<LanguageIdentifier>) -> Lazy {
+        Lazy {
+            lang,
+            loader: OnceCell::new(),
+        }
+    }
+}
+
+impl std::ops::Deref for Lazy {
+    type Target = FluentLanguageLoader;
+
+    fn deref(&self) -> &FluentLanguageLoader {
+        self.loader.get_or_init(|| {
+            let lang = self.lang.clone().or_else(crate::env::language);
+            debug!("Loading localizations for {:?}", lang);
+
+            // Kept off `stdout`, which may well be `--json` by now.
+            load(lang).unwrap_or_else(|e| {
+                eprintln!("{}", a!("Failed to localise Aura!".red()));
+                eprintln!("\n  {}", e);
+                load(None).unwrap_or_else(|_| fluent_language_loader!())
+            })
+        })
+    }
+}
+
 /// Like [`load`], but loads all available languages.
 ///
 /// There is no guarantee about what language will end up the default, so this
@@ -142,15 +186,30 @@ pub(crate) fn load_all() -> Result<HashMap<LanguageIdentifier, FluentLanguageLoa
 /// The list of languages that Aura has localization files for (e.g. `en-US`).
 pub(crate) fn available_languages() -> Vec<LanguageIdentifier> {
     let mut vec: Vec<_> = Translations::iter()
-        .filter_map(|l| l[0..5].parse().ok())
+        .filter_map(|l| l.split('/').next().and_then(|d| d.parse().ok()))
         .collect();
     vec.sort();
+    vec.dedup();
     vec
 }
 
+/// The available language closest to the one asked for: that very language if
+/// Aura has it, else the same language of another region, like `de-DE` for `de`
+/// or `de-AT`.
+pub(crate) fn resolve(lang: &LanguageIdentifier) -> Option<LanguageIdentifier> {
+    let available = available_languages();
+
+    available
+        .iter()
+        .find(|l| *l == lang)
+        .or_else(|| available.iter().find(|l| l.language == lang.language))
+        .cloned()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use unic_langid::langid;
 
     /// Prove that localizations don't contain extra fields that aren't expected in
     /// English, the base language.
@@ -168,4 +227,12 @@ mod test {
             })
         }
     }
+
+    #[test]
+    fn region_fallback() {
+        assert_eq!(Some(langid!("de-DE")), resolve(&langid!("de")));
+        assert_eq!(Some(langid!("es-ES")), resolve(&langid!("es-MX")));
+        assert_eq!(Some(langid!("pl-PL")), resolve(&langid!("pl-PL")));
+        assert_eq!(None, resolve(&langid!("xx")));
+    }
 }
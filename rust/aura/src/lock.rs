@@ -0,0 +1,224 @@
+//! Keep two Aura runs from doing the same kind of work at the same time.
+//!
+//! Two overlapping `-Au` runs (say, one manual and one from a timer) would
+//! otherwise fight over the same build directories and over `pacman`'s own
+//! lock. The locks are kept in one place for every user, so that a run by
+//! root and one by a user see each other.
+//!
+//! A lock is an `flock(2)` on a file that's never removed. The kernel drops it
+//! when its holder exits, however that happens, so no lock is ever left stale.
+//! The file itself only describes the run holding it.
+
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::yellow;
+use aura::caps::Mutating;
+use aura_core::Coded;
+use colored::*;
+use from_variants::FromVariants;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+use std::ops::Not;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+/// How long to give a run that just took a lock to say who it is.
+const SETTLE: Duration = Duration::from_millis(100);
+
+#[derive(FromVariants)]
+pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    #[from_variants(skip)]
+    Held(Holder),
+    #[from_variants(skip)]
+    FileWrite(PathBuf, std::io::Error),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Dirs(e) => e.nested(),
+            Error::Held(_) => {}
+            Error::FileWrite(_, e) => error!("{e}"),
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Dirs(e) => e.localise(fll),
+            Error::Held(h) => fl!(
+                fll,
+                "lock-held",
+                cmd = h.command.as_str(),
+                time = h.started.as_str()
+            ),
+            Error::FileWrite(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Dirs(e) => e.code(),
+            Error::Held(_) => "lock-held",
+            Error::FileWrite(_, _) => "lock-file-write",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::Dirs(e) => e.context(),
+            Error::Held(h) => vec![
+                ("pid", h.pid.to_string()),
+                ("command", h.command.clone()),
+                ("started", h.started.clone()),
+            ],
+            Error::FileWrite(p, _) => vec![("path", p.display().to_string())],
+        }
+    }
+}
+
+/// The Aura run that holds a lock.
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct Holder {
+    pid: u32,
+    command: String,
+    started: String,
+}
+
+impl Holder {
+    fn current() -> Holder {
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let started = now
+            .format(format_description!(
+                "[year]-[month]-[day] [hour]:[minute]:[second]"
+            ))
+            .unwrap_or_default();
+
+        Holder {
+            pid: std::process::id(),
+            command: std::env::args().collect::<Vec<_>>().join(" "),
+            started,
+        }
+    }
+
+    /// The run described in a lock file, if it's said who it is yet.
+    fn read(path: &Path) -> Option<Holder> {
+        let s = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&s).ok()
+    }
+}
+
+/// Proof that this run holds some lock. Dropping it releases the lock.
+pub(crate) struct Lock {
+    path: PathBuf,
+    file: File,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        debug!("Releasing {}", self.path.display());
+        // Closing the file is what actually releases the lock.
+        let _ = self.file.set_len(0);
+    }
+}
+
+/// Take the lock for some kind of work, like `aur`. If another Aura run holds
+/// it, either fail with a description of that run, or wait for it to finish.
+pub(crate) fn acquire(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    name: &str,
+    wait: bool,
+) -> Result<Lock, Error> {
+    let path = crate::dirs::locks(m)?.join(format!("{name}.lock"));
+    let mut file = open(&path).map_err(|e| Error::FileWrite(path.clone(), e))?;
+
+    if flock(&file, libc::LOCK_EX | libc::LOCK_NB).is_err() {
+        // It may take a moment for the holder to say who it is.
+        let holder = Holder::read(&path)
+            .or_else(|| {
+                std::thread::sleep(SETTLE);
+                Holder::read(&path)
+            })
+            .unwrap_or_default();
+
+        if wait.not() {
+            return Err(Error::Held(holder));
+        }
+
+        yellow!(
+            fll,
+            "lock-waiting",
+            cmd = holder.command.as_str(),
+            time = holder.started.as_str()
+        );
+        flock(&file, libc::LOCK_EX).map_err(|e| Error::FileWrite(path.clone(), e))?;
+    }
+
+    let json = serde_json::to_string(&Holder::current()).unwrap_or_default();
+    file.set_len(0)
+        .and_then(|_| file.seek(SeekFrom::Start(0)))
+        .and_then(|_| file.write_all(json.as_bytes()))
+        .map_err(|e| Error::FileWrite(path.clone(), e))?;
+    debug!("Acquired {}", path.display());
+
+    Ok(Lock { path, file })
+}
+
+/// Open a lock file, creating it if need be, readable and writable by all.
+///
+/// An existing file is never opened with `O_CREAT`, which the kernel refuses in
+/// a sticky directory for files owned by someone else.
+fn open(path: &Path) -> std::io::Result<File> {
+    loop {
+        match OpenOptions::new().read(true).write(true).open(path) {
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            res => return res,
+        }
+
+        match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(0o666)
+            .open(path)
+        {
+            Ok(file) => {
+                // The mode given above is subject to the umask.
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o666))?;
+                return Ok(file);
+            }
+            // Another run may have only just created it.
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Apply an `flock(2)` operation to an open file, retrying if interrupted.
+fn flock(file: &File, op: libc::c_int) -> std::io::Result<()> {
+    loop {
+        match unsafe { libc::flock(file.as_raw_fd(), op) } {
+            0 => return Ok(()),
+            _ => {
+                let e = std::io::Error::last_os_error();
+                if e.kind() != ErrorKind::Interrupted {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
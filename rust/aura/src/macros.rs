@@ -20,7 +20,7 @@ macro_rules! a {
 /// Print a localized Aura message, given some Fluent tag.
 macro_rules! aura {
     ($fll:expr, $msg:expr, $($arg:expr),*) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg)*));
+        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg),*));
     };
     ($fll:expr, $msg:expr) => {
         $crate::aln!(i18n_embed_fl::fl!($fll, $msg));
@@ -31,7 +31,7 @@ macro_rules! aura {
 /// Print a localized Aura message in green, given some Fluent tag.
 macro_rules! green {
     ($fll:expr, $msg:expr, $($arg:expr),*) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg)*).green());
+        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg),*).green());
     };
     ($fll:expr, $msg:expr) => {
         $crate::aln!(i18n_embed_fl::fl!($fll, $msg).green());
@@ -42,7 +42,7 @@ macro_rules! green {
 /// Print a localized Aura message in yellow, given some Fluent tag.
 macro_rules! yellow {
     ($fll:expr, $msg:expr, $($arg:expr),*) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg)*).yellow());
+        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg),*).yellow());
     };
     ($fll:expr, $msg:expr) => {
         $crate::aln!(i18n_embed_fl::fl!($fll, $msg).yellow());
@@ -53,7 +53,7 @@ macro_rules! yellow {
 /// Print a localized Aura message in red, given some Fluent tag.
 macro_rules! red {
     ($fll:expr, $msg:expr, $($arg:expr),*) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg)*).red());
+        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg),*).red());
     };
     ($fll:expr, $msg:expr) => {
         $crate::aln!(i18n_embed_fl::fl!($fll, $msg).red());
@@ -61,49 +61,18 @@ macro_rules! red {
 }
 
 #[macro_export]
-/// Detect an executable or warn if it's missing.
-///
-/// Used by the `check` module.
-macro_rules! executable {
-    ($fll:expr, $exec:expr, $msg:expr, $($arg:expr),*) => {
-        let good = which::which($exec).is_ok();
-        let symb = if good { $crate::command::check::GOOD.green() } else { $crate::command::check::BAD.red() };
-        println!(
-            "  [{}] {}",
-            symb,
-            i18n_embed_fl::fl!($fll, $msg, $($arg)*)
-        );
-
-        if !good {
-            let msg = fl!($fll, "check-missing-exec", exec = $exec.cyan().bold().to_string());
-            println!("      └─ {}", msg);
-        }
+/// Ask for permission to proceed, but with a custom message. The question's ID
+/// is the message's key, unless another is given. Given a number of packages
+/// too, the question is one of the dangerous ones that may require typing that
+/// number to confirm.
+macro_rules! proceed {
+    ($fll:expr, $msg:expr) => {
+        $crate::proceed!($fll, $msg, $msg)
     };
-    ($fll:expr, $exec:expr, $msg:expr) => {
-        let good = which::which($exec).is_ok();
-        let symb = if good { $crate::command::check::GOOD.green() } else { $crate::command::check::BAD.red() };
-        println!(
-            "  [{}] {}",
-            symb,
-            i18n_embed_fl::fl!($fll, $msg)
-        );
-
-        if !good {
-            let msg = fl!($fll, "check-missing-exec", exec = $exec.cyan().bold().to_string());
-            println!("      └─ {}", msg);
-        }
+    ($fll:expr, $msg:expr, $id:expr) => {
+        $crate::prompts::ask($fll, $id, i18n_embed_fl::fl!($fll, $msg))
+    };
+    ($fll:expr, $msg:expr, $id:expr, $count:expr) => {
+        $crate::prompts::ask_count($fll, $id, i18n_embed_fl::fl!($fll, $msg), $count)
     };
-}
-
-#[macro_export]
-/// Ask for permission to proceed, but with a custom message.
-macro_rules! proceed {
-    ($fll:expr, $msg:expr) => {{
-        let formatted = format!(
-            "{} {} ",
-            i18n_embed_fl::fl!($fll, $msg),
-            i18n_embed_fl::fl!($fll, "proceed-yes")
-        );
-        $crate::utils::prompt(&$crate::a!(formatted))
-    }};
 }
@@ -29,121 +29,336 @@
 
 #![warn(missing_docs)]
 
+pub(crate) mod audit;
 pub(crate) mod command;
 pub(crate) mod dirs;
 pub(crate) mod download;
 pub(crate) mod env;
 pub(crate) mod error;
+pub(crate) mod etc;
 pub(crate) mod fetch;
+pub(crate) mod kernel;
 pub(crate) mod localization;
+pub(crate) mod lock;
 mod macros;
+pub(crate) mod notify;
 pub(crate) mod pacman;
+pub(crate) mod pager;
+pub(crate) mod prompts;
+pub(crate) mod reasons;
+pub(crate) mod reporter;
+pub(crate) mod signal;
+pub(crate) mod space;
+pub(crate) mod state;
+pub(crate) mod suggest;
+pub(crate) mod timings;
 pub(crate) mod utils;
 
-use crate::command::{aur, cache, check, conf, deps, log, open, orphans, snapshot, stats};
+use crate::command::{
+    alias, aur, cache, canary, check, clean, conf, debug, deps, external, info, lang, log, migrate,
+    open, optdeps, orphans, pin, provides, report, search, select, setup, snapshot, stats, switch,
+    upgrade, verify,
+};
 use crate::error::{Error, Nested};
 use crate::localization::Localised;
 use ::log::debug;
-use aura::flags::{Args, Cache, SubCmd, AURA_GLOBALS};
+use aura::caps::Mutating;
+use aura::flags::{Args, Cache, Check, Report, SubCmd, Sync, AURA_GLOBALS};
 use clap::Parser;
 use colored::Colorize;
 use i18n_embed::fluent::FluentLanguageLoader;
-use simplelog::{ColorChoice, Config, TermLogger, TerminalMode};
+use simplelog::{
+    ColorChoice, CombinedLogger, Config, ConfigBuilder, LevelFilter, SharedLogger, TermLogger,
+    TerminalMode, WriteLogger,
+};
+use std::fs::OpenOptions;
 use std::ops::Not;
+use std::path::Path;
 use std::process::ExitCode;
+use std::time::Instant;
 
 fn main() -> ExitCode {
+    let start = Instant::now();
+    signal::install();
+
+    // --- User-defined Aliases --- //
+    let raws = match aura::flags::expand_aliases(std::env::args().collect(), &env::aliases()) {
+        Ok(raws) => raws,
+        Err(cycle) => {
+            // The CLI isn't parsed yet, so any language flag is unknown.
+            let fll = localization::Lazy::new(None);
+            red!(&fll, "alias-cycle", cycle = cycle.join(" -> "));
+            return ExitCode::FAILURE;
+        }
+    };
+
     // Parse all CLI input. Exits immediately if invalid input is given.
-    let args = aura::flags::Args::parse();
+    let args = aura::flags::Args::parse_from(&raws);
 
     // --- Localisation --- //
-    match localization::load(args.language()) {
-        Err(e) => {
-            aln!("Failed to localise Aura!".red());
-            println!("\n  {}", e);
-            ExitCode::FAILURE
+    // Nothing is loaded until the first message is looked up, which many
+    // paths, like most `--json` output, never do.
+    let fll = localization::Lazy::new(args.language());
+    let report_as = args.timings.clone();
+    let json = args.is_json();
+
+    let code = match work(args, &raws, &fll) {
+        Err(_) if signal::interrupted() => {
+            yellow!(&fll, "common-interrupted-resume");
+            ExitCode::from(signal::EXIT_CODE)
         }
-        Ok(fll) => match work(args, &fll) {
-            Err(e) => {
-                e.nested();
+        Err(e) => {
+            e.nested();
+
+            if json {
+                println!("{}", e.json(&fll));
+            } else {
                 aln!(e.localise(&fll).red());
-                ExitCode::FAILURE
+                suggest::suggest(&fll, &e);
             }
-            Ok(_) => ExitCode::SUCCESS,
-        },
+
+            e.exit_code().map_or(ExitCode::FAILURE, ExitCode::from)
+        }
+        Ok(_) => ExitCode::SUCCESS,
+    };
+
+    if let Some(format) = report_as {
+        timings::report(&fll, format == "json", start.elapsed());
     }
+
+    code
 }
 
-fn work(args: Args, fll: &FluentLanguageLoader) -> Result<(), Error> {
-    // --- Terminal Logging --- //
-    if let Some(l) = args.log_level {
-        // Silently ignore logger init failure. Realistically it should never
-        // fail, since its docs claim this only occurs when a logger has been
-        // previously initialized.
-        let _ = TermLogger::init(l, Config::default(), TerminalMode::Mixed, ColorChoice::Auto);
+fn work(args: Args, raws: &[String], fll: &localization::Lazy) -> Result<(), Error> {
+    // --- Logging --- //
+    if let Err(e) = logging(args.log_level(), args.log_file.as_deref()) {
+        let file = args.log_file.as_deref().map(|p| p.display().to_string());
+        red!(fll, "log-file-open", file = file.unwrap_or_default());
+        ::log::error!("{e}");
     }
 
     // --- Runtime Settings --- //
     let env = {
         let mut env = crate::env::Env::try_new()?;
-        env.reconcile_cli(&args.subcmd);
+        env.reconcile_cli(&args);
         env.validate()?;
         env
     };
     debug!("{:#?}", env);
+    prompts::init(env.prompts.clone());
+    if args.explain {
+        aura_core::explain::enable();
+    }
+
+    // --- Capabilities --- //
+    let caps = aura::caps::mutating(&args.subcmd);
+    let mutating = || caps.as_ref().ok_or(Error::ReadOnly);
+
+    // --- Paging --- //
+    let _pager = (args.subcmd.is_pageable() && args.no_pager.not())
+        .then(pager::start)
+        .flatten();
 
     match args.subcmd {
         // --- Pacman Commands --- //
-        SubCmd::Database(d) => pacman(d.needs_sudo())?,
-        SubCmd::Files(f) => pacman(f.needs_sudo())?,
-        SubCmd::Query(_) => pacman(false)?,
-        SubCmd::Remove(r) => pacman(r.needs_sudo())?,
-        SubCmd::Sync(s) => pacman(s.needs_sudo())?,
-        SubCmd::DepTest(_) => pacman(false)?,
-        SubCmd::Upgrade(u) => pacman(u.needs_sudo())?,
+        SubCmd::Database(_) => pacman(fll, &env, raws, caps.as_ref())?,
+        SubCmd::Files(_) => pacman(fll, &env, raws, caps.as_ref())?,
+        SubCmd::Query(_) => pacman(fll, &env, raws, None)?,
+        SubCmd::Remove(r) if r.needs_sudo() => {
+            let etc = etc::Watch::start(&env);
+            let dkms = kernel::Watch::start(&env);
+            let result = pacman(fll, &env, raws, caps.as_ref());
+            if let Some(w) = etc {
+                w.finish(fll, &env);
+            }
+            if let Some(w) = dkms {
+                w.finish(fll, &env);
+            }
+            result?
+        }
+        SubCmd::Remove(_) => pacman(fll, &env, raws, caps.as_ref())?,
+        SubCmd::Sync(s) => {
+            if s.is_targeted_install() {
+                check::partial_upgrade_warning(fll, &env, s.is_refresh());
+            }
+
+            let watch = s
+                .is_targeted_install()
+                .then(|| reasons::Watch::start(&env))
+                .flatten();
+            let transacting = s.is_targeted_install() || s.is_sysupgrade();
+            let etc = transacting.then(|| etc::Watch::start(&env)).flatten();
+            let dkms = transacting.then(|| kernel::Watch::start(&env)).flatten();
+
+            let staged = env.pins.is_empty().not() || s.canary().is_some();
+            let result = if s.is_sysupgrade() && staged {
+                sysupgrade(mutating()?, fll, &env, &s, raws)
+            } else {
+                // Judged against the databases as last synced, since syncing
+                // them apart from the upgrade would invite a partial one.
+                if s.is_sysupgrade() {
+                    before_upgrade(fll, &env, &env.alpm()?, &[]);
+                }
+                pacman(fll, &env, raws, caps.as_ref()).map_err(Error::Pacman)
+            };
+            if let Some(w) = watch {
+                w.finish(&env);
+            }
+            if let Some(w) = etc {
+                w.finish(fll, &env);
+            }
+            if let Some(w) = dkms {
+                w.finish(fll, &env);
+            }
+            result?;
+
+            if s.is_refresh() || s.is_sysupgrade() {
+                check::pending::refresh_repo(&env);
+            }
+
+            if s.is_sysupgrade() {
+                aur::stale::advice(fll, &env);
+                check::restart_advice(fll);
+            }
+        }
+        SubCmd::DepTest(_) => pacman(fll, &env, raws, None)?,
+        SubCmd::Upgrade(u) if u.needs_sudo() => {
+            let targets = upgrade::prepare(fll, u.targets())?;
+            let etc = etc::Watch::start(&env);
+            let dkms = kernel::Watch::start(&env);
+            let result = upgrade::install(mutating()?, fll, &env, pacman_args(raws));
+            if let Some(w) = etc {
+                w.finish(fll, &env);
+            }
+            if let Some(w) = dkms {
+                w.finish(fll, &env);
+            }
+            result?;
+            upgrade::record(fll, &env, &targets)?;
+        }
+        SubCmd::Upgrade(_) => pacman(fll, &env, raws, caps.as_ref())?,
         // --- AUR Packages --- //
-        SubCmd::Aur(a) if a.info.is_empty().not() => aur::info(fll, &a.info)?,
-        SubCmd::Aur(a) if a.search.is_empty().not() => {
-            aur::search(&env.alpm()?, a.abc, a.reverse, a.limit, a.quiet, a.search)?
+        SubCmd::Aur(a) if a.info.is_empty().not() && a.local => aur::local_info(fll, &a.info)?,
+        SubCmd::Aur(a) if a.info.is_empty().not() && a.trend => {
+            aur::trend(fll, &env.network, &a.info)?
         }
+        SubCmd::Aur(a) if a.info.is_empty().not() => aur::info(
+            fll,
+            &env.alpm()?,
+            &env.network,
+            &a.info,
+            a.format.as_deref(),
+        )?,
+        SubCmd::Aur(a) if a.json.is_empty().not() => aur::json(&env.network, &a.json, &a.fields)?,
+        SubCmd::Aur(a) if a.search.is_empty().not() => aur::search(&env.alpm()?, &env.network, &a)?,
         SubCmd::Aur(a) if a.open.is_some() => aur::open(&a.open.unwrap())?,
         SubCmd::Aur(a) if a.pkgbuild.is_some() => {
-            aur::pkgbuild(&a.pkgbuild.unwrap(), &env.aur.clones)?
+            aur::pkgbuild(&env.network, &a.pkgbuild.unwrap(), &env.aur.clones)?
         }
+        SubCmd::Aur(a) if a.push.is_some() => aur::push(fll, &a.push.unwrap())?,
         SubCmd::Aur(a) if a.wclone.is_empty().not() => aur::clone_aur_repos(fll, &a.wclone)?,
-        SubCmd::Aur(a) if a.sysupgrade => aur::upgrade(fll, &env.alpm()?, env)?,
-        SubCmd::Aur(a) if a.refresh => aur::refresh(fll, &env.alpm()?, &env.aur.clones)?,
-        SubCmd::Aur(a) => aur::install(fll, &env, a.packages.iter().map(|s| s.as_str()))?,
+        SubCmd::Aur(a) if a.sysupgrade => {
+            let m = mutating()?;
+            let _lock = lock::acquire(m, fll, "aur", a.wait)?;
+            let notify = env.notify.clone();
+            let network = env.network.clone();
+            let started = std::time::Instant::now();
+            let alpm = env.alpm()?;
+            let res: Result<aur::Summary, Error> =
+                aur::upgrade(m, fll, &alpm, env).map_err(Error::from);
+            notify::report(fll, &network, &notify, started, &res);
+            let summary = res?;
+            // Whatever couldn't be built is still waiting to be upgraded.
+            check::pending::update(|p| p.aur = summary.unbuilt());
+            check::restart_advice(fll);
+            incomplete(&summary)?;
+        }
+        SubCmd::Aur(a) if a.rebuild_from.is_some() => {
+            let m = mutating()?;
+            let _lock = lock::acquire(m, fll, "aur", a.wait)?;
+            let list = a.rebuild_from.unwrap();
+            aur::rebuild_from(m, fll, &env, &list)?
+        }
+        SubCmd::Aur(a) if a.stale => {
+            let m = mutating()?;
+            let _lock = lock::acquire(m, fll, "aur", a.wait)?;
+            aur::stale::rebuild(m, fll, &env)?
+        }
+        SubCmd::Aur(a) if a.refresh => {
+            aur::refresh(fll, &env.alpm()?, &env.network, &env.aur.clones)?
+        }
+        SubCmd::Aur(a) => {
+            let m = mutating()?;
+            let _lock = lock::acquire(m, fll, "aur", a.wait)?;
+            let pkgs = if a.exact {
+                a.packages
+            } else {
+                aur::disambiguate(fll, &env, a.packages, a.pick_first)?
+            };
+            let summary = aur::install(m, fll, &env, pkgs.iter().map(|s| s.as_str()))?;
+            incomplete(&summary)?;
+        }
         // --- Package Sets --- //
         SubCmd::Backup(b) if b.clean => {
             snapshot::clean(fll, &env.caches(), &env.backups.snapshots)?
         }
-        SubCmd::Backup(b) if b.list => snapshot::list(&env.backups.snapshots)?,
-        SubCmd::Backup(b) if b.restore => {
-            snapshot::restore(fll, &env.alpm()?, &env.caches(), &env.backups.snapshots)?
+        SubCmd::Backup(b) if b.list => snapshot::list(&env.backups.snapshots, &b.listing)?,
+        SubCmd::Backup(b) if b.restore => snapshot::restore(
+            mutating()?,
+            fll,
+            &env.alpm()?,
+            &env.network,
+            &env.caches(),
+            &env.backups.snapshots,
+            b.name.as_deref(),
+        )?,
+        SubCmd::Backup(b) if b.diff => {
+            snapshot::diff(fll, &env.alpm()?, &env.backups.snapshots, b.name.as_deref())?
         }
-        SubCmd::Backup(_) => snapshot::save(fll, &env.alpm()?, &env.backups.snapshots)?,
+        SubCmd::Backup(b) if b.push => snapshot::push(fll, &env.backups)?,
+        SubCmd::Backup(b) if b.pull => snapshot::pull(fll, &env.backups)?,
+        SubCmd::Backup(b) => snapshot::save(fll, &env.alpm()?, &env.backups, b.name.as_deref())?,
         // --- Cache Management --- //
         SubCmd::Cache(c) if !c.info.is_empty() => {
             cache::info(fll, &env.alpm()?, &env.caches(), c.info)?
         }
-        SubCmd::Cache(c) if c.search.is_some() => cache::search(&env.caches(), &c.search.unwrap())?,
+        SubCmd::Cache(c) if c.search.is_some() => {
+            cache::search(&env.caches(), c.search.as_ref().unwrap(), &c.listing)?
+        }
         SubCmd::Cache(c) if c.backup.is_some() => cache::backup(fll, &env, &c.backup.unwrap())?,
-        SubCmd::Cache(Cache { clean: Some(n), .. }) => cache::clean(fll, &env.caches(), n)?,
+        SubCmd::Cache(Cache {
+            clean: Some(n),
+            packages,
+            ..
+        }) => cache::clean(mutating()?, fll, &env.caches(), n, packages)?,
         SubCmd::Cache(c) if c.clean_unsaved => cache::clean_not_saved(fll, &env)?,
-        SubCmd::Cache(c) if c.invalid => cache::invalid(fll, &env.alpm()?, &env.caches())?,
-        SubCmd::Cache(c) if c.list => cache::list(&env.caches())?,
-        SubCmd::Cache(c) if c.refresh => cache::refresh(fll, &env.alpm()?, &env.caches())?,
+        SubCmd::Cache(c) if c.recompress => {
+            cache::recompress(mutating()?, fll, &env.alpm()?, &env.caches(), c.zstd_level)?
+        }
+        SubCmd::Cache(c) if c.invalid => {
+            cache::invalid(mutating()?, fll, &env.alpm()?, &env.caches())?
+        }
+        SubCmd::Cache(c) if c.list => cache::list(&env.caches(), &c.listing)?,
+        SubCmd::Cache(c) if c.refresh => {
+            cache::refresh(mutating()?, fll, &env.alpm()?, &env.network, &env.caches())?
+        }
         SubCmd::Cache(c) if c.missing => cache::missing(&env.alpm()?, &env.caches()),
-        SubCmd::Cache(c) => cache::downgrade(fll, &env.caches(), c.packages)?,
+        SubCmd::Cache(c) if c.owns.is_some() => {
+            cache::owns(fll, env.alpm_pool()?, &env.caches(), &c.owns.unwrap())
+        }
+        SubCmd::Cache(c) => cache::downgrade(mutating()?, fll, &env.caches(), c.packages)?,
         // --- Logs --- //
+        SubCmd::Log(l) if l.audit => log::audit(fll)?,
         SubCmd::Log(l) if l.search.is_some() => log::search(env.alpm_log(), l.search.unwrap())?,
         SubCmd::Log(l) if !l.info.is_empty() => log::info(fll, env.alpm_log(), l.info)?,
         SubCmd::Log(l) => log::view(env.alpm_log(), l.before, l.after)?,
         // --- Orphan Packages --- //
-        SubCmd::Orphans(o) if o.abandon => orphans::remove(&mut env.alpm()?, fll)?,
-        SubCmd::Orphans(o) if !o.adopt.is_empty() => orphans::adopt(&env.alpm()?, fll, o.adopt)?,
-        SubCmd::Orphans(_) => orphans::list(&env.alpm()?),
+        SubCmd::Orphans(o) if o.abandon => orphans::remove(mutating()?, &mut env.alpm()?, fll)?,
+        SubCmd::Orphans(o) if !o.adopt.is_empty() => {
+            orphans::adopt(mutating()?, &env.alpm()?, fll, o.adopt)?
+        }
+        SubCmd::Orphans(o) if o.why => orphans::why(fll, &env.alpm()?, env.alpm_log()),
+        SubCmd::Orphans(o) => orphans::list(&env.alpm()?, &o.listing)?,
         // --- PKGBUILD Analysis --- //
         // SubCmd::Analysis(_) => unimplemented!(),
         // --- Configuration --- //
@@ -153,10 +368,16 @@ fn work(args: Args, fll: &FluentLanguageLoader) -> Result<(), Error> {
         SubCmd::Conf(c) if c.gen => conf::gen(&env)?,
         SubCmd::Conf(_) => conf::general(&env),
         // --- Statistics --- //
-        SubCmd::Stats(s) if s.lang => stats::localization()?,
+        SubCmd::Stats(s) if s.lang => lang::list()?,
         SubCmd::Stats(s) if s.heavy => stats::heavy_packages(&env.alpm()?),
         SubCmd::Stats(s) if s.groups => stats::groups(&env.alpm()?),
+        SubCmd::Stats(s) if s.prometheus => stats::prometheus(&env, s.output.as_deref())?,
         SubCmd::Stats(_) => unimplemented!(),
+        // --- Languages --- //
+        SubCmd::Lang(l) => match l.missing {
+            Some(m) => lang::missing(fll, &m)?,
+            None => lang::list()?,
+        },
         // --- Opening Webpages --- //
         SubCmd::Open(o) if o.docs => open::book()?,
         SubCmd::Open(o) if o.repo => open::repo()?,
@@ -169,34 +390,265 @@ fn work(args: Args, fll: &FluentLanguageLoader) -> Result<(), Error> {
             deps::reverse(&env.alpm()?, d.limit, d.optional, d.packages)
         }
         SubCmd::Deps(d) => deps::graph(&env.alpm()?, d.limit, d.optional, d.packages),
+        // --- Package Queries --- //
+        SubCmd::Search(s) => {
+            search::search(fll, &env, s.terms, s.limit, s.quiet, s.format.as_deref())?
+        }
+        SubCmd::Select(s) => select::select(&env, &s.expr.join(" "), s.json, s.format.as_deref())?,
+        // --- Switching Variants --- //
+        SubCmd::Switch(s) => switch::switch(mutating()?, fll, &env, &s.package, &s.variant)?,
+        // --- First-run Setup --- //
+        SubCmd::Setup(_) => setup::setup(fll, &env)?,
+        // --- Migrating from Other Helpers --- //
+        SubCmd::Migrate(m) => migrate::migrate(fll, &env, &m.from)?,
+        // --- Aliases --- //
+        SubCmd::Alias(_) => alias::list(fll, &env),
+        // --- Package Information --- //
+        SubCmd::Info(i) => info::info(fll, &env, &i.packages)?,
+        // --- Optional Dependencies --- //
+        SubCmd::Optdeps(o) if o.unused => optdeps::unused(mutating()?, fll, &env.alpm()?)?,
+        SubCmd::Optdeps(o) => optdeps::list(fll, &env.alpm()?, o.packages)?,
+        // --- Pinning --- //
+        SubCmd::Pin(p) if p.remove.is_empty().not() => pin::remove(fll, &p.remove)?,
+        SubCmd::Pin(p) if p.add.is_empty().not() || p.repo.is_empty().not() => {
+            pin::add(fll, &env, &p.add, &p.repo)?
+        }
+        SubCmd::Pin(_) => pin::list(fll, &env),
+        // --- File Ownership in the AUR --- //
+        SubCmd::Provides(p) => {
+            if p.refresh {
+                provides::refresh(fll, &env)?;
+            }
+            if let Some(file) = p.file {
+                provides::search(fll, &file)?;
+            }
+        }
+        // --- Bug Reports --- //
+        SubCmd::Debug(d) if d.bench_resolve.is_empty().not() => {
+            debug::bench_resolve(fll, &env, &d.bench_resolve)?
+        }
+        SubCmd::Debug(d) => debug::bundle(fll, &env, &d.package, d.log)?,
         // --- System Validation --- //
+        SubCmd::Check(c) if c.self_check => check::self_check(mutating()?, fll, env)?,
+        SubCmd::Check(c) if c.updates => check::updates(fll, &env, &c)?,
+        SubCmd::Check(c) if c.keys => check::keys(mutating()?, fll, &env)?,
+        SubCmd::Check(Check {
+            files: Some(ps), ..
+        }) => check::files(fll, &env, &ps)?,
+        SubCmd::Check(c) if c.boot => check::boot(fll),
+        SubCmd::Check(c) if c.fix => {
+            check::check(fll, &env)?;
+            check::fix(mutating()?, fll, &env)?
+        }
+        SubCmd::Check(c) if c.suggest_only => check::suggest_only(mutating()?, fll, &env)?,
         SubCmd::Check(_) => check::check(fll, &env)?,
+        // --- Build Artifacts --- //
+        SubCmd::Clean(c) => clean::builds(fll, &env.aur.build, &c.keep, c.builds)?,
+        // --- Fleet Reports --- //
+        SubCmd::Report(Report {
+            aggregate: Some(dir),
+            signer,
+            ..
+        }) => report::aggregate(fll, &dir, &signer)?,
+        SubCmd::Report(r) => report::emit(&env, r.sign)?,
+        // --- Reproducibility --- //
+        SubCmd::Verify(v) => verify::verify(fll, &env, &v.package)?,
+        // --- Version Comparison --- //
+        SubCmd::Vercmp(v) => println!("{}", aura_core::version::cmp(&v.a, &v.b) as i8),
+        // --- External Subcommands --- //
+        SubCmd::External(e) => external::run(fll, args.log_level(), &e)?,
+    }
+
+    let (hits, misses) = fetch::cache_stats();
+    if hits + misses > 0 {
+        debug!("HTTP cache: {hits} hits, {misses} misses");
     }
 
     Ok(())
 }
 
-/// Run a Pacman command.
-fn pacman(sudo: bool) -> Result<(), crate::pacman::Error> {
-    let mut raws: Vec<String> = std::env::args()
-        .skip(1)
-        .filter(|a| !(AURA_GLOBALS.contains(&a.as_str()) || a.starts_with("--log-level=")))
+/// Send log messages to the terminal, if some level was requested, and to a
+/// file, if one was given. The file also records the time and module of each
+/// message, and defaults to the full trace.
+fn logging(level: Option<LevelFilter>, file: Option<&Path>) -> std::io::Result<()> {
+    let mut loggers: Vec<Box<dyn SharedLogger>> = Vec::new();
+
+    if let Some(l) = level {
+        loggers.push(TermLogger::new(
+            l,
+            Config::default(),
+            TerminalMode::Mixed,
+            ColorChoice::Auto,
+        ));
+    }
+
+    if let Some(path) = file {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        // Remembered so that `debug --bundle` can find it later.
+        if let (Ok(record), Ok(full)) = (dirs::last_log(), path.canonicalize()) {
+            let _ = std::fs::write(record, full.display().to_string());
+        }
+
+        let config = ConfigBuilder::new()
+            .set_target_level(LevelFilter::Error)
+            .set_time_format_rfc3339()
+            .build();
+        let l = level.unwrap_or(LevelFilter::Trace);
+        loggers.push(WriteLogger::new(l, config, file));
+    }
+
+    // Silently ignore logger init failure. Realistically it should never
+    // fail, since its docs claim this only occurs when a logger has been
+    // previously initialized.
+    let _ = CombinedLogger::init(loggers);
+
+    Ok(())
+}
+
+/// Run a Pacman command, given the full (alias-expanded) command line. It runs
+/// elevated only when permission to change the system is given.
+fn pacman(
+    fll: &FluentLanguageLoader,
+    env: &crate::env::Env,
+    raws: &[String],
+    sudo: Option<&Mutating>,
+) -> Result<(), crate::pacman::Error> {
+    if let Some(m) = sudo {
+        pacman::ensure_unlocked(m, fll, &env.pacman.db_path)?;
+    }
+
+    let raws = pacman_args(raws);
+    ::log::debug!("Passing to Pacman: {:?}", raws);
+    match sudo {
+        Some(m) => pacman::sudo_pacman_batch(m, raws),
+        None => pacman::pacman(raws),
+    }
+}
+
+/// A `-Su` held back by pins, or staged with `--canary`, and checked for
+/// upgrades that need attention.
+fn sysupgrade(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    env: &crate::env::Env,
+    s: &Sync,
+    raws: &[String],
+) -> Result<(), Error> {
+    pacman::ensure_unlocked(m, fll, &env.pacman.db_path)?;
+
+    // Pins and canaries are judged against fresh databases. Syncing `pacman`'s
+    // own apart from the upgrade would invite a partial one, so Aura's copies
+    // of them are synced instead.
+    let alpm = match s.is_refresh() {
+        true => check::syncdb::refresh(env)?,
+        false => env.alpm()?,
+    };
+
+    let held = pin::held(fll, env, &alpm);
+    if let Some(n) = s.canary() {
+        canary::canary(m, fll, env, &alpm, n, &held, pacman_args(raws))?;
+    }
+
+    before_upgrade(fll, env, &alpm, &held);
+    upgrade_space(env, &alpm, &held)?;
+
+    let _timer = aura_core::timings::time(aura_core::timings::Phase::Install);
+    pacman::sudo_pacman_batch(m, pin::ignoring(pacman_args(raws), &held))?;
+    Ok(())
+}
+
+/// Warn of the pending upgrades that need attention, less those held back, and
+/// keep aside whatever data the rules say they may need.
+fn before_upgrade(
+    fll: &FluentLanguageLoader,
+    env: &crate::env::Env,
+    alpm: &alpm::Alpm,
+    held: &[String],
+) {
+    let pending = pin::upgrades(alpm);
+    let upgrades: Vec<_> = pending
+        .iter()
+        .filter(|(p, _, _)| held.iter().all(|h| h != p.name()))
+        .map(|(p, _, new)| (p.name(), p.version().as_str(), new.as_str()))
         .collect();
+    state::before(fll, env, &upgrades);
+    kernel::before(fll, alpm, &upgrades);
+}
 
-    // Special consideration for split cases like `--log-level debug`.
-    if let Some(ix) = raws
+/// Abort a `-Su` before it starts if what it would download and unpack wouldn't
+/// fit on disk.
+fn upgrade_space(
+    env: &crate::env::Env,
+    alpm: &alpm::Alpm,
+    held: &[String],
+) -> Result<(), space::Shortfall> {
+    let size = |n: i64| n.max(0) as u64;
+    let (download, unpacked) = pin::upgrades(alpm)
         .iter()
-        .enumerate()
-        .find_map(|(i, v)| (v == "--log-level").then(|| i))
-    {
-        raws.remove(ix); // --log-level
-        raws.remove(ix); // Its argument.
+        .filter(|(p, _, _)| held.iter().all(|h| h != p.name()))
+        .filter_map(|(old, _, _)| alpm.syncdbs().pkg(old.name()).ok().map(|new| (old, new)))
+        .fold((0, 0), |(d, u), (old, new)| {
+            (
+                d + size(new.download_size()),
+                u + size(new.isize()).saturating_sub(size(old.isize())),
+            )
+        });
+
+    space::check(&[
+        (space::pacman_cache(&env.pacman), download),
+        (Path::new(&env.pacman.root_dir), unpacked),
+    ])
+}
+
+/// A `--keep-going` run that left some packages unbuilt still fails, once the
+/// rest is done.
+fn incomplete(summary: &aur::Summary) -> Result<(), Error> {
+    match summary.unbuilt() {
+        0 => Ok(()),
+        n => Err(Error::Incomplete(n)),
     }
+}
 
-    ::log::debug!("Passing to Pacman: {:?}", raws);
-    if sudo {
-        pacman::sudo_pacman_batch(raws)
-    } else {
-        pacman::pacman(raws)
+/// The original arguments, less those meant only for Aura.
+fn pacman_args(raws: &[String]) -> Vec<String> {
+    let mut raws: Vec<String> = raws
+        .iter()
+        .skip(1)
+        .filter(|a| {
+            !(AURA_GLOBALS.contains(&a.as_str())
+                || a.starts_with("--log-level=")
+                || a.starts_with("--limit-rate=")
+                || a.starts_with("--log-file=")
+                || a.starts_with("--timings=")
+                || a.starts_with("--canary="))
+        })
+        .cloned()
+        .collect();
+
+    // Special consideration for split cases like `--log-level debug`.
+    for flag in ["--log-level", "--limit-rate", "--log-file", "--canary"] {
+        if let Some(ix) = raws
+            .iter()
+            .enumerate()
+            .find_map(|(i, v)| (v == flag).then(|| i))
+        {
+            raws.remove(ix); // The flag itself.
+            raws.remove(ix); // Its argument.
+        }
     }
+
+    // Aura's own `-v`, which can only come before the subcommand. Any after it
+    // belong to Pacman.
+    let verbosity = raws
+        .iter()
+        .take_while(|a| {
+            a.len() > 1
+                && a.strip_prefix('-')
+                    .map_or(false, |vs| vs.bytes().all(|b| b == b'v'))
+        })
+        .count();
+    raws.drain(..verbosity);
+
+    raws
 }
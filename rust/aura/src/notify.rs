@@ -0,0 +1,117 @@
+//! Tell someone how an unattended `-Au` run went.
+//!
+//! A JSON summary is sent to whatever the `[notify]` section of `aura.toml`
+//! names, so that a broken upgrade on a timer doesn't go unnoticed. It lists
+//! each package that was upgraded, failed to build, or was skipped because a
+//! dependency failed:
+//!
+//! ```toml
+//! [notify]
+//! webhook = "https://example.com/hooks/aura"
+//! command = "mail -s 'aura upgrade' admin@example.com"
+//! ```
+
+use crate::command::aur::Summary;
+use crate::env::Notify;
+use crate::fetch::Network;
+use crate::localization::Localised;
+use crate::yellow;
+use aura_core::Coded;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use log::debug;
+use serde_json::json;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Report the outcome of some work that began at `started`.
+pub(crate) fn report<E>(
+    fll: &FluentLanguageLoader,
+    network: &Network,
+    notify: &Notify,
+    started: Instant,
+    result: &Result<Summary, E>,
+) where
+    E: Coded + Localised,
+{
+    if notify.webhook.is_none() && notify.command.is_none() {
+        return;
+    }
+
+    let summary = summary(fll, started, result);
+
+    if let Some(url) = notify.webhook.as_deref() {
+        debug!("Notifying {url}");
+
+        if network.post_json(url, &summary).is_err() {
+            yellow!(fll, "notify-failed", target = url);
+        }
+    }
+
+    if let Some(cmd) = notify.command.as_deref() {
+        debug!("Notifying via: {cmd}");
+
+        if pipe(cmd, &summary.to_string()).is_none() {
+            yellow!(fll, "notify-failed", target = cmd);
+        }
+    }
+}
+
+fn summary<E>(
+    fll: &FluentLanguageLoader,
+    started: Instant,
+    result: &Result<Summary, E>,
+) -> serde_json::Value
+where
+    E: Coded + Localised,
+{
+    let packages: Vec<serde_json::Value> = match result {
+        Err(_) => Vec::new(),
+        Ok(s) => s
+            .upgraded
+            .iter()
+            .map(|(name, from, to)| {
+                json!({ "name": name, "status": "upgraded", "from": from, "to": to })
+            })
+            .chain(s.failed.iter().map(|f| {
+                json!({ "name": f.base, "status": "failed", "reason": f.reason })
+            }))
+            .chain(s.skipped.iter().map(|(name, dep)| {
+                json!({ "name": name, "status": "skipped", "failed-dependency": dep })
+            }))
+            .collect(),
+    };
+
+    let error = result.as_ref().err().map(|e| {
+        json!({
+            "code": e.code(),
+            "message": e.localise(fll),
+        })
+    });
+
+    json!({
+        "command": std::env::args().collect::<Vec<_>>().join(" "),
+        "host": crate::utils::hostname(),
+        "success": result.as_ref().map(|s| s.unbuilt() == 0).unwrap_or(false),
+        "seconds": started.elapsed().as_secs(),
+        "finished": OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+        "packages": packages,
+        "error": error,
+    })
+}
+
+/// Run a shell command with the given text as its `stdin`.
+fn pipe(cmd: &str, input: &str) -> Option<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    child.stdin.take()?.write_all(input.as_bytes()).ok()?;
+    child.wait().ok()?.success().then(|| ())
+}
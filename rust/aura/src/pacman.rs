@@ -2,16 +2,55 @@
 
 use crate::error::Nested;
 use crate::localization::Localised;
+use crate::utils::PathStr;
+use crate::{aura, green, proceed, yellow};
+use aura::caps::Mutating;
+use aura_core::explain::Explain;
+use aura_core::timings::{self, Phase};
+use aura_core::Coded;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
-use log::error;
+use log::{debug, error};
 use std::ffi::OsStr;
-use std::process::Command;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// What marks an entry of `pacman`'s log as coming from ALPM itself, rather
+/// than from the output of a hook or scriptlet.
+const LOG_ALPM: &str = "] [ALPM] ";
+
+/// How often `pacman`'s log is checked for new entries while it runs.
+const POLL: Duration = Duration::from_millis(50);
+
+/// What `pacman` says on `stderr` when a hook exits with an error. Only
+/// `pacman`'s English output is understood.
+const HOOK_FAILED: &str = "command failed to execute correctly";
+
+/// Programs besides `pacman` itself that are known to lock its database.
+const LOCKERS: &[&str] = &[
+    "pacman",
+    "pamac",
+    "pamac-daemon",
+    "packagekitd",
+    "octopi",
+    "yay",
+    "paru",
+    "pikaur",
+];
 
 pub(crate) enum Error {
     ExternalCmd(std::io::Error),
     InstallFromTarball,
     InstallFromRepos,
     Misc,
+    DbLocked(u32, String),
+    DbStale(PathBuf),
 }
 
 impl Nested for Error {
@@ -21,6 +60,8 @@ impl Nested for Error {
             Error::InstallFromTarball => {}
             Error::InstallFromRepos => {}
             Error::Misc => {}
+            Error::DbLocked(_, _) => {}
+            Error::DbStale(_) => {}
         }
     }
 }
@@ -32,6 +73,108 @@ impl Localised for Error {
             Error::InstallFromTarball => fl!(fll, "pacman-u"),
             Error::InstallFromRepos => fl!(fll, "pacman-s"),
             Error::Misc => fl!(fll, "pacman-misc"),
+            Error::DbLocked(pid, name) => {
+                fl!(fll, "pacman-db-held", pid = pid, name = name.as_str())
+            }
+            Error::DbStale(p) => fl!(fll, "pacman-db-kept", file = p.utf8()),
+        }
+    }
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::ExternalCmd(_) => "pacman-external-cmd",
+            Error::InstallFromTarball => "pacman-install-from-tarball",
+            Error::InstallFromRepos => "pacman-install-from-repos",
+            Error::Misc => "pacman-misc",
+            Error::DbLocked(_, _) => "pacman-db-locked",
+            Error::DbStale(_) => "pacman-db-stale",
+        }
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::DbLocked(pid, name) => vec![("pid", pid.to_string()), ("command", name.clone())],
+            Error::DbStale(p) => vec![("path", p.display().to_string())],
+            Error::ExternalCmd(_)
+            | Error::InstallFromTarball
+            | Error::InstallFromRepos
+            | Error::Misc => Vec::new(),
+        }
+    }
+}
+
+/// The state of `pacman`'s database lock, `db.lck`.
+pub(crate) enum DbLock {
+    /// Nothing holds the lock.
+    Free,
+    /// Some running process (given by PID and name) may hold the lock.
+    Held(u32, String),
+    /// The lock exists, but nothing seems to be holding it.
+    Stale(PathBuf),
+}
+
+/// Is `pacman`'s database locked, and if so, by whom?
+pub(crate) fn db_lock(db_path: &str) -> DbLock {
+    let lock = Path::new(db_path).join("db.lck");
+
+    if lock.exists().not() {
+        return DbLock::Free;
+    }
+
+    let procs: Vec<(u32, String)> = std::fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_str().and_then(|n| n.parse().ok()))
+        .filter(|pid| *pid != std::process::id())
+        .filter_map(|pid| {
+            std::fs::read_to_string(format!("/proc/{pid}/comm"))
+                .ok()
+                .map(|c| (pid, c.trim().to_string()))
+        })
+        .collect();
+
+    // The holder keeps the lock file open, which is visible to anyone allowed
+    // to look. Otherwise, the usual suspects are assumed to hold it.
+    let by_fd = procs.iter().find(|(pid, _)| {
+        std::fs::read_dir(format!("/proc/{pid}/fd"))
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .any(|e| std::fs::read_link(e.path()).ok().as_deref() == Some(lock.as_path()))
+    });
+    let by_name = || procs.iter().find(|(_, c)| LOCKERS.contains(&c.as_str()));
+
+    match by_fd.or_else(by_name) {
+        Some((pid, comm)) => DbLock::Held(*pid, comm.clone()),
+        None => DbLock::Stale(lock),
+    }
+}
+
+/// Fail early if `pacman`'s database is locked, rather than letting `pacman`
+/// fail opaquely. A lock that nothing holds can be removed, after asking.
+pub(crate) fn ensure_unlocked(
+    _: &Mutating,
+    fll: &FluentLanguageLoader,
+    db_path: &str,
+) -> Result<(), Error> {
+    match db_lock(db_path) {
+        DbLock::Free => Ok(()),
+        DbLock::Held(pid, name) => Err(Error::DbLocked(pid, name)),
+        DbLock::Stale(lock) => {
+            yellow!(fll, "pacman-db-stale", file = lock.utf8());
+            proceed!(fll, "pacman-db-remove").ok_or_else(|| Error::DbStale(lock.clone()))?;
+
+            crate::audit::status(Command::new("sudo").arg("rm").arg(&lock))
+                .map_err(Error::ExternalCmd)?
+                .success()
+                .then(|| ())
+                .ok_or(Error::Misc)?;
+
+            green!(fll, "common-done");
+            Ok(())
         }
     }
 }
@@ -44,6 +187,7 @@ where
 {
     Command::new("pacman")
         .args(args)
+        .explained()
         .status()
         .map_err(Error::ExternalCmd)?
         .success()
@@ -52,19 +196,38 @@ where
 }
 
 /// Make an elevated shell call to `pacman`.
-pub(crate) fn sudo_pacman<I, J, S, T>(command: &str, flags: I, args: J) -> Result<(), Error>
+pub(crate) fn sudo_pacman<I, J, S, T>(
+    _: &Mutating,
+    command: &str,
+    flags: I,
+    args: J,
+) -> Result<(), Error>
 where
     I: IntoIterator<Item = S>,
     J: IntoIterator<Item = T>,
     S: AsRef<OsStr>,
     T: AsRef<OsStr>,
 {
-    Command::new("sudo")
-        .arg("pacman")
-        .arg(command)
-        .args(flags)
-        .args(args)
-        .status()
+    crate::audit::status(
+        Command::new("sudo")
+            .arg("pacman")
+            .arg(command)
+            .args(flags)
+            .args(args),
+    )
+    .map_err(Error::ExternalCmd)?
+    .success()
+    .then(|| ())
+    .ok_or(Error::Misc)
+}
+
+/// Make an elevated shell call to `pacman-key`.
+pub(crate) fn sudo_pacman_key<I, S>(_: &Mutating, args: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    crate::audit::status(Command::new("sudo").arg("pacman-key").args(args))
         .map_err(Error::ExternalCmd)?
         .success()
         .then(|| ())
@@ -72,39 +235,331 @@ where
 }
 
 /// Make an elevated shell call to `pacman`, passing all arguments to pacman as-is.
-pub(crate) fn sudo_pacman_batch<I, S>(args: I) -> Result<(), Error>
+pub(crate) fn sudo_pacman_batch<I, S>(_: &Mutating, args: I) -> Result<(), Error>
 where
     I: IntoIterator<Item = S>,
     S: AsRef<OsStr>,
 {
-    Command::new("sudo")
-        .arg("pacman")
-        .args(args)
-        .status()
+    crate::audit::status(Command::new("sudo").arg("pacman").args(args))
         .map_err(Error::ExternalCmd)?
         .success()
         .then(|| ())
         .ok_or(Error::Misc)
 }
 
-/// Call `sudo pacman -U`.
-pub(crate) fn pacman_install_from_tarball<I, J, S, T>(flags: I, args: J) -> Result<(), Error>
+/// Call `sudo pacman -U`, and summarize the hooks it ran.
+pub(crate) fn pacman_install_from_tarball<I, J, S, T>(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    log: &Path,
+    flags: I,
+    args: J,
+) -> Result<(), Error>
 where
     I: IntoIterator<Item = S>,
     J: IntoIterator<Item = T>,
     S: AsRef<OsStr>,
     T: AsRef<OsStr>,
 {
-    sudo_pacman("-U", flags, args).map_err(|_| Error::InstallFromTarball)
+    let (hooks, result) = sudo_pacman_hooked(m, log, "-U", flags, args);
+    report_hooks(fll, &hooks);
+    result.map_err(|_| Error::InstallFromTarball)
 }
 
-/// Call `sudo pacman -S`.
-pub(crate) fn pacman_install_from_repos<I, J, S, T>(flags: I, args: J) -> Result<(), Error>
+/// Call `sudo pacman` with the arguments of `-U` as given, and summarize the
+/// hooks it ran.
+pub(crate) fn pacman_upgrade<I, S>(
+    _: &Mutating,
+    fll: &FluentLanguageLoader,
+    log: &Path,
+    args: I,
+) -> Result<(), Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut cmd = Command::new("sudo");
+    cmd.arg("pacman").args(args);
+
+    let (hooks, result) = watch(log, cmd);
+    report_hooks(fll, &hooks);
+    result
+}
+
+/// Call `sudo pacman -S`, and summarize the hooks it ran.
+pub(crate) fn pacman_install_from_repos<I, J, S, T>(
+    m: &Mutating,
+    fll: &FluentLanguageLoader,
+    log: &Path,
+    flags: I,
+    args: J,
+) -> Result<(), Error>
+where
+    I: IntoIterator<Item = S>,
+    J: IntoIterator<Item = T>,
+    S: AsRef<OsStr>,
+    T: AsRef<OsStr>,
+{
+    let (hooks, result) = sudo_pacman_hooked(m, log, "-S", flags, args);
+    report_hooks(fll, &hooks);
+    result.map_err(|_| Error::InstallFromRepos)
+}
+
+/// A `pacman` hook that ran during a transaction.
+struct Hook {
+    /// The hook's filename, without the `.hook`.
+    name: String,
+    /// How long the hook took, as seen from the outside.
+    took: Duration,
+    /// Did `pacman` report that the hook exited with an error?
+    failed: bool,
+}
+
+/// Like [`sudo_pacman`], but note the hooks that `pacman` runs.
+fn sudo_pacman_hooked<I, J, S, T>(
+    _: &Mutating,
+    log: &Path,
+    command: &str,
+    flags: I,
+    args: J,
+) -> (Vec<Hook>, Result<(), Error>)
 where
     I: IntoIterator<Item = S>,
     J: IntoIterator<Item = T>,
     S: AsRef<OsStr>,
     T: AsRef<OsStr>,
 {
-    sudo_pacman("-S", flags, args).map_err(|_| Error::InstallFromRepos)
+    let _timer = timings::time(Phase::Install);
+    let mut cmd = Command::new("sudo");
+    cmd.arg("pacman").arg(command).args(flags).args(args);
+
+    watch(log, cmd)
+}
+
+/// Run a `pacman` command while noting the hooks it runs.
+///
+/// Its `stdout` is left alone, so that prompts and progress bars work as
+/// usual. Hooks are instead followed through `pacman`'s log as it's written,
+/// which is never translated. A hook starts at its `running '<name>.hook'...`
+/// entry, and is considered finished at the next entry that isn't its own
+/// output. Only `stderr` is passed through by Aura, to notice which hooks
+/// failed.
+fn watch(log: &Path, mut cmd: Command) -> (Vec<Hook>, Result<(), Error>) {
+    // Only what's logged from here on belongs to this transaction.
+    let mut log = File::open(log).ok().and_then(|mut f| {
+        f.seek(SeekFrom::End(0)).ok()?;
+        Some(f)
+    });
+
+    aura_core::explain::show(&cmd);
+
+    let mut child = match cmd.stderr(Stdio::piped()).spawn() {
+        Ok(c) => c,
+        Err(e) => return (Vec::new(), Err(Error::ExternalCmd(e))),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let reader = child.stderr.take().map(|e| forward(e, tx));
+
+    let mut tracker = Tracker::default();
+    let mut err_line = String::new();
+
+    loop {
+        match rx.recv_timeout(POLL) {
+            Ok(bytes) => {
+                let _ = std::io::stderr().write_all(&bytes);
+                err_line.push_str(&String::from_utf8_lossy(&bytes));
+
+                // Warnings are common and harmless, so only the hook's own
+                // result counts. It's noted before reading the log any
+                // further, which may already name the next hook.
+                while let Some(ix) = err_line.find('\n') {
+                    let full: String = err_line.drain(..=ix).collect();
+                    if full.contains(HOOK_FAILED) {
+                        tracker.failed();
+                    }
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        follow(log.as_mut(), &mut tracker);
+    }
+
+    if let Some(h) = reader {
+        let _ = h.join();
+    }
+
+    let status = child.wait();
+    crate::audit::record(&cmd, status.as_ref().ok());
+    follow(log.as_mut(), &mut tracker);
+
+    let result = status
+        .map_err(Error::ExternalCmd)
+        .and_then(|s| s.success().then(|| ()).ok_or(Error::Misc));
+
+    (tracker.finish(), result)
+}
+
+/// Pass what's newly been written to the log on to the tracker.
+fn follow(log: Option<&mut File>, tracker: &mut Tracker) {
+    let mut text = String::new();
+
+    if let Some(f) = log {
+        if f.read_to_string(&mut text).is_ok() {
+            tracker.feed(&text);
+        }
+    }
+}
+
+/// Pass the raw output of a stream along as it arrives.
+fn forward<R>(mut from: R, tx: mpsc::Sender<Vec<u8>>) -> std::thread::JoinHandle<()>
+where
+    R: Read + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let mut buf = [0; 4096];
+
+        while let Ok(n) = from.read(&mut buf) {
+            if n == 0 || tx.send(buf[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// The hooks seen so far in `pacman`'s log.
+#[derive(Default)]
+struct Tracker {
+    done: Vec<Hook>,
+    current: Option<(String, Instant, bool)>,
+    /// The end of the log that isn't yet a whole line.
+    partial: String,
+}
+
+impl Tracker {
+    fn feed(&mut self, text: &str) {
+        self.partial.push_str(text);
+
+        while let Some(ix) = self.partial.find('\n') {
+            let full: String = self.partial.drain(..=ix).collect();
+
+            if let Some(name) = hook_start(&full) {
+                self.end();
+                self.current = Some((name.to_string(), Instant::now(), false));
+            } else if full.contains(LOG_ALPM) {
+                self.end();
+            }
+        }
+    }
+
+    /// The hook running now has reported an error.
+    fn failed(&mut self) {
+        if let Some((_, _, failed)) = self.current.as_mut() {
+            *failed = true;
+        }
+    }
+
+    fn end(&mut self) {
+        if let Some((name, start, failed)) = self.current.take() {
+            self.done.push(Hook {
+                name,
+                took: start.elapsed(),
+                failed,
+            });
+        }
+    }
+
+    fn finish(mut self) -> Vec<Hook> {
+        self.end();
+        self.done
+    }
+}
+
+/// If this line of `pacman`'s log announces a hook, the name of that hook.
+fn hook_start(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once(LOG_ALPM)?;
+
+    rest.trim_end()
+        .strip_prefix("running '")?
+        .strip_suffix("'...")?
+        .strip_suffix(".hook")
+}
+
+/// Display which hooks ran, how long each took, and which seem to have failed.
+fn report_hooks(fll: &FluentLanguageLoader, hooks: &[Hook]) {
+    if hooks.is_empty() {
+        return;
+    }
+
+    debug!("{} hooks ran.", hooks.len());
+    aura!(fll, "pacman-hooks");
+    let longest = hooks
+        .iter()
+        .map(|h| h.name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for h in hooks {
+        let took = format!("{:.1}s", h.took.as_secs_f32());
+        let name = if h.failed {
+            h.name.red()
+        } else {
+            h.name.normal()
+        };
+        println!(" {:w$} {:>7}", name, took, w = longest);
+    }
+
+    let failed: Vec<&str> = hooks
+        .iter()
+        .filter(|h| h.failed)
+        .map(|h| h.name.as_str())
+        .collect();
+
+    if failed.is_empty().not() {
+        yellow!(fll, "pacman-hooks-failed", hooks = failed.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hook_lines() {
+        let log = "\
+[2026-10-02T10:00:00+0000] [ALPM] transaction completed
+[2026-10-02T10:00:00+0000] [ALPM] running '30-systemd-update.hook'...
+[2026-10-02T10:00:01+0000] [ALPM] running '90-mkinitcpio-install.hook'...
+[2026-10-02T10:00:01+0000] [ALPM-SCRIPTLET] ==> Building image from preset
+[2026-10-02T10:00:09+0000] [ALPM-SCRIPTLET] ==> Image generation successful
+[2026-10-02T10:00:09+0000] [ALPM] running 'dbus-reload.hook'...
+";
+        let mut t = Tracker::default();
+        let split = log
+            .find("[2026-10-02T10:00:01+0000] [ALPM-SCRIPTLET]")
+            .unwrap();
+        t.feed(&log[..split]);
+        t.failed();
+        t.feed(&log[split..]);
+        let hooks = t.finish();
+
+        let names: Vec<&str> = hooks.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(
+            vec!["30-systemd-update", "90-mkinitcpio-install", "dbus-reload"],
+            names
+        );
+        assert!(hooks[1].failed);
+        assert!(hooks[0].failed.not());
+
+        assert_eq!(
+            None,
+            hook_start("[2026-10-02T10:00:00+0000] [ALPM] transaction started")
+        );
+        assert_eq!(
+            None,
+            hook_start("[2026-10-02T10:00:00+0000] [ALPM-SCRIPTLET] running 'x.hook'...")
+        );
+    }
 }
@@ -0,0 +1,81 @@
+//! Paging long output, as `git` and `systemctl` do.
+//!
+//! Output only goes through a pager when it's headed for a terminal. The pager
+//! is `$AURA_PAGER`, `$PAGER`, or `less`, in that order, and `less` is told to
+//! quit by itself when everything fits on one screen, so that short output
+//! looks no different than it would without one.
+
+use log::debug;
+use std::io::Write;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::process::{Child, Command, Stdio};
+
+/// What `less` is run with, unless `$LESS` says otherwise: quit if the output
+/// fits on one screen, pass colours through, and leave the output on screen.
+const LESS: &str = "FRX";
+
+/// While alive, everything printed goes to a pager.
+pub(crate) struct Pager {
+    child: Child,
+    /// The original standard output, for putting it back.
+    stdout: RawFd,
+}
+
+/// Send standard output through a pager, if it's a terminal and some pager is
+/// available.
+pub(crate) fn start() -> Option<Pager> {
+    if unsafe { libc::isatty(libc::STDOUT_FILENO) } != 1 {
+        return None;
+    }
+
+    let cmd = std::env::var("AURA_PAGER")
+        .or_else(|_| std::env::var("PAGER"))
+        .unwrap_or_else(|_| "less".to_string());
+    let mut words = cmd.split_whitespace();
+    let program = words.next().filter(|p| *p != "cat")?;
+
+    let mut child = Command::new(program)
+        .args(words)
+        .env(
+            "LESS",
+            std::env::var("LESS").unwrap_or_else(|_| LESS.to_string()),
+        )
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| debug!("Couldn't start the pager {program}: {e}"))
+        .ok()?;
+    let stdin = child.stdin.take()?;
+
+    // Colours are decided by whether stdout is a terminal, which it's about to
+    // stop being.
+    let colour = colored::control::SHOULD_COLORIZE.should_colorize();
+    colored::control::set_override(colour);
+
+    let stdout = unsafe { libc::dup(libc::STDOUT_FILENO) };
+    if stdout < 0 || unsafe { libc::dup2(stdin.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        return None;
+    }
+
+    // Quitting the pager early should end Aura quietly, not with a panic
+    // about a broken pipe.
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+
+    Some(Pager { child, stdout })
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        let _ = std::io::stdout().flush();
+
+        // Putting the original stdout back closes the pager's end of the pipe,
+        // so that it knows nothing more is coming.
+        unsafe {
+            libc::dup2(self.stdout, libc::STDOUT_FILENO);
+            libc::close(self.stdout);
+        }
+
+        let _ = self.child.wait();
+    }
+}
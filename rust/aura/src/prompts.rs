@@ -0,0 +1,116 @@
+//! Aura's yes/no questions, and how they're answered.
+//!
+//! Every question has an ID: the Fluent key of its message, or a more specific
+//! name when the message is shared, like the plain "Proceed?". The `[prompts]`
+//! section of `aura.toml` can change the answer that pressing Enter gives:
+//!
+//! ```toml
+//! [prompts]
+//! confirm_count = true
+//!
+//! [prompts.defaults]
+//! A-build-diff = false
+//! ```
+//!
+//! With `confirm_count`, destructive operations like restoring a snapshot must
+//! be confirmed by typing the number of packages they affect.
+
+use crate::env::Prompts;
+use crate::{a, aln};
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use once_cell::sync::OnceCell;
+use rustyline::Editor;
+use std::ops::Not;
+
+static SETTINGS: OnceCell<Prompts> = OnceCell::new();
+
+/// Use these settings for all questions asked from now on.
+pub(crate) fn init(prompts: Prompts) {
+    let _ = SETTINGS.set(prompts);
+}
+
+/// The answer that pressing Enter gives to some question.
+pub(crate) fn default(id: &str) -> bool {
+    SETTINGS.get().map(|p| default_in(p, id)).unwrap_or(true)
+}
+
+/// The answer that pressing Enter gives to some question, given settings.
+fn default_in(prompts: &Prompts, id: &str) -> bool {
+    prompts.defaults.get(id).copied().unwrap_or(true)
+}
+
+/// Ask a yes/no question.
+pub(crate) fn ask(fll: &FluentLanguageLoader, id: &str, msg: String) -> Option<()> {
+    let default = default(id);
+    let hint = if default {
+        fl!(fll, "proceed-yes")
+    } else {
+        fl!(fll, "proceed-no")
+    };
+
+    crate::utils::prompt(&a!(format!("{} {} ", msg, hint)), default)
+}
+
+/// Ask for permission to do something destructive to some number of packages.
+/// In the safer mode, only typing that number counts as a "yes".
+pub(crate) fn ask_count(
+    fll: &FluentLanguageLoader,
+    id: &str,
+    msg: String,
+    count: usize,
+) -> Option<()> {
+    if SETTINGS
+        .get()
+        .map(|p| p.confirm_count)
+        .unwrap_or(false)
+        .not()
+    {
+        return ask(fll, id, msg);
+    }
+
+    // The count itself is never shown, or typing it would prove nothing.
+    aln!(msg);
+    let question = fl!(fll, "prompt-type-count");
+    let mut rl = Editor::<()>::new().ok()?;
+    let line = rl.readline(&a!(format!("{} ", question))).ok()?;
+
+    counted(&line, count).then(|| ())
+}
+
+/// Does a typed line confirm an operation on some number of packages?
+fn counted(line: &str, count: usize) -> bool {
+    line.trim()
+        .parse::<usize>()
+        .map(|n| n == count)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let prompts = Prompts {
+            defaults: [("A-build-diff".to_string(), false)].into_iter().collect(),
+            confirm_count: false,
+        };
+
+        assert!(default_in(&prompts, "A-build-diff").not());
+        assert!(default_in(&prompts, "proceed"));
+        assert!(default_in(&Prompts::default(), "A-build-diff"));
+    }
+
+    #[test]
+    fn counts() {
+        assert!(counted("12", 12));
+        assert!(counted(" 12\n", 12));
+        assert!(counted("012", 12));
+        assert!(counted("", 12).not());
+        assert!(counted("y", 12).not());
+        assert!(counted("11", 12).not());
+        assert!(counted("12 packages", 12).not());
+    }
+}
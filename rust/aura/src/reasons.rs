@@ -0,0 +1,231 @@
+//! A record of why each dependency was installed.
+//!
+//! ALPM only remembers that a package is a dependency, not whose. So whenever
+//! Aura installs packages, each new dependency is noted along with the
+//! packages that pulled it in. Together with the removals in the ALPM log,
+//! this explains how an orphan came to be one.
+
+use crate::env::Env;
+use alpm::{Alpm, PackageReason};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::ops::Not;
+use std::path::Path;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// A dependency, and the packages that it was installed for.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Reason {
+    /// When the dependency was installed, in RFC 3339.
+    pub(crate) time: String,
+    /// The name of the dependency itself.
+    pub(crate) dep: String,
+    /// The packages that required the dependency when it was installed,
+    /// sorted by name.
+    pub(crate) parents: Vec<String>,
+}
+
+/// The packages that were installed before some work began.
+pub(crate) struct Watch {
+    before: HashSet<String>,
+}
+
+impl Watch {
+    /// Note what's installed now.
+    pub(crate) fn start(env: &Env) -> Option<Watch> {
+        let alpm = env.alpm().ok()?;
+        let before = installed(&alpm);
+        Some(Watch { before })
+    }
+
+    /// Record the parents of each dependency installed since the watch began.
+    pub(crate) fn finish(self, env: &Env) {
+        let alpm = match env.alpm() {
+            Ok(a) => a,
+            Err(_) => return,
+        };
+
+        let now = installed(&alpm);
+        let new: HashSet<String> = now.difference(&self.before).cloned().collect();
+        let time = OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_default();
+
+        let db = alpm.localdb();
+        let reasons: Vec<Reason> = new
+            .iter()
+            .filter_map(|n| db.pkg(n.as_str()).ok())
+            .filter(|p| p.reason() == PackageReason::Depend)
+            .map(|p| {
+                let required: Vec<String> = p.required_by().into_iter().collect();
+                // Those installed alongside it are the likeliest culprits.
+                let fresh: Vec<String> = required
+                    .iter()
+                    .filter(|r| new.contains(*r))
+                    .cloned()
+                    .collect();
+                let mut parents = if fresh.is_empty() { required } else { fresh };
+                parents.sort_unstable();
+
+                Reason {
+                    time: time.clone(),
+                    dep: p.name().to_string(),
+                    parents,
+                }
+            })
+            .collect();
+
+        if reasons.is_empty().not() {
+            write(&now, reasons);
+        }
+    }
+}
+
+fn installed(alpm: &Alpm) -> HashSet<String> {
+    alpm.localdb()
+        .pkgs()
+        .iter()
+        .map(|p| p.name().to_string())
+        .collect()
+}
+
+fn write(installed: &HashSet<String>, reasons: Vec<Reason>) {
+    match crate::dirs::reasons() {
+        Err(_) => warn!("Failed to locate the record of install reasons."),
+        Ok(path) => {
+            debug!("Recording install reasons to {}", path.display());
+
+            if let Err(e) = record(&path, installed, reasons) {
+                warn!("Failed to write to {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+/// Merge new reasons into the record, rewriting it with only the latest
+/// reason for each dependency that's still installed. Otherwise the record
+/// would grow with every transaction.
+fn record(path: &Path, installed: &HashSet<String>, reasons: Vec<Reason>) -> std::io::Result<()> {
+    let mut all = read(path);
+    all.extend(reasons.into_iter().map(|r| (r.dep.clone(), r)));
+    all.retain(|dep, _| installed.contains(dep));
+
+    let mut kept: Vec<Reason> = all.into_values().collect();
+    kept.sort_unstable_by(|a, b| a.dep.cmp(&b.dep));
+
+    // Written aside first, so that an interruption can't truncate the record.
+    let temp = path.with_extension("log.tmp");
+    let mut file = BufWriter::new(File::create(&temp)?);
+    for r in kept.iter() {
+        let json = serde_json::to_string(r).unwrap_or_default();
+        writeln!(file, "{json}")?;
+    }
+    file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+    std::fs::rename(temp, path)
+}
+
+/// The most recent reason recorded for each dependency.
+pub(crate) fn load() -> HashMap<String, Reason> {
+    crate::dirs::reasons().map(|p| read(&p)).unwrap_or_default()
+}
+
+fn read(path: &Path) -> HashMap<String, Reason> {
+    File::open(path)
+        .map(|f| {
+            BufReader::new(f)
+                .lines()
+                .filter_map(|l| l.ok())
+                .filter_map(|l| serde_json::from_str::<Reason>(&l).ok())
+                .map(|r| (r.dep.clone(), r))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The date on which each package was last removed, according to the ALPM
+/// log.
+pub(crate) fn removals(log: &Path) -> HashMap<String, String> {
+    let file = match std::fs::File::open(log) {
+        Ok(f) => BufReader::new(f),
+        Err(_) => return HashMap::new(),
+    };
+
+    file.lines()
+        .filter_map(|l| l.ok())
+        .filter_map(|line| {
+            let action = aura_core::log::Action::parse(&line).ok()?;
+            let date = action.time.get(..10)?;
+            (action.verb == "removed").then(|| (action.package.to_string(), date.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn reason(time: &str, dep: &str, parents: &[&str]) -> Reason {
+        Reason {
+            time: time.to_string(),
+            dep: dep.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn compaction() {
+        let dir = std::env::temp_dir().join(format!("aura-reasons-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("reasons.log");
+        let installed: HashSet<String> = ["libfoo", "libbar"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let first = vec![
+            reason("2024-05-01T00:00:00Z", "libfoo", &["foo"]),
+            reason("2024-05-01T00:00:00Z", "libgone", &["gone"]),
+        ];
+        record(&path, &installed, first).unwrap();
+        let second = vec![
+            reason("2024-05-02T00:00:00Z", "libfoo", &["bar", "baz"]),
+            reason("2024-05-02T00:00:00Z", "libbar", &["bar"]),
+        ];
+        record(&path, &installed, second).unwrap();
+
+        let lines = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(2, lines.lines().count());
+
+        let reasons = read(&path);
+        assert_eq!(2, reasons.len());
+        assert_eq!(
+            Some(&reason("2024-05-02T00:00:00Z", "libfoo", &["bar", "baz"])),
+            reasons.get("libfoo")
+        );
+        assert!(reasons.contains_key("libgone").not());
+        assert!(path.with_extension("log.tmp").exists().not());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_lines() {
+        let dir = std::env::temp_dir().join(format!("aura-reasons-bad-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("reasons.log");
+        let good = r#"{"time":"2024-05-01T00:00:00Z","dep":"libfoo","parents":["foo"]}"#;
+        std::fs::write(&path, format!("garbage\n{good}\n")).unwrap();
+
+        let reasons = read(&path);
+        assert_eq!(1, reasons.len());
+        assert!(reasons.contains_key("libfoo"));
+        assert!(read(&dir.join("missing")).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
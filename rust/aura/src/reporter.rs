@@ -0,0 +1,124 @@
+//! How much of the work of installing and upgrading AUR packages is shown.
+//!
+//! Every step of the pipeline reports through here, so the three tiers behave
+//! the same no matter which command drove the work:
+//!
+//! - `quiet`: One line per package, saying how it ended.
+//! - `normal`: Each stage as it begins, the packages involved, and the build
+//!   output as it happens.
+//! - `verbose`: As `normal`, as well as how dependencies were resolved, and
+//!   every build command that was run.
+//!
+//! At the `quiet` tier, build output goes to a log per package in Aura's
+//! cache, which is pointed to should the build fail.
+
+use crate::aln;
+use crate::env::Verbosity;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use std::fmt::Display;
+use std::fs::File;
+use std::ops::Not;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// How a package's part of the work ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    /// Built and installed.
+    Installed,
+    /// Installed pre-built from a third-party repo.
+    Prebuilt,
+    /// Its build failed.
+    Failed,
+    /// Not attempted, since a dependency wasn't built.
+    Skipped,
+}
+
+/// Presents the progress of the pipeline at some [`Verbosity`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Reporter {
+    verbosity: Verbosity,
+}
+
+impl Reporter {
+    pub(crate) fn new(verbosity: Verbosity) -> Reporter {
+        Reporter { verbosity }
+    }
+
+    pub(crate) fn is_quiet(&self) -> bool {
+        self.verbosity == Verbosity::Quiet
+    }
+
+    /// Announce the beginning of some stage of the work.
+    pub(crate) fn stage(&self, msg: String) {
+        if self.is_quiet().not() {
+            aln!(msg);
+        }
+    }
+
+    /// Mention something only worth seeing at the verbose tier.
+    pub(crate) fn detail<D: Display>(&self, msg: D) {
+        if self.verbosity == Verbosity::Verbose {
+            println!("    {}", msg.to_string().truecolor(128, 128, 128));
+        }
+    }
+
+    /// Mention a command that's about to be run.
+    pub(crate) fn command(&self, cmd: &Command) {
+        if aura_core::explain::is_enabled() {
+            aura_core::explain::show(cmd);
+        } else if self.verbosity == Verbosity::Verbose {
+            let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+            let line = format!("{} {}", cmd.get_program().to_string_lossy(), args.join(" "));
+            self.detail(line.trim_end());
+        }
+    }
+
+    /// Report how a package's part of the work ended. The other tiers have
+    /// already said as much along the way, so only the quiet one prints this.
+    pub(crate) fn outcome(&self, fll: &FluentLanguageLoader, pkg: &str, outcome: Outcome) {
+        if self.is_quiet().not() {
+            return;
+        }
+
+        let status = match outcome {
+            Outcome::Installed => fl!(fll, "report-installed").green(),
+            Outcome::Prebuilt => fl!(fll, "report-prebuilt").green(),
+            Outcome::Failed => fl!(fll, "report-failed").red(),
+            Outcome::Skipped => fl!(fll, "report-skipped").yellow(),
+        };
+
+        println!(" {} {}", pkg.cyan(), status);
+
+        if outcome == Outcome::Failed {
+            if let Some(log) = build_log(pkg).filter(|l| l.is_file()) {
+                let path = log.display().to_string();
+                println!("      └─ {}", fl!(fll, "report-log", path = path));
+            }
+        }
+    }
+
+    /// Send the output of a package's build to its log when quiet. Otherwise,
+    /// or if the log can't be written, it's shown as it happens.
+    pub(crate) fn build_output(&self, base: &str, cmd: &mut Command) {
+        if self.is_quiet().not() {
+            return;
+        }
+
+        let log = build_log(base).and_then(|l| File::create(l).ok());
+        let err = log.as_ref().and_then(|f| f.try_clone().ok());
+
+        if let (Some(out), Some(err)) = (log, err) {
+            cmd.stdout(out).stderr(err);
+        }
+    }
+}
+
+/// Where the output of a quiet build of the given package base is kept.
+fn build_log(base: &str) -> Option<PathBuf> {
+    crate::dirs::build_logs()
+        .ok()
+        .map(|d| d.join(format!("{base}.log")))
+}
@@ -0,0 +1,241 @@
+//! Graceful handling of Ctrl-C and termination requests.
+//!
+//! Outside of a [`Guard`], a signal kills Aura as it always has. Within one,
+//! the first signal is only recorded (and passed on to tracked child
+//! processes), so that the work in progress fails normally and everything
+//! cleans up after itself on the way out. A second signal is fatal.
+//!
+//! A child with a time limit stays in the terminal's foreground group, so that
+//! it can still read from the terminal and receives Ctrl-C as usual. When its
+//! time runs out, everything it started is found through `/proc` and stopped
+//! along with it.
+
+use std::collections::HashMap;
+use std::ops::Not;
+use std::process::{Child, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// The conventional exit code of a program stopped by Ctrl-C.
+pub(crate) const EXIT_CODE: u8 = 130;
+
+/// How often a child with a time limit is checked on.
+const POLL: Duration = Duration::from_millis(250);
+
+/// How long a child that has run out of time may take to exit cleanly before
+/// it's killed outright.
+const GRACE: Duration = Duration::from_secs(10);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// The number of live [`Guard`]s.
+static GUARDS: AtomicUsize = AtomicUsize::new(0);
+
+/// Running child processes to pass termination requests on to. An unused slot
+/// holds `0`.
+static CHILDREN: [AtomicI32; 8] = [
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+];
+
+/// While a `Guard` is alive, signals no longer kill Aura outright.
+pub(crate) struct Guard;
+
+impl Guard {
+    pub(crate) fn new() -> Guard {
+        GUARDS.fetch_add(1, Ordering::SeqCst);
+        Guard
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        GUARDS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Start listening for `SIGINT` and `SIGTERM`.
+pub(crate) fn install() {
+    let handler = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+
+    // SAFETY: The handler only touches atomics and calls async-signal-safe
+    // functions.
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+/// Has the user asked Aura to stop?
+pub(crate) fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Like [`Command::status`], but a termination request sent to Aura is also
+/// sent to the child.
+///
+/// Ctrl-C needs no such help, since the terminal signals every process in the
+/// foreground at once.
+pub(crate) fn status(cmd: &mut Command) -> std::io::Result<ExitStatus> {
+    tracked(cmd, |child| child.wait())
+}
+
+/// Like [`status`], but the child and everything it starts are stopped once it
+/// has run for longer than the given limit. Yields `None` if that happened.
+pub(crate) fn status_within(
+    cmd: &mut Command,
+    limit: Duration,
+) -> std::io::Result<Option<ExitStatus>> {
+    tracked(cmd, |child| wait_within(child, limit))
+}
+
+/// Spawn a child that termination requests are passed on to while it runs.
+fn tracked<F, T>(cmd: &mut Command, wait: F) -> std::io::Result<T>
+where
+    F: FnOnce(&mut Child) -> std::io::Result<T>,
+{
+    let mut child = cmd.spawn()?;
+    let pid = child.id() as i32;
+    let slot = CHILDREN.iter().find(|s| {
+        s.compare_exchange(0, pid, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    });
+
+    let result = wait(&mut child);
+
+    if let Some(s) = slot {
+        s.store(0, Ordering::SeqCst);
+    }
+
+    result
+}
+
+fn wait_within(child: &mut Child, limit: Duration) -> std::io::Result<Option<ExitStatus>> {
+    let start = Instant::now();
+
+    while start.elapsed() < limit {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        std::thread::sleep(POLL);
+    }
+
+    // Politely at first, so that `makepkg` and the like can clean up after
+    // themselves.
+    let root = child.id() as i32;
+    kill_tree(root, libc::SIGTERM);
+
+    let grace = Instant::now();
+    while grace.elapsed() < GRACE {
+        if child.try_wait()?.is_some() {
+            return Ok(None);
+        }
+
+        std::thread::sleep(POLL);
+    }
+
+    kill_tree(root, libc::SIGKILL);
+    child.wait()?;
+    Ok(None)
+}
+
+/// Signal a process and all of its descendants. Those that can't be signalled,
+/// like anything run as another user, are left to their parents.
+fn kill_tree(root: i32, sig: libc::c_int) {
+    for pid in descendants(root).into_iter().chain(std::iter::once(root)) {
+        // SAFETY: `kill` has no memory-safety requirements.
+        unsafe {
+            libc::kill(pid, sig);
+        }
+    }
+}
+
+/// Every live process descended from the given one, as far as `/proc` shows.
+fn descendants(root: i32) -> Vec<i32> {
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for (pid, ppid) in entries.filter_map(|e| e.ok()).filter_map(|e| {
+            let pid = e.file_name().to_str()?.parse::<i32>().ok()?;
+            let stat = std::fs::read_to_string(e.path().join("stat")).ok()?;
+            Some((pid, parent(&stat)?))
+        }) {
+            children.entry(ppid).or_default().push(pid);
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut todo = vec![root];
+    while let Some(pid) = todo.pop() {
+        if let Some(cs) = children.get(&pid) {
+            found.extend_from_slice(cs);
+            todo.extend_from_slice(cs);
+        }
+    }
+
+    found
+}
+
+/// The parent's id from the contents of `/proc/<pid>/stat`. The command name
+/// in parentheses may itself hold spaces or parentheses, so fields are counted
+/// from the last `)`.
+fn parent(stat: &str) -> Option<i32> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+extern "C" fn handler(sig: libc::c_int) {
+    let first = INTERRUPTED.swap(true, Ordering::SeqCst).not();
+
+    let guarded = first && GUARDS.load(Ordering::SeqCst) > 0;
+
+    for slot in CHILDREN.iter() {
+        let pid = slot.load(Ordering::SeqCst);
+
+        // Children in the foreground group already got any Ctrl-C from the
+        // terminal.
+        if pid > 0 && guarded && sig == libc::SIGTERM {
+            // SAFETY: `kill` is async-signal-safe.
+            unsafe {
+                libc::kill(pid, sig);
+            }
+        }
+    }
+
+    if guarded.not() {
+        // SAFETY: Both are async-signal-safe. Restoring the default action
+        // and raising the signal again lets Aura die as usual.
+        unsafe {
+            libc::signal(sig, libc::SIG_DFL);
+            libc::raise(sig);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stat_parent() {
+        assert_eq!(Some(1), parent("42 (makepkg) S 1 42 42 0 -1"));
+        assert_eq!(Some(7), parent("9 (a) b) (c) R 7 9 9 0 -1"));
+        assert_eq!(None, parent("garbage"));
+    }
+
+    #[test]
+    fn own_descendants() {
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let me = std::process::id() as i32;
+        assert!(descendants(me).contains(&(child.id() as i32)));
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,142 @@
+//! Make sure there's room on disk before starting long-running work.
+//!
+//! Running out of space halfway through a build or an upgrade can leave a
+//! system in a worse state than never starting. Each piece of work states how
+//! much it expects to write where, and the needs of paths that share a
+//! filesystem are added together before being compared to what's free.
+
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use aura_core::Coded;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use ubyte::ToByteUnit;
+
+/// A filesystem that wouldn't have enough room.
+#[derive(Debug)]
+pub(crate) struct Shortfall {
+    /// One of the paths that lives on the full filesystem.
+    pub(crate) path: PathBuf,
+    /// The total bytes expected to be written to that filesystem.
+    pub(crate) need: u64,
+    /// The bytes available to unprivileged users there.
+    pub(crate) free: u64,
+}
+
+impl Localised for Shortfall {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        fl!(
+            fll,
+            "A-space",
+            path = self.path.utf8(),
+            need = self.need.bytes().to_string(),
+            free = self.free.bytes().to_string()
+        )
+    }
+}
+
+impl Coded for Shortfall {
+    fn code(&self) -> &'static str {
+        "space"
+    }
+
+    fn context(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("path", self.path.display().to_string()),
+            ("need", self.need.to_string()),
+            ("free", self.free.to_string()),
+        ]
+    }
+}
+
+/// Where `pacman` downloads packages to.
+pub(crate) fn pacman_cache(pacman: &pacmanconf::Config) -> &Path {
+    pacman
+        .cache_dir
+        .first()
+        .map(Path::new)
+        .unwrap_or_else(|| Path::new("/var/cache/pacman/pkg"))
+}
+
+/// Will every filesystem have room for what's expected to be written to it?
+/// Paths whose filesystem can't be inspected are given the benefit of the
+/// doubt.
+pub(crate) fn check(needs: &[(&Path, u64)]) -> Result<(), Shortfall> {
+    // Keyed by device, so that `/var/cache` and `/` on the same partition are
+    // considered together.
+    let mut totals: Vec<(u64, &Path, u64)> = Vec::new();
+
+    for (path, bytes) in needs.iter().filter(|(_, b)| *b > 0) {
+        match existing(path)
+            .and_then(|p| p.metadata().ok())
+            .map(|m| m.dev())
+        {
+            None => debug!("Can't determine the filesystem of {}", path.display()),
+            Some(dev) => match totals.iter_mut().find(|(d, _, _)| *d == dev) {
+                Some((_, _, total)) => *total += bytes,
+                None => totals.push((dev, path, *bytes)),
+            },
+        }
+    }
+
+    totals
+        .into_iter()
+        .find_map(|(_, path, need)| {
+            let free = free(path)?;
+            debug!("{}: {need} bytes needed, {free} free", path.display());
+            (need > free).then(|| Shortfall {
+                path: path.to_path_buf(),
+                need,
+                free,
+            })
+        })
+        .map_or(Ok(()), Err)
+}
+
+/// The bytes available to unprivileged users on the filesystem of some path.
+pub(crate) fn free(path: &Path) -> Option<u64> {
+    let path = existing(path)?;
+    let c = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: `c` is a valid C string, and `stat` is only read once the call
+    // has reported that it filled it.
+    let stat = unsafe {
+        if libc::statvfs(c.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// The nearest ancestor of some path that actually exists, since directories
+/// like the build cache might not have been created yet.
+fn existing(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|p| p.exists())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn room() {
+        let dir = std::env::temp_dir();
+        let tmp = dir.as_path();
+        let missing = dir.join("aura-space-test").join("not-yet");
+        let missing = missing.as_path();
+
+        assert!(free(missing).is_some());
+        assert!(check(&[(tmp, 1), (missing, 1)]).is_ok());
+
+        let short = check(&[(tmp, u64::MAX / 2), (missing, 1)]).unwrap_err();
+        assert_eq!(u64::MAX / 2 + 1, short.need);
+    }
+}
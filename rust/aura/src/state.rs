@@ -0,0 +1,311 @@
+//! The user data that belongs to certain packages, and upgrades that need
+//! manual steps.
+//!
+//! Some packages keep data whose format is tied to their version, like the
+//! database cluster of `postgresql`, or a browser's profiles, and others need
+//! some commands run after certain upgrades. Rules say which files mark the
+//! version of each such package's data, and which of its upgrades need
+//! attention. Before a matching upgrade, a warning is given, and with
+//! `state_hooks` enabled in the `[general]` table of `aura.toml`, copies of the
+//! package's markers are kept in Aura's state directory.
+//!
+//! ```toml
+//! [[rule]]
+//! package = "postgresql"
+//! id = "postgresql"
+//! markers = ["/var/lib/postgres/data/PG_VERSION"]
+//! migrate = "major"
+//! note = "Run pg_upgrade, or dump and restore the cluster."
+//! ```
+//!
+//! A `~` at the start of a marker is the home directory, and a `*` may stand
+//! for any one component of the path, as in
+//! `~/.mozilla/firefox/*/compatibility.ini`. `migrate` is one of `never`,
+//! `major`, or `any`. When a localised `rule-<id>` message exists, it's shown
+//! instead of the `note`.
+//!
+//! Aura ships a set of rules, and refreshes it weekly from upstream unless
+//! `refresh_rules = false` is set in `[general]`. The rules in
+//! `~/.config/aura/rules.toml` are applied as well. Rules files are meant to be
+//! shared, so a newer one can always be dropped in place of the old.
+
+use crate::env::Env;
+use crate::{aura, yellow};
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use serde::Deserialize;
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The rules that Aura ships with.
+const SHIPPED: &str = include_str!("../rules/upgrades.toml");
+
+/// Where the latest shipped rules can be found.
+const RULES_URL: &str =
+    "https://raw.githubusercontent.com/fosskers/aura/master/rust/aura/rules/upgrades.toml";
+
+/// How old the fetched copy of the shipped rules may grow before it's renewed.
+const REFRESH: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+/// How long renewing the rules may hold up an upgrade.
+const FETCH_LIMIT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Default)]
+struct Rules {
+    #[serde(default)]
+    rule: Vec<Rule>,
+}
+
+/// What's known about the data of one package.
+#[derive(Deserialize)]
+struct Rule {
+    package: String,
+    /// Names the localised version of the note.
+    id: Option<String>,
+    #[serde(default)]
+    markers: Vec<String>,
+    #[serde(default)]
+    migrate: Migrate,
+    note: Option<String>,
+}
+
+/// Which upgrades of a package leave its data needing migration.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Migrate {
+    #[default]
+    Never,
+    /// Those that change the epoch or the leading component of the version.
+    Major,
+    Any,
+}
+
+impl Migrate {
+    fn needed(self, old: &str, new: &str) -> bool {
+        match self {
+            Migrate::Never => false,
+            Migrate::Major => aura_core::version::major(old) != aura_core::version::major(new),
+            Migrate::Any => true,
+        }
+    }
+}
+
+/// Warn about, and keep the markers of, whichever of the given upgrades the
+/// rules know of. Each is a package name, with its old and new versions.
+pub(crate) fn before(fll: &FluentLanguageLoader, env: &Env, upgrades: &[(&str, &str, &str)]) {
+    if upgrades.is_empty() {
+        return;
+    }
+
+    let rules = load(fll, env);
+
+    for (name, old, new) in upgrades {
+        for rule in rules.iter().filter(|r| r.package == *name) {
+            if env.general.state_hooks {
+                let kept = keep(rule, old);
+                if kept > 0 {
+                    aura!(fll, "state-kept", pkg = *name, count = kept);
+                }
+            }
+
+            if rule.migrate.needed(old, new) {
+                yellow!(fll, "state-migrate", pkg = *name, from = *old, to = *new);
+                if let Some(note) = note(fll, rule) {
+                    println!("      └─ {}", note);
+                }
+            }
+        }
+    }
+}
+
+/// The localised note of a rule, if there is one, else its own.
+fn note(fll: &FluentLanguageLoader, rule: &Rule) -> Option<String> {
+    rule.id
+        .as_deref()
+        .map(|id| format!("rule-{id}"))
+        .filter(|id| fll.has(id))
+        .map(|id| fll.get(&id))
+        .or_else(|| rule.note.clone())
+}
+
+/// Every rule: the shipped ones, as recently fetched as possible, and the
+/// user's own.
+fn load(fll: &FluentLanguageLoader, env: &Env) -> Vec<Rule> {
+    let shipped = crate::dirs::upgrade_rules()
+        .ok()
+        .and_then(|p| refreshed(env, &p))
+        .and_then(|raw| toml::from_str::<Rules>(&raw).ok())
+        .or_else(|| toml::from_str(SHIPPED).ok())
+        .unwrap_or_default();
+
+    let own = crate::dirs::state_rules()
+        .ok()
+        .and_then(|path| {
+            let raw = std::fs::read_to_string(&path).ok()?;
+            match toml::from_str::<Rules>(&raw) {
+                Ok(rs) => Some(rs),
+                Err(e) => {
+                    debug!("{e}");
+                    let file = path.display().to_string();
+                    yellow!(fll, "state-rules-bad", file = file);
+                    None
+                }
+            }
+        })
+        .unwrap_or_default();
+
+    shipped.rule.into_iter().chain(own.rule).collect()
+}
+
+/// The contents of the fetched copy of the shipped rules, renewing it first if
+/// it's gone stale and renewal is allowed. A failed fetch leaves the old copy,
+/// if any, in use.
+fn refreshed(env: &Env, path: &Path) -> Option<String> {
+    let stale = path
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .map(|age| age > REFRESH)
+        .unwrap_or(true);
+
+    if env.general.refresh_rules && stale {
+        match env.network.bytes_within(RULES_URL, FETCH_LIMIT) {
+            Ok(bytes) => {
+                let parses = std::str::from_utf8(&bytes)
+                    .ok()
+                    .and_then(|raw| toml::from_str::<Rules>(raw).ok())
+                    .is_some();
+
+                if parses {
+                    let _ = std::fs::write(path, &bytes);
+                }
+            }
+            Err(_) => debug!("Couldn't fetch the latest upgrade rules."),
+        }
+    }
+
+    std::fs::read_to_string(path).ok()
+}
+
+/// Copy the markers of a package's data, yielding how many were kept.
+fn keep(rule: &Rule, version: &str) -> usize {
+    let dir = match crate::dirs::markers() {
+        Ok(d) => d.join(&rule.package).join(version),
+        Err(_) => return 0,
+    };
+
+    let markers: Vec<PathBuf> = rule
+        .markers
+        .iter()
+        .flat_map(|m| expand(m))
+        .filter(|p| p.is_file())
+        .collect();
+
+    if markers.is_empty() || std::fs::create_dir_all(&dir).is_err() {
+        return 0;
+    }
+
+    markers
+        .iter()
+        .filter(|p| {
+            // The full path is kept in the name, so markers can't collide.
+            let name = p
+                .to_string_lossy()
+                .trim_start_matches('/')
+                .replace('/', "%");
+            let copied = std::fs::copy(p, dir.join(name)).is_ok();
+            if copied.not() {
+                debug!("Couldn't keep a copy of {}", p.display());
+            }
+            copied
+        })
+        .count()
+}
+
+/// The existing paths that a marker pattern refers to.
+fn expand(pattern: &str) -> Vec<PathBuf> {
+    let full = match (pattern.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(pattern),
+    };
+
+    let mut paths = vec![PathBuf::new()];
+
+    for comp in full.components() {
+        let comp = comp.as_os_str().to_string_lossy();
+
+        paths = if comp.contains('*') {
+            paths
+                .into_iter()
+                .filter_map(|p| p.read_dir().ok())
+                .flatten()
+                .filter_map(|e| e.ok())
+                .filter(|e| aura_core::glob::matches(&comp, &e.file_name().to_string_lossy()))
+                .map(|e| e.path())
+                .collect()
+        } else {
+            paths.into_iter().map(|p| p.join(&*comp)).collect()
+        };
+    }
+
+    paths.into_iter().filter(|p| p.exists()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        let raw = r#"
+[[rule]]
+package = "postgresql"
+markers = ["/var/lib/postgres/data/PG_VERSION"]
+migrate = "major"
+note = "Run pg_upgrade."
+
+[[rule]]
+package = "firefox"
+markers = ["~/.mozilla/firefox/*/compatibility.ini"]
+"#;
+
+        let rules: Rules = toml::from_str(raw).unwrap();
+        let pg = &rules.rule[0];
+        let ff = &rules.rule[1];
+
+        assert!(pg.migrate.needed("15.4-1", "16.0-1"));
+        assert!(pg.migrate.needed("15.4-1", "15.5-1").not());
+        assert!(ff.migrate.needed("118.0-1", "119.0-1").not());
+    }
+
+    #[test]
+    fn shipped_rules() {
+        let fll = crate::localization::load(None).unwrap();
+        let rules: Rules = toml::from_str(SHIPPED).unwrap();
+
+        for rule in rules.rule {
+            let id = rule.id.unwrap();
+            assert!(fll.has(&format!("rule-{id}")), "No message for {id}");
+        }
+    }
+
+    #[test]
+    fn expansion() {
+        let dir = std::env::temp_dir().join(format!("aura-state-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("a.default")).unwrap();
+        std::fs::create_dir_all(dir.join("b.default")).unwrap();
+        std::fs::create_dir_all(dir.join("other")).unwrap();
+        std::fs::write(dir.join("a.default/compatibility.ini"), "").unwrap();
+        std::fs::write(dir.join("other/compatibility.ini"), "").unwrap();
+
+        let pattern = format!("{}/*.default/compatibility.ini", dir.display());
+        let expected = vec![dir.join("a.default/compatibility.ini")];
+        assert_eq!(expected, expand(&pattern));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
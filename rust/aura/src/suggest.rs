@@ -0,0 +1,157 @@
+//! "Did you mean" suggestions for package names that don't exist.
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::yellow;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use log::debug;
+use std::ops::Not;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Every package name in the AUR, compressed with `gzip`.
+const AUR_NAMES: &str = "https://aur.archlinux.org/packages.gz";
+
+/// How long the local copy of the AUR's package names is trusted.
+const AUR_NAMES_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// The most suggestions to offer for any one name.
+const LIMIT: usize = 5;
+
+/// How similar a name must be to be suggested, from `0.0` to `1.0`.
+const THRESHOLD: f64 = 0.85;
+
+/// Suggest real packages for any that an error says don't exist.
+pub(crate) fn suggest(fll: &FluentLanguageLoader, error: &Error) {
+    let unknown = error.unknown_packages();
+
+    if unknown.is_empty() {
+        return;
+    }
+
+    let env = match Env::try_new() {
+        Ok(env) => env,
+        Err(_) => return,
+    };
+
+    let mut names: Vec<String> = aur_names(&env);
+    if let Ok(alpm) = env.alpm() {
+        names.extend(
+            alpm.syncdbs()
+                .iter()
+                .flat_map(|db| db.pkgs().iter().map(|p| p.name().to_string())),
+        );
+    }
+    names.sort_unstable();
+    names.dedup();
+
+    for pkg in unknown {
+        let close = closest(pkg, names.iter().map(|n| n.as_str()));
+
+        if close.is_empty().not() {
+            yellow!(
+                fll,
+                "suggest-did-you-mean",
+                pkg = pkg,
+                suggestions = close.join(", ")
+            );
+        }
+    }
+}
+
+/// The names most similar to the given one, best first.
+fn closest<'a, I>(name: &str, names: I) -> Vec<&'a str>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut scored: Vec<(f64, &str)> = names
+        .filter(|n| *n != name)
+        .filter_map(|n| {
+            let score = strsim::jaro_winkler(name, n);
+            let near = score >= THRESHOLD || strsim::levenshtein(name, n) <= 2;
+            near.then(|| (score, n))
+        })
+        .collect();
+
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    scored.into_iter().take(LIMIT).map(|(_, n)| n).collect()
+}
+
+/// The names of all AUR packages, refreshed at most once a day. Empty if they
+/// can't be had.
+fn aur_names(env: &Env) -> Vec<String> {
+    let path = match crate::dirs::aur_names() {
+        Ok(p) => p,
+        Err(_) => return Vec::new(),
+    };
+
+    let fresh = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .map(|age| age < AUR_NAMES_TTL)
+        .unwrap_or(false);
+
+    if fresh.not() {
+        debug!("Refreshing {}", path.display());
+
+        match env.network.bytes(AUR_NAMES).ok().and_then(|b| gunzip(&b)) {
+            Some(names) => {
+                let _ = std::fs::write(&path, names);
+            }
+            None => debug!("Failed to fetch {AUR_NAMES}"),
+        }
+    }
+
+    std::fs::read_to_string(&path)
+        .map(|s| {
+            s.lines()
+                .filter(|l| l.starts_with('#').not())
+                .map(|l| l.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Decompress some bytes with the system's `gzip`.
+fn gunzip(bytes: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Write;
+
+    // Some servers decompress it for us.
+    if bytes.starts_with(&[0x1f, 0x8b]).not() {
+        return Some(bytes.to_vec());
+    }
+
+    let mut child = Command::new("gzip")
+        .arg("-dc")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    // Written from another thread, so that a full `stdout` pipe can't block it.
+    let mut stdin = child.stdin.take()?;
+    let input = bytes.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().ok()?;
+    writer.join().ok()?.ok()?;
+
+    output.status.success().then(|| output.stdout)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn close_names() {
+        let names = ["firefox", "firefox-esr", "firewalld", "gcc"];
+
+        assert_eq!(
+            vec!["firefox", "firefox-esr"],
+            closest("firefx", names.into_iter())
+        );
+        assert!(closest("zzzzzz", names.into_iter()).is_empty());
+    }
+}
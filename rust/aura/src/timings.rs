@@ -0,0 +1,57 @@
+//! Reporting how long each phase of Aura's own work took, for `--timings`.
+//!
+//! The report goes to `stderr`, so that it never mixes with output meant for
+//! other programs. As JSON, it has a stable shape for comparing runs over time:
+//!
+//! ```json
+//! {"total": 12.3, "phases": [{"phase": "metadata", "seconds": 0.4}]}
+//! ```
+
+use aura_core::timings::Phase;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use std::time::Duration;
+
+/// Print the time taken by each phase that ran, and by the whole run.
+pub(crate) fn report(fll: &FluentLanguageLoader, json: bool, total: Duration) {
+    let phases = aura_core::timings::totals();
+
+    if json {
+        let phases: Vec<_> = phases
+            .iter()
+            .map(|(p, d)| serde_json::json!({ "phase": p.name(), "seconds": d.as_secs_f64() }))
+            .collect();
+        let report = serde_json::json!({ "total": total.as_secs_f64(), "phases": phases });
+        eprintln!("{report}");
+        return;
+    }
+
+    let rows: Vec<(String, Duration)> = phases
+        .into_iter()
+        .map(|(p, d)| (label(fll, p), d))
+        .chain(std::iter::once((fl!(fll, "timings-total"), total)))
+        .collect();
+    let width = rows
+        .iter()
+        .map(|(l, _)| l.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let header = fl!(fll, "timings-header");
+    eprintln!("{} {} {}", "aura".bold(), "::".cyan().bold(), header.bold());
+    for (label, d) in rows {
+        let pad = width - label.chars().count();
+        eprintln!("  {label}{}  {:>8.2}s", " ".repeat(pad), d.as_secs_f64());
+    }
+}
+
+fn label(fll: &FluentLanguageLoader, phase: Phase) -> String {
+    match phase {
+        Phase::Metadata => fl!(fll, "timings-metadata"),
+        Phase::Resolution => fl!(fll, "timings-resolution"),
+        Phase::Clones => fl!(fll, "timings-clones"),
+        Phase::Builds => fl!(fll, "timings-builds"),
+        Phase::Install => fl!(fll, "timings-install"),
+    }
+}
@@ -2,18 +2,23 @@
 
 use crate::error::Nested;
 use crate::localization::Localised;
+use aura::caps::Mutating;
 use colored::{ColoredString, Colorize};
 use i18n_embed_fl::fl;
 use rustyline::Editor;
+use std::collections::{BTreeSet, HashMap};
 use std::io::Write;
 use std::iter::Peekable;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::str::FromStr;
 use unic_langid::LanguageIdentifier;
 
 /// An empty array to satisfy typechecking in a few places around the codebase.
 pub(crate) const NOTHING: [&str; 0] = [];
 
+pub(crate) const SECS_IN_DAY: u64 = 60 * 60 * 24;
+
 /// Injection of the `void` method into [`Result`], which is a common shorthand
 /// for "forgetting" the internal return value of a `Result`. Note that this
 /// also automatically lifts the Error type via [`From`], as it is intended as
@@ -82,17 +87,71 @@ where
     Ok(())
 }
 
+/// The total size in bytes of every file beneath a directory.
+pub(crate) fn dir_size(path: &Path) -> u64 {
+    path.read_dir()
+        .map(|rd| {
+            rd.filter_map(|de| de.ok())
+                .map(|de| match de.file_type() {
+                    Ok(ft) if ft.is_dir() => dir_size(&de.path()),
+                    Ok(ft) if ft.is_file() => de.metadata().map(|m| m.len()).unwrap_or(0),
+                    _ => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// How many files to hand to each `sha256sum` call.
+const HASH_BATCH: usize = 256;
+
+/// The SHA-256 of each file that could be read.
+pub(crate) fn sha256s<P>(paths: &[P]) -> HashMap<PathBuf, String>
+where
+    P: AsRef<Path>,
+{
+    paths
+        .chunks(HASH_BATCH)
+        .filter_map(|chunk| {
+            Command::new("sha256sum")
+                .args(chunk.iter().map(|p| p.as_ref()))
+                .output()
+                .ok()
+        })
+        .flat_map(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .filter_map(|l| {
+                    let (hash, path) = l.split_once("  ")?;
+                    Some((PathBuf::from(path), hash.to_string()))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The name of this machine.
+pub(crate) fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .unwrap_or_default()
+}
+
 fn pad(mult: usize, longest: usize, s: &str) -> usize {
     mult * (longest - s.chars().count())
 }
 
 // TODO Localize the acceptance chars.
-/// Prompt the user for confirmation.
-pub(crate) fn prompt(msg: &str) -> Option<()> {
+/// Prompt the user for confirmation. An empty line gives the default answer.
+pub(crate) fn prompt(msg: &str, default: bool) -> Option<()> {
     let mut rl = Editor::<()>::new().ok()?;
     let line = rl.readline(msg).ok()?;
 
-    (line.is_empty() || line == "y" || line == "Y").then(|| ())
+    match line.as_str() {
+        "" => default.then(|| ()),
+        "y" | "Y" => Some(()),
+        _ => None,
+    }
 }
 
 /// Prompt the user for a numerical selection.
@@ -110,6 +169,78 @@ pub(crate) fn select(msg: &str, max: usize) -> Result<usize, rustyline::error::R
     }
 }
 
+/// Prompt the user to toggle items of a numbered list on and off. Several
+/// numbers may be given at once, and an empty line accepts the current choices.
+pub(crate) fn toggle<T>(
+    msg: &str,
+    items: &[T],
+    chosen: &mut [bool],
+) -> Result<(), rustyline::error::ReadlineError>
+where
+    T: std::fmt::Display,
+{
+    let mut rl = Editor::<()>::new()?;
+
+    loop {
+        for (i, (item, on)) in items.iter().zip(chosen.iter()).enumerate() {
+            let mark = if *on { "x" } else { " " };
+            println!(" [{}] {:>2}) {}", mark.green().bold(), i, item);
+        }
+
+        let raw = rl.readline(msg)?;
+
+        if raw.trim().is_empty() {
+            return Ok(());
+        }
+
+        raw.split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|n| usize::from_str(n).ok())
+            .filter_map(|n| chosen.get_mut(n))
+            .for_each(|on| *on = !*on);
+    }
+}
+
+/// Expand any wildcard patterns (like `linux-*`) among the given package names
+/// against the packages that actually exist. Plain names are left as they are.
+/// If anything was expanded, the matches are shown and must be confirmed.
+/// Patterns that match nothing cancel the whole command, since otherwise a
+/// typo would leave it with no restriction at all.
+pub(crate) fn expand<I>(
+    fll: &i18n_embed::fluent::FluentLanguageLoader,
+    patterns: Vec<String>,
+    universe: I,
+) -> Option<Vec<String>>
+where
+    I: IntoIterator<Item = String>,
+{
+    let (globs, mut plain): (Vec<_>, Vec<_>) = patterns
+        .into_iter()
+        .partition(|p| aura_core::glob::is_glob(p));
+
+    if globs.is_empty() {
+        return Some(plain);
+    }
+
+    let matched: BTreeSet<String> = universe
+        .into_iter()
+        .filter(|u| globs.iter().any(|g| aura_core::glob::matches(g, u)))
+        .collect();
+
+    if matched.is_empty() {
+        crate::yellow!(fll, "select-none", patterns = globs.join(" "));
+        return None;
+    }
+
+    crate::aura!(fll, "select-matched", count = matched.len());
+    for m in matched.iter() {
+        println!(" {}", m.cyan());
+    }
+    crate::proceed!(fll, "proceed", "select-matched")?;
+
+    plain.extend(matched);
+    Some(plain)
+}
+
 pub(crate) struct SudoError;
 
 impl Nested for SudoError {
@@ -123,7 +254,11 @@ impl Localised for SudoError {
 }
 
 /// Escalate the privileges of the Aura process, if necessary.
-pub(crate) fn sudo() -> Result<(), SudoError> {
+pub(crate) fn sudo(_: &Mutating) -> Result<(), SudoError> {
+    if let sudo::RunningAs::User = sudo::check() {
+        crate::audit::escalation();
+    }
+
     sudo::escalate_if_needed().map_err(|_| SudoError).void()
 }
 
@@ -23,9 +23,21 @@ fn main() -> std::io::Result<()> {
         (Orphans::command(), "aura-orphans.1"),
         (Conf::command(), "aura-conf.1"),
         (Stats::command(), "aura-stats.1"),
+        (Lang::command(), "aura-lang.1"),
         (Open::command(), "aura-open.1"),
+        (Info::command(), "aura-info.1"),
+        (Alias::command(), "aura-alias.1"),
+        (Optdeps::command(), "aura-optdeps.1"),
+        (Provides::command(), "aura-provides.1"),
+        (Select::command(), "aura-select.1"),
+        (Setup::command(), "aura-setup.1"),
+        (Migrate::command(), "aura-migrate.1"),
         (Deps::command(), "aura-deps.1"),
         (Check::command(), "aura-check.1"),
+        (Clean::command(), "aura-clean.1"),
+        (DebugInfo::command(), "aura-debug.1"),
+        (Verify::command(), "aura-verify.1"),
+        (Vercmp::command(), "aura-vercmp.1"),
     ]
     .into_iter()
     .try_for_each(|(cmd, path)| work(cmd, path))